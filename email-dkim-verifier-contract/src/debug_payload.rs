@@ -0,0 +1,62 @@
+use crate::error_code::VerifyErrorCode;
+use crate::{log_request, EmailDkimVerifier, VerificationResult};
+use near_sdk::serde_json;
+
+/// Largest stored debug payload, in bytes of serialized JSON.
+pub const DEBUG_PAYLOAD_MAX_BYTES: usize = 2048;
+
+/// Failures caused by a worker response the contract could not parse; only
+/// these keep the raw payload.
+const PARSE_FAILURE_ERRORS: [VerifyErrorCode; 3] = [
+    VerifyErrorCode::InvalidWorkerResponse,
+    VerifyErrorCode::InvalidVerifyResponse,
+    VerifyErrorCode::InvalidDnsResponse,
+];
+
+impl EmailDkimVerifier {
+    /// The raw worker response to keep for `vr`, or `None`. Only clones the
+    /// callback value while `store_debug_payload` is enabled.
+    pub(crate) fn debug_payload_candidate(
+        &self,
+        result: &Result<Option<serde_json::Value>, near_sdk::PromiseError>,
+    ) -> Option<serde_json::Value> {
+        if !self.store_debug_payload {
+            return None;
+        }
+        result.as_ref().ok().cloned().flatten()
+    }
+
+    /// Store `raw` (serialized, truncated to `DEBUG_PAYLOAD_MAX_BYTES`) under
+    /// the request id when `vr` failed to parse the worker response.
+    pub(crate) fn record_debug_payload(
+        &mut self,
+        vr: &VerificationResult,
+        raw: Option<serde_json::Value>,
+    ) {
+        let Some(raw) = raw else {
+            return;
+        };
+        if !vr
+            .error
+            .as_deref()
+            .and_then(VerifyErrorCode::from_error)
+            .is_some_and(|code| PARSE_FAILURE_ERRORS.contains(&code))
+        {
+            return;
+        }
+
+        let mut payload = raw.to_string();
+        if payload.len() > DEBUG_PAYLOAD_MAX_BYTES {
+            let mut end = DEBUG_PAYLOAD_MAX_BYTES;
+            while !payload.is_char_boundary(end) {
+                end -= 1;
+            }
+            payload.truncate(end);
+        }
+        log_request(
+            &vr.request_id,
+            &format!("Stored {} byte debug payload", payload.len()),
+        );
+        self.debug_payloads.insert(vr.request_id.clone(), payload);
+    }
+}
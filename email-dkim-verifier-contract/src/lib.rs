@@ -1,12 +1,18 @@
+pub mod debug_payload;
+pub mod error_code;
+#[cfg(feature = "legacy-onchain")]
 pub mod onchain_verify;
+pub mod rate_limit;
 pub mod tee_verify;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::serde_json::{self};
+use near_sdk::store::{IterableMap, LookupMap};
 use near_sdk::{
-    env, ext_contract, near, AccountId, Promise, PromiseError,
+    env, ext_contract, near, AccountId, BorshStorageKey, Gas, Promise, PromiseError, PublicKey,
 };
+use rate_limit::RateLimits;
 use schemars::JsonSchema;
 use tee_verify::AeadContext;
 
@@ -29,9 +35,93 @@ pub struct EmailDkimVerifier {
     outlayer_encryption_public_key: String,
     outlayer_worker_wasm_url: String,
     outlayer_worker_wasm_hash: String,
+    /// Maximum verification callbacks per signing domain (and per requester
+    /// account) within a rolling day. `0` disables rate limiting.
+    max_requests_per_domain_per_day: u32,
+    rate_limits: RateLimits,
+    /// Accept `Resent-From:` / `Resent-Date:` when an email has no `From:` /
+    /// `Date:` header. Off by default: it changes which identity is hashed.
+    allow_resent_headers: bool,
+    /// Accept caller-supplied DKIM key records on the on-chain path instead of
+    /// fetching them via Outlayer. Off by default; meant for testnet only.
+    allow_inline_dns: bool,
+    /// Owner-pinned DKIM key records by lowercased `(domain, selector)`. A
+    /// pinned entry replaces the DNS lookup result for matching signatures.
+    pinned_keys: IterableMap<(String, String), Vec<String>>,
+    /// Require every `DKIM-Signature` on a message to verify, not just one.
+    /// Off by default: forwarding and mailing lists often add a signature that
+    /// no longer verifies, or break the original one.
+    require_all_signatures: bool,
+    /// Keep the raw worker response (truncated) for requests that failed to
+    /// parse it. Off by default; for debugging only.
+    store_debug_payload: bool,
+    debug_payloads: LookupMap<String, String>,
+    /// Rotation counter and hex SHA-256 fingerprint the worker reported with
+    /// `outlayer_encryption_public_key`; `0` / empty until first refreshed.
+    outlayer_encryption_key_version: u32,
+    outlayer_encryption_key_fingerprint: String,
+    /// Owner-configured DKIM key TXT records by lowercased
+    /// `<selector>._domainkey.<domain>` name, forwarded to the TEE worker so it
+    /// can verify without reaching DoH.
+    static_dns_records: IterableMap<String, Vec<String>>,
+    /// Block time the current worker wasm source was set (`0` if unknown).
+    outlayer_worker_wasm_set_at_ms: u64,
+    /// Previously active worker wasm sources, oldest first, capped at
+    /// `WORKER_WASM_HISTORY_MAX` entries.
+    worker_wasm_history: Vec<WorkerWasmRecord>,
+    /// Most `DKIM-Signature` headers the TEE worker tries per message; the
+    /// rest are skipped. Bounds the RSA work one email can cause.
+    max_dkim_signatures: u32,
+    /// Lowercased domain the on-chain path requires among the email's `To:`
+    /// addresses (e.g. `web3authn.org`); `None` accepts any recipient.
+    expected_to_domain: Option<String>,
+    /// TGas reserved for each `on_*` callback, on top of its share of the
+    /// unused gas, so an expensive Outlayer call cannot starve it.
+    callback_gas_tgas: u64,
+    /// Fail verification of emails with mailing-list headers (`List-Id`,
+    /// `List-Unsubscribe`, or a `Sender:` other than `From:`). Off by default.
+    reject_mailing_list_emails: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, BorshSerialize, BorshDeserialize)]
+/// Contract state layout of the deployed baseline (worker source and
+/// encryption key only); read by `migrate`.
+#[near(serializers = [borsh])]
+struct EmailDkimVerifierV0 {
+    outlayer_encryption_public_key: String,
+    outlayer_worker_wasm_url: String,
+    outlayer_worker_wasm_hash: String,
+}
+
+#[derive(BorshSerialize, BorshStorageKey)]
+enum StorageKey {
+    RateLimits,
+    PinnedKeys,
+    DebugPayloads,
+    StaticDnsRecords,
+    RateLimitStarts,
+}
+
+/// DKIM key TXT records pinned by the owner for one signing domain and selector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PinnedDkimKey {
+    pub domain: String,
+    pub selector: String,
+    pub records: Vec<String>,
+}
+
+/// DKIM key TXT records the owner configured for one `_domainkey` name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StaticDnsRecord {
+    pub name: String,
+    pub records: Vec<String>,
+}
+
+/// API-facing verification outcome (JSON). Contract state, if any, stores
+/// `StoredVerificationResultV1` instead, so this type's field order and
+/// extra fields are free to change.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(crate = "near_sdk::serde")]
 pub struct VerificationResult {
     pub verified: bool,
@@ -44,10 +134,17 @@ pub struct VerificationResult {
     pub from_address_hash: Vec<u8>,
     pub email_timestamp_ms: Option<u64>,
     pub request_id: String,
+    /// DKIM `s=` selector of the signature that verified (empty on failure).
+    /// Together with the signing domain it identifies the DNS key record used.
+    /// Not persisted (see `StoredVerificationResultV1`), like `error` below.
+    #[serde(default)]
+    pub selector: String,
+    /// Lowercased DKIM `d=` domain of the signature that verified (empty on
+    /// failure). Not persisted, like `selector`.
+    #[serde(default)]
+    pub signing_domain: String,
     /// Optional diagnostic string for failures (e.g. worker error, DNS error).
-    /// Note: this is not persisted in contract state (Borsh) so that adding it
-    /// stays backwards-compatible with previously stored `VerificationResult`s.
-    #[borsh(skip)]
+    /// Not persisted in contract state.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
@@ -61,9 +158,105 @@ impl VerificationResult {
             from_address_hash: Vec::new(),
             email_timestamp_ms: None,
             request_id: request_id.as_ref().to_string(),
+            selector: String::new(),
+            signing_domain: String::new(),
             error: Some(error.into()),
         }
     }
+
+    /// `{ account_id, new_public_key }` for a recovery contract call when this
+    /// result is actionable: verified, with a valid NEAR account id and an
+    /// ed25519 `new_public_key`. `None` otherwise, so relayers do not act on
+    /// a result with a missing or malformed field.
+    pub fn to_recovery_args(&self) -> Option<serde_json::Value> {
+        if !self.verified {
+            return None;
+        }
+        let account_id: AccountId = self.account_id.parse().ok()?;
+        // `PublicKey` reads an unprefixed key as ed25519; require the prefix.
+        if !self.new_public_key.starts_with("ed25519:") {
+            return None;
+        }
+        let new_public_key: PublicKey = self.new_public_key.parse().ok()?;
+        Some(serde_json::json!({
+            "account_id": account_id,
+            "new_public_key": new_public_key,
+        }))
+    }
+}
+
+/// Borsh layout of a persisted `VerificationResult`. Frozen: fields are never
+/// reordered, added or removed here; a layout change gets a new
+/// `StoredVerificationResultV2` and a migration instead.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct StoredVerificationResultV1 {
+    pub verified: bool,
+    pub account_id: String,
+    pub new_public_key: String,
+    pub from_address_hash: Vec<u8>,
+    pub email_timestamp_ms: Option<u64>,
+    pub request_id: String,
+}
+
+impl From<&VerificationResult> for StoredVerificationResultV1 {
+    fn from(result: &VerificationResult) -> Self {
+        Self {
+            verified: result.verified,
+            account_id: result.account_id.clone(),
+            new_public_key: result.new_public_key.clone(),
+            from_address_hash: result.from_address_hash.clone(),
+            email_timestamp_ms: result.email_timestamp_ms,
+            request_id: result.request_id.clone(),
+        }
+    }
+}
+
+impl From<StoredVerificationResultV1> for VerificationResult {
+    fn from(stored: StoredVerificationResultV1) -> Self {
+        Self {
+            verified: stored.verified,
+            account_id: stored.account_id,
+            new_public_key: stored.new_public_key,
+            from_address_hash: stored.from_address_hash,
+            email_timestamp_ms: stored.email_timestamp_ms,
+            request_id: stored.request_id,
+            selector: String::new(),
+            signing_domain: String::new(),
+            error: None,
+        }
+    }
+}
+
+/// Whether a recovery instruction carrying `exp=<unix_secs>` in its (DKIM-signed)
+/// Subject has passed its deadline at the current block time.
+pub(crate) fn is_recovery_expired(expires_at_secs: u64) -> bool {
+    env::block_timestamp() / 1_000_000_000 > expires_at_secs
+}
+
+/// How far past the current block time an email's `Date:` may lie before
+/// `email_timestamp_ms` is dropped (clock skew between mail servers and chain).
+pub const EMAIL_DATE_FUTURE_TOLERANCE_MS: u64 = 5 * 60 * 1000;
+
+/// Latest acceptable `email_timestamp_ms` at the current block time.
+pub(crate) fn email_timestamp_upper_bound_ms() -> u64 {
+    env::block_timestamp() / 1_000_000 + EMAIL_DATE_FUTURE_TOLERANCE_MS
+}
+
+/// `env::log_str` prefixed with `[request_id=...]`, so the log lines of one
+/// verification can be picked out of a busy block.
+pub(crate) fn log_request(request_id: &str, message: &str) {
+    env::log_str(&format!("[request_id={request_id}] {message}"));
+}
+
+/// The stored worker encryption key with the metadata `get-public-key`
+/// reported for it, so relayers can cache the key and notice rotations.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OutlayerEncryptionKeyInfo {
+    pub public_key: String,
+    pub key_algorithm: String,
+    pub key_fingerprint_sha256: String,
+    pub key_version: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -73,6 +266,30 @@ pub struct OutlayerWorkerWasmSource {
     pub hash: String,
 }
 
+/// Most previous worker wasm sources kept in `get_worker_wasm_history`.
+pub const WORKER_WASM_HISTORY_MAX: usize = 20;
+
+/// `get_max_dkim_signatures` until the owner changes it; matches the worker's
+/// own default.
+pub const DEFAULT_MAX_DKIM_SIGNATURES: u32 = 5;
+
+/// `get_callback_gas_tgas` until the owner changes it: enough for the on-chain
+/// callback's RSA verification plus the rate limit and debug payload writes.
+pub const DEFAULT_CALLBACK_GAS_TGAS: u64 = 30;
+
+/// A worker wasm source that was replaced, and the block time (ms) it had
+/// been set at (`0` when it predates the history).
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, BorshSerialize,
+    BorshDeserialize,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WorkerWasmRecord {
+    pub url: String,
+    pub hash: String,
+    pub set_at_ms: u64,
+}
+
 #[derive(near_sdk::serde::Serialize, near_sdk::serde::Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 struct SecretsReference {
@@ -197,7 +414,282 @@ impl EmailDkimVerifier {
             outlayer_encryption_public_key: OUTLAYER_ENCRYPTION_PUBKEY.to_string(),
             outlayer_worker_wasm_url: String::new(),
             outlayer_worker_wasm_hash: String::new(),
+            max_requests_per_domain_per_day: 0,
+            rate_limits: RateLimits::new(StorageKey::RateLimits, StorageKey::RateLimitStarts),
+            allow_resent_headers: false,
+            allow_inline_dns: false,
+            pinned_keys: IterableMap::new(StorageKey::PinnedKeys),
+            require_all_signatures: false,
+            store_debug_payload: false,
+            debug_payloads: LookupMap::new(StorageKey::DebugPayloads),
+            outlayer_encryption_key_version: 0,
+            outlayer_encryption_key_fingerprint: String::new(),
+            static_dns_records: IterableMap::new(StorageKey::StaticDnsRecords),
+            outlayer_worker_wasm_set_at_ms: 0,
+            worker_wasm_history: Vec::new(),
+            max_dkim_signatures: DEFAULT_MAX_DKIM_SIGNATURES,
+            expected_to_domain: None,
+            callback_gas_tgas: DEFAULT_CALLBACK_GAS_TGAS,
+            reject_mailing_list_emails: false,
+        }
+    }
+
+    /// Upgrade hook: re-reads the baseline state layout, keeps its worker
+    /// source and encryption key, and takes every other field from `new`.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let raw = env::storage_read(b"STATE")
+            .unwrap_or_else(|| env::panic_str("Contract state is missing"));
+        let old = EmailDkimVerifierV0::try_from_slice(&raw)
+            .unwrap_or_else(|_| env::panic_str("Cannot deserialize previous contract state"));
+        Self {
+            outlayer_encryption_public_key: old.outlayer_encryption_public_key,
+            outlayer_worker_wasm_url: old.outlayer_worker_wasm_url,
+            outlayer_worker_wasm_hash: old.outlayer_worker_wasm_hash,
+            ..Self::new()
+        }
+    }
+
+    pub fn get_max_requests_per_domain_per_day(&self) -> u32 {
+        self.max_requests_per_domain_per_day
+    }
+
+    /// Owner-only: cap verification callbacks per signing domain and per
+    /// requester account within a rolling day. Set to `0` to disable.
+    pub fn set_max_requests_per_domain_per_day(&mut self, max_requests: u32) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can set the rate limit"
+        );
+        self.max_requests_per_domain_per_day = max_requests;
+    }
+
+    pub fn get_allow_resent_headers(&self) -> bool {
+        self.allow_resent_headers
+    }
+
+    /// Owner-only: when enabled, emails without a `From:` / `Date:` header fall
+    /// back to `Resent-From:` / `Resent-Date:` for the sender hash and timestamp.
+    pub fn set_allow_resent_headers(&mut self, allow: bool) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can set the Resent-* header policy"
+        );
+        self.allow_resent_headers = allow;
+    }
+
+    pub fn get_allow_inline_dns(&self) -> bool {
+        self.allow_inline_dns
+    }
+
+    /// Owner-only: when enabled, `request_email_verification_onchain` accepts
+    /// `inline_dns_records` and verifies against them without an Outlayer call.
+    /// Keep disabled on mainnet: the caller then chooses the DKIM key.
+    pub fn set_allow_inline_dns(&mut self, allow: bool) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can set the inline DNS policy"
+        );
+        self.allow_inline_dns = allow;
+    }
+
+    pub fn get_require_all_signatures(&self) -> bool {
+        self.require_all_signatures
+    }
+
+    /// Owner-only: when enabled, a message verifies only if every
+    /// `DKIM-Signature` on it verifies. Stronger against a signature added by
+    /// an untrusted hop, but rejects most forwarded and mailing-list mail.
+    pub fn set_require_all_signatures(&mut self, require_all: bool) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can set the DKIM signature policy"
+        );
+        self.require_all_signatures = require_all;
+    }
+
+    pub fn get_max_dkim_signatures(&self) -> u32 {
+        self.max_dkim_signatures
+    }
+
+    /// Owner-only: most `DKIM-Signature` headers the TEE worker tries per
+    /// message, top to bottom; any further ones are skipped (and, under
+    /// `require_all_signatures`, fail the message). Must be at least 1.
+    pub fn set_max_dkim_signatures(&mut self, max_signatures: u32) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can set the DKIM signature policy"
+        );
+        if max_signatures == 0 {
+            env::panic_str("max_dkim_signatures must be at least 1");
         }
+        self.max_dkim_signatures = max_signatures;
+    }
+
+    pub fn get_expected_to_domain(&self) -> Option<String> {
+        self.expected_to_domain.clone()
+    }
+
+    /// Owner-only: require `request_email_verification_onchain` emails to be
+    /// addressed (`To:`) to this domain, e.g. `web3authn.org`. Matching is
+    /// exact, so subdomains are not accepted. `None` (or an empty domain)
+    /// accepts any recipient. The TEE path cannot check this, since the
+    /// contract never sees the plaintext email.
+    pub fn set_expected_to_domain(&mut self, domain: Option<String>) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can set the expected To domain"
+        );
+        self.expected_to_domain = domain
+            .map(|d| d.trim().trim_start_matches('@').to_ascii_lowercase())
+            .filter(|d| !d.is_empty());
+    }
+
+    pub fn get_callback_gas_tgas(&self) -> u64 {
+        self.callback_gas_tgas
+    }
+
+    /// Owner-only: TGas reserved for each verification and key refresh
+    /// callback. Requests attaching less gas than the Outlayer call needs plus
+    /// this reservation fail up front instead of losing their result. Must be
+    /// at least 1.
+    pub fn set_callback_gas_tgas(&mut self, tgas: u64) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can set the callback gas"
+        );
+        if tgas == 0 {
+            env::panic_str("callback_gas_tgas must be at least 1");
+        }
+        self.callback_gas_tgas = tgas;
+    }
+
+    pub fn get_reject_mailing_list_emails(&self) -> bool {
+        self.reject_mailing_list_emails
+    }
+
+    /// Owner-only: when enabled, emails carrying mailing-list headers fail with
+    /// `mailing_list_message` even if their DKIM signature verifies. A list that
+    /// rewrites `From:` signs as itself, so the signature does not vouch for
+    /// the original author.
+    pub fn set_reject_mailing_list_emails(&mut self, reject: bool) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can set the mailing list policy"
+        );
+        self.reject_mailing_list_emails = reject;
+    }
+
+    pub fn get_store_debug_payload(&self) -> bool {
+        self.store_debug_payload
+    }
+
+    /// Owner-only: when enabled, a callback that cannot parse the worker
+    /// response keeps it (truncated to `DEBUG_PAYLOAD_MAX_BYTES`) for
+    /// `get_debug_payload`. Entries cost contract storage; disable when done.
+    pub fn set_store_debug_payload(&mut self, store: bool) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can set the debug payload policy"
+        );
+        self.store_debug_payload = store;
+    }
+
+    /// Raw (truncated) worker response stored for a failed `request_id`.
+    pub fn get_debug_payload(&self, request_id: String) -> Option<String> {
+        self.debug_payloads.get(&request_id).cloned()
+    }
+
+    pub fn get_pinned_dkim_keys(&self) -> Vec<PinnedDkimKey> {
+        self.pinned_keys
+            .iter()
+            .map(|((domain, selector), records)| PinnedDkimKey {
+                domain: domain.clone(),
+                selector: selector.clone(),
+                records: records.clone(),
+            })
+            .collect()
+    }
+
+    /// Owner-only: pin the DKIM key TXT records for `domain` / `selector`.
+    /// Signatures with that `d=` / `s=` are then checked against `records`
+    /// instead of DNS; pin a revoked record (`p=`) to reject them outright.
+    pub fn set_pinned_dkim_key(&mut self, domain: String, selector: String, records: Vec<String>) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can pin DKIM keys"
+        );
+        let domain = domain.trim().to_ascii_lowercase();
+        let selector = selector.trim().to_ascii_lowercase();
+        if domain.is_empty() || selector.is_empty() {
+            env::panic_str("Pinned DKIM key domain and selector must not be empty");
+        }
+        self.pinned_keys.insert((domain, selector), records);
+    }
+
+    /// Owner-only: drop a pinned key, returning to DNS for that selector.
+    /// Returns whether an entry was removed.
+    pub fn remove_pinned_dkim_key(&mut self, domain: String, selector: String) -> bool {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can pin DKIM keys"
+        );
+        let key = (
+            domain.trim().to_ascii_lowercase(),
+            selector.trim().to_ascii_lowercase(),
+        );
+        self.pinned_keys.remove(&key).is_some()
+    }
+
+    pub fn get_static_dns_records(&self) -> Vec<StaticDnsRecord> {
+        self.static_dns_records
+            .iter()
+            .map(|(name, records)| StaticDnsRecord {
+                name: name.clone(),
+                records: records.clone(),
+            })
+            .collect()
+    }
+
+    /// Owner-only: serve `records` for `name` (`<selector>._domainkey.<domain>`)
+    /// on the TEE-private path instead of a DoH lookup, for networks where the
+    /// worker cannot reach DoH. Pinned keys still take precedence.
+    pub fn set_static_dns_records(&mut self, name: String, records: Vec<String>) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can set static DNS records"
+        );
+        let name = name.trim().trim_end_matches('.').to_ascii_lowercase();
+        match name.split_once("._domainkey.") {
+            Some((selector, domain)) if !selector.is_empty() && !domain.is_empty() => {}
+            _ => env::panic_str("Static DNS record name must be <selector>._domainkey.<domain>"),
+        }
+        self.static_dns_records.insert(name, records);
+    }
+
+    /// Owner-only: drop the static records for `name`, returning to DoH.
+    /// Returns whether an entry was removed.
+    pub fn remove_static_dns_records(&mut self, name: String) -> bool {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Only the contract owner can set static DNS records"
+        );
+        let name = name.trim().trim_end_matches('.').to_ascii_lowercase();
+        self.static_dns_records.remove(&name).is_some()
     }
 
     pub fn get_outlayer_encryption_public_key(&self) -> String {
@@ -209,6 +701,17 @@ impl EmailDkimVerifier {
         self.outlayer_encryption_public_key.clone()
     }
 
+    /// `get_outlayer_encryption_public_key` plus the worker-reported version
+    /// and SHA-256 fingerprint (`0` / empty if the worker did not report them).
+    pub fn get_outlayer_encryption_key_info(&self) -> OutlayerEncryptionKeyInfo {
+        OutlayerEncryptionKeyInfo {
+            public_key: self.get_outlayer_encryption_public_key(),
+            key_algorithm: "x25519".to_string(),
+            key_fingerprint_sha256: self.outlayer_encryption_key_fingerprint.clone(),
+            key_version: self.outlayer_encryption_key_version,
+        }
+    }
+
     pub fn get_outlayer_worker_wasm_source(&self) -> OutlayerWorkerWasmSource {
         OutlayerWorkerWasmSource {
             url: self.outlayer_worker_wasm_url.clone(),
@@ -225,16 +728,46 @@ impl EmailDkimVerifier {
         );
 
         let url = url.trim().to_string();
-        let hash = hash.trim().to_string();
+        let hash = hash.trim().to_ascii_lowercase();
         if url.is_empty() {
             env::panic_str("Outlayer worker wasm URL must not be empty");
         }
         if hash.is_empty() {
             env::panic_str("Outlayer worker wasm hash must not be empty");
         }
+        // Outlayer checks the download against this hash on every call, so a
+        // typo would otherwise only show up as failed executions.
+        let has_host = url
+            .strip_prefix("https://")
+            .is_some_and(|rest| !rest.is_empty() && !rest.starts_with('/'));
+        if !has_host || url.contains(char::is_whitespace) {
+            env::panic_str("Outlayer worker wasm URL must be an https:// URL");
+        }
+        if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            env::panic_str(
+                "Outlayer worker wasm hash must be a hex SHA-256 digest (64 characters)",
+            );
+        }
 
+        if !self.outlayer_worker_wasm_url.is_empty() {
+            if self.worker_wasm_history.len() >= WORKER_WASM_HISTORY_MAX {
+                self.worker_wasm_history.remove(0);
+            }
+            self.worker_wasm_history.push(WorkerWasmRecord {
+                url: std::mem::take(&mut self.outlayer_worker_wasm_url),
+                hash: std::mem::take(&mut self.outlayer_worker_wasm_hash),
+                set_at_ms: self.outlayer_worker_wasm_set_at_ms,
+            });
+        }
         self.outlayer_worker_wasm_url = url;
         self.outlayer_worker_wasm_hash = hash;
+        self.outlayer_worker_wasm_set_at_ms = env::block_timestamp_ms();
+    }
+
+    /// Worker wasm sources replaced by `set_outlayer_worker_wasm_source`,
+    /// oldest first, for auditing and rollback.
+    pub fn get_worker_wasm_history(&self) -> Vec<WorkerWasmRecord> {
+        self.worker_wasm_history.clone()
     }
 
     #[payable]
@@ -305,6 +838,7 @@ impl EmailDkimVerifier {
             )
             .then(
                 ext_self::ext(env::current_account_id())
+                    .with_static_gas(self.callback_gas())
                     .with_unused_gas_weight(1)
                     .on_worker_public_key_result(),
             )
@@ -329,8 +863,24 @@ impl EmailDkimVerifier {
                     .and_then(|v| v.as_str())
                     .expect("Response missing public_key")
                     .to_string();
+                // Older workers report only `public_key`.
+                let key_version = response.response
+                    .get("key_version")
+                    .and_then(|v| v.as_u64())
+                    .and_then(|v| u32::try_from(v).ok())
+                    .unwrap_or(0);
+                let key_fingerprint = response.response
+                    .get("key_fingerprint_sha256")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
 
+                env::log_str(&format!(
+                    "Outlayer encryption public key updated: version={key_version}, fingerprint={key_fingerprint}"
+                ));
                 self.outlayer_encryption_public_key = pubkey_str;
+                self.outlayer_encryption_key_version = key_version;
+                self.outlayer_encryption_key_fingerprint = key_fingerprint;
             }
             Ok(None) => env::panic_str("Worker returned empty result"),
             Err(_) => env::panic_str("Worker execution failed"),
@@ -353,6 +903,11 @@ impl EmailDkimVerifier {
         OutlayerWorkerWasmSource { url, hash }
     }
 
+    /// Static gas for an `on_*` callback (see `callback_gas_tgas`).
+    pub(crate) fn callback_gas(&self) -> Gas {
+        Gas::from_tgas(self.callback_gas_tgas)
+    }
+
     /// Unified entrypoint for requesting DKIM verification.
     ///
     /// - On-chain DKIM (public): set `email_blob = Some(raw_rfc5322_email)`.
@@ -370,11 +925,16 @@ impl EmailDkimVerifier {
         request_id: Option<String>,
     ) -> Promise {
         match (email_blob, encrypted_email_blob, aead_context) {
+            #[cfg(feature = "legacy-onchain")]
             (Some(email_blob), None, _) => onchain_verify::request_email_verification_onchain_inner(
                 self,
                 payer_account_id,
                 email_blob,
             ),
+            #[cfg(not(feature = "legacy-onchain"))]
+            (Some(_), None, _) => env::panic_str(
+                "On-chain DKIM verification is not available in this build; use encrypted_email_blob",
+            ),
             (None, Some(encrypted_email_blob), Some(aead_context)) => {
                 tee_verify::request_email_verification_private_inner(
                     self,
@@ -403,6 +963,8 @@ impl EmailDkimVerifier {
     ///   - forwarded to the worker (used for ChaCha20-Poly1305 AEAD AAD in decrypting email).
     ///   - context fields must follow alphabetization:
     ///     { "account_id": "...", "network_id": "...", "payer_account_id": "..." }`
+    ///   - a non-empty `request_id` is added to the context as `request_id`, so the
+    ///     envelope must have been encrypted with it in the AAD.
     ///
     /// @returns
     /// - A `Promise` that resolves to `VerificationResult`
@@ -423,24 +985,68 @@ impl EmailDkimVerifier {
         )
     }
 
+    #[private]
+    pub fn on_email_verification_private_result(
+        &mut self,
+        requested_by: AccountId,
+        request_id: String,
+        #[callback_result] result: Result<Option<serde_json::Value>, PromiseError>,
+    ) -> VerificationResult {
+        let debug_payload = self.debug_payload_candidate(&result);
+        let vr = tee_verify::on_email_verification_private_result(
+            requested_by.clone(),
+            request_id,
+            result,
+        );
+        self.record_debug_payload(&vr, debug_payload);
+        self.apply_rate_limit(&requested_by, vr)
+    }
+}
+
+/// Deprecated on-chain DKIM path, compiled only with the `legacy-onchain` feature.
+#[cfg(feature = "legacy-onchain")]
+#[near]
+impl EmailDkimVerifier {
+    /// Compute the `from_address_hash` the verification callbacks would return
+    /// for a sender, without running DKIM verification.
+    ///
+    /// `from_header` may be a bare address, a display form like
+    /// `Name <user@example.com>`, or a full `From: ...` header line.
+    pub fn get_from_address_hash(&self, from_header: String, account_id: String) -> Vec<u8> {
+        onchain_verify::compute_from_address_hash_for_header(&from_header, &account_id)
+    }
+
     /// @deprecated Public Onchain Email DKIM verifier.
     /// User for legacy testing purposes.
     /// @params
     /// - `payer_account_id`: Account that pays for the Outlayer execution.
     /// - `email_blob`: Plaintext RFC‑5322 email: for on‑chain DKIM verification.
+    /// - `inline_dns_records`: Optional DKIM key TXT records to verify against
+    ///   instead of fetching them via Outlayer. Requires `allow_inline_dns`.
     /// @returns
-    /// - A `Promise` that resolves to `VerificationResult`
+    /// - A `Promise` that resolves to `VerificationResult`, or the
+    ///   `VerificationResult` itself when `inline_dns_records` is given
     #[payable]
     pub fn request_email_verification_onchain(
         &mut self,
         payer_account_id: AccountId,
         email_blob: String,
-    ) -> Promise {
-        onchain_verify::request_email_verification_onchain_inner(
+        inline_dns_records: Option<Vec<String>>,
+    ) -> near_sdk::PromiseOrValue<VerificationResult> {
+        if let Some(dns_records) = inline_dns_records {
+            let requested_by = env::predecessor_account_id();
+            let vr = onchain_verify::verify_email_with_inline_dns_records(
+                self,
+                &email_blob,
+                &dns_records,
+            );
+            return near_sdk::PromiseOrValue::Value(self.apply_rate_limit(&requested_by, vr));
+        }
+        near_sdk::PromiseOrValue::Promise(onchain_verify::request_email_verification_onchain_inner(
             self,
             payer_account_id,
             email_blob,
-        )
+        ))
     }
 
     #[private]
@@ -450,17 +1056,30 @@ impl EmailDkimVerifier {
         email_blob: String,
         #[callback_result] result: Result<Option<serde_json::Value>, PromiseError>,
     ) -> VerificationResult {
-        onchain_verify::on_email_verification_onchain_result(self, requested_by, email_blob, result)
+        let debug_payload = self.debug_payload_candidate(&result);
+        let vr = onchain_verify::on_email_verification_onchain_result(
+            self,
+            requested_by.clone(),
+            email_blob,
+            result,
+        );
+        self.record_debug_payload(&vr, debug_payload);
+        self.apply_rate_limit(&requested_by, vr)
     }
+}
 
-    #[private]
-    pub fn on_email_verification_private_result(
-        &mut self,
-        requested_by: AccountId,
-        request_id: String,
-        #[callback_result] result: Result<Option<serde_json::Value>, PromiseError>,
-    ) -> VerificationResult {
-        tee_verify::on_email_verification_private_result(requested_by, request_id, result)
+/// Test-only entrypoints, compiled only with the `test-hooks` feature.
+#[cfg(feature = "test-hooks")]
+#[near]
+impl EmailDkimVerifier {
+    /// Run the private-path callback as if the Outlayer execution had failed
+    /// (`Ok(None)`), returning the resulting `outlayer_execution_failed` result.
+    pub fn test_on_private_result_failure(&mut self, request_id: String) -> VerificationResult {
+        self.on_email_verification_private_result(
+            env::predecessor_account_id(),
+            request_id,
+            Ok(None),
+        )
     }
 }
 
@@ -1,36 +1,160 @@
 use base64;
 use rsa::pkcs1v15::{Signature as RsaSignature, VerifyingKey};
+use rsa::pkcs1::DecodeRsaPublicKey;
 use rsa::pkcs8::DecodePublicKey;
 use rsa::sha2::{Digest, Sha256};
 use rsa::signature::hazmat::PrehashVerifier;
 use rsa::RsaPublicKey;
+use std::fmt;
 
 use super::parsers::{
     build_canonicalized_dkim_header_relaxed,
+    build_canonicalized_dkim_header_simple,
     canonicalize_body_relaxed,
+    canonicalize_body_simple,
     canonicalize_headers_relaxed,
+    canonicalize_headers_simple,
+    parse_dkim_canonicalization,
     parse_dkim_tags,
     parse_headers,
     split_headers_body,
+    strip_bom,
+    Canonicalization,
 };
 
+/// Why no DKIM-Signature on a message verified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DkimError {
+    /// Nothing verified and at least one signature used an algorithm that is
+    /// refused outright (`rsa-sha1`), rather than merely not implemented.
+    UnsupportedAlgorithm(String),
+    /// No DKIM-Signature verified.
+    VerificationFailed,
+}
+
+impl fmt::Display for DkimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DkimError::UnsupportedAlgorithm(detail) => {
+                write!(f, "unsupported DKIM algorithm {detail}")
+            }
+            DkimError::VerificationFailed => f.write_str("DKIM verification failed"),
+        }
+    }
+}
+
+/// The DKIM-Signature that verified: its `d=` domain and `s=` selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedDkimSignature {
+    pub domain: String,
+    pub selector: String,
+}
+
 pub fn verify_dkim(email_blob: &str, dns_records: &[String]) -> bool {
-    let (raw_headers, body) = split_headers_body(email_blob);
+    verify_dkim_signature(email_blob, dns_records).is_ok()
+}
+
+/// Like `verify_dkim`, but every DKIM-Signature on the message must verify.
+pub fn verify_dkim_all(email_blob: &str, dns_records: &[String]) -> bool {
+    verify_all_dkim_signatures(email_blob, dns_records).is_ok()
+}
+
+/// A DKIM key TXT record tagged with the selector and domain it was fetched for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DkimKeyRecord {
+    pub selector: String,
+    pub domain: String,
+    pub record: String,
+}
+
+/// Like `verify_dkim`, but returns which signature verified.
+///
+/// Every signature is checked against every record in `dns_records`; use
+/// `verify_dkim_with_keys` when records for several selectors are available.
+pub fn verify_dkim_signature(
+    email_blob: &str,
+    dns_records: &[String],
+) -> Result<VerifiedDkimSignature, DkimError> {
+    verify_signatures(email_blob, false, |_, _| {
+        public_key_from_records(dns_records.iter().map(String::as_str))
+    })
+}
+
+/// Like `verify_dkim_signature`, but fails unless every DKIM-Signature
+/// verifies; returns the first one.
+pub fn verify_all_dkim_signatures(
+    email_blob: &str,
+    dns_records: &[String],
+) -> Result<VerifiedDkimSignature, DkimError> {
+    verify_signatures(email_blob, true, |_, _| {
+        public_key_from_records(dns_records.iter().map(String::as_str))
+    })
+}
+
+/// Like `verify_dkim_signature`, but each signature is only checked against the
+/// key records fetched for its own `d=` domain and `s=` selector.
+pub fn verify_dkim_with_keys(
+    email_blob: &str,
+    keys: &[DkimKeyRecord],
+) -> Result<VerifiedDkimSignature, DkimError> {
+    verify_signatures(email_blob, false, |domain, selector| key_for_selector(keys, domain, selector))
+}
+
+/// Like `verify_dkim_with_keys`, but fails unless every DKIM-Signature
+/// verifies against its own selector's records; returns the first one.
+pub fn verify_all_dkim_signatures_with_keys(
+    email_blob: &str,
+    keys: &[DkimKeyRecord],
+) -> Result<VerifiedDkimSignature, DkimError> {
+    verify_signatures(email_blob, true, |domain, selector| key_for_selector(keys, domain, selector))
+}
+
+fn key_for_selector(keys: &[DkimKeyRecord], domain: &str, selector: &str) -> Option<RsaPublicKey> {
+    public_key_from_records(
+        keys.iter()
+            .filter(|k| {
+                k.domain.eq_ignore_ascii_case(domain) && k.selector.eq_ignore_ascii_case(selector)
+            })
+            .map(|k| k.record.as_str()),
+    )
+}
+
+/// Verify against `public_key` directly, skipping the DNS key records. For
+/// tests and off-chain tooling only; not compiled into the contract WASM.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn verify_dkim_with_explicit_key(email_blob: &str, public_key: &RsaPublicKey) -> bool {
+    verify_signatures(email_blob, false, |_, _| Some(public_key.clone())).is_ok()
+}
+
+/// Shared verification loop; `key_for(domain, selector)` returns the public key
+/// a signature is checked against. With `require_all`, every signature must
+/// verify rather than any one.
+fn verify_signatures(
+    email_blob: &str,
+    require_all: bool,
+    key_for: impl Fn(&str, &str) -> Option<RsaPublicKey>,
+) -> Result<VerifiedDkimSignature, DkimError> {
+    let (raw_headers, body) = split_headers_body(strip_bom(email_blob));
     let headers = parse_headers(raw_headers);
 
     // Collect all DKIM-Signature header values (support multiple signatures).
-    let dkim_values: Vec<String> = headers
+    let dkim_values: Vec<(String, String)> = headers
         .iter()
         .filter(|(name, _)| name.eq_ignore_ascii_case("DKIM-Signature"))
-        .map(|(_, v)| v.clone())
+        .cloned()
         .collect();
 
     if dkim_values.is_empty() {
-        return false;
+        return Err(DkimError::VerificationFailed);
     }
 
-    // Try each DKIM-Signature in turn; accept if any one verifies.
-    'signatures: for dkim_value in dkim_values {
+    let signature_count = dkim_values.len();
+    let mut verified = Vec::new();
+    let mut rejected = None;
+
+    // Try each DKIM-Signature in turn; accept if any one verifies (or, with
+    // `require_all`, once all of them have).
+    'signatures: for (dkim_name, dkim_value) in dkim_values {
         let tags = parse_dkim_tags(&dkim_value);
 
         // Tighten DKIM-Signature tag validation.
@@ -50,19 +174,23 @@ pub fn verify_dkim(email_blob: &str, dns_records: &[String]) -> bool {
             _ => continue 'signatures,
         };
 
-        let _ = (d, s); // suppress unused warnings; values are only used conceptually.
-
-        // Algorithm: support only rsa-sha256 for now.
+        // Algorithm: support only rsa-sha256 for now. SHA-1 is broken for
+        // signatures, so rsa-sha1 is never accepted even if the key allows it.
         match tags.get("a").map(String::as_str) {
             Some("rsa-sha256") => {}
+            Some(a) if a.eq_ignore_ascii_case("rsa-sha1") => {
+                rejected = Some(DkimError::UnsupportedAlgorithm(format!("{a}: sha1 disallowed")));
+                continue 'signatures;
+            }
             _ => continue 'signatures,
         }
 
-        // Canonicalization: support only relaxed/relaxed for now.
-        let canon = tags.get("c").map(String::as_str).unwrap_or("simple/simple");
-        if canon != "relaxed/relaxed" {
-            continue 'signatures;
-        }
+        // Canonicalization (c=): header/body, defaulting per RFC 6376 §3.5.
+        let (header_canon, body_canon) =
+            match parse_dkim_canonicalization(tags.get("c").map(String::as_str)) {
+                Some(c) => c,
+                None => continue 'signatures,
+            };
 
         // Body hash (bh=).
         let bh_b64 = match tags.get("bh") {
@@ -102,8 +230,11 @@ pub fn verify_dkim(email_blob: &str, dns_records: &[String]) -> bool {
         let signed_headers: Vec<String> =
             h_list.split(':').map(|s| s.trim().to_ascii_lowercase()).collect();
 
-        // Body canonicalization (relaxed) and l= handling.
-        let canon_body = canonicalize_body_relaxed(body);
+        // Body canonicalization and l= handling.
+        let canon_body = match body_canon {
+            Canonicalization::Simple => canonicalize_body_simple(body),
+            Canonicalization::Relaxed => canonicalize_body_relaxed(body),
+        };
         let body_bytes = canon_body.as_bytes();
         let body_to_hash: &[u8] = if let Some(l_str) = tags.get("l") {
             let l_val = match l_str.parse::<u128>() {
@@ -126,54 +257,28 @@ pub fn verify_dkim(email_blob: &str, dns_records: &[String]) -> bool {
         }
 
         // Header canonicalization.
-        let canon_headers = canonicalize_headers_relaxed(&headers, &signed_headers);
-        let canon_dkim_header = build_canonicalized_dkim_header_relaxed(&dkim_value);
-        let mut data = canon_headers;
-        data.push_str(&canon_dkim_header);
+        let data = match header_canon {
+            Canonicalization::Simple => {
+                let mut data = canonicalize_headers_simple(&headers, &signed_headers);
+                data.push_str(&build_canonicalized_dkim_header_simple(&dkim_name, &dkim_value));
+                data
+            }
+            Canonicalization::Relaxed => {
+                let mut data = canonicalize_headers_relaxed(&headers, &signed_headers);
+                data.push_str(&build_canonicalized_dkim_header_relaxed(&dkim_value));
+                data
+            }
+        };
 
         let mut hasher = Sha256::new();
         hasher.update(data.as_bytes());
         let data_hash = hasher.finalize().to_vec();
 
-        // Extract RSA public key bytes from the DKIM DNS records (p= tag).
-        let mut pk_bytes_opt = None;
-        for rec in dns_records {
-            let key_tags = parse_dkim_tags(rec);
-
-            // Tighten key-record validation.
-            if let Some(v) = key_tags.get("v") {
-                if v != "DKIM1" {
-                    continue;
-                }
-            }
-            if let Some(k) = key_tags.get("k") {
-                if k.to_ascii_lowercase() != "rsa" {
-                    continue;
-                }
-            }
-
-            if let Some(p) = key_tags.get("p") {
-                if p.is_empty() {
-                    // Explicitly revoked key; skip.
-                    continue;
-                }
-                if let Ok(bytes) = base64::decode(p) {
-                    pk_bytes_opt = Some(bytes);
-                    break;
-                }
-            }
-        }
-        let pk_bytes = match pk_bytes_opt {
-            Some(v) => v,
+        let public_key = match key_for(d, s) {
+            Some(k) => k,
             None => continue 'signatures,
         };
 
-        // Interpret the DKIM p= value as a DER-encoded SubjectPublicKeyInfo (SPKI).
-        let public_key = match RsaPublicKey::from_public_key_der(&pk_bytes) {
-            Ok(k) => k,
-            Err(_) => continue 'signatures,
-        };
-
         // Verify RSASSA-PKCS1-v1_5 with SHA-256 over the canonicalized data.
         let verifying_key = VerifyingKey::<Sha256>::new(public_key);
         let sig = match RsaSignature::try_from(signature.as_slice()) {
@@ -182,11 +287,65 @@ pub fn verify_dkim(email_blob: &str, dns_records: &[String]) -> bool {
         };
 
         if verifying_key.verify_prehash(&data_hash, &sig).is_ok() {
-            return true;
+            let signature = VerifiedDkimSignature {
+                domain: d.clone(),
+                selector: s.clone(),
+            };
+            if !require_all {
+                return Ok(signature);
+            }
+            verified.push(signature);
         }
     }
 
-    false
+    if require_all && verified.len() == signature_count {
+        return Ok(verified.swap_remove(0));
+    }
+    Err(rejected.unwrap_or(DkimError::VerificationFailed))
+}
+
+/// The RSA public key from the first usable DKIM key record (`p=` tag).
+fn public_key_from_records<'a>(records: impl IntoIterator<Item = &'a str>) -> Option<RsaPublicKey> {
+    let mut pk_bytes_opt = None;
+    for rec in records {
+        let key_tags = parse_dkim_tags(rec);
+
+        // Tighten key-record validation.
+        if let Some(v) = key_tags.get("v") {
+            if v != "DKIM1" {
+                continue;
+            }
+        }
+        if let Some(k) = key_tags.get("k") {
+            if k.to_ascii_lowercase() != "rsa" {
+                continue;
+            }
+        }
+
+        if let Some(p) = key_tags.get("p") {
+            if p.is_empty() {
+                // Explicitly revoked key; skip.
+                continue;
+            }
+            if let Ok(bytes) = base64::decode(p) {
+                pk_bytes_opt = Some(bytes);
+                break;
+            }
+        }
+    }
+
+    // Interpret the DKIM p= value as DER (SPKI, or bare PKCS#1 as a fallback).
+    parse_rsa_public_key(&pk_bytes_opt?).ok()
+}
+
+/// Parse a DKIM `p=` key: DER-encoded SubjectPublicKeyInfo (RFC 6376), falling
+/// back to a bare PKCS#1 `RSAPublicKey`, which some DNS records publish instead.
+pub fn parse_rsa_public_key(pk_bytes: &[u8]) -> Result<RsaPublicKey, String> {
+    RsaPublicKey::from_public_key_der(pk_bytes).or_else(|spki_err| {
+        RsaPublicKey::from_pkcs1_der(pk_bytes).map_err(|pkcs1_err| {
+            format!("invalid RSA public key (SPKI: {spki_err}; PKCS#1: {pkcs1_err})")
+        })
+    })
 }
 
 #[cfg(test)]
@@ -1,5 +1,5 @@
 use crate::{
-    ext_outlayer, ext_self,
+    email_timestamp_upper_bound_ms, ext_outlayer, ext_self, is_recovery_expired, log_request,
     EmailDkimVerifier, OutlayerInputArgs, VerificationResult,
     OutlayerWorkerResponse, MIN_DEPOSIT,
     OUTLAYER_CONTRACT_ID,
@@ -11,15 +11,14 @@ pub mod dkim;
 pub use parsers::parse_dkim_tags;
 
 use parsers::*;
+use crate::error_code::VerifyErrorCode;
 use near_sdk::serde_json::{self, json};
 use near_sdk::{env, AccountId, NearToken, Promise, PromiseError};
 
 #[derive(near_sdk::serde::Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 struct DnsLookupParams {
-    #[allow(dead_code)]
     selector: Option<String>,
-    #[allow(dead_code)]
     domain: Option<String>,
     #[allow(dead_code)]
     name: String,
@@ -27,15 +26,121 @@ struct DnsLookupParams {
     #[allow(dead_code)]
     record_type: String,
     records: Vec<String>,
+    /// Records tagged with the selector/domain they were fetched for.
+    /// Older workers omit this; see `dns_key_records`.
+    #[serde(default)]
+    keys: Vec<DnsKeyRecord>,
     error: Option<String>,
 }
 
+#[derive(near_sdk::serde::Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct DnsKeyRecord {
+    selector: String,
+    domain: String,
+    record: String,
+}
+
+/// Selector-tagged key records from a `get-dns-records` response. Falls back to
+/// tagging the flat `records` with the top-level selector/domain, and returns
+/// `None` when the worker gave no selector information at all.
+fn dns_key_records(dns_params: &DnsLookupParams) -> Option<Vec<dkim::DkimKeyRecord>> {
+    if !dns_params.keys.is_empty() {
+        return Some(
+            dns_params
+                .keys
+                .iter()
+                .map(|k| dkim::DkimKeyRecord {
+                    selector: k.selector.clone(),
+                    domain: k.domain.clone(),
+                    record: k.record.clone(),
+                })
+                .collect(),
+        );
+    }
+    let selector = dns_params.selector.as_ref()?;
+    let domain = dns_params.domain.as_ref()?;
+    Some(
+        dns_params
+            .records
+            .iter()
+            .map(|record| dkim::DkimKeyRecord {
+                selector: selector.clone(),
+                domain: domain.clone(),
+                record: record.clone(),
+            })
+            .collect(),
+    )
+}
+
+/// `records` tagged with the first DKIM-Signature's lowercased `d=` / `s=`,
+/// the selector they were pinned or supplied for. `None` when that signature
+/// names no domain or selector.
+fn first_signature_key_records(
+    email_blob: &str,
+    records: impl Fn(&str, &str) -> Option<Vec<String>>,
+) -> Option<Vec<dkim::DkimKeyRecord>> {
+    let tags = parse_dkim_tags(&extract_header_value(email_blob, "DKIM-Signature")?);
+    let domain = tags.get("d")?.trim().to_ascii_lowercase();
+    let selector = tags.get("s")?.trim().to_ascii_lowercase();
+    Some(
+        records(&domain, &selector)?
+            .into_iter()
+            .map(|record| dkim::DkimKeyRecord {
+                selector: selector.clone(),
+                domain: domain.clone(),
+                record,
+            })
+            .collect(),
+    )
+}
+
+/// Pinned key records for the first DKIM-Signature's `d=` / `s=`, if the owner
+/// has pinned that selector.
+fn pinned_key_records(
+    contract: &EmailDkimVerifier,
+    email_blob: &str,
+) -> Option<Vec<dkim::DkimKeyRecord>> {
+    first_signature_key_records(email_blob, |domain, selector| {
+        contract.pinned_keys.get(&(domain.to_string(), selector.to_string())).cloned()
+    })
+}
+
+/// Panics unless some `To:` address is in the owner's `expected_to_domain`
+/// (when one is set), before the email costs an Outlayer call.
+fn assert_addressed_to_expected_domain(contract: &EmailDkimVerifier, email_blob: &str) {
+    if let Some(domain) = contract.expected_to_domain.as_deref() {
+        let addressed = parse_to_addresses(email_blob)
+            .iter()
+            .any(|address| address_in_domain(address, domain));
+        if !addressed {
+            env::panic_str(&format!("email_blob is not addressed to {domain}"));
+        }
+    }
+}
+
+/// The `request_id` from a `recover-<id> ...` Subject, or empty.
+fn email_request_id(email_blob: &str) -> String {
+    extract_header_value(email_blob, "Subject")
+        .as_deref()
+        .and_then(parsers::parse_recover_request_id)
+        .unwrap_or_default()
+}
+
 /// Internal helper: on-chain DKIM verification request path.
 pub fn request_email_verification_onchain_inner(
     contract: &mut EmailDkimVerifier,
     payer_account_id: AccountId,
     email_blob: String,
 ) -> Promise {
+    // Reject blobs that cannot verify before the deposit pays for Outlayer.
+    assert!(!email_blob.trim().is_empty(), "email_blob must not be empty");
+    assert!(
+        extract_header_value(&email_blob, "DKIM-Signature").is_some(),
+        "email_blob has no DKIM-Signature header"
+    );
+    assert_addressed_to_expected_domain(contract, &email_blob);
+
     let caller = env::predecessor_account_id();
     let attached = env::attached_deposit().as_yoctonear();
     assert!(
@@ -47,10 +152,10 @@ pub fn request_email_verification_onchain_inner(
     let refund = attached.saturating_sub(outlayer_deposit);
 
     if refund > 0 {
-        env::log_str(&format!(
-            "Refunding {} yoctoNEAR of unused DKIM fees to {}",
-            refund, caller
-        ));
+        log_request(
+            &email_request_id(&email_blob),
+            &format!("Refunding {} yoctoNEAR of unused DKIM fees to {}", refund, caller),
+        );
         let _ = Promise::new(caller.clone()).transfer(NearToken::from_yoctonear(refund));
     }
 
@@ -111,6 +216,7 @@ pub fn request_email_verification_onchain_inner(
         )
         .then(
             ext_self::ext(env::current_account_id())
+                .with_static_gas(contract.callback_gas())
                 .with_unused_gas_weight(1)
                 .on_email_verification_onchain_result(caller, email_blob),
         )
@@ -118,208 +224,258 @@ pub fn request_email_verification_onchain_inner(
 
 /// Internal helper: on-chain DKIM verification callback path.
 pub fn on_email_verification_onchain_result(
-    _contract: &mut EmailDkimVerifier,
+    contract: &mut EmailDkimVerifier,
     requested_by: AccountId,
     email_blob: String,
     result: Result<Option<serde_json::Value>, PromiseError>,
 ) -> VerificationResult {
     let _ = requested_by;
-    let subject = extract_header_value(&email_blob, "Subject");
-    let request_id = subject.as_deref()
-        .and_then(parsers::parse_recover_request_id)
-        .unwrap_or_default();
+    let request_id = email_request_id(&email_blob);
 
     let value = match result {
         Ok(Some(v)) => v,
         _ => {
-            return VerificationResult::failure(&request_id, "outlayer_execution_failed");
+            return VerificationResult::failure(
+                &request_id,
+                VerifyErrorCode::OutlayerExecutionFailed,
+            );
         }
     };
 
     let worker_response: OutlayerWorkerResponse = match serde_json::from_value(value.clone()) {
         Ok(r) => r,
         Err(e) => {
-            env::log_str(&format!("Failed to parse worker response: {e}"));
-            return VerificationResult::failure(&request_id, "invalid_worker_response");
+            log_request(&request_id, &format!("Failed to parse worker response: {e}"));
+            return VerificationResult::failure(&request_id, VerifyErrorCode::InvalidWorkerResponse);
         }
     };
 
     if worker_response.method != GET_DNS_RECORDS_METHOD {
-        env::log_str(&format!(
-            "Unexpected worker method in on_email_verification_onchain_result: {}",
-            worker_response.method
-        ));
-        return VerificationResult::failure(
+        log_request(
             &request_id,
-            format!(
-            "unexpected_worker_method: {}",
-            worker_response.method
+            &format!(
+                "Unexpected worker method in on_email_verification_onchain_result: {}",
+                worker_response.method
             ),
         );
+        return VerificationResult::failure(
+            &request_id,
+            VerifyErrorCode::UnexpectedWorkerMethod.with_detail(&worker_response.method),
+        );
     }
 
     let dns_params: DnsLookupParams =
         match serde_json::from_value(worker_response.response.clone()) {
             Ok(p) => p,
             Err(e) => {
-                env::log_str(&format!("Failed to parse {GET_DNS_RECORDS_METHOD} response: {e}"));
-                return VerificationResult::failure(&request_id, "invalid_dns_response");
+                log_request(
+                    &request_id,
+                    &format!("Failed to parse {GET_DNS_RECORDS_METHOD} response: {e}"),
+                );
+                return VerificationResult::failure(
+                    &request_id,
+                    VerifyErrorCode::InvalidDnsResponse,
+                );
             }
         };
 
-    if let Some(err) = dns_params.error.as_deref() {
-        env::log_str(&format!("DKIM DNS fetch error: {err}"));
-        return VerificationResult::failure(&request_id, format!("dns_error: {err}"));
+    // Owner-pinned records for the email's `d=` / `s=` take the place of the
+    // DNS lookup result, even a failed or empty one.
+    if let Some(keys) = pinned_key_records(contract, &email_blob) {
+        let verified_signature = verify_with_keys(contract, &email_blob, &keys);
+        return build_verification_result(contract, &email_blob, request_id, verified_signature);
     }
 
-    let record_strings = dns_params.records;
+    if let Some(err) = dns_params.error.as_deref() {
+        log_request(&request_id, &format!("DKIM DNS fetch error: {err}"));
+        return VerificationResult::failure(&request_id, VerifyErrorCode::DnsError.with_detail(err));
+    }
 
-    if record_strings.is_empty() {
-        return VerificationResult::failure(&request_id, "dns_records_empty");
+    if dns_params.records.is_empty() && dns_params.keys.is_empty() {
+        return VerificationResult::failure(&request_id, VerifyErrorCode::DnsRecordsEmpty);
     }
 
-    let verified = dkim::verify_dkim(&email_blob, &record_strings);
+    // Match each signature only against the records for its own selector when
+    // the worker tells us which selector/domain the records came from.
+    let verified_signature = match dns_key_records(&dns_params) {
+        Some(keys) => verify_with_keys(contract, &email_blob, &keys),
+        None => verify_with_records(contract, &email_blob, &dns_params.records),
+    };
+    build_verification_result(contract, &email_blob, request_id, verified_signature)
+}
 
-    if !verified {
-        return VerificationResult::failure(&request_id, "dkim_verification_failed");
+/// Internal helper: on-chain DKIM verification against caller-supplied key
+/// records, skipping the Outlayer DNS lookup. Only available while the owner
+/// has enabled `allow_inline_dns`; any attached deposit is refunded.
+pub fn verify_email_with_inline_dns_records(
+    contract: &mut EmailDkimVerifier,
+    email_blob: &str,
+    dns_records: &[String],
+) -> VerificationResult {
+    if !contract.allow_inline_dns {
+        env::panic_str("Inline DNS records are disabled on this contract");
     }
+    assert_addressed_to_expected_domain(contract, email_blob);
 
-    let subject = extract_header_value(&email_blob, "Subject");
+    let request_id = email_request_id(email_blob);
 
-    // Primary: parse both account_id and key from the Subject line.
-    let (account_id, new_public_key) = if let Some(s) = subject.as_deref() {
-        if let Some((acc, pk)) = parse_recover_instruction(s) {
-            (acc.to_string(), pk)
-        } else {
-            let acc = parse_recover_subject(s)
-                .map(|a| a.to_string())
-                .unwrap_or_default();
-            let pk = parse_recover_public_key_from_body(&email_blob).unwrap_or_default();
-            (acc, pk)
-        }
-    } else {
-        let pk = parse_recover_public_key_from_body(&email_blob).unwrap_or_default();
-        (String::new(), pk)
-    };
+    let attached = env::attached_deposit().as_yoctonear();
+    if attached > 0 {
+        let caller = env::predecessor_account_id();
+        log_request(
+            &request_id,
+            &format!("Refunding {} yoctoNEAR of unused DKIM fees to {}", attached, caller),
+        );
+        let _ = Promise::new(caller).transfer(NearToken::from_yoctonear(attached));
+    }
 
-    let email_timestamp_ms = parse_email_timestamp_ms(&email_blob);
-    let from_address_hash = compute_from_address_hash(&email_blob, &account_id);
+    if dns_records.is_empty() {
+        return VerificationResult::failure(&request_id, VerifyErrorCode::DnsRecordsEmpty);
+    }
 
-    let vr = VerificationResult {
-        verified: true,
-        account_id,
-        new_public_key,
-        from_address_hash,
-        email_timestamp_ms,
-        request_id: request_id.clone(),
-        error: None,
+    // The records were supplied for the first signature's selector; no other
+    // signature is checked against them.
+    let keys = first_signature_key_records(email_blob, |_, _| Some(dns_records.to_vec()));
+    let verified_signature = match keys {
+        Some(keys) => verify_with_keys(contract, email_blob, &keys),
+        None => Err(dkim::DkimError::VerificationFailed),
     };
-    vr
+    build_verification_result(contract, email_blob, request_id, verified_signature)
 }
 
-fn compute_from_address_hash(email_blob: &str, account_id: &str) -> Vec<u8> {
-    let from_header = extract_header_value(email_blob, "From").unwrap_or_default();
-    let canonical_from = canonicalize_email_address(&from_header);
-    let salt = account_id.trim().to_lowercase();
-    if canonical_from.is_empty() || salt.is_empty() {
-        return Vec::new();
+/// `dkim::verify_dkim_with_keys` under the contract's signature policy.
+fn verify_with_keys(
+    contract: &EmailDkimVerifier,
+    email_blob: &str,
+    keys: &[dkim::DkimKeyRecord],
+) -> Result<dkim::VerifiedDkimSignature, dkim::DkimError> {
+    if contract.require_all_signatures {
+        dkim::verify_all_dkim_signatures_with_keys(email_blob, keys)
+    } else {
+        dkim::verify_dkim_with_keys(email_blob, keys)
     }
-    let input = format!("{canonical_from}|{salt}");
-    env::sha256(input.as_bytes())
 }
 
-fn canonicalize_email_address(input: &str) -> String {
-    let raw = input.trim();
-    if raw.is_empty() {
-        return String::new();
+/// `dkim::verify_dkim_signature` under the contract's signature policy.
+fn verify_with_records(
+    contract: &EmailDkimVerifier,
+    email_blob: &str,
+    dns_records: &[String],
+) -> Result<dkim::VerifiedDkimSignature, dkim::DkimError> {
+    if contract.require_all_signatures {
+        dkim::verify_all_dkim_signatures(email_blob, dns_records)
+    } else {
+        dkim::verify_dkim_signature(email_blob, dns_records)
     }
+}
 
-    // Strip leading "Header-Name:" when present (e.g. "From: ...").
-    let without_header_name = if let Some(colon_idx) = raw.find(':') {
-        let (prefix, rest) = raw.split_at(colon_idx);
-        let prefix = prefix.trim();
-        if !prefix.is_empty()
-            && prefix
-                .chars()
-                .all(|c| c.is_ascii_alphanumeric() || c == '-')
-        {
-            rest[1..].trim_start()
-        } else {
-            raw
+/// Shared tail of both on-chain paths: map the DKIM outcome to a
+/// `VerificationResult` and parse the recovery instruction from the email.
+fn build_verification_result(
+    contract: &EmailDkimVerifier,
+    email_blob: &str,
+    request_id: String,
+    verified_signature: Result<dkim::VerifiedDkimSignature, dkim::DkimError>,
+) -> VerificationResult {
+    // Rejected whatever the DKIM outcome: a second From: can ride along with
+    // a valid signature over the other one.
+    if has_multiple_from_headers(email_blob) {
+        return VerificationResult::failure(&request_id, VerifyErrorCode::MultipleFromHeaders);
+    }
+
+    let verified_signature = match verified_signature {
+        Ok(sig) => sig,
+        Err(dkim::DkimError::UnsupportedAlgorithm(detail)) => {
+            log_request(&request_id, &format!("DKIM signature rejected: {detail}"));
+            return VerificationResult::failure(
+                &request_id,
+                VerifyErrorCode::UnsupportedAlgorithm.with_detail(detail),
+            );
+        }
+        Err(dkim::DkimError::VerificationFailed) => {
+            return VerificationResult::failure(
+                &request_id,
+                VerifyErrorCode::DkimVerificationFailed,
+            );
         }
-    } else {
-        raw
     };
 
-    // Prefer the common "Name <email@domain>" format.
-    let mut candidates: [&str; 2] = ["", without_header_name];
-    if let Some(start) = without_header_name.find('<') {
-        if let Some(end_rel) = without_header_name[start + 1..].find('>') {
-            let end = start + 1 + end_rel;
-            candidates[0] = &without_header_name[start + 1..end];
+    // A list that rewrote From: signs as itself, so a verified signature says
+    // nothing about the original author.
+    if contract.reject_mailing_list_emails {
+        let signals = mailing_list_signals(email_blob);
+        if !signals.is_empty() {
+            return VerificationResult::failure(
+                &request_id,
+                VerifyErrorCode::MailingListMessage.with_detail(signals.join(", ")),
+            );
         }
     }
 
-    for candidate in candidates.iter().copied() {
-        let candidate = candidate.trim();
-        if candidate.is_empty() {
-            continue;
-        }
+    let subject = extract_header_value(email_blob, "Subject");
 
-        let candidate = if candidate.len() >= 7 && candidate[..7].eq_ignore_ascii_case("mailto:") {
-            candidate[7..].trim_start()
+    // Primary: parse account_id, key and optional expiry from the Subject line.
+    let (account_id, new_public_key, recovery_expires_at_secs) = if let Some(s) = subject.as_deref() {
+        if let Some((acc, pk, exp)) = parse_recover_instruction(s) {
+            (acc.to_string(), pk, exp)
         } else {
-            candidate
+            let acc = parse_recover_subject(s)
+                .map(|a| a.to_string())
+                .unwrap_or_default();
+            let pk = match parse_unambiguous_recover_public_key_from_body(email_blob) {
+                Ok(pk) => pk.unwrap_or_default(),
+                Err(e) => return VerificationResult::failure(&request_id, e),
+            };
+            (acc, pk, None)
+        }
+    } else {
+        let pk = match parse_unambiguous_recover_public_key_from_body(email_blob) {
+            Ok(pk) => pk.unwrap_or_default(),
+            Err(e) => return VerificationResult::failure(&request_id, e),
         };
+        (String::new(), pk, None)
+    };
 
-        if let Some(found) = extract_email_like(candidate) {
-            return found.to_lowercase();
-        }
+    if recovery_expires_at_secs.is_some_and(is_recovery_expired) {
+        return VerificationResult::failure(&request_id, VerifyErrorCode::RecoveryExpired);
     }
 
-    without_header_name.to_lowercase()
-}
-
-fn extract_email_like(input: &str) -> Option<&str> {
-    let bytes = input.as_bytes();
-    for (idx, b) in bytes.iter().enumerate() {
-        if *b != b'@' {
-            continue;
-        }
-
-        let mut start = idx;
-        while start > 0 && is_email_local_byte(bytes[start - 1]) {
-            start -= 1;
-        }
-
-        let mut end = idx + 1;
-        while end < bytes.len() && (is_email_domain_byte(bytes[end]) || bytes[end] == b'.') {
-            end += 1;
-        }
-
-        if start == idx || end == idx + 1 {
-            continue;
-        }
-
-        // Domain must not end with '.'.
-        if bytes[end - 1] == b'.' {
-            continue;
-        }
+    let allow_resent = contract.allow_resent_headers;
+    let email_timestamp_ms = parse_email_timestamp_ms_with_resent_fallback(
+        email_blob,
+        allow_resent,
+        Some(email_timestamp_upper_bound_ms()),
+    );
+    let from_address_hash = compute_from_address_hash(email_blob, &account_id, allow_resent);
 
-        return Some(&input[start..end]);
-    }
-    None
+    let vr = VerificationResult {
+        verified: true,
+        account_id,
+        new_public_key,
+        from_address_hash,
+        email_timestamp_ms,
+        request_id,
+        selector: verified_signature.selector,
+        signing_domain: verified_signature.domain.to_ascii_lowercase(),
+        error: None,
+    };
+    vr
 }
 
-fn is_email_local_byte(b: u8) -> bool {
-    matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9')
-        || matches!(
-            b,
-            b'.' | b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' | b'+' | b'/' | b'=' | b'?' | b'^'
-                | b'_' | b'`' | b'{' | b'|' | b'}' | b'~' | b'-'
-        )
+fn compute_from_address_hash(email_blob: &str, account_id: &str, allow_resent: bool) -> Vec<u8> {
+    let from_header = extract_sender_header(email_blob, allow_resent).unwrap_or_default();
+    compute_from_address_hash_for_header(&from_header, account_id)
 }
 
-fn is_email_domain_byte(b: u8) -> bool {
-    matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-')
+/// `sha256("<canonical_from>|<account_id_lower>")` for a raw `From:` header value
+/// (with or without the `From:` prefix). Returns an empty vec when either the
+/// canonical address or the account id is empty.
+pub(crate) fn compute_from_address_hash_for_header(from_header: &str, account_id: &str) -> Vec<u8> {
+    let canonical_from = canonicalize_email_address(from_header);
+    let salt = account_id.trim().to_lowercase();
+    if canonical_from.is_empty() || salt.is_empty() {
+        return Vec::new();
+    }
+    let input = format!("{canonical_from}|{salt}");
+    env::sha256(input.as_bytes())
 }
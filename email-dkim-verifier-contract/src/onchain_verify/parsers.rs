@@ -55,11 +55,13 @@ pub fn parse_recover_subject(subject: &str) -> Option<AccountId> {
     Some(account_id)
 }
 
-/// Parse both account_id and public key from a recovery Subject header.
+/// Parse account_id, public key and optional expiry from a recovery Subject header.
 ///
 /// Expected primary format:
-///   "recover-<request_id> <account_id> ed25519:<public_key>"
-pub fn parse_recover_instruction(subject: &str) -> Option<(AccountId, String)> {
+///   "recover-<request_id> <account_id> ed25519:<public_key> [exp=<unix_secs>]"
+/// The `exp=` token is optional; when present but not a valid integer the
+/// instruction is rejected.
+pub fn parse_recover_instruction(subject: &str) -> Option<(AccountId, String, Option<u64>)> {
     let subject = subject.trim();
     let mut parts = subject.split_whitespace();
 
@@ -77,17 +79,22 @@ pub fn parse_recover_instruction(subject: &str) -> Option<(AccountId, String)> {
         Err(_) => return None,
     };
 
-    // Scan remaining tokens for an ed25519:<pk> token.
+    // Scan remaining tokens for an ed25519:<pk> token and an optional exp=<secs>.
     let mut new_public_key: Option<String> = None;
+    let mut expires_at_secs: Option<u64> = None;
     for token in parts {
-        if token.starts_with("ed25519:") && token.len() > "ed25519:".len() {
+        if let Some(exp) = token.strip_prefix("exp=") {
+            expires_at_secs = Some(exp.parse().ok()?);
+        } else if new_public_key.is_none()
+            && token.starts_with("ed25519:")
+            && token.len() > "ed25519:".len()
+        {
             new_public_key = Some(token.to_string());
-            break;
         }
     }
 
     let new_public_key = new_public_key?;
-    Some((account_id, new_public_key))
+    Some((account_id, new_public_key, expires_at_secs))
 }
 
 /// Parse the short request_id from a recovery Subject header.
@@ -109,7 +116,55 @@ pub fn parse_recover_request_id(subject: &str) -> Option<String> {
     None
 }
 
-pub fn parse_dkim_tags(value: &str) -> std::collections::HashMap<String, String> {
+/// A DKIM tag value, remembering whether it was written as a quoted string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DkimTagValue {
+    Plain(String),
+    /// Written as `"..."`; holds the value with the quotes stripped and `\"`
+    /// and `\\` unescaped.
+    Quoted(String),
+}
+
+impl DkimTagValue {
+    fn parse(raw: &str) -> Self {
+        match raw.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(inner) => DkimTagValue::Quoted(unescape_quoted_string(inner)),
+            None => DkimTagValue::Plain(raw.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            DkimTagValue::Plain(v) | DkimTagValue::Quoted(v) => v,
+        }
+    }
+}
+
+/// Undo `\"` and `\\` escapes; any other backslash is kept as is.
+fn unescape_quoted_string(inner: &str) -> String {
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(escaped @ ('"' | '\\')) => out.push(escaped),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// DKIM `tag=value` pairs with lowercased tag names. Quoted-string values
+/// (`z=`, some `n=` notes) keep track of their quoting; see `parse_dkim_tags`
+/// for plain strings.
+pub fn parse_dkim_tag_values(value: &str) -> std::collections::HashMap<String, DkimTagValue> {
     let mut tags = std::collections::HashMap::new();
     let unfolded = value.replace("\r\n", " ");
     for part in unfolded.split(';') {
@@ -120,13 +175,53 @@ pub fn parse_dkim_tags(value: &str) -> std::collections::HashMap<String, String>
         if let Some(pos) = part.find('=') {
             let (k, v) = part.split_at(pos);
             let key = k.trim().to_ascii_lowercase();
-            let val = v[1..].trim().to_string();
-            tags.insert(key, val);
+            tags.insert(key, DkimTagValue::parse(v[1..].trim()));
         }
     }
     tags
 }
 
+/// Like `parse_dkim_tag_values`, with quoted values already unquoted.
+pub fn parse_dkim_tags(value: &str) -> std::collections::HashMap<String, String> {
+    parse_dkim_tag_values(value)
+        .into_iter()
+        .map(|(key, value)| (key, value.as_str().to_string()))
+        .collect()
+}
+
+/// A DKIM canonicalization algorithm (RFC 6376 §3.4).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Canonicalization {
+    Simple,
+    Relaxed,
+}
+
+/// Header and body algorithms from a DKIM `c=` tag value (RFC 6376 §3.5).
+/// An absent tag means `simple/simple`, and a lone header algorithm (`relaxed`
+/// or `relaxed/`) implies `simple` for the body. `None` for unknown algorithms.
+pub fn parse_dkim_canonicalization(
+    c: Option<&str>,
+) -> Option<(Canonicalization, Canonicalization)> {
+    let algorithm = |name: &str| match name.trim().to_ascii_lowercase().as_str() {
+        "simple" | "" => Some(Canonicalization::Simple),
+        "relaxed" => Some(Canonicalization::Relaxed),
+        _ => None,
+    };
+    let c = c.unwrap_or("simple/simple");
+    let (header, body) = c.split_once('/').unwrap_or((c, ""));
+    if header.trim().is_empty() {
+        return None;
+    }
+    Some((algorithm(header)?, algorithm(body)?))
+}
+
+/// Remove a leading UTF-8 byte-order mark (`EF BB BF`), which some mail
+/// clients prepend to the raw message and which would otherwise glue itself
+/// onto the first header name.
+pub fn strip_bom(email: &str) -> &str {
+    email.strip_prefix('\u{feff}').unwrap_or(email)
+}
+
 pub fn split_headers_body(email: &str) -> (&str, &str) {
     if let Some(idx) = email.find("\r\n\r\n") {
         let (h, rest) = email.split_at(idx);
@@ -221,14 +316,15 @@ pub fn canonicalize_headers_relaxed(
                 continue;
             }
             let (name, _) = &headers[idx];
-            if name.eq_ignore_ascii_case(signed) {
+            if name.trim_end().eq_ignore_ascii_case(signed.trim()) {
                 selected = Some(idx);
                 break;
             }
         }
         if let Some(idx) = selected {
             let (name, value) = &headers[idx];
-            result.push_str(&name.to_ascii_lowercase());
+            // Relaxed drops whitespace before the colon (RFC 6376 §3.4.2).
+            result.push_str(&name.trim_end().to_ascii_lowercase());
             result.push(':');
             result.push_str(&canonicalize_header_relaxed(value.clone()));
             result.push_str("\r\n");
@@ -239,6 +335,35 @@ pub fn canonicalize_headers_relaxed(
     result
 }
 
+/// Simple header canonicalization (RFC 6376 §3.4.1): each signed field is
+/// emitted exactly as it appears, original name case and whitespace included;
+/// only folding line endings are normalized to CRLF (`parse_headers` already
+/// rejoins continuation lines with `\r\n`).
+pub fn canonicalize_headers_simple(
+    headers: &[(String, String)],
+    signed_headers: &[String],
+) -> String {
+    let mut result = String::new();
+    let mut used = vec![false; headers.len()];
+
+    // Same bottom-up selection as the relaxed variant (RFC 6376 §5.4.2).
+    for signed in signed_headers {
+        let selected = (0..headers.len()).rev().find(|&idx| {
+            !used[idx] && headers[idx].0.trim_end().eq_ignore_ascii_case(signed.trim())
+        });
+        if let Some(idx) = selected {
+            let (name, value) = &headers[idx];
+            result.push_str(name);
+            result.push(':');
+            result.push_str(value);
+            result.push_str("\r\n");
+            used[idx] = true;
+        }
+    }
+
+    result
+}
+
 pub fn canonicalize_body_relaxed(body: &str) -> String {
     // Implement relaxed body canonicalization per RFC 6376:
     // - Convert all whitespace runs within lines to a single SP.
@@ -288,8 +413,99 @@ pub fn canonicalize_body_relaxed(body: &str) -> String {
     result
 }
 
-pub fn parse_email_timestamp_ms(email: &str) -> Option<u64> {
-    let date_value = extract_header_value(email, "Date")?;
+/// Simple body canonicalization (RFC 6376 §3.4.3): lines are kept verbatim,
+/// trailing empty lines are removed and the body ends with a single CRLF.
+pub fn canonicalize_body_simple(body: &str) -> String {
+    let mut lines: Vec<&str> = body
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+
+    while matches!(lines.last(), Some(l) if l.is_empty()) {
+        lines.pop();
+    }
+
+    // An empty body canonicalizes to a single CRLF.
+    let mut result = lines.join("\r\n");
+    result.push_str("\r\n");
+    result
+}
+
+/// `Date:` header as milliseconds since the Unix epoch. `None` when the header
+/// is missing or malformed, or when it lies after `upper_bound_ms` (a sender
+/// cannot date a message into the future to outlive freshness checks).
+pub fn parse_email_timestamp_ms(email: &str, upper_bound_ms: Option<u64>) -> Option<u64> {
+    parse_email_timestamp_ms_with_resent_fallback(email, false, upper_bound_ms)
+}
+
+/// Like `parse_email_timestamp_ms`, but when the `Date:` header is absent and
+/// `allow_resent_fallback` is set, uses `Resent-Date:` (the forward time).
+pub fn parse_email_timestamp_ms_with_resent_fallback(
+    email: &str,
+    allow_resent_fallback: bool,
+    upper_bound_ms: Option<u64>,
+) -> Option<u64> {
+    let ts_ms = match extract_header_value(email, "Date") {
+        Some(date_value) => parse_rfc2822_date_ms(&date_value),
+        None if allow_resent_fallback => {
+            parse_rfc2822_date_ms(&extract_header_value(email, "Resent-Date")?)
+        }
+        None => None,
+    }?;
+    upper_bound_ms.is_none_or(|bound| ts_ms <= bound).then_some(ts_ms)
+}
+
+/// More than one `From:` header, which RFC 5322 §3.6 forbids. DKIM signs the
+/// bottom-most instance while `extract_header_value` and mail clients may read
+/// another, so such messages are rejected rather than interpreted.
+pub fn has_multiple_from_headers(email: &str) -> bool {
+    parse_headers(split_headers_body(email).0)
+        .iter()
+        .filter(|(name, _)| name.trim_end().eq_ignore_ascii_case("From"))
+        .nth(1)
+        .is_some()
+}
+
+/// Signs that a mailing list relayed the message: `"list-id"`,
+/// `"list-unsubscribe"`, and `"sender"` when a `Sender:` address differs from
+/// the `From:` one. Lists often rewrite `From:` (e.g. `Alice via List`), so
+/// the signed sender may be the list rather than the author.
+pub fn mailing_list_signals(email: &str) -> Vec<&'static str> {
+    // `extract_header_value` does not stop at the blank line; a body line must
+    // not count as a header here.
+    let (headers, _) = split_headers_body(email);
+    let mut signals = Vec::new();
+    if extract_header_value(headers, "List-Id").is_some() {
+        signals.push("list-id");
+    }
+    if extract_header_value(headers, "List-Unsubscribe").is_some() {
+        signals.push("list-unsubscribe");
+    }
+    if let Some(sender) = extract_header_value(headers, "Sender") {
+        let from = extract_header_value(headers, "From").unwrap_or_default();
+        if canonicalize_email_address(&sender) != canonicalize_email_address(&from) {
+            signals.push("sender");
+        }
+    }
+    signals
+}
+
+/// `From:` header value, falling back to `Resent-From:` when `From:` is absent
+/// or empty and `allow_resent_fallback` is set. The fallback changes which
+/// identity is authenticated, so it is opt-in.
+pub fn extract_sender_header(email: &str, allow_resent_fallback: bool) -> Option<String> {
+    let from = extract_header_value(email, "From").filter(|v| !v.trim().is_empty());
+    if from.is_some() || !allow_resent_fallback {
+        return from;
+    }
+    extract_header_value(email, "Resent-From").filter(|v| !v.trim().is_empty())
+}
+
+/// Latest `Date:` year accepted. Far-future years are bogus for an email and
+/// would make the year-by-year day count below needlessly expensive.
+const MAX_EMAIL_YEAR: i32 = 9999;
+
+fn parse_rfc2822_date_ms(date_value: &str) -> Option<u64> {
     let date_str = date_value.trim();
 
     // Strip optional weekday prefix, e.g. "Wed, "
@@ -310,7 +526,7 @@ pub fn parse_email_timestamp_ms(email: &str) -> Option<u64> {
 
     let day: u32 = day_str.parse().ok()?;
     let year: i32 = year_str.parse().ok()?;
-    if year < 1970 {
+    if !(1970..=MAX_EMAIL_YEAR).contains(&year) {
         return None;
     }
 
@@ -334,19 +550,25 @@ pub fn parse_email_timestamp_ms(email: &str) -> Option<u64> {
     let hour: u32 = time_parts.next()?.parse().ok()?;
     let minute: u32 = time_parts.next()?.parse().ok()?;
     let second: u32 = time_parts.next()?.parse().ok()?;
+    // Allow a leap second (`:60`); anything else out of range is not a real time.
+    if hour >= 24 || minute >= 60 || second > 60 {
+        return None;
+    }
 
     // Parse numeric zone offset of the form "+HHMM" or "-HHMM".
     if offset_str.len() < 3 {
         return None;
     }
-    let sign = match &offset_str[0..1] {
+    let sign = match offset_str.get(..1)? {
         "+" => 1i64,
         "-" => -1i64,
         _ => return None,
     };
-    let (off_hour_str, off_min_str) = offset_str[1..].split_at(2);
-    let off_hour: i64 = off_hour_str.parse().ok()?;
-    let off_min: i64 = off_min_str.parse().ok()?;
+    let off_hour: i64 = offset_str.get(1..3)?.parse().ok()?;
+    let off_min: i64 = offset_str.get(3..)?.parse().ok()?;
+    if !(0..24).contains(&off_hour) || !(0..60).contains(&off_min) {
+        return None;
+    }
     let offset_sec = sign
         .checked_mul(off_hour.checked_mul(3600)? + off_min.checked_mul(60)?)?;
 
@@ -363,14 +585,31 @@ pub fn parse_email_timestamp_ms(email: &str) -> Option<u64> {
 }
 
 pub fn parse_recover_public_key_from_body(email: &str) -> Option<String> {
+    parse_all_recover_public_keys_from_body(email).into_iter().next()
+}
+
+/// Every `ed25519:<key>` line in the body, in order (duplicates included).
+pub fn parse_all_recover_public_keys_from_body(email: &str) -> Vec<String> {
     let (_, body) = split_headers_body(email);
-    for line in body.lines() {
-        let trimmed = line.trim();
-        if trimmed.starts_with("ed25519:") && trimmed.len() > "ed25519:".len() {
-            return Some(trimmed.to_string());
+    body.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("ed25519:") && line.len() > "ed25519:".len())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The body's recovery key when every `ed25519:` line carries the same key
+/// (`Ok(None)` when there is none). Quoted replies can put an old key above
+/// the new one, so differing keys are rejected with `"ambiguous_public_key"`.
+pub fn parse_unambiguous_recover_public_key_from_body(email: &str) -> Result<Option<String>, String> {
+    let mut keys = parse_all_recover_public_keys_from_body(email).into_iter();
+    let first = keys.next();
+    if let Some(first) = first.as_deref() {
+        if keys.any(|key| key != first) {
+            return Err("ambiguous_public_key".to_string());
         }
     }
-    None
+    Ok(first)
 }
 
 fn is_leap_year(year: i32) -> bool {
@@ -426,9 +665,19 @@ fn days_since_unix_epoch(year: i32, month: u32, day: u32) -> Option<i64> {
 }
 
 pub fn build_canonicalized_dkim_header_relaxed(value: &str) -> String {
-    // Locate the b= tag and remove its value (handling optional FWS),
-    // then apply relaxed header canonicalization to the resulting field value.
+    let canon_value = canonicalize_header_relaxed(strip_dkim_b_value(value));
+    format!("dkim-signature:{}", canon_value)
+}
 
+/// The DKIM-Signature field with an empty `b=` value under simple header
+/// canonicalization. `name` and `value` are the raw field name and value from
+/// `parse_headers`; simple canonicalization keeps the name exactly as sent.
+pub fn build_canonicalized_dkim_header_simple(name: &str, value: &str) -> String {
+    format!("{name}:{}", strip_dkim_b_value(value))
+}
+
+fn strip_dkim_b_value(value: &str) -> String {
+    // Locate the b= tag and remove its value (handling optional FWS).
     let bytes = value.as_bytes();
     let mut b_value_start: Option<usize> = None;
     let mut b_value_end: Option<usize> = None;
@@ -489,7 +738,7 @@ pub fn build_canonicalized_dkim_header_relaxed(value: &str) -> String {
         i += 1;
     }
 
-    let save = if let (Some(start), Some(end)) = (b_value_start, b_value_end) {
+    if let (Some(start), Some(end)) = (b_value_start, b_value_end) {
         // Build the DKIM value with an empty b= tag.
         let mut tmp = String::new();
         tmp.push_str(&value[..start]);
@@ -498,10 +747,187 @@ pub fn build_canonicalized_dkim_header_relaxed(value: &str) -> String {
     } else {
         // No b= tag detected; fall back to the original value.
         value.to_string()
+    }
+}
+
+/// Canonical form of an email address as stored and hashed by the contract:
+/// the first `local@domain` found (preferring `<...>`, after any `Header-Name:`
+/// and `mailto:` prefix), lowercased. Falls back to the lowercased input.
+///
+/// The worker keeps a byte-for-byte copy in `src/parsers.rs`; both are checked
+/// against `tests/data/email_address_vectors.tsv`.
+pub fn canonicalize_email_address(input: &str) -> String {
+    let raw = input.trim();
+    if raw.is_empty() {
+        return String::new();
+    }
+
+    // Strip leading "Header-Name:" when present (e.g. "From: ...").
+    let without_header_name = if let Some(colon_idx) = raw.find(':') {
+        let (prefix, rest) = raw.split_at(colon_idx);
+        let prefix = prefix.trim();
+        if !prefix.is_empty()
+            && prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            rest[1..].trim_start()
+        } else {
+            raw
+        }
+    } else {
+        raw
     };
 
-    let canon_value = canonicalize_header_relaxed(save);
-    format!("dkim-signature:{}", canon_value)
+    // Prefer the common "Name <email@domain>" format.
+    let mut candidates: [&str; 2] = ["", without_header_name];
+    if let Some(start) = without_header_name.find('<') {
+        if let Some(end_rel) = without_header_name[start + 1..].find('>') {
+            let end = start + 1 + end_rel;
+            candidates[0] = &without_header_name[start + 1..end];
+        }
+    }
+
+    for candidate in candidates {
+        let candidate = candidate.trim();
+        if candidate.is_empty() {
+            continue;
+        }
+
+        let candidate = if candidate.len() >= 7 && candidate[..7].eq_ignore_ascii_case("mailto:") {
+            candidate[7..].trim_start()
+        } else {
+            candidate
+        };
+
+        if let Some(found) = extract_email_like(candidate) {
+            return found.to_lowercase();
+        }
+    }
+
+    without_header_name.to_lowercase()
+}
+
+fn extract_email_like(input: &str) -> Option<&str> {
+    let bytes = input.as_bytes();
+    for (idx, b) in bytes.iter().enumerate() {
+        if *b != b'@' {
+            continue;
+        }
+
+        let mut start = idx;
+        while start > 0 && is_email_local_byte(bytes[start - 1]) {
+            start -= 1;
+        }
+
+        let mut end = idx + 1;
+        while end < bytes.len() && (is_email_domain_byte(bytes[end]) || bytes[end] == b'.') {
+            end += 1;
+        }
+
+        if start == idx || end == idx + 1 {
+            continue;
+        }
+
+        // Domain must not end with '.'.
+        if bytes[end - 1] == b'.' {
+            continue;
+        }
+
+        return Some(&input[start..end]);
+    }
+    None
+}
+
+fn is_email_local_byte(b: u8) -> bool {
+    matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9')
+        || matches!(
+            b,
+            b'.' | b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' | b'+' | b'/' | b'=' | b'?' | b'^'
+                | b'_' | b'`' | b'{' | b'|' | b'}' | b'~' | b'-'
+        )
+}
+
+fn is_email_domain_byte(b: u8) -> bool {
+    matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-')
+}
+
+/// Parse the `To:` header into a list of bare email addresses, split on commas
+/// outside quotes and angle brackets. The worker keeps the same parser in
+/// `src/parsers.rs` for `check-recipient`.
+pub fn parse_to_addresses(email: &str) -> Vec<String> {
+    let value = match extract_header_value(email, "To") {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+
+    split_address_list(&value)
+        .into_iter()
+        .map(|token| normalize_address(token.trim()))
+        .filter(|address| !address.is_empty())
+        .collect()
+}
+
+/// Whether `address`'s domain (after the last `@`) is exactly `domain`,
+/// ignoring ASCII case and a leading `@` on `domain`. Subdomains do not match.
+pub fn address_in_domain(address: &str, domain: &str) -> bool {
+    let domain = domain.trim();
+    let domain = domain.strip_prefix('@').unwrap_or(domain);
+    match address.rsplit_once('@') {
+        Some((local, address_domain)) => {
+            !local.is_empty() && !domain.is_empty() && address_domain.eq_ignore_ascii_case(domain)
+        }
+        None => false,
+    }
+}
+
+fn split_address_list(value: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut in_quotes = false;
+    let mut in_angle = false;
+    let mut start = 0;
+
+    for (idx, ch) in value.char_indices() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_angle = true,
+            '>' if !in_quotes => in_angle = false,
+            ',' if !in_quotes && !in_angle => {
+                tokens.push(&value[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    tokens.push(&value[start..]);
+
+    tokens
+}
+
+fn normalize_address(value: &str) -> String {
+    // Prefer the address inside angle brackets if present.
+    if let Some(start) = value.find('<') {
+        if let Some(end_rel) = value[start + 1..].find('>') {
+            let end = start + 1 + end_rel;
+            let inner = &value[start + 1..end];
+            return inner.trim().to_string();
+        }
+    }
+
+    // Fallback: pick the last token containing '@'.
+    for token in value.split_whitespace().rev() {
+        if token.contains('@') {
+            let cleaned = token
+                .trim_matches(|c| c == '<' || c == '>' || c == '"' || c == '\'')
+                .to_string();
+            if !cleaned.is_empty() {
+                return cleaned;
+            }
+        }
+    }
+
+    // As a last resort, return the raw header value.
+    value.to_string()
 }
 
 #[cfg(test)]
@@ -548,22 +974,63 @@ mod tests {
         RsaPublicKey::from_public_key_der(&pk_bytes).expect("valid RSA public key");
     }
 
+    #[test]
+    fn quoted_dkim_tag_values_are_unquoted_and_unescaped() {
+        let sig = "v=1; d=example.com; s=sel; z=\"From:a\\\"b\\\\c\"; bh=abc=";
+        let values = parse_dkim_tag_values(sig);
+        assert_eq!(
+            values["z"],
+            DkimTagValue::Quoted(r#"From:a"b\c"#.to_string())
+        );
+        assert_eq!(values["d"], DkimTagValue::Plain("example.com".to_string()));
+        assert_eq!(parse_dkim_tags(sig)["z"], r#"From:a"b\c"#);
+    }
+
     #[test]
     fn gmail_reset_full_email_timestamp_parses() {
         let email_blob = include_str!("../../tests/data/gmail_reset_full.eml");
 
-        let ts_ms = parse_email_timestamp_ms(email_blob);
+        let ts_ms = parse_email_timestamp_ms(email_blob, None);
         assert!(ts_ms.is_some(), "expected email timestamp to parse");
     }
 
+    #[test]
+    fn email_timestamp_rejects_out_of_range_fields() {
+        let with_date = |date: &str| format!("Date: {date}\r\n\r\nbody\r\n");
+
+        assert!(parse_email_timestamp_ms(&with_date("1 Jan 2026 25:00:00 +0000"), None).is_none());
+        assert!(parse_email_timestamp_ms(&with_date("1 Jan 2026 12:70:00 +0000"), None).is_none());
+        assert!(parse_email_timestamp_ms(&with_date("1 Jan 2026 12:00:00 +2400"), None).is_none());
+        assert!(parse_email_timestamp_ms(&with_date("1 Jan 2147483647 00:00:00 +0000"), None).is_none());
+    }
+
+    #[test]
+    fn email_timestamp_rejects_dates_past_upper_bound() {
+        let with_date = |date: &str| format!("Date: {date}\r\n\r\nbody\r\n");
+        // Now: 2026-01-01T00:00:00Z, with five minutes of tolerance.
+        let bound = Some(1_767_225_600_000 + 5 * 60 * 1000);
+
+        assert!(parse_email_timestamp_ms(&with_date("1 Jan 2027 00:00:00 +0000"), bound).is_none());
+        assert!(parse_email_timestamp_ms(&with_date("1 Jan 2026 00:06:00 +0000"), bound).is_none());
+        assert_eq!(
+            parse_email_timestamp_ms(&with_date("1 Jan 2026 00:04:00 +0000"), bound),
+            Some(1_767_225_840_000)
+        );
+        assert_eq!(
+            parse_email_timestamp_ms(&with_date("1 Jan 2027 00:00:00 +0000"), None),
+            Some(1_798_761_600_000)
+        );
+    }
+
     #[test]
     fn parse_recover_subject_and_body_key() {
         let subject = "recover-REQ123 alice.testnet ed25519:NEW_PUBLIC_KEY";
 
-        let (account_id, key_from_subject) =
+        let (account_id, key_from_subject, expires_at_secs) =
             parse_recover_instruction(subject).expect("instruction");
         assert_eq!(account_id.as_str(), "alice.testnet");
         assert_eq!(key_from_subject, "ed25519:NEW_PUBLIC_KEY");
+        assert!(expires_at_secs.is_none());
 
         let email = "From: alice@example.com\n\
 Subject: recover-REQ123 alice.testnet ed25519:NEW_PUBLIC_KEY\n\
@@ -573,11 +1040,40 @@ ed25519:NEW_PUBLIC_KEY\n";
         assert_eq!(key_from_body, "ed25519:NEW_PUBLIC_KEY");
     }
 
+    #[test]
+    fn body_public_keys_must_agree() {
+        let single = "Subject: recover-1 alice.testnet\r\n\r\ned25519:NEW_KEY\r\n";
+        assert_eq!(parse_all_recover_public_keys_from_body(single), vec!["ed25519:NEW_KEY"]);
+        assert_eq!(
+            parse_unambiguous_recover_public_key_from_body(single),
+            Ok(Some("ed25519:NEW_KEY".to_string()))
+        );
+
+        let duplicate = "Subject: recover-1 alice.testnet\r\n\r\ned25519:NEW_KEY\r\n> ed25519:NEW_KEY\r\n ed25519:NEW_KEY \r\n";
+        assert_eq!(
+            parse_unambiguous_recover_public_key_from_body(duplicate),
+            Ok(Some("ed25519:NEW_KEY".to_string()))
+        );
+
+        let differing = "Subject: recover-1 alice.testnet\r\n\r\ned25519:OLD_KEY\r\n\r\ned25519:NEW_KEY\r\n";
+        assert_eq!(
+            parse_all_recover_public_keys_from_body(differing),
+            vec!["ed25519:OLD_KEY", "ed25519:NEW_KEY"]
+        );
+        assert_eq!(
+            parse_unambiguous_recover_public_key_from_body(differing),
+            Err("ambiguous_public_key".to_string())
+        );
+
+        let none = "Subject: recover-1 alice.testnet\r\n\r\nno key here\r\n";
+        assert_eq!(parse_unambiguous_recover_public_key_from_body(none), Ok(None));
+    }
+
     #[test]
     fn parse_recover_subject_with_request_id() {
         let subject = "recover-123ABC alice.testnet ed25519:NEW_PUBLIC_KEY";
 
-        let (account_id, key_from_subject) =
+        let (account_id, key_from_subject, _) =
             parse_recover_instruction(subject).expect("instruction");
         assert_eq!(account_id.as_str(), "alice.testnet");
         assert_eq!(key_from_subject, "ed25519:NEW_PUBLIC_KEY");
@@ -586,4 +1082,115 @@ ed25519:NEW_PUBLIC_KEY\n";
         assert_eq!(req_id, "123ABC");
     }
 
+    #[test]
+    fn parse_recover_subject_with_expiry() {
+        let subject = "recover-123ABC alice.testnet ed25519:NEW_PUBLIC_KEY exp=1767225600";
+
+        let (account_id, key_from_subject, expires_at_secs) =
+            parse_recover_instruction(subject).expect("instruction");
+        assert_eq!(account_id.as_str(), "alice.testnet");
+        assert_eq!(key_from_subject, "ed25519:NEW_PUBLIC_KEY");
+        assert_eq!(expires_at_secs, Some(1_767_225_600));
+
+        // Token order after the account id does not matter.
+        let subject = "recover-123ABC alice.testnet exp=42 ed25519:NEW_PUBLIC_KEY";
+        let (_, key_from_subject, expires_at_secs) =
+            parse_recover_instruction(subject).expect("instruction");
+        assert_eq!(key_from_subject, "ed25519:NEW_PUBLIC_KEY");
+        assert_eq!(expires_at_secs, Some(42));
+    }
+
+    #[test]
+    fn parse_recover_subject_with_malformed_expiry_is_rejected() {
+        let subject = "recover-123ABC alice.testnet ed25519:NEW_PUBLIC_KEY exp=tomorrow";
+        assert!(parse_recover_instruction(subject).is_none());
+    }
+
+    #[test]
+    fn resent_headers_are_used_only_when_allowed() {
+        let email_blob = include_str!("../../tests/data/resent_headers.eml");
+
+        assert!(extract_sender_header(email_blob, false).is_none());
+        assert_eq!(
+            extract_sender_header(email_blob, true).as_deref(),
+            Some("Pta <n6378056@gmail.com>")
+        );
+
+        assert!(parse_email_timestamp_ms_with_resent_fallback(email_blob, false, None).is_none());
+        assert_eq!(
+            parse_email_timestamp_ms_with_resent_fallback(email_blob, true, None),
+            Some(1_763_460_900_000)
+        );
+    }
+
+    #[test]
+    fn from_and_date_take_precedence_over_resent_headers() {
+        let email_blob = "From: alice@example.com\r\n\
+Resent-From: bob@example.com\r\n\
+Date: Thu, 1 Jan 2026 00:00:00 +0000\r\n\
+Resent-Date: Fri, 2 Jan 2026 00:00:00 +0000\r\n\
+\r\n\
+body\r\n";
+
+        assert_eq!(
+            extract_sender_header(email_blob, true).as_deref(),
+            Some("alice@example.com")
+        );
+        assert_eq!(
+            parse_email_timestamp_ms_with_resent_fallback(email_blob, true, None),
+            Some(1_767_225_600_000)
+        );
+    }
+
+    // RFC 6376 §3.4.5 canonicalization example.
+    const RFC6376_EXAMPLE: &str = "A: X\r\nB : Y\t\r\n\tZ  \r\n\r\n C \r\nD \t E\r\n\r\n\r\n";
+
+    #[test]
+    fn rfc6376_example_simple_canonicalization() {
+        let (raw_headers, body) = split_headers_body(RFC6376_EXAMPLE);
+        let headers = parse_headers(raw_headers);
+        let signed = vec!["a".to_string(), "b".to_string()];
+
+        assert_eq!(
+            canonicalize_headers_simple(&headers, &signed),
+            "A: X\r\nB : Y\t\r\n\tZ  \r\n"
+        );
+        assert_eq!(canonicalize_body_simple(body), " C \r\nD \t E\r\n");
+    }
+
+    #[test]
+    fn rfc6376_appendix_a_body_simple_hash_matches_bh() {
+        // Body of the RFC 6376 Appendix A.1 message; A.2 signs it with c=simple/simple.
+        let body = "Hi.\r\n\r\nWe lost the game. Are you hungry yet?\r\n\r\nJoe.\r\n\r\n\r\n";
+        let canon = canonicalize_body_simple(body);
+        assert_eq!(canon, "Hi.\r\n\r\nWe lost the game. Are you hungry yet?\r\n\r\nJoe.\r\n");
+
+        let computed = base64::encode(Sha256::digest(canon.as_bytes()));
+        assert_eq!(computed, "2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=");
+    }
+
+    #[test]
+    fn simple_body_canonicalization_of_empty_body_is_single_crlf() {
+        assert_eq!(canonicalize_body_simple(""), "\r\n");
+        assert_eq!(canonicalize_body_simple("\r\n\r\n"), "\r\n");
+        // No trailing CRLF: one is added.
+        assert_eq!(canonicalize_body_simple("last line"), "last line\r\n");
+    }
+
+    #[test]
+    fn simple_dkim_header_keeps_whitespace_and_empties_b() {
+        let value = " v=1; a=rsa-sha256; d=example.net; s=brisbane;\r\n\tbh=abc=; b=dGVzdA==\r\n\t  c2ln";
+        assert_eq!(
+            build_canonicalized_dkim_header_simple("DKIM-Signature", value),
+            "DKIM-Signature: v=1; a=rsa-sha256; d=example.net; s=brisbane;\r\n\tbh=abc=; b="
+        );
+    }
+
+    #[test]
+    fn simple_dkim_header_keeps_field_name_casing() {
+        assert_eq!(
+            build_canonicalized_dkim_header_simple("dkim-signature", " v=1; b=dGVzdA=="),
+            "dkim-signature: v=1; b="
+        );
+    }
 }
@@ -0,0 +1,137 @@
+use crate::{log_request, EmailDkimVerifier, VerificationResult};
+use crate::error_code::VerifyErrorCode;
+use near_sdk::store::LookupMap;
+use near_sdk::{env, near, AccountId, IntoStorageKey};
+
+/// Length of the rate-limit window (one day) in nanoseconds.
+pub const RATE_LIMIT_WINDOW_NS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+/// Expired windows deleted per recorded request, so the cleanup cost stays
+/// bounded no matter how many keys went quiet at once.
+pub const MAX_EXPIRED_WINDOWS_PRUNED: u32 = 4;
+
+/// Per-key request counter for the current window.
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug)]
+pub struct RateLimitWindow {
+    pub window_start_ns: u64,
+    pub count: u32,
+}
+
+/// Request counters by key, plus every window start in the order it
+/// happened. Block time only moves forward, so the oldest starts expire
+/// first and can be deleted without iterating the map.
+#[near(serializers = [borsh])]
+pub struct RateLimits {
+    windows: LookupMap<String, RateLimitWindow>,
+    starts: LookupMap<u64, (String, u64)>,
+    oldest_start: u64,
+    next_start: u64,
+}
+
+impl RateLimits {
+    pub fn new<W, S>(windows_prefix: W, starts_prefix: S) -> Self
+    where
+        W: IntoStorageKey,
+        S: IntoStorageKey,
+    {
+        Self {
+            windows: LookupMap::new(windows_prefix),
+            starts: LookupMap::new(starts_prefix),
+            oldest_start: 0,
+            next_start: 0,
+        }
+    }
+
+    /// Count a request for `key` at `now_ns`, starting a new window when the
+    /// previous one has elapsed. Returns `false` once `max_requests` is
+    /// exceeded within the window.
+    pub fn record(&mut self, key: &str, max_requests: u32, now_ns: u64) -> bool {
+        self.prune_expired(now_ns);
+
+        let window = match self.windows.get(key) {
+            Some(w) if now_ns.saturating_sub(w.window_start_ns) < RATE_LIMIT_WINDOW_NS => {
+                RateLimitWindow {
+                    window_start_ns: w.window_start_ns,
+                    count: w.count.saturating_add(1),
+                }
+            }
+            _ => {
+                self.starts.insert(self.next_start, (key.to_string(), now_ns));
+                self.next_start += 1;
+                RateLimitWindow {
+                    window_start_ns: now_ns,
+                    count: 1,
+                }
+            }
+        };
+        let allowed = window.count <= max_requests;
+        self.windows.insert(key.to_string(), window);
+        allowed
+    }
+
+    /// The live counter for `key`, if any.
+    pub fn window(&self, key: &str) -> Option<&RateLimitWindow> {
+        self.windows.get(key)
+    }
+
+    /// Delete up to `MAX_EXPIRED_WINDOWS_PRUNED` windows that ended before
+    /// `now_ns`. A start whose key has since opened a newer window only
+    /// drops its queue entry.
+    fn prune_expired(&mut self, now_ns: u64) {
+        for _ in 0..MAX_EXPIRED_WINDOWS_PRUNED {
+            let Some((key, start_ns)) = self.starts.get(&self.oldest_start).cloned() else {
+                return;
+            };
+            if now_ns.saturating_sub(start_ns) < RATE_LIMIT_WINDOW_NS {
+                return;
+            }
+            self.starts.remove(&self.oldest_start);
+            self.oldest_start += 1;
+            if self.windows.get(&key).is_some_and(|w| w.window_start_ns == start_ns) {
+                self.windows.remove(&key);
+            }
+        }
+    }
+}
+
+impl EmailDkimVerifier {
+    /// Count this callback against the requester account and, for a verified
+    /// result, the `d=` domain of the signature that verified. Once either
+    /// counter exceeds `max_requests_per_domain_per_day` within the window,
+    /// the result is replaced with a `rate_limited` failure.
+    pub(crate) fn apply_rate_limit(
+        &mut self,
+        requested_by: &AccountId,
+        vr: VerificationResult,
+    ) -> VerificationResult {
+        let max_requests = self.max_requests_per_domain_per_day;
+        if max_requests == 0 {
+            return vr;
+        }
+
+        let now = env::block_timestamp();
+        let mut limited = !self
+            .rate_limits
+            .record(&format!("account:{requested_by}"), max_requests, now);
+        // An unverified `d=` is whatever the sender wrote, so only a domain
+        // that actually signed the email is charged.
+        let signing_domain =
+            Some(vr.signing_domain.as_str()).filter(|d| vr.verified && !d.is_empty());
+        if let Some(domain) = signing_domain {
+            limited |= !self.rate_limits.record(&format!("domain:{domain}"), max_requests, now);
+        }
+
+        if limited {
+            log_request(
+                &vr.request_id,
+                &format!(
+                    "Rate limit exceeded for requester {requested_by} (domain: {})",
+                    signing_domain.unwrap_or("unknown")
+                ),
+            );
+            return VerificationResult::failure(&vr.request_id, VerifyErrorCode::RateLimited);
+        }
+        vr
+    }
+}
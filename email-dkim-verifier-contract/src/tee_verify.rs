@@ -1,11 +1,12 @@
 use crate::{
-    ext_outlayer, ext_self,
+    email_timestamp_upper_bound_ms, ext_outlayer, ext_self, is_recovery_expired, log_request,
     EmailDkimVerifier, ExecutionParams, OutlayerInputArgs,
     VerificationResult, OutlayerWorkerResponse,
     MIN_DEPOSIT, OUTLAYER_CONTRACT_ID,
     VERIFY_ENCRYPTED_EMAIL_METHOD,
     SecretsReference, SECRETS_OWNER_ID, SECRETS_PROFILE,
 };
+use crate::error_code::VerifyErrorCode;
 use near_sdk::serde_json::{self, json};
 use near_sdk::{env, AccountId, NearToken, Promise, PromiseError};
 
@@ -20,6 +21,15 @@ struct VerifyEncryptedEmailResponse {
     email_timestamp_ms: Option<u64>,
     #[serde(default)]
     request_id: String,
+    #[serde(default)]
+    selector: String,
+    /// `d=` of the signature that verified; its key was looked up for that
+    /// domain, so the contract can rate limit by it.
+    #[serde(default)]
+    signing_domain: String,
+    /// Optional `exp=<unix_secs>` deadline parsed from the recovery Subject.
+    #[serde(default)]
+    recovery_expires_at_secs: Option<u64>,
     error: Option<String>,
 }
 
@@ -32,6 +42,21 @@ pub struct AeadContext {
     pub account_id: String,
     pub network_id: String,
     pub payer_account_id: String,
+    /// Binds the ciphertext to one request so it cannot be replayed under
+    /// another `request_id`. Set by the contract from the call's `request_id`;
+    /// omitted from the AAD when `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Pre-registered `from_address_hash` the sender must match. Not part of
+    /// the AAD: forwarded to the worker as a separate argument, which rejects
+    /// other senders with `from_address_hash_mismatch`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_from_address_hash: Option<Vec<u8>>,
+    /// Account the Subject's recovery instruction must name. Not part of the
+    /// AAD: forwarded to the worker, which rejects a signed email for another
+    /// account with `account_id_mismatch`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_account_id: Option<String>,
 }
 
 /// Internal helper: encrypted/TEE DKIM verification request path.
@@ -39,9 +64,16 @@ pub fn request_email_verification_private_inner(
     contract: &mut EmailDkimVerifier,
     payer_account_id: AccountId,
     encrypted_email_blob: serde_json::Value,
-    aead_context: AeadContext,
+    mut aead_context: AeadContext,
     request_id: Option<String>,
 ) -> Promise {
+    // An empty ciphertext can only fail to decrypt; don't spend the deposit on it.
+    let has_ciphertext = encrypted_email_blob
+        .get("ciphertext")
+        .and_then(|c| c.as_str())
+        .is_some_and(|c| !c.trim().is_empty());
+    assert!(has_ciphertext, "encrypted_email_blob.ciphertext must not be empty");
+
     let caller = env::predecessor_account_id();
     let attached = env::attached_deposit().as_yoctonear();
     assert!(
@@ -51,12 +83,13 @@ pub fn request_email_verification_private_inner(
 
     let outlayer_deposit = MIN_DEPOSIT;
     let refund = attached.saturating_sub(outlayer_deposit);
+    let request_id = request_id.unwrap_or_default().trim().to_string();
 
     if refund > 0 {
-        env::log_str(&format!(
-            "Refunding {} yoctoNEAR of unused DKIM fees to {}",
-            refund, caller
-        ));
+        log_request(
+            &request_id,
+            &format!("Refunding {} yoctoNEAR of unused DKIM fees to {}", refund, caller),
+        );
         let _ = Promise::new(caller.clone()).transfer(NearToken::from_yoctonear(refund));
     }
 
@@ -64,19 +97,34 @@ pub fn request_email_verification_private_inner(
     // The worker uses this JSON object as AEAD AAD for ChaCha20‑Poly1305
     // after serializing it with serde_json.
     // Expected keys (alphabetical for canonical AAD):
-    //   account_id, network_id, payer_account_id.
-    let request_id = request_id.unwrap_or_default().trim().to_string();
+    //   account_id, network_id, payer_account_id[, request_id].
+    if !request_id.is_empty() {
+        aead_context.request_id = Some(request_id.clone());
+    }
+    let mut context = json!({
+        // alphabetized
+        "account_id": aead_context.account_id,
+        "network_id": aead_context.network_id,
+        "payer_account_id": aead_context.payer_account_id,
+    });
+    if let Some(request_id) = aead_context.request_id {
+        context["request_id"] = json!(request_id);
+    }
     let input_args = OutlayerInputArgs::new(
         VERIFY_ENCRYPTED_EMAIL_METHOD,
         serde_json::json!({
             "encrypted_email_blob": encrypted_email_blob,
-            "context": json!({
-                // alphabetized
-                "account_id": aead_context.account_id,
-                "network_id": aead_context.network_id,
-                "payer_account_id": aead_context.payer_account_id,
-            }),
+            "context": context,
             "request_id": request_id.clone(),
+            "allow_resent_headers": contract.allow_resent_headers,
+            "expected_from_address_hash": aead_context.expected_from_address_hash,
+            "expected_account_id": aead_context.expected_account_id,
+            "payer_account_id": payer_account_id,
+            "pinned_keys": contract.get_pinned_dkim_keys(),
+            "dns_records": contract.get_static_dns_records(),
+            "require_all_signatures": contract.require_all_signatures,
+            "max_signatures": contract.max_dkim_signatures,
+            "reject_mailing_list": contract.reject_mailing_list_emails,
         }),
     );
     let input_payload = input_args.to_json_string();
@@ -135,6 +183,7 @@ pub fn request_email_verification_private_inner(
         )
         .then(
             ext_self::ext(env::current_account_id())
+                .with_static_gas(contract.callback_gas())
                 .with_unused_gas_weight(1)
                 .on_email_verification_private_result(caller, request_id),
         )
@@ -151,43 +200,52 @@ pub fn on_email_verification_private_result(
     let value = match result {
         Ok(Some(v)) => v,
         _ => {
-            return VerificationResult::failure(&request_id, "outlayer_execution_failed");
+            return VerificationResult::failure(
+                &request_id,
+                VerifyErrorCode::OutlayerExecutionFailed,
+            );
         }
     };
 
     let worker_response: OutlayerWorkerResponse = match serde_json::from_value(value.clone()) {
         Ok(r) => r,
         Err(e) => {
-            env::log_str(&format!("Failed to parse worker response (private): {e}"));
-            return VerificationResult::failure(&request_id, "invalid_worker_response");
+            log_request(&request_id, &format!("Failed to parse worker response (private): {e}"));
+            return VerificationResult::failure(&request_id, VerifyErrorCode::InvalidWorkerResponse);
         }
     };
 
     if worker_response.method != VERIFY_ENCRYPTED_EMAIL_METHOD {
-        env::log_str(&format!(
-            "Unexpected worker method in on_email_verification_private_result: {}",
-            worker_response.method
-        ));
-        return VerificationResult::failure(
+        log_request(
             &request_id,
-            format!(
-                "unexpected_worker_method: {}",
+            &format!(
+                "Unexpected worker method in on_email_verification_private_result: {}",
                 worker_response.method
             ),
         );
+        return VerificationResult::failure(
+            &request_id,
+            VerifyErrorCode::UnexpectedWorkerMethod.with_detail(&worker_response.method),
+        );
     }
 
     let verify_params: VerifyEncryptedEmailResponse =
         match serde_json::from_value(worker_response.response.clone()) {
             Ok(p) => p,
             Err(e) => {
-                env::log_str(&format!("Failed to parse {VERIFY_ENCRYPTED_EMAIL_METHOD} response: {e}"));
-                return VerificationResult::failure(&request_id, "invalid_verify_response");
+                log_request(
+                    &request_id,
+                    &format!("Failed to parse {VERIFY_ENCRYPTED_EMAIL_METHOD} response: {e}"),
+                );
+                return VerificationResult::failure(
+                    &request_id,
+                    VerifyErrorCode::InvalidVerifyResponse,
+                );
             }
         };
 
     if let Some(err) = verify_params.error.as_deref() {
-        env::log_str(&format!("{VERIFY_ENCRYPTED_EMAIL_METHOD} worker error: {err}"));
+        log_request(&request_id, &format!("{VERIFY_ENCRYPTED_EMAIL_METHOD} worker error: {err}"));
     }
 
     let final_request_id = if verify_params.request_id.trim().is_empty() {
@@ -196,13 +254,26 @@ pub fn on_email_verification_private_result(
         verify_params.request_id.clone()
     };
 
+    if verify_params.verified
+        && verify_params
+            .recovery_expires_at_secs
+            .is_some_and(is_recovery_expired)
+    {
+        return VerificationResult::failure(&final_request_id, VerifyErrorCode::RecoveryExpired);
+    }
+
     let vr = VerificationResult {
         verified: verify_params.verified,
         account_id: verify_params.account_id,
         new_public_key: verify_params.new_public_key,
         from_address_hash: verify_params.from_address_hash,
-        email_timestamp_ms: verify_params.email_timestamp_ms,
+        // The worker has no trusted clock; apply the same future bound as the on-chain path.
+        email_timestamp_ms: verify_params
+            .email_timestamp_ms
+            .filter(|&ts_ms| ts_ms <= email_timestamp_upper_bound_ms()),
         request_id: final_request_id.clone(),
+        selector: verify_params.selector,
+        signing_domain: verify_params.signing_domain.to_ascii_lowercase(),
         error: verify_params.error.clone(),
     };
     vr
@@ -0,0 +1,48 @@
+use email_dkim_verifier_contract::{EmailDkimVerifier, DEFAULT_CALLBACK_GAS_TGAS};
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(predecessor: &str) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id(predecessor))
+        .build());
+}
+
+#[test]
+fn callback_gas_has_a_default_reservation() {
+    set_context("verifier.testnet");
+    let contract = EmailDkimVerifier::new();
+    assert_eq!(contract.get_callback_gas_tgas(), DEFAULT_CALLBACK_GAS_TGAS);
+    assert_eq!(DEFAULT_CALLBACK_GAS_TGAS, 30);
+}
+
+#[test]
+fn owner_can_set_callback_gas() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_callback_gas_tgas(50);
+    assert_eq!(contract.get_callback_gas_tgas(), 50);
+}
+
+#[test]
+#[should_panic(expected = "callback_gas_tgas must be at least 1")]
+fn zero_callback_gas_is_rejected() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_callback_gas_tgas(0);
+}
+
+#[test]
+#[should_panic(expected = "Only the contract owner can set the callback gas")]
+fn non_owner_cannot_set_callback_gas() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    set_context("mallory.testnet");
+    contract.set_callback_gas_tgas(50);
+}
@@ -0,0 +1,97 @@
+use email_dkim_verifier_contract::debug_payload::DEBUG_PAYLOAD_MAX_BYTES;
+use email_dkim_verifier_contract::EmailDkimVerifier;
+use near_sdk::serde_json;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(predecessor: &str) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id(predecessor))
+        .build());
+}
+
+/// A worker response without `method`, padded past the storage cap.
+fn malformed_worker_response() -> serde_json::Value {
+    serde_json::json!({
+        "response": { "verified": true },
+        "padding": "x".repeat(DEBUG_PAYLOAD_MAX_BYTES * 2),
+    })
+}
+
+#[test]
+fn debug_payloads_are_off_by_default() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    assert!(!contract.get_store_debug_payload());
+
+    let vr = contract.on_email_verification_private_result(
+        test_account_id("relayer.testnet"),
+        "RID41".to_string(),
+        Ok(Some(malformed_worker_response())),
+    );
+    assert_eq!(vr.error.as_deref(), Some("invalid_worker_response"));
+    assert_eq!(contract.get_debug_payload("RID41".to_string()), None);
+}
+
+#[test]
+fn stores_truncated_payload_for_unparseable_response() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_store_debug_payload(true);
+
+    let raw = malformed_worker_response();
+    let vr = contract.on_email_verification_private_result(
+        test_account_id("relayer.testnet"),
+        "RID42".to_string(),
+        Ok(Some(raw.clone())),
+    );
+    assert!(!vr.verified);
+    assert_eq!(vr.error.as_deref(), Some("invalid_worker_response"));
+
+    let payload = contract
+        .get_debug_payload("RID42".to_string())
+        .expect("debug payload stored");
+    assert_eq!(payload.len(), DEBUG_PAYLOAD_MAX_BYTES);
+    assert!(raw.to_string().starts_with(&payload));
+}
+
+#[test]
+fn other_failures_do_not_store_payload() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_store_debug_payload(true);
+
+    let vr = contract.on_email_verification_private_result(
+        test_account_id("relayer.testnet"),
+        "RID43".to_string(),
+        Ok(Some(serde_json::json!({
+            "method": "verify-encrypted-email",
+            "response": {
+                "verified": false,
+                "account_id": "",
+                "new_public_key": "",
+                "from_address_hash": [],
+                "email_timestamp_ms": null,
+                "request_id": "RID43",
+                "error": "dkim_verification_failed"
+            }
+        }))),
+    );
+    assert!(!vr.verified);
+    assert_eq!(contract.get_debug_payload("RID43".to_string()), None);
+}
+
+#[test]
+#[should_panic(expected = "Only the contract owner can set the debug payload policy")]
+fn non_owner_cannot_enable_debug_payloads() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    set_context("mallory.testnet");
+    contract.set_store_debug_payload(true);
+}
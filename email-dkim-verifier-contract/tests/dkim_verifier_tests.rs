@@ -1,5 +1,15 @@
-use email_dkim_verifier_contract::onchain_verify::{parse_dkim_tags, dkim::verify_dkim};
-use rsa::pkcs8::DecodePublicKey;
+#![cfg(feature = "legacy-onchain")]
+
+use email_dkim_verifier_contract::onchain_verify::{
+    parse_dkim_tags,
+    parsers::{canonicalize_headers_relaxed, parse_headers},
+    dkim::{
+        parse_rsa_public_key, verify_all_dkim_signatures_with_keys, verify_dkim, verify_dkim_all,
+        verify_dkim_signature, verify_dkim_with_explicit_key, verify_dkim_with_keys, DkimError,
+        DkimKeyRecord,
+    },
+};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePublicKey};
 use rsa::RsaPublicKey;
 
 fn real_gmail_dns_records() -> Vec<String> {
@@ -58,6 +68,91 @@ fn real_gmail_full_message_verifies() {
     assert!(verify_dkim(email_blob, &real_gmail_dns_records()));
 }
 
+#[test]
+fn real_gmail_full_message_with_utf8_bom_verifies() {
+    let email_blob = format!("\u{feff}{}", include_str!("data/gmail_reset_full.eml"));
+    assert!(email_blob.as_bytes().starts_with(&[0xEF, 0xBB, 0xBF]));
+    assert!(verify_dkim(&email_blob, &real_gmail_dns_records()));
+}
+
+#[test]
+fn real_gmail_full_message_verifies_with_explicit_key() {
+    let email_blob = include_str!("data/gmail_reset_full.eml");
+    let tags = parse_dkim_tags(&real_gmail_dns_records()[0]);
+    let pk_bytes = base64::decode(tags.get("p").expect("p tag")).expect("p base64");
+    let public_key = RsaPublicKey::from_public_key_der(&pk_bytes).expect("SPKI key");
+
+    assert!(verify_dkim_with_explicit_key(email_blob, &public_key));
+
+    let tampered = email_blob.replace("kerp30.w3a-v1.testnet", "mallory.testnet");
+    assert!(!verify_dkim_with_explicit_key(&tampered, &public_key));
+}
+
+#[test]
+fn real_gmail_full_message_reports_verifying_selector() {
+    let email_blob = include_str!("data/gmail_reset_full.eml");
+    let sig = verify_dkim_signature(email_blob, &real_gmail_dns_records())
+        .expect("gmail fixture verifies");
+    assert_eq!(sig.selector, "20230601");
+    assert_eq!(sig.domain, "gmail.com");
+}
+
+/// The Gmail key from `real_gmail_dns_records`, re-encoded as bare PKCS#1
+/// `RSAPublicKey` DER (the SPKI wrapper stripped).
+const GMAIL_PKCS1_P: &str = "MIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB";
+
+#[test]
+fn pkcs1_encoded_dkim_key_parses_and_verifies() {
+    let pk_bytes = base64::decode(GMAIL_PKCS1_P).expect("base64");
+    assert!(RsaPublicKey::from_public_key_der(&pk_bytes).is_err());
+    assert!(parse_rsa_public_key(&pk_bytes).is_ok());
+
+    let email_blob = include_str!("data/gmail_reset_full.eml");
+    let records = vec![format!("v=DKIM1; k=rsa; p={GMAIL_PKCS1_P}")];
+    assert!(verify_dkim(email_blob, &records));
+}
+
+#[test]
+fn parse_rsa_public_key_rejects_garbage() {
+    let err = parse_rsa_public_key(&[1, 2, 3]).expect_err("not a key");
+    assert!(err.starts_with("invalid RSA public key"));
+}
+
+fn key_record(selector: &str, domain: &str, record: &str) -> DkimKeyRecord {
+    DkimKeyRecord {
+        selector: selector.to_string(),
+        domain: domain.to_string(),
+        record: record.to_string(),
+    }
+}
+
+#[test]
+fn verify_dkim_with_keys_only_uses_records_for_signature_selector() {
+    let email_blob = include_str!("data/gmail_reset_full.eml");
+    let gmail_record = real_gmail_dns_records().remove(0);
+
+    // The real key is published under a different selector than the one the
+    // signature names (s=20230601), which carries a revoked key instead.
+    let mismatched = vec![
+        key_record("20230601", "gmail.com", "v=DKIM1; k=rsa; p="),
+        key_record("20161025", "gmail.com", &gmail_record),
+    ];
+    let flat: Vec<String> = mismatched.iter().map(|k| k.record.clone()).collect();
+    assert!(verify_dkim_signature(email_blob, &flat).is_ok());
+    assert!(verify_dkim_with_keys(email_blob, &mismatched).is_err());
+
+    let matched = vec![
+        key_record("20161025", "gmail.com", "v=DKIM1; k=rsa; p="),
+        key_record("20230601", "Gmail.com", &gmail_record),
+    ];
+    let sig = verify_dkim_with_keys(email_blob, &matched).expect("selector-matched key verifies");
+    assert_eq!(sig.selector, "20230601");
+
+    // Same selector, wrong domain.
+    let other_domain = vec![key_record("20230601", "example.com", &gmail_record)];
+    assert!(verify_dkim_with_keys(email_blob, &other_domain).is_err());
+}
+
 #[test]
 fn real_gmail_dns_p_parses_as_rsa_key() {
     let dns_record = real_gmail_dns_records().pop().unwrap();
@@ -99,3 +194,113 @@ fn modifying_from_breaks_dkim() {
     );
     assert!(!verify_dkim(&modified, &real_gmail_dns_records()));
 }
+
+#[test]
+fn rsa_sha1_signature_is_rejected_with_specific_error() {
+    let email_blob = include_str!("data/gmail_reset_full.eml").replace("a=rsa-sha256", "a=rsa-sha1");
+
+    assert_eq!(
+        verify_dkim_signature(&email_blob, &real_gmail_dns_records()),
+        Err(DkimError::UnsupportedAlgorithm("rsa-sha1: sha1 disallowed".to_string()))
+    );
+    assert!(!verify_dkim(&email_blob, &real_gmail_dns_records()));
+}
+
+/// DKIM record for `data/test_dkim_private_key.pem` (`d=example.com; s=test`).
+fn test_key_dns_records() -> Vec<String> {
+    let spki = rsa::RsaPrivateKey::from_pkcs8_pem(include_str!("data/test_dkim_private_key.pem"))
+        .expect("test key")
+        .to_public_key()
+        .to_public_key_der()
+        .expect("encode public key");
+    vec![format!("v=DKIM1; k=rsa; p={}", base64::encode(spki.as_bytes()))]
+}
+
+#[test]
+fn empty_body_signature_verifies() {
+    let email_blob = include_str!("data/empty_body.eml");
+    assert!(email_blob.ends_with("\r\n\r\n"));
+    assert!(verify_dkim(email_blob, &test_key_dns_records()));
+}
+
+#[test]
+fn require_all_fails_when_any_signature_does_not_verify() {
+    // Signed by example.com (s=test), then by forwarder.example (s=fwd), both
+    // with the test key.
+    let email_blob = include_str!("data/two_signatures.eml");
+    let test_record = test_key_dns_records().remove(0);
+    let gmail_record = real_gmail_dns_records().remove(0);
+
+    let stale_original = vec![
+        key_record("fwd", "forwarder.example", &test_record),
+        key_record("test", "example.com", &gmail_record),
+    ];
+    let sig = verify_dkim_with_keys(email_blob, &stale_original).expect("any signature verifies");
+    assert_eq!(sig.domain, "forwarder.example");
+    assert_eq!(
+        verify_all_dkim_signatures_with_keys(email_blob, &stale_original),
+        Err(DkimError::VerificationFailed)
+    );
+
+    let both = vec![
+        key_record("fwd", "forwarder.example", &test_record),
+        key_record("test", "example.com", &test_record),
+    ];
+    let sig = verify_all_dkim_signatures_with_keys(email_blob, &both).expect("all signatures verify");
+    assert_eq!(sig.selector, "fwd");
+
+    assert!(verify_dkim_all(email_blob, &test_key_dns_records()));
+    assert!(!verify_dkim_all(email_blob, &real_gmail_dns_records()));
+}
+
+#[test]
+fn c_tag_defaults_follow_rfc6376() {
+    // c=relaxed and c=relaxed/ mean relaxed/simple; no c= means simple/simple.
+    for email_blob in [
+        include_str!("data/c_relaxed_header_only.eml"),
+        include_str!("data/c_relaxed_trailing_slash.eml"),
+        include_str!("data/c_absent.eml"),
+    ] {
+        assert!(verify_dkim(email_blob, &test_key_dns_records()));
+        let respaced = email_blob.replacen("Hello  world", "Hello world", 1);
+        assert!(!verify_dkim(&respaced, &test_key_dns_records()));
+    }
+}
+
+#[test]
+fn simple_header_canonicalization_keeps_dkim_signature_name_casing() {
+    let email_blob = include_str!("data/c_absent_lowercase_name.eml");
+    assert!(verify_dkim(email_blob, &test_key_dns_records()));
+    let recased = email_blob.replacen("dkim-signature:", "DKIM-Signature:", 1);
+    assert!(!verify_dkim(&recased, &test_key_dns_records()));
+}
+
+#[test]
+fn repeated_signed_header_is_selected_bottom_up_when_interleaved() {
+    let headers = parse_headers(
+        "From: first@example.com\r\nTo: bob@example.com\r\nSubject: hi\r\nFrom : second@example.com",
+    );
+    let signed: Vec<String> = ["from", "to", "from"].iter().map(|n| n.to_string()).collect();
+    assert_eq!(
+        canonicalize_headers_relaxed(&headers, &signed),
+        "from:second@example.com\r\nto:bob@example.com\r\nfrom:first@example.com\r\n"
+    );
+}
+
+#[test]
+fn repeated_from_in_h_tag_signs_both_from_fields() {
+    // Signed by example.com (s=test), then by forwarder.example (s=fwd), both
+    // with the test key and `h=from:from:to`.
+    let email_blob = include_str!("data/two_signatures_repeated_from.eml");
+    assert!(verify_dkim_all(email_blob, &test_key_dns_records()));
+
+    // Swapping the two From: fields changes the signed order.
+    let swapped = email_blob
+        .replacen("From: Alice <alice@example.com>\r\n", "", 1)
+        .replacen(
+            "From: Bob <bob@example.com>\r\n",
+            "From: Bob <bob@example.com>\r\nFrom: Alice <alice@example.com>\r\n",
+            1,
+        );
+    assert!(!verify_dkim(&swapped, &test_key_dns_records()));
+}
@@ -0,0 +1,18 @@
+#![cfg(feature = "legacy-onchain")]
+
+use email_dkim_verifier_contract::onchain_verify::parsers::canonicalize_email_address;
+
+/// `input<TAB>canonical` pairs shared with the worker's copy of
+/// `canonicalize_email_address` (`src/parsers.rs`).
+const VECTORS: &str = include_str!("data/email_address_vectors.tsv");
+
+#[test]
+fn canonicalize_email_address_matches_shared_vectors() {
+    let mut count = 0;
+    for line in VECTORS.lines().filter(|line| !line.starts_with('#')) {
+        let (input, canonical) = line.split_once('\t').expect("input<TAB>canonical");
+        assert_eq!(canonicalize_email_address(input), canonical, "input: {input:?}");
+        count += 1;
+    }
+    assert!(count >= 10, "expected the shared vector file to be loaded");
+}
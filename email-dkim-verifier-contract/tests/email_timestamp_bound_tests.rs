@@ -0,0 +1,109 @@
+use email_dkim_verifier_contract::{tee_verify, EMAIL_DATE_FUTURE_TOLERANCE_MS};
+use near_sdk::serde_json;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+const EMAIL_TIMESTAMP_MS: u64 = 1_700_000_000_000;
+const MINUTE_MS: u64 = 60 * 1000;
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_block_time_ms(block_time_ms: u64) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .block_timestamp(block_time_ms * 1_000_000)
+        .build());
+}
+
+fn private_result_timestamp() -> Option<u64> {
+    let vr = tee_verify::on_email_verification_private_result(
+        test_account_id("relayer.testnet"),
+        "RID123".to_string(),
+        Ok(Some(serde_json::json!({
+            "method": "verify-encrypted-email",
+            "response": {
+                "verified": true,
+                "account_id": "alice.testnet",
+                "new_public_key": "ed25519:abc",
+                "from_address_hash": [1, 2, 3],
+                "email_timestamp_ms": EMAIL_TIMESTAMP_MS,
+                "request_id": "RID123",
+                "error": null
+            }
+        }))),
+    );
+    assert!(vr.verified, "{:?}", vr.error);
+    vr.email_timestamp_ms
+}
+
+#[test]
+fn tolerance_is_five_minutes() {
+    assert_eq!(EMAIL_DATE_FUTURE_TOLERANCE_MS, 5 * MINUTE_MS);
+}
+
+#[test]
+fn private_result_drops_timestamp_six_minutes_in_the_future() {
+    set_block_time_ms(EMAIL_TIMESTAMP_MS - 6 * MINUTE_MS);
+    assert_eq!(private_result_timestamp(), None);
+}
+
+#[test]
+fn private_result_drops_timestamp_one_year_in_the_future() {
+    set_block_time_ms(EMAIL_TIMESTAMP_MS - 365 * 24 * 60 * MINUTE_MS);
+    assert_eq!(private_result_timestamp(), None);
+}
+
+#[test]
+fn private_result_keeps_timestamp_within_tolerance() {
+    set_block_time_ms(EMAIL_TIMESTAMP_MS - 4 * MINUTE_MS);
+    assert_eq!(private_result_timestamp(), Some(EMAIL_TIMESTAMP_MS));
+
+    set_block_time_ms(EMAIL_TIMESTAMP_MS + 60 * MINUTE_MS);
+    assert_eq!(private_result_timestamp(), Some(EMAIL_TIMESTAMP_MS));
+}
+
+#[cfg(feature = "legacy-onchain")]
+mod onchain {
+    use super::*;
+    use email_dkim_verifier_contract::EmailDkimVerifier;
+
+    const GMAIL_DKIM_RECORD: &str = "v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB";
+    // `Date: Tue, 9 Dec 2025 17:13:23 +0900` in gmail_reset_full.eml.
+    const GMAIL_EMAIL_TIMESTAMP_MS: u64 = 1_765_268_003_000;
+
+    fn onchain_result_timestamp() -> Option<u64> {
+        let mut contract = EmailDkimVerifier::new();
+        let vr = contract.on_email_verification_onchain_result(
+            test_account_id("relayer.testnet"),
+            include_str!("data/gmail_reset_full.eml").to_string(),
+            Ok(Some(serde_json::json!({
+                "method": "get-dns-records",
+                "response": {
+                    "selector": "20230601",
+                    "domain": "gmail.com",
+                    "name": "20230601._domainkey.gmail.com",
+                    "type": "TXT",
+                    "records": [GMAIL_DKIM_RECORD],
+                    "error": null
+                }
+            }))),
+        );
+        assert!(vr.verified, "{:?}", vr.error);
+        vr.email_timestamp_ms
+    }
+
+    #[test]
+    fn onchain_result_drops_timestamp_past_tolerance() {
+        set_block_time_ms(GMAIL_EMAIL_TIMESTAMP_MS - 6 * MINUTE_MS);
+        assert_eq!(onchain_result_timestamp(), None);
+    }
+
+    #[test]
+    fn onchain_result_keeps_timestamp_within_tolerance() {
+        set_block_time_ms(GMAIL_EMAIL_TIMESTAMP_MS - 4 * MINUTE_MS);
+        assert_eq!(onchain_result_timestamp(), Some(GMAIL_EMAIL_TIMESTAMP_MS));
+    }
+}
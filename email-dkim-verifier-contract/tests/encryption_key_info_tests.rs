@@ -0,0 +1,64 @@
+use email_dkim_verifier_contract::{EmailDkimVerifier, OutlayerEncryptionKeyInfo};
+use near_sdk::serde_json;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+const WORKER_PUBLIC_KEY: &str = "3p7bfXt9wbTTW2HC7OQ1Nz+DQ8hbeGdNrfx+FG+IK08=";
+/// Hex SHA-256 of the decoded `WORKER_PUBLIC_KEY`.
+const WORKER_KEY_FINGERPRINT: &str =
+    "f35e5616160a30bf3c6e79fa73c576d40205e8fc3ba4e1c6dcf93e6b98e857b4";
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_owner_context() {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id("verifier.testnet"))
+        .build());
+}
+
+#[test]
+fn stores_worker_key_version_and_fingerprint() {
+    set_owner_context();
+    let mut contract = EmailDkimVerifier::new();
+
+    contract.on_worker_public_key_result(Ok(Some(serde_json::json!({
+        "method": "get-public-key",
+        "response": {
+            "public_key": WORKER_PUBLIC_KEY,
+            "key_algorithm": "x25519",
+            "key_fingerprint_sha256": WORKER_KEY_FINGERPRINT,
+            "key_version": 2
+        }
+    }))));
+
+    assert_eq!(contract.get_outlayer_encryption_public_key(), WORKER_PUBLIC_KEY);
+    assert_eq!(
+        contract.get_outlayer_encryption_key_info(),
+        OutlayerEncryptionKeyInfo {
+            public_key: WORKER_PUBLIC_KEY.to_string(),
+            key_algorithm: "x25519".to_string(),
+            key_fingerprint_sha256: WORKER_KEY_FINGERPRINT.to_string(),
+            key_version: 2,
+        }
+    );
+}
+
+#[test]
+fn response_from_older_worker_leaves_metadata_unknown() {
+    set_owner_context();
+    let mut contract = EmailDkimVerifier::new();
+
+    contract.on_worker_public_key_result(Ok(Some(serde_json::json!({
+        "method": "get-public-key",
+        "response": { "public_key": WORKER_PUBLIC_KEY }
+    }))));
+
+    let info = contract.get_outlayer_encryption_key_info();
+    assert_eq!(info.public_key, WORKER_PUBLIC_KEY);
+    assert_eq!(info.key_version, 0);
+    assert_eq!(info.key_fingerprint_sha256, "");
+}
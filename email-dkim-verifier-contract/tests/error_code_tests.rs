@@ -0,0 +1,80 @@
+use email_dkim_verifier_contract::error_code::VerifyErrorCode;
+use email_dkim_verifier_contract::EmailDkimVerifier;
+use near_sdk::serde_json;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+/// The documented string for every code (README, "Error codes").
+const DOCUMENTED: [(VerifyErrorCode, &str); 21] = [
+    (VerifyErrorCode::InvalidContextShape, "invalid_context_shape"),
+    (VerifyErrorCode::RequestIdMismatch, "request_id_mismatch"),
+    (VerifyErrorCode::PayerAccountIdContextMismatch, "payer_account_id_context_mismatch"),
+    (VerifyErrorCode::SelectorNotFound, "selector_not_found"),
+    (VerifyErrorCode::SignatureInvalid, "signature_invalid"),
+    (VerifyErrorCode::FromAddressHashMismatch, "from_address_hash_mismatch"),
+    (VerifyErrorCode::AccountIdMismatch, "account_id_mismatch"),
+    (VerifyErrorCode::AmbiguousPublicKey, "ambiguous_public_key"),
+    (VerifyErrorCode::MultipleFromHeaders, "multiple_from_headers"),
+    (VerifyErrorCode::MailingListMessage, "mailing_list_message"),
+    (VerifyErrorCode::RecoveryExpired, "recovery_expired"),
+    (VerifyErrorCode::OutlayerExecutionFailed, "outlayer_execution_failed"),
+    (VerifyErrorCode::InvalidWorkerResponse, "invalid_worker_response"),
+    (VerifyErrorCode::InvalidVerifyResponse, "invalid_verify_response"),
+    (VerifyErrorCode::InvalidDnsResponse, "invalid_dns_response"),
+    (VerifyErrorCode::UnexpectedWorkerMethod, "unexpected_worker_method"),
+    (VerifyErrorCode::DnsError, "dns_error"),
+    (VerifyErrorCode::DnsRecordsEmpty, "dns_records_empty"),
+    (VerifyErrorCode::UnsupportedAlgorithm, "unsupported_algorithm"),
+    (VerifyErrorCode::DkimVerificationFailed, "dkim_verification_failed"),
+    (VerifyErrorCode::RateLimited, "rate_limited"),
+];
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+#[test]
+fn codes_serialize_to_documented_strings() {
+    assert_eq!(DOCUMENTED.len(), VerifyErrorCode::ALL.len());
+    for (code, expected) in DOCUMENTED {
+        assert_eq!(serde_json::to_value(code).unwrap(), expected);
+        assert_eq!(code.as_str(), expected);
+        assert_eq!(
+            serde_json::from_value::<VerifyErrorCode>(expected.into()).unwrap(),
+            code
+        );
+    }
+}
+
+#[test]
+fn callback_failures_carry_codes() {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id("verifier.testnet"))
+        .build());
+    let mut contract = EmailDkimVerifier::new();
+
+    let vr = contract.on_email_verification_private_result(
+        test_account_id("relayer.testnet"),
+        "RID1".to_string(),
+        Ok(None),
+    );
+    let error = vr.error.expect("failure has an error");
+    assert_eq!(
+        VerifyErrorCode::from_error(&error),
+        Some(VerifyErrorCode::OutlayerExecutionFailed)
+    );
+
+    let vr = contract.on_email_verification_private_result(
+        test_account_id("relayer.testnet"),
+        "RID2".to_string(),
+        Ok(Some(serde_json::json!({ "method": "get-dns-records", "response": {} }))),
+    );
+    let error = vr.error.expect("failure has an error");
+    assert_eq!(error, "unexpected_worker_method: get-dns-records");
+    assert_eq!(
+        VerifyErrorCode::from_error(&error),
+        Some(VerifyErrorCode::UnexpectedWorkerMethod)
+    );
+}
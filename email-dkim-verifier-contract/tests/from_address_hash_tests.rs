@@ -0,0 +1,69 @@
+#![cfg(feature = "legacy-onchain")]
+
+use email_dkim_verifier_contract::EmailDkimVerifier;
+use near_sdk::serde_json;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+const GMAIL_DKIM_RECORD: &str = "v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB";
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+#[test]
+fn from_address_hash_view_matches_onchain_callback() {
+    testing_env!(VMContextBuilder::new().build());
+    let mut contract = EmailDkimVerifier::new();
+
+    let email_blob = include_str!("data/gmail_reset_full.eml").to_string();
+    let dns_response = serde_json::json!({
+        "method": "get-dns-records",
+        "response": {
+            "selector": "20230601",
+            "domain": "gmail.com",
+            "name": "20230601._domainkey.gmail.com",
+            "type": "TXT",
+            "records": [GMAIL_DKIM_RECORD],
+            "error": null
+        }
+    });
+
+    let vr = contract.on_email_verification_onchain_result(
+        test_account_id("relayer.testnet"),
+        email_blob,
+        Ok(Some(dns_response)),
+    );
+    assert!(vr.verified);
+    assert_eq!(vr.from_address_hash.len(), 32);
+    assert_eq!(vr.selector, "20230601");
+
+    let expected = vr.from_address_hash;
+    let account_id = "kerp30.w3a-v1.testnet".to_string();
+    assert_eq!(
+        contract.get_from_address_hash("Pta <n6378056@gmail.com>".to_string(), account_id.clone()),
+        expected
+    );
+    assert_eq!(
+        contract.get_from_address_hash("From: Pta <N6378056@Gmail.com>".to_string(), account_id.clone()),
+        expected
+    );
+    assert_eq!(
+        contract.get_from_address_hash("n6378056@gmail.com".to_string(), account_id),
+        expected
+    );
+}
+
+#[test]
+fn from_address_hash_view_empty_inputs_return_empty_hash() {
+    testing_env!(VMContextBuilder::new().build());
+    let contract = EmailDkimVerifier::new();
+
+    assert!(contract
+        .get_from_address_hash(String::new(), "alice.testnet".to_string())
+        .is_empty());
+    assert!(contract
+        .get_from_address_hash("alice@example.com".to_string(), "  ".to_string())
+        .is_empty());
+}
@@ -0,0 +1,192 @@
+#![cfg(feature = "legacy-onchain")]
+
+use email_dkim_verifier_contract::onchain_verify::dkim::verify_dkim;
+use email_dkim_verifier_contract::{EmailDkimVerifier, VerificationResult};
+use near_sdk::serde_json;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::{AccountId, PromiseOrValue};
+use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey};
+
+const GMAIL_DKIM_RECORD: &str = "v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB";
+
+/// DKIM record for `data/test_dkim_private_key.pem` (`d=example.com; s=test`).
+fn test_key_record() -> String {
+    let spki = rsa::RsaPrivateKey::from_pkcs8_pem(include_str!("data/test_dkim_private_key.pem"))
+        .expect("test key")
+        .to_public_key()
+        .to_public_key_der()
+        .expect("encode public key");
+    format!("v=DKIM1; k=rsa; p={}", base64::encode(spki.as_bytes()))
+}
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(predecessor: &str) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id(predecessor))
+        .build());
+}
+
+fn contract_with_inline_dns() -> EmailDkimVerifier {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_allow_inline_dns(true);
+    set_context("relayer.testnet");
+    contract
+}
+
+fn request_inline(
+    contract: &mut EmailDkimVerifier,
+    email_blob: &str,
+    dns_records: Vec<String>,
+) -> VerificationResult {
+    match contract.request_email_verification_onchain(
+        test_account_id("relayer.testnet"),
+        email_blob.to_string(),
+        Some(dns_records),
+    ) {
+        PromiseOrValue::Value(vr) => vr,
+        PromiseOrValue::Promise(_) => panic!("inline DNS records must not schedule a promise"),
+    }
+}
+
+#[test]
+fn inline_dns_is_disabled_by_default() {
+    set_context("verifier.testnet");
+    let contract = EmailDkimVerifier::new();
+    assert!(!contract.get_allow_inline_dns());
+}
+
+#[test]
+#[should_panic(expected = "Only the contract owner can set the inline DNS policy")]
+fn non_owner_cannot_enable_inline_dns() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    set_context("mallory.testnet");
+    contract.set_allow_inline_dns(true);
+}
+
+#[test]
+#[should_panic(expected = "Inline DNS records are disabled on this contract")]
+fn inline_dns_records_rejected_when_disabled() {
+    set_context("relayer.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    request_inline(
+        &mut contract,
+        include_str!("data/gmail_reset_full.eml"),
+        vec![GMAIL_DKIM_RECORD.to_string()],
+    );
+}
+
+#[test]
+fn inline_dns_records_verify_without_outlayer() {
+    let mut contract = contract_with_inline_dns();
+    let email_blob = include_str!("data/gmail_reset_full.eml");
+
+    let vr = request_inline(&mut contract, email_blob, vec![GMAIL_DKIM_RECORD.to_string()]);
+    assert!(vr.verified, "{:?}", vr.error);
+    assert_eq!(vr.selector, "20230601");
+    assert_eq!(vr.account_id, "kerp30.w3a-v1.testnet");
+
+    // Same result as the Outlayer callback path given the same record.
+    let dns_response = serde_json::json!({
+        "method": "get-dns-records",
+        "response": {
+            "selector": "20230601",
+            "domain": "gmail.com",
+            "name": "20230601._domainkey.gmail.com",
+            "type": "TXT",
+            "records": [GMAIL_DKIM_RECORD],
+            "error": null
+        }
+    });
+    let callback_vr = contract.on_email_verification_onchain_result(
+        test_account_id("relayer.testnet"),
+        email_blob.to_string(),
+        Ok(Some(dns_response)),
+    );
+    assert_eq!(vr.from_address_hash, callback_vr.from_address_hash);
+    assert_eq!(vr.new_public_key, callback_vr.new_public_key);
+    assert_eq!(vr.email_timestamp_ms, callback_vr.email_timestamp_ms);
+}
+
+#[test]
+fn inline_dns_records_for_another_key_fail_verification() {
+    let mut contract = contract_with_inline_dns();
+    let vr = request_inline(
+        &mut contract,
+        include_str!("data/empty_body.eml"),
+        vec![GMAIL_DKIM_RECORD.to_string()],
+    );
+    assert!(!vr.verified);
+    assert_eq!(vr.error.as_deref(), Some("dkim_verification_failed"));
+}
+
+#[test]
+fn empty_inline_dns_records_fail() {
+    let mut contract = contract_with_inline_dns();
+    let vr = request_inline(&mut contract, include_str!("data/gmail_reset_full.eml"), Vec::new());
+    assert!(!vr.verified);
+    assert_eq!(vr.error.as_deref(), Some("dns_records_empty"));
+}
+
+#[test]
+fn second_from_header_is_rejected_despite_valid_signature() {
+    let mut contract = contract_with_inline_dns();
+    let email_blob = include_str!("data/empty_body.eml")
+        .replacen("From: Alice", "From: Mallory <mallory@example.net>\r\nFrom: Alice", 1);
+
+    // `h=from` signs only the bottom-most From:, so the signature still verifies.
+    assert!(verify_dkim(&email_blob, &[test_key_record()]));
+
+    let vr = request_inline(&mut contract, &email_blob, vec![test_key_record()]);
+    assert!(!vr.verified);
+    assert_eq!(vr.error.as_deref(), Some("multiple_from_headers"));
+    assert_eq!(vr.request_id, "EMPTY1");
+}
+
+#[test]
+fn mailing_list_email_is_rejected_only_under_policy() {
+    let email_blob = include_str!("data/mailing_list_rewritten.eml");
+
+    let mut contract = contract_with_inline_dns();
+    let vr = request_inline(&mut contract, email_blob, vec![test_key_record()]);
+    assert!(vr.verified, "{:?}", vr.error);
+
+    set_context("verifier.testnet");
+    contract.set_reject_mailing_list_emails(true);
+    set_context("relayer.testnet");
+    let vr = request_inline(&mut contract, email_blob, vec![test_key_record()]);
+    assert!(!vr.verified);
+    assert_eq!(
+        vr.error.as_deref(),
+        Some("mailing_list_message: list-id, list-unsubscribe, sender")
+    );
+    assert_eq!(vr.request_id, "LIST01");
+}
+
+#[test]
+fn inline_dns_records_verify_only_the_first_signature_selector() {
+    let mut contract = contract_with_inline_dns();
+    // The first signature (`d=forwarder.example; s=fwd`) is broken; the second
+    // claims `d=example.com` but was made with the key supplied for `fwd`.
+    let email_blob =
+        include_str!("data/two_signatures.eml").replacen("b=WWs6S/14", "b=AAAAS/14", 1);
+    let vr = request_inline(&mut contract, &email_blob, vec![test_key_record()]);
+    assert!(!vr.verified);
+    assert_eq!(vr.error.as_deref(), Some("dkim_verification_failed"));
+
+    // Unbroken, the forwarder's own signature verifies with the same record.
+    let vr = request_inline(
+        &mut contract,
+        include_str!("data/two_signatures.eml"),
+        vec![test_key_record()],
+    );
+    assert!(vr.verified, "{:?}", vr.error);
+    assert_eq!(vr.signing_domain, "forwarder.example");
+    assert_eq!(vr.selector, "fwd");
+}
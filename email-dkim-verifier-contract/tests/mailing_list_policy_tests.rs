@@ -0,0 +1,39 @@
+use email_dkim_verifier_contract::EmailDkimVerifier;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(predecessor: &str) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id(predecessor))
+        .build());
+}
+
+#[test]
+fn mailing_list_emails_are_accepted_by_default() {
+    set_context("verifier.testnet");
+    let contract = EmailDkimVerifier::new();
+    assert!(!contract.get_reject_mailing_list_emails());
+}
+
+#[test]
+fn owner_can_reject_mailing_list_emails() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_reject_mailing_list_emails(true);
+    assert!(contract.get_reject_mailing_list_emails());
+}
+
+#[test]
+#[should_panic(expected = "Only the contract owner can set the mailing list policy")]
+fn non_owner_cannot_set_mailing_list_policy() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    set_context("mallory.testnet");
+    contract.set_reject_mailing_list_emails(true);
+}
@@ -0,0 +1,56 @@
+//! Minimal stand-in for the OutLayer contract used by sandbox tests.
+//!
+//! Records the deposit attached to `request_execution` and returns a
+//! pre-canned worker response set via `set_response`.
+
+use near_sdk::{env, near, serde_json, AccountId, NearToken};
+
+#[near(contract_state)]
+#[derive(Default)]
+pub struct MockOutlayer {
+    response: Option<String>,
+    last_deposit: NearToken,
+    last_input_data: Option<String>,
+    last_payer_account_id: Option<AccountId>,
+}
+
+#[near]
+impl MockOutlayer {
+    /// Set the JSON value returned from the next `request_execution` calls.
+    /// `None` makes `request_execution` return `null`.
+    pub fn set_response(&mut self, response: Option<serde_json::Value>) {
+        self.response = response.map(|value| value.to_string());
+    }
+
+    #[payable]
+    #[allow(unused_variables)]
+    pub fn request_execution(
+        &mut self,
+        source: serde_json::Value,
+        resource_limits: serde_json::Value,
+        input_data: String,
+        secrets_ref: Option<serde_json::Value>,
+        response_format: String,
+        payer_account_id: Option<AccountId>,
+        params: Option<serde_json::Value>,
+    ) -> Option<serde_json::Value> {
+        self.last_deposit = env::attached_deposit();
+        self.last_input_data = Some(input_data);
+        self.last_payer_account_id = payer_account_id;
+        self.response
+            .as_deref()
+            .map(|raw| serde_json::from_str(raw).expect("stored response is valid JSON"))
+    }
+
+    pub fn get_last_deposit(&self) -> NearToken {
+        self.last_deposit
+    }
+
+    pub fn get_last_input_data(&self) -> Option<String> {
+        self.last_input_data.clone()
+    }
+
+    pub fn get_last_payer_account_id(&self) -> Option<AccountId> {
+        self.last_payer_account_id.clone()
+    }
+}
@@ -0,0 +1,113 @@
+use email_dkim_verifier_contract::{EmailDkimVerifier, PinnedDkimKey};
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+const GMAIL_DKIM_RECORD: &str = "v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB";
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(predecessor: &str) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id(predecessor))
+        .build());
+}
+
+#[test]
+fn owner_can_pin_and_remove_dkim_keys() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    assert!(contract.get_pinned_dkim_keys().is_empty());
+
+    contract.set_pinned_dkim_key(
+        " Gmail.com ".to_string(),
+        "20230601".to_string(),
+        vec![GMAIL_DKIM_RECORD.to_string()],
+    );
+    assert_eq!(
+        contract.get_pinned_dkim_keys(),
+        vec![PinnedDkimKey {
+            domain: "gmail.com".to_string(),
+            selector: "20230601".to_string(),
+            records: vec![GMAIL_DKIM_RECORD.to_string()],
+        }]
+    );
+
+    assert!(contract.remove_pinned_dkim_key("gmail.com".to_string(), "20230601".to_string()));
+    assert!(!contract.remove_pinned_dkim_key("gmail.com".to_string(), "20230601".to_string()));
+    assert!(contract.get_pinned_dkim_keys().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Only the contract owner can pin DKIM keys")]
+fn non_owner_cannot_pin_dkim_keys() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    set_context("mallory.testnet");
+    contract.set_pinned_dkim_key(
+        "gmail.com".to_string(),
+        "20230601".to_string(),
+        vec![GMAIL_DKIM_RECORD.to_string()],
+    );
+}
+
+#[cfg(feature = "legacy-onchain")]
+mod onchain {
+    use super::*;
+    use near_sdk::serde_json;
+
+    fn gmail_dns_response(records: Vec<&str>, error: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "method": "get-dns-records",
+            "response": {
+                "selector": "20230601",
+                "domain": "gmail.com",
+                "name": "20230601._domainkey.gmail.com",
+                "type": "TXT",
+                "records": records,
+                "error": error
+            }
+        })
+    }
+
+    #[test]
+    fn pinned_key_verifies_even_when_dns_lookup_failed() {
+        set_context("verifier.testnet");
+        let mut contract = EmailDkimVerifier::new();
+        contract.set_pinned_dkim_key(
+            "gmail.com".to_string(),
+            "20230601".to_string(),
+            vec![GMAIL_DKIM_RECORD.to_string()],
+        );
+
+        let vr = contract.on_email_verification_onchain_result(
+            test_account_id("relayer.testnet"),
+            include_str!("data/gmail_reset_full.eml").to_string(),
+            Ok(Some(gmail_dns_response(Vec::new(), Some("HTTP status 503")))),
+        );
+        assert!(vr.verified, "{:?}", vr.error);
+        assert_eq!(vr.selector, "20230601");
+    }
+
+    #[test]
+    fn pinned_revoked_key_rejects_despite_valid_dns_record() {
+        set_context("verifier.testnet");
+        let mut contract = EmailDkimVerifier::new();
+        contract.set_pinned_dkim_key(
+            "gmail.com".to_string(),
+            "20230601".to_string(),
+            vec!["v=DKIM1; k=rsa; p=".to_string()],
+        );
+
+        let vr = contract.on_email_verification_onchain_result(
+            test_account_id("relayer.testnet"),
+            include_str!("data/gmail_reset_full.eml").to_string(),
+            Ok(Some(gmail_dns_response(vec![GMAIL_DKIM_RECORD], None))),
+        );
+        assert!(!vr.verified);
+        assert_eq!(vr.error.as_deref(), Some("dkim_verification_failed"));
+    }
+}
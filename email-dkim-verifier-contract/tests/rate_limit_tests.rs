@@ -0,0 +1,270 @@
+use email_dkim_verifier_contract::rate_limit::{RateLimits, RATE_LIMIT_WINDOW_NS};
+use email_dkim_verifier_contract::EmailDkimVerifier;
+use near_sdk::serde_json;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(block_timestamp: u64) {
+    let contract_id = test_account_id("verifier.testnet");
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(contract_id.clone())
+        .predecessor_account_id(contract_id)
+        .block_timestamp(block_timestamp)
+        .build());
+}
+
+fn verified_worker_response() -> serde_json::Value {
+    serde_json::json!({
+        "method": "verify-encrypted-email",
+        "response": {
+            "verified": true,
+            "account_id": "alice.testnet",
+            "new_public_key": "ed25519:abc",
+            "from_address_hash": [1, 2, 3],
+            "email_timestamp_ms": 1700000000000u64,
+            "request_id": "RID",
+            "selector": "test",
+            "signing_domain": "Example.com",
+            "error": null
+        }
+    })
+}
+
+#[test]
+fn private_path_rate_limits_requester_and_resets_after_window() {
+    set_context(1_000);
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_max_requests_per_domain_per_day(2);
+    let relayer = test_account_id("relayer.testnet");
+
+    for _ in 0..2 {
+        let vr = contract.on_email_verification_private_result(
+            relayer.clone(),
+            "RID".to_string(),
+            Ok(Some(verified_worker_response())),
+        );
+        assert!(vr.verified);
+    }
+
+    let vr = contract.on_email_verification_private_result(
+        relayer.clone(),
+        "RID".to_string(),
+        Ok(Some(verified_worker_response())),
+    );
+    assert!(!vr.verified);
+    assert_eq!(vr.request_id, "RID");
+    assert_eq!(vr.error.as_deref(), Some("rate_limited"));
+
+    // Fast-forward past the window: the counter starts over.
+    set_context(1_000 + RATE_LIMIT_WINDOW_NS);
+    let vr = contract.on_email_verification_private_result(
+        relayer,
+        "RID".to_string(),
+        Ok(Some(verified_worker_response())),
+    );
+    assert!(vr.verified);
+    assert!(vr.error.is_none());
+}
+
+#[test]
+fn private_path_rate_limits_verified_signing_domain_across_requesters() {
+    set_context(1_000);
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_max_requests_per_domain_per_day(1);
+
+    let vr = contract.on_email_verification_private_result(
+        test_account_id("relayer-one.testnet"),
+        "RID".to_string(),
+        Ok(Some(verified_worker_response())),
+    );
+    assert!(vr.verified, "{:?}", vr.error);
+    assert_eq!(vr.signing_domain, "example.com");
+
+    // A different requester hits the same domain counter.
+    let vr = contract.on_email_verification_private_result(
+        test_account_id("relayer-two.testnet"),
+        "RID".to_string(),
+        Ok(Some(verified_worker_response())),
+    );
+    assert!(!vr.verified);
+    assert_eq!(vr.error.as_deref(), Some("rate_limited"));
+
+    // Another domain has its own counter.
+    let mut other_domain = verified_worker_response();
+    other_domain["response"]["signing_domain"] = serde_json::json!("other.example");
+    let vr = contract.on_email_verification_private_result(
+        test_account_id("relayer-three.testnet"),
+        "RID".to_string(),
+        Ok(Some(other_domain)),
+    );
+    assert!(vr.verified, "{:?}", vr.error);
+}
+
+/// DKIM record for `data/test_dkim_private_key.pem` (`d=example.com; s=test`).
+#[cfg(feature = "legacy-onchain")]
+fn test_key_record() -> String {
+    use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey};
+
+    let spki = rsa::RsaPrivateKey::from_pkcs8_pem(include_str!("data/test_dkim_private_key.pem"))
+        .expect("test key")
+        .to_public_key()
+        .to_public_key_der()
+        .expect("encode public key");
+    format!("v=DKIM1; k=rsa; p={}", base64::encode(spki.as_bytes()))
+}
+
+/// Run the on-chain callback for `email_blob` as `requested_by`, with the
+/// test key served as the DNS lookup result.
+#[cfg(feature = "legacy-onchain")]
+fn onchain_result_with_test_key(
+    contract: &mut EmailDkimVerifier,
+    requested_by: &str,
+    email_blob: &str,
+) -> email_dkim_verifier_contract::VerificationResult {
+    let dns_response = serde_json::json!({
+        "method": "get-dns-records",
+        "response": {
+            "selector": "test",
+            "domain": "example.com",
+            "name": "test._domainkey.example.com",
+            "type": "TXT",
+            "records": [test_key_record()],
+            "error": null
+        }
+    });
+    contract.on_email_verification_onchain_result(
+        test_account_id(requested_by),
+        email_blob.to_string(),
+        Ok(Some(dns_response)),
+    )
+}
+
+#[cfg(feature = "legacy-onchain")]
+#[test]
+fn onchain_path_rate_limits_verified_signing_domain_across_requesters() {
+    set_context(1_000);
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_max_requests_per_domain_per_day(1);
+
+    // The first DKIM-Signature claims `d=forwarder.example`; only the second
+    // one (`d=example.com`) verifies, and that is the domain charged.
+    let vr = onchain_result_with_test_key(
+        &mut contract,
+        "relayer-one.testnet",
+        include_str!("data/two_signatures.eml"),
+    );
+    assert!(vr.verified, "{:?}", vr.error);
+    assert_eq!(vr.signing_domain, "example.com");
+
+    // A different requester hits the same domain counter.
+    let vr = onchain_result_with_test_key(
+        &mut contract,
+        "relayer-two.testnet",
+        include_str!("data/empty_body.eml"),
+    );
+    assert!(!vr.verified);
+    assert_eq!(vr.request_id, "EMPTY1");
+    assert_eq!(vr.error.as_deref(), Some("rate_limited"));
+}
+
+#[cfg(feature = "legacy-onchain")]
+#[test]
+fn failed_verification_is_not_charged_to_the_claimed_domain() {
+    set_context(1_000);
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_max_requests_per_domain_per_day(1);
+
+    // Anyone can put `d=example.com` on an email that does not verify.
+    let forged = include_str!("data/empty_body.eml").replacen("Alice", "Mallory", 1);
+    for requester in ["mallory-one.testnet", "mallory-two.testnet"] {
+        let vr = onchain_result_with_test_key(&mut contract, requester, &forged);
+        assert_eq!(vr.error.as_deref(), Some("dkim_verification_failed"));
+    }
+
+    let vr = onchain_result_with_test_key(
+        &mut contract,
+        "relayer.testnet",
+        include_str!("data/empty_body.eml"),
+    );
+    assert!(vr.verified, "{:?}", vr.error);
+}
+
+#[test]
+fn failed_private_result_still_counts_against_requester() {
+    set_context(1_000);
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_max_requests_per_domain_per_day(1);
+    let relayer = test_account_id("relayer.testnet");
+
+    let vr = contract.on_email_verification_private_result(
+        relayer.clone(),
+        "RID".to_string(),
+        Ok(None),
+    );
+    assert_eq!(vr.error.as_deref(), Some("outlayer_execution_failed"));
+
+    let vr = contract.on_email_verification_private_result(
+        relayer,
+        "RID".to_string(),
+        Ok(Some(verified_worker_response())),
+    );
+    assert_eq!(vr.error.as_deref(), Some("rate_limited"));
+}
+
+#[test]
+fn expired_windows_are_deleted_by_later_requests() {
+    set_context(1_000);
+    let mut limits = RateLimits::new(b"r".to_vec(), b"s".to_vec());
+    assert!(limits.record("domain:quiet.example", 1, 1_000));
+    assert!(limits.record("domain:busy.example", 1, 2_000));
+    assert!(limits.window("domain:quiet.example").is_some());
+
+    // Still inside the quiet key's window: nothing is deleted.
+    assert!(limits.record("account:relayer.testnet", 1, RATE_LIMIT_WINDOW_NS));
+    assert!(limits.window("domain:quiet.example").is_some());
+
+    // Once both windows have ended, any request deletes them, even though
+    // neither key is used again.
+    let later = 2_000 + RATE_LIMIT_WINDOW_NS;
+    assert!(limits.record("account:other.testnet", 1, later));
+    assert!(limits.window("domain:quiet.example").is_none());
+    assert!(limits.window("domain:busy.example").is_none());
+    assert!(limits.window("account:relayer.testnet").is_some());
+}
+
+#[test]
+fn restarted_window_is_not_deleted_with_its_old_start() {
+    set_context(1_000);
+    let mut limits = RateLimits::new(b"r".to_vec(), b"s".to_vec());
+    assert!(limits.record("domain:example.com", 1, 1_000));
+
+    // The key opens a new window; the stale start must not delete it.
+    let restarted = 1_000 + RATE_LIMIT_WINDOW_NS;
+    assert!(limits.record("domain:example.com", 1, restarted));
+    assert!(limits.record("account:relayer.testnet", 1, restarted + 1));
+    let window = limits.window("domain:example.com").expect("live window");
+    assert_eq!(window.window_start_ns, restarted);
+    assert!(!limits.record("domain:example.com", 1, restarted + 2));
+}
+
+#[test]
+fn rate_limiting_is_disabled_by_default() {
+    set_context(1_000);
+    let mut contract = EmailDkimVerifier::new();
+    assert_eq!(contract.get_max_requests_per_domain_per_day(), 0);
+    let relayer = test_account_id("relayer.testnet");
+
+    for _ in 0..5 {
+        let vr = contract.on_email_verification_private_result(
+            relayer.clone(),
+            "RID".to_string(),
+            Ok(Some(verified_worker_response())),
+        );
+        assert!(vr.verified);
+    }
+}
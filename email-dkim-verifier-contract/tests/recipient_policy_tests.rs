@@ -0,0 +1,117 @@
+use email_dkim_verifier_contract::EmailDkimVerifier;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(predecessor: &str) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id(predecessor))
+        .build());
+}
+
+#[test]
+fn any_recipient_is_accepted_by_default() {
+    set_context("verifier.testnet");
+    let contract = EmailDkimVerifier::new();
+    assert_eq!(contract.get_expected_to_domain(), None);
+}
+
+#[test]
+fn owner_can_set_and_clear_expected_to_domain() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_expected_to_domain(Some(" @Web3Authn.org ".to_string()));
+    assert_eq!(
+        contract.get_expected_to_domain().as_deref(),
+        Some("web3authn.org")
+    );
+
+    contract.set_expected_to_domain(Some(" ".to_string()));
+    assert_eq!(contract.get_expected_to_domain(), None);
+}
+
+#[test]
+#[should_panic(expected = "Only the contract owner can set the expected To domain")]
+fn non_owner_cannot_set_expected_to_domain() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    set_context("mallory.testnet");
+    contract.set_expected_to_domain(Some("example.com".to_string()));
+}
+
+#[cfg(feature = "legacy-onchain")]
+mod onchain {
+    use super::*;
+    use email_dkim_verifier_contract::onchain_verify::parsers::{
+        address_in_domain, parse_to_addresses,
+    };
+    use email_dkim_verifier_contract::MIN_DEPOSIT;
+    use near_sdk::NearToken;
+
+    fn contract_expecting(domain: &str) -> EmailDkimVerifier {
+        set_context("verifier.testnet");
+        let mut contract = EmailDkimVerifier::new();
+        contract.set_expected_to_domain(Some(domain.to_string()));
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(test_account_id("verifier.testnet"))
+            .predecessor_account_id(test_account_id("relayer.testnet"))
+            .attached_deposit(NearToken::from_yoctonear(MIN_DEPOSIT))
+            .build());
+        contract
+    }
+
+    fn request_onchain(contract: &mut EmailDkimVerifier, email_blob: &str) {
+        let _ = contract.request_email_verification_onchain(
+            test_account_id("relayer.testnet"),
+            email_blob.to_string(),
+            None,
+        );
+    }
+
+    #[test]
+    fn to_addresses_are_split_and_normalized() {
+        let email = "To: \"Ops, Team\" <ops@example.com>, recover@web3authn.org\r\n\r\nbody\r\n";
+        assert_eq!(
+            parse_to_addresses(email),
+            vec![
+                "ops@example.com".to_string(),
+                "recover@web3authn.org".to_string()
+            ]
+        );
+        assert!(address_in_domain("recover@Web3Authn.org", "web3authn.org"));
+        assert!(!address_in_domain(
+            "recover@mail.web3authn.org",
+            "web3authn.org"
+        ));
+    }
+
+    #[test]
+    fn email_to_expected_domain_is_forwarded() {
+        let mut contract = contract_expecting("web3authn.org");
+        request_onchain(&mut contract, include_str!("data/gmail_reset_full.eml"));
+    }
+
+    #[test]
+    #[should_panic(expected = "email_blob is not addressed to example.com")]
+    fn email_to_other_domain_is_rejected() {
+        let mut contract = contract_expecting("example.com");
+        request_onchain(&mut contract, include_str!("data/gmail_reset_full.eml"));
+    }
+
+    #[test]
+    #[should_panic(expected = "email_blob is not addressed to web3authn.org")]
+    fn email_without_to_header_is_rejected() {
+        let mut contract = contract_expecting("web3authn.org");
+        let email_blob = include_str!("data/gmail_reset_full.eml").replacen(
+            "To: recover@web3authn.org\n",
+            "",
+            1,
+        );
+        request_onchain(&mut contract, &email_blob);
+    }
+}
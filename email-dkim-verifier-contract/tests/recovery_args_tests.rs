@@ -0,0 +1,62 @@
+use email_dkim_verifier_contract::VerificationResult;
+use near_sdk::serde_json;
+
+const NEW_PUBLIC_KEY: &str = "ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm";
+
+fn verified_result(account_id: &str, new_public_key: &str) -> VerificationResult {
+    VerificationResult {
+        verified: true,
+        account_id: account_id.to_string(),
+        new_public_key: new_public_key.to_string(),
+        from_address_hash: vec![1; 32],
+        email_timestamp_ms: Some(1_765_268_003_000),
+        request_id: "123abc".to_string(),
+        selector: "20230601".to_string(),
+        signing_domain: "gmail.com".to_string(),
+        error: None,
+    }
+}
+
+#[test]
+fn verified_result_yields_recovery_args() {
+    let vr = verified_result("kerp30.w3a-v1.testnet", NEW_PUBLIC_KEY);
+    assert_eq!(
+        vr.to_recovery_args(),
+        Some(serde_json::json!({
+            "account_id": "kerp30.w3a-v1.testnet",
+            "new_public_key": NEW_PUBLIC_KEY,
+        }))
+    );
+}
+
+#[test]
+fn unverified_result_is_not_actionable() {
+    let mut vr = verified_result("kerp30.w3a-v1.testnet", NEW_PUBLIC_KEY);
+    vr.verified = false;
+    assert_eq!(vr.to_recovery_args(), None);
+
+    let failure = VerificationResult::failure("123abc", "dkim_verification_failed");
+    assert_eq!(failure.to_recovery_args(), None);
+}
+
+#[test]
+fn missing_or_invalid_account_id_is_not_actionable() {
+    for account_id in ["", "Not An Account!"] {
+        let vr = verified_result(account_id, NEW_PUBLIC_KEY);
+        assert_eq!(vr.to_recovery_args(), None, "{account_id:?}");
+    }
+}
+
+#[test]
+fn missing_or_invalid_public_key_is_not_actionable() {
+    for new_public_key in [
+        "",
+        "ed25519:",
+        "ed25519:NEW_PUBLIC_KEY",
+        "86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm",
+        "secp256k1:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm",
+    ] {
+        let vr = verified_result("kerp30.w3a-v1.testnet", new_public_key);
+        assert_eq!(vr.to_recovery_args(), None, "{new_public_key:?}");
+    }
+}
@@ -0,0 +1,93 @@
+use email_dkim_verifier_contract::tee_verify::AeadContext;
+use email_dkim_verifier_contract::{EmailDkimVerifier, MIN_DEPOSIT};
+use near_sdk::serde_json;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::{AccountId, NearToken};
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn relayer_contract() -> EmailDkimVerifier {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id("relayer.testnet"))
+        .attached_deposit(NearToken::from_yoctonear(MIN_DEPOSIT))
+        .build());
+    EmailDkimVerifier::new()
+}
+
+fn request_private(ciphertext: serde_json::Value) {
+    let _ = relayer_contract().request_email_verification_private(
+        test_account_id("relayer.testnet"),
+        serde_json::json!({
+            "version": 1,
+            "ephemeral_pub": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+            "nonce": "AAAAAAAAAAAAAAAA",
+            "ciphertext": ciphertext,
+        }),
+        AeadContext {
+            account_id: "alice.testnet".to_string(),
+            network_id: "testnet".to_string(),
+            payer_account_id: "relayer.testnet".to_string(),
+            request_id: None,
+            expected_from_address_hash: None,
+            expected_account_id: None,
+        },
+        None,
+    );
+}
+
+#[test]
+fn private_request_with_ciphertext_is_forwarded() {
+    request_private(serde_json::json!("AAAAAAAAAAAAAAAAAAAAAA=="));
+}
+
+#[test]
+#[should_panic(expected = "encrypted_email_blob.ciphertext must not be empty")]
+fn private_request_rejects_empty_ciphertext() {
+    request_private(serde_json::json!(" "));
+}
+
+#[test]
+#[should_panic(expected = "encrypted_email_blob.ciphertext must not be empty")]
+fn private_request_rejects_missing_ciphertext() {
+    request_private(serde_json::Value::Null);
+}
+
+#[cfg(feature = "legacy-onchain")]
+mod onchain {
+    use super::*;
+
+    fn request_onchain(email_blob: &str) {
+        let _ = relayer_contract().request_email_verification_onchain(
+            test_account_id("relayer.testnet"),
+            email_blob.to_string(),
+            None,
+        );
+    }
+
+    #[test]
+    fn onchain_request_with_dkim_signature_is_forwarded() {
+        request_onchain(include_str!("data/gmail_reset_full.eml"));
+    }
+
+    #[test]
+    #[should_panic(expected = "email_blob must not be empty")]
+    fn onchain_request_rejects_empty_blob() {
+        request_onchain("");
+    }
+
+    #[test]
+    #[should_panic(expected = "email_blob must not be empty")]
+    fn onchain_request_rejects_whitespace_blob() {
+        request_onchain(" \r\n\t");
+    }
+
+    #[test]
+    #[should_panic(expected = "email_blob has no DKIM-Signature header")]
+    fn onchain_request_rejects_blob_without_dkim_signature() {
+        request_onchain("From: alice@example.com\r\nSubject: hi\r\n\r\nbody\r\n");
+    }
+}
@@ -0,0 +1,96 @@
+use email_dkim_verifier_contract::tee_verify::AeadContext;
+use email_dkim_verifier_contract::{EmailDkimVerifier, MIN_DEPOSIT};
+use near_sdk::serde_json;
+use near_sdk::test_utils::{get_logs, VMContextBuilder};
+use near_sdk::testing_env;
+use near_sdk::{AccountId, NearToken};
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(predecessor: &str, attached_deposit: NearToken) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id(predecessor))
+        .attached_deposit(attached_deposit)
+        .build());
+}
+
+fn assert_logged(prefix: &str) {
+    let logs = get_logs();
+    assert!(
+        logs.iter().any(|line| line.starts_with(prefix)),
+        "no log line starting with {prefix:?} in {logs:?}"
+    );
+}
+
+#[test]
+fn private_refund_log_carries_request_id() {
+    set_context("verifier.testnet", NearToken::from_yoctonear(0));
+    let mut contract = EmailDkimVerifier::new();
+
+    set_context("relayer.testnet", NearToken::from_yoctonear(MIN_DEPOSIT * 3));
+    let _ = contract.request_email_verification_private(
+        test_account_id("relayer.testnet"),
+        serde_json::json!({
+            "version": 1,
+            "ephemeral_pub": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+            "nonce": "AAAAAAAAAAAAAAAA",
+            "ciphertext": "AAAAAAAAAAAAAAAAAAAAAA=="
+        }),
+        AeadContext {
+            account_id: "alice.testnet".to_string(),
+            network_id: "testnet".to_string(),
+            payer_account_id: "relayer.testnet".to_string(),
+            request_id: None,
+            expected_from_address_hash: None,
+            expected_account_id: None,
+        },
+        Some("RID42".to_string()),
+    );
+
+    assert_logged("[request_id=RID42] Refunding");
+}
+
+#[test]
+fn private_failure_log_carries_request_id() {
+    set_context("verifier.testnet", NearToken::from_yoctonear(0));
+    let mut contract = EmailDkimVerifier::new();
+
+    let vr = contract.on_email_verification_private_result(
+        test_account_id("relayer.testnet"),
+        "RID42".to_string(),
+        Ok(Some(serde_json::json!({ "method": "get-dns-records", "response": {} }))),
+    );
+    assert!(!vr.verified);
+
+    assert_logged("[request_id=RID42] Unexpected worker method");
+}
+
+#[cfg(feature = "legacy-onchain")]
+#[test]
+fn onchain_dns_failure_log_carries_email_request_id() {
+    set_context("verifier.testnet", NearToken::from_yoctonear(0));
+    let mut contract = EmailDkimVerifier::new();
+
+    let vr = contract.on_email_verification_onchain_result(
+        test_account_id("relayer.testnet"),
+        include_str!("data/gmail_reset_full.eml").to_string(),
+        Ok(Some(serde_json::json!({
+            "method": "get-dns-records",
+            "response": {
+                "selector": "20230601",
+                "domain": "gmail.com",
+                "name": "20230601._domainkey.gmail.com",
+                "type": "TXT",
+                "records": [],
+                "error": "HTTP status 503"
+            }
+        }))),
+    );
+    assert!(!vr.verified);
+    assert!(!vr.request_id.is_empty());
+
+    assert_logged(&format!("[request_id={}] DKIM DNS fetch error", vr.request_id));
+}
@@ -0,0 +1,39 @@
+use email_dkim_verifier_contract::EmailDkimVerifier;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(predecessor: &str) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id(predecessor))
+        .build());
+}
+
+#[test]
+fn resent_header_fallback_is_disabled_by_default() {
+    set_context("verifier.testnet");
+    let contract = EmailDkimVerifier::new();
+    assert!(!contract.get_allow_resent_headers());
+}
+
+#[test]
+fn owner_can_enable_resent_header_fallback() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_allow_resent_headers(true);
+    assert!(contract.get_allow_resent_headers());
+}
+
+#[test]
+#[should_panic(expected = "Only the contract owner can set the Resent-* header policy")]
+fn non_owner_cannot_enable_resent_header_fallback() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    set_context("mallory.testnet");
+    contract.set_allow_resent_headers(true);
+}
@@ -0,0 +1,135 @@
+use email_dkim_verifier_contract::{EmailDkimVerifier, DEFAULT_MAX_DKIM_SIGNATURES};
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(predecessor: &str) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id(predecessor))
+        .build());
+}
+
+#[test]
+fn any_signature_policy_is_the_default() {
+    set_context("verifier.testnet");
+    let contract = EmailDkimVerifier::new();
+    assert!(!contract.get_require_all_signatures());
+}
+
+#[test]
+fn owner_can_require_all_signatures() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_require_all_signatures(true);
+    assert!(contract.get_require_all_signatures());
+}
+
+#[test]
+#[should_panic(expected = "Only the contract owner can set the DKIM signature policy")]
+fn non_owner_cannot_set_signature_policy() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    set_context("mallory.testnet");
+    contract.set_require_all_signatures(true);
+}
+
+#[test]
+fn signature_cap_defaults_to_five() {
+    set_context("verifier.testnet");
+    let contract = EmailDkimVerifier::new();
+    assert_eq!(contract.get_max_dkim_signatures(), DEFAULT_MAX_DKIM_SIGNATURES);
+    assert_eq!(DEFAULT_MAX_DKIM_SIGNATURES, 5);
+}
+
+#[test]
+fn owner_can_set_signature_cap() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_max_dkim_signatures(2);
+    assert_eq!(contract.get_max_dkim_signatures(), 2);
+}
+
+#[test]
+#[should_panic(expected = "max_dkim_signatures must be at least 1")]
+fn signature_cap_of_zero_is_rejected() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_max_dkim_signatures(0);
+}
+
+#[test]
+#[should_panic(expected = "Only the contract owner can set the DKIM signature policy")]
+fn non_owner_cannot_set_signature_cap() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    set_context("mallory.testnet");
+    contract.set_max_dkim_signatures(10);
+}
+
+#[cfg(feature = "legacy-onchain")]
+mod onchain {
+    use super::*;
+    use near_sdk::serde_json;
+    use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey};
+
+    const GMAIL_DKIM_RECORD: &str = "v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB";
+
+    /// DKIM record for `data/test_dkim_private_key.pem`.
+    fn test_key_record() -> String {
+        let spki = rsa::RsaPrivateKey::from_pkcs8_pem(include_str!("data/test_dkim_private_key.pem"))
+            .expect("test key")
+            .to_public_key()
+            .to_public_key_der()
+            .expect("encode public key");
+        format!("v=DKIM1; k=rsa; p={}", base64::encode(spki.as_bytes()))
+    }
+
+    /// Worker DNS response for `two_signatures.eml`: the forwarder's key is
+    /// current, the original signer's key (`example.com`) no longer matches.
+    fn stale_original_key_response() -> serde_json::Value {
+        serde_json::json!({
+            "method": "get-dns-records",
+            "response": {
+                "selector": "fwd",
+                "domain": "forwarder.example",
+                "name": "fwd._domainkey.forwarder.example",
+                "type": "TXT",
+                "records": [test_key_record()],
+                "keys": [
+                    { "selector": "fwd", "domain": "forwarder.example", "record": test_key_record() },
+                    { "selector": "test", "domain": "example.com", "record": GMAIL_DKIM_RECORD }
+                ],
+                "error": null
+            }
+        })
+    }
+
+    #[test]
+    fn require_all_signatures_rejects_partially_verified_email() {
+        set_context("verifier.testnet");
+        let mut contract = EmailDkimVerifier::new();
+        let email_blob = include_str!("data/two_signatures.eml");
+
+        let vr = contract.on_email_verification_onchain_result(
+            test_account_id("relayer.testnet"),
+            email_blob.to_string(),
+            Ok(Some(stale_original_key_response())),
+        );
+        assert!(vr.verified, "{:?}", vr.error);
+        assert_eq!(vr.selector, "fwd");
+
+        contract.set_require_all_signatures(true);
+        let vr = contract.on_email_verification_onchain_result(
+            test_account_id("relayer.testnet"),
+            email_blob.to_string(),
+            Ok(Some(stale_original_key_response())),
+        );
+        assert!(!vr.verified);
+        assert_eq!(vr.error.as_deref(), Some("dkim_verification_failed"));
+    }
+}
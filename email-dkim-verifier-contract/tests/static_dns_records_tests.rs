@@ -0,0 +1,60 @@
+use email_dkim_verifier_contract::{EmailDkimVerifier, StaticDnsRecord};
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+const GMAIL_DKIM_RECORD: &str = "v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB";
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(predecessor: &str) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id(predecessor))
+        .build());
+}
+
+#[test]
+fn owner_can_set_and_remove_static_dns_records() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    assert!(contract.get_static_dns_records().is_empty());
+
+    contract.set_static_dns_records(
+        " 20230601._domainkey.Gmail.com. ".to_string(),
+        vec![GMAIL_DKIM_RECORD.to_string()],
+    );
+    assert_eq!(
+        contract.get_static_dns_records(),
+        vec![StaticDnsRecord {
+            name: "20230601._domainkey.gmail.com".to_string(),
+            records: vec![GMAIL_DKIM_RECORD.to_string()],
+        }]
+    );
+
+    assert!(contract.remove_static_dns_records("20230601._domainkey.gmail.com".to_string()));
+    assert!(!contract.remove_static_dns_records("20230601._domainkey.gmail.com".to_string()));
+    assert!(contract.get_static_dns_records().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Static DNS record name must be <selector>._domainkey.<domain>")]
+fn rejects_name_without_domainkey_label() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_static_dns_records("gmail.com".to_string(), vec![GMAIL_DKIM_RECORD.to_string()]);
+}
+
+#[test]
+#[should_panic(expected = "Only the contract owner can set static DNS records")]
+fn non_owner_cannot_set_static_dns_records() {
+    set_context("verifier.testnet");
+    let mut contract = EmailDkimVerifier::new();
+    set_context("mallory.testnet");
+    contract.set_static_dns_records(
+        "20230601._domainkey.gmail.com".to_string(),
+        vec![GMAIL_DKIM_RECORD.to_string()],
+    );
+}
@@ -0,0 +1,50 @@
+use email_dkim_verifier_contract::{StoredVerificationResultV1, VerificationResult};
+
+fn stored_fixture() -> StoredVerificationResultV1 {
+    StoredVerificationResultV1 {
+        verified: true,
+        account_id: "alice.testnet".to_string(),
+        new_public_key: "ed25519:HPHNMfHwmBJSqcArYZ5ptTZpukvFoMtuU8TcV2T7mEEy".to_string(),
+        from_address_hash: vec![1, 2, 3],
+        email_timestamp_ms: Some(1_767_225_600_000),
+        request_id: "RID123".to_string(),
+    }
+}
+
+/// Golden Borsh encoding of `stored_fixture()`. If this changes, previously
+/// stored entries no longer decode: add a V2 type instead of editing V1.
+fn golden_bytes() -> Vec<u8> {
+    let mut bytes = vec![1u8]; // verified
+    bytes.extend_from_slice(&[13, 0, 0, 0]);
+    bytes.extend_from_slice(b"alice.testnet");
+    bytes.extend_from_slice(&[52, 0, 0, 0]);
+    bytes.extend_from_slice(b"ed25519:HPHNMfHwmBJSqcArYZ5ptTZpukvFoMtuU8TcV2T7mEEy");
+    bytes.extend_from_slice(&[3, 0, 0, 0, 1, 2, 3]); // from_address_hash
+    bytes.extend_from_slice(&[1, 0x00, 0xa8, 0xda, 0x76, 0x9b, 0x01, 0x00, 0x00]); // Some(ts)
+    bytes.extend_from_slice(&[6, 0, 0, 0]);
+    bytes.extend_from_slice(b"RID123");
+    bytes
+}
+
+#[test]
+fn stored_verification_result_v1_borsh_bytes_are_stable() {
+    let encoded = borsh::to_vec(&stored_fixture()).expect("borsh encodes");
+    assert_eq!(encoded, golden_bytes());
+
+    let decoded: StoredVerificationResultV1 =
+        borsh::from_slice(&golden_bytes()).expect("borsh decodes");
+    assert_eq!(decoded, stored_fixture());
+}
+
+#[test]
+fn api_only_fields_do_not_reach_storage() {
+    let mut result = VerificationResult::from(stored_fixture());
+    assert!(result.selector.is_empty());
+    assert_eq!(result.error, None);
+
+    result.selector = "test".to_string();
+    result.error = Some("diagnostic".to_string());
+    let stored = StoredVerificationResultV1::from(&result);
+    assert_eq!(stored, stored_fixture());
+    assert_eq!(borsh::to_vec(&stored).expect("borsh encodes"), golden_bytes());
+}
@@ -0,0 +1,24 @@
+#![cfg(feature = "test-hooks")]
+
+use email_dkim_verifier_contract::EmailDkimVerifier;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+#[test]
+fn private_result_failure_hook_reports_outlayer_execution_failed() {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id("relayer.testnet"))
+        .build());
+    let mut contract = EmailDkimVerifier::new();
+
+    let vr = contract.test_on_private_result_failure("RIDFAIL".to_string());
+    assert!(!vr.verified);
+    assert_eq!(vr.request_id, "RIDFAIL");
+    assert_eq!(vr.error.as_deref(), Some("outlayer_execution_failed"));
+}
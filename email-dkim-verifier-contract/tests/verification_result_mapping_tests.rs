@@ -1,4 +1,6 @@
-use email_dkim_verifier_contract::{onchain_verify, tee_verify, EmailDkimVerifier};
+#[cfg(feature = "legacy-onchain")]
+use email_dkim_verifier_contract::{onchain_verify, EmailDkimVerifier};
+use email_dkim_verifier_contract::tee_verify;
 use near_sdk::test_utils::VMContextBuilder;
 use near_sdk::testing_env;
 use near_sdk::AccountId;
@@ -121,6 +123,7 @@ fn private_verification_invalid_verify_response_returns_error_and_request_id() {
     assert_eq!(vr.error.as_deref(), Some("invalid_verify_response"));
 }
 
+#[cfg(feature = "legacy-onchain")]
 #[test]
 fn onchain_outlayer_failure_returns_request_id_and_error() {
     let mut contract = EmailDkimVerifier::new();
@@ -144,3 +147,127 @@ fn onchain_outlayer_failure_returns_request_id_and_error() {
     assert_eq!(vr.request_id, "ABC123");
     assert_eq!(vr.error.as_deref(), Some("outlayer_execution_failed"));
 }
+
+#[cfg(feature = "legacy-onchain")]
+#[test]
+fn onchain_verification_matches_keys_by_selector() {
+    testing_env!(VMContextBuilder::new().build());
+    let mut contract = EmailDkimVerifier::new();
+    let email_blob = include_str!("data/gmail_reset_full.eml").to_string();
+    let gmail_record = "v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB";
+
+    let dns_response = |key_selector: &str| {
+        serde_json::json!({
+            "method": "get-dns-records",
+            "response": {
+                "selector": "20230601",
+                "domain": "gmail.com",
+                "name": "20230601._domainkey.gmail.com",
+                "type": "TXT",
+                "records": [gmail_record],
+                "keys": [
+                    { "selector": key_selector, "domain": "gmail.com", "record": gmail_record }
+                ],
+                "error": null
+            }
+        })
+    };
+
+    let vr = onchain_verify::on_email_verification_onchain_result(
+        &mut contract,
+        test_account_id("relayer.testnet"),
+        email_blob.clone(),
+        Ok(Some(dns_response("20161025"))),
+    );
+    assert!(!vr.verified);
+    assert_eq!(vr.error.as_deref(), Some("dkim_verification_failed"));
+
+    let vr = onchain_verify::on_email_verification_onchain_result(
+        &mut contract,
+        test_account_id("relayer.testnet"),
+        email_blob,
+        Ok(Some(dns_response("20230601"))),
+    );
+    assert!(vr.verified);
+    assert_eq!(vr.selector, "20230601");
+}
+
+fn verified_response_with_expiry(expires_at_secs: u64) -> serde_json::Value {
+    serde_json::json!({
+        "method": "verify-encrypted-email",
+        "response": {
+            "verified": true,
+            "account_id": "alice.testnet",
+            "new_public_key": "ed25519:abc",
+            "from_address_hash": [1, 2, 3],
+            "email_timestamp_ms": 1700000000000u64,
+            "request_id": "RID123",
+            "recovery_expires_at_secs": expires_at_secs,
+            "error": null
+        }
+    })
+}
+
+#[test]
+fn private_verification_rejects_expired_recovery_instruction() {
+    // Block time: 2_000 seconds after the epoch.
+    testing_env!(VMContextBuilder::new()
+        .block_timestamp(2_000 * 1_000_000_000)
+        .build());
+
+    let vr = tee_verify::on_email_verification_private_result(
+        test_account_id("relayer.testnet"),
+        "RID123".to_string(),
+        Ok(Some(verified_response_with_expiry(1_999))),
+    );
+
+    assert!(!vr.verified);
+    assert!(vr.account_id.is_empty());
+    assert_eq!(vr.request_id, "RID123");
+    assert_eq!(vr.error.as_deref(), Some("recovery_expired"));
+}
+
+#[test]
+fn private_verification_accepts_unexpired_recovery_instruction() {
+    testing_env!(VMContextBuilder::new()
+        .block_timestamp(2_000 * 1_000_000_000)
+        .build());
+
+    let vr = tee_verify::on_email_verification_private_result(
+        test_account_id("relayer.testnet"),
+        "RID123".to_string(),
+        Ok(Some(verified_response_with_expiry(2_000))),
+    );
+
+    assert!(vr.verified);
+    assert_eq!(vr.account_id, "alice.testnet");
+    assert!(vr.error.is_none());
+}
+
+#[test]
+fn private_verification_propagates_selector() {
+    testing_env!(VMContextBuilder::new().build());
+
+    let val = serde_json::json!({
+        "method": "verify-encrypted-email",
+        "response": {
+            "verified": true,
+            "account_id": "alice.testnet",
+            "new_public_key": "ed25519:abc",
+            "from_address_hash": [1, 2, 3],
+            "email_timestamp_ms": 1700000000000u64,
+            "request_id": "RID123",
+            "selector": "20230601",
+            "error": null
+        }
+    });
+
+    let vr = tee_verify::on_email_verification_private_result(
+        test_account_id("relayer.testnet"),
+        "RID123".to_string(),
+        Ok(Some(val)),
+    );
+
+    assert!(vr.verified);
+    assert_eq!(vr.selector, "20230601");
+}
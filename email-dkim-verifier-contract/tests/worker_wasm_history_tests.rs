@@ -0,0 +1,118 @@
+use email_dkim_verifier_contract::{EmailDkimVerifier, WorkerWasmRecord, WORKER_WASM_HISTORY_MAX};
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+use near_sdk::AccountId;
+
+/// A well-formed (hex SHA-256) wasm hash distinct per `i`.
+fn wasm_hash(i: usize) -> String {
+    format!("{i:064x}")
+}
+
+fn test_account_id(account_id: &str) -> AccountId {
+    account_id.parse().expect("invalid AccountId")
+}
+
+fn set_context(predecessor: &str, block_timestamp_ms: u64) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(test_account_id("verifier.testnet"))
+        .predecessor_account_id(test_account_id(predecessor))
+        .block_timestamp(block_timestamp_ms * 1_000_000)
+        .build());
+}
+
+#[test]
+fn replaced_source_is_recorded_in_history() {
+    set_context("verifier.testnet", 1_000);
+    let mut contract = EmailDkimVerifier::new();
+    assert!(contract.get_worker_wasm_history().is_empty());
+
+    contract.set_outlayer_worker_wasm_source(
+        "https://example.com/worker-v1.wasm".to_string(),
+        wasm_hash(1),
+    );
+    assert!(contract.get_worker_wasm_history().is_empty());
+
+    set_context("verifier.testnet", 2_000);
+    contract.set_outlayer_worker_wasm_source(
+        "https://example.com/worker-v2.wasm".to_string(),
+        wasm_hash(2),
+    );
+    assert_eq!(
+        contract.get_worker_wasm_history(),
+        vec![WorkerWasmRecord {
+            url: "https://example.com/worker-v1.wasm".to_string(),
+            hash: wasm_hash(1),
+            set_at_ms: 1_000,
+        }]
+    );
+    assert_eq!(contract.get_outlayer_worker_wasm_source().hash, wasm_hash(2));
+}
+
+#[test]
+fn history_keeps_only_the_most_recent_sources() {
+    set_context("verifier.testnet", 1_000);
+    let mut contract = EmailDkimVerifier::new();
+    for i in 0..=WORKER_WASM_HISTORY_MAX + 1 {
+        contract.set_outlayer_worker_wasm_source(
+            format!("https://example.com/worker-{i}.wasm"),
+            wasm_hash(i),
+        );
+    }
+
+    let history = contract.get_worker_wasm_history();
+    assert_eq!(history.len(), WORKER_WASM_HISTORY_MAX);
+    assert_eq!(history[0].hash, wasm_hash(1));
+    assert_eq!(history[WORKER_WASM_HISTORY_MAX - 1].hash, wasm_hash(WORKER_WASM_HISTORY_MAX));
+}
+
+#[test]
+fn hash_is_normalized_to_lowercase() {
+    set_context("verifier.testnet", 1_000);
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_outlayer_worker_wasm_source(
+        "https://example.com/worker.wasm".to_string(),
+        format!(" {} ", "AB".repeat(32)),
+    );
+    assert_eq!(contract.get_outlayer_worker_wasm_source().hash, "ab".repeat(32));
+}
+
+#[test]
+#[should_panic(expected = "Outlayer worker wasm hash must be a hex SHA-256 digest (64 characters)")]
+fn rejects_too_short_hash() {
+    set_context("verifier.testnet", 1_000);
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_outlayer_worker_wasm_source(
+        "https://example.com/worker.wasm".to_string(),
+        "abc123".to_string(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Outlayer worker wasm hash must be a hex SHA-256 digest (64 characters)")]
+fn rejects_non_hex_hash() {
+    set_context("verifier.testnet", 1_000);
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_outlayer_worker_wasm_source(
+        "https://example.com/worker.wasm".to_string(),
+        "z".repeat(64),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Outlayer worker wasm URL must be an https:// URL")]
+fn rejects_non_https_url() {
+    set_context("verifier.testnet", 1_000);
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_outlayer_worker_wasm_source(
+        "http://example.com/worker.wasm".to_string(),
+        wasm_hash(1),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Outlayer worker wasm URL must be an https:// URL")]
+fn rejects_url_without_host() {
+    set_context("verifier.testnet", 1_000);
+    let mut contract = EmailDkimVerifier::new();
+    contract.set_outlayer_worker_wasm_source("https:///worker.wasm".to_string(), wasm_hash(1));
+}
@@ -0,0 +1,320 @@
+//! Sandbox tests that run the contract against `tests/mock_outlayer`, deployed
+//! at the hardcoded `outlayer.testnet` account.
+
+use email_dkim_verifier_contract::{VerificationResult, MIN_DEPOSIT};
+use near_sdk::serde_json::{self, json};
+use near_workspaces::network::Sandbox;
+use near_workspaces::types::{AccountDetailsPatch, Gas, NearToken};
+use near_workspaces::{Account, AccountId, Contract, Worker};
+
+const OUTLAYER_ACCOUNT_ID: &str = "outlayer.testnet";
+
+struct Env {
+    worker: Worker<Sandbox>,
+    verifier: Contract,
+    outlayer_id: AccountId,
+    caller: Account,
+}
+
+async fn setup() -> anyhow::Result<Env> {
+    let worker = near_workspaces::sandbox().await?;
+
+    let verifier_wasm = near_workspaces::compile_project("./").await?;
+    let verifier = worker.dev_deploy(&verifier_wasm).await?;
+    verifier.call("new").transact().await?.into_result()?;
+
+    let outlayer_wasm = near_workspaces::compile_project("./tests/mock_outlayer").await?;
+    let outlayer_id: AccountId = OUTLAYER_ACCOUNT_ID.parse()?;
+    worker
+        .patch(&outlayer_id)
+        .account(AccountDetailsPatch::default().balance(NearToken::from_near(10)))
+        .code(&outlayer_wasm)
+        .transact()
+        .await?;
+
+    let caller = worker.dev_create_account().await?;
+
+    Ok(Env {
+        worker,
+        verifier,
+        outlayer_id,
+        caller,
+    })
+}
+
+#[cfg(feature = "legacy-onchain")]
+#[tokio::test]
+async fn onchain_request_refunds_excess_deposit_and_forwards_min_deposit() -> anyhow::Result<()> {
+    let env = setup().await?;
+    let email_blob = include_str!("data/gmail_reset_full.eml");
+
+    let balance_before = env.caller.view_account().await?.balance;
+    let outcome = env
+        .caller
+        .call(env.verifier.id(), "request_email_verification_onchain")
+        .args_json(json!({
+            "payer_account_id": env.caller.id(),
+            "email_blob": email_blob,
+        }))
+        .deposit(NearToken::from_near(1))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(outcome.is_success(), "{:#?}", outcome.outcomes());
+    let balance_after = env.caller.view_account().await?.balance;
+
+    // The caller pays exactly MIN_DEPOSIT plus gas; the rest of the 1 NEAR is refunded.
+    let spent = balance_before.as_yoctonear() - balance_after.as_yoctonear();
+    let max_gas_cost = NearToken::from_millinear(50).as_yoctonear();
+    assert!(spent >= MIN_DEPOSIT, "spent {spent} < MIN_DEPOSIT");
+    assert!(
+        spent < MIN_DEPOSIT + max_gas_cost,
+        "spent {spent}; excess deposit was not refunded"
+    );
+
+    let forwarded: NearToken = env
+        .worker
+        .view(&env.outlayer_id, "get_last_deposit")
+        .await?
+        .json()?;
+    assert_eq!(forwarded.as_yoctonear(), MIN_DEPOSIT);
+
+    // The mock returns `null`, so the callback reports a failed verification.
+    let result: serde_json::Value = outcome.json()?;
+    assert_eq!(result["verified"], json!(false));
+
+    Ok(())
+}
+
+#[cfg(feature = "legacy-onchain")]
+#[tokio::test]
+async fn onchain_request_to_unexpected_domain_fails_before_outlayer() -> anyhow::Result<()> {
+    let env = setup().await?;
+    env.verifier
+        .call("set_expected_to_domain")
+        .args_json(json!({ "domain": "example.com" }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let outcome = env
+        .caller
+        .call(env.verifier.id(), "request_email_verification_onchain")
+        .args_json(json!({
+            "payer_account_id": env.caller.id(),
+            "email_blob": include_str!("data/gmail_reset_full.eml"),
+        }))
+        .deposit(NearToken::from_near(1))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(outcome.is_failure());
+    assert!(
+        format!("{:?}", outcome.into_result().unwrap_err())
+            .contains("email_blob is not addressed to example.com")
+    );
+
+    // The request panicked before calling Outlayer, so nothing was forwarded.
+    let forwarded: NearToken = env
+        .worker
+        .view(&env.outlayer_id, "get_last_deposit")
+        .await?
+        .json()?;
+    assert_eq!(forwarded.as_yoctonear(), 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn private_request_returns_worker_verification_result() -> anyhow::Result<()> {
+    let env = setup().await?;
+
+    env.caller
+        .call(&env.outlayer_id, "set_response")
+        .args_json(json!({
+            "response": {
+                "method": "verify-encrypted-email",
+                "response": {
+                    "verified": true,
+                    "account_id": "kerp30.w3a-v1.testnet",
+                    "new_public_key": "ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm",
+                    "from_address_hash": [1, 2, 3],
+                    "email_timestamp_ms": 1700000000000u64,
+                    "request_id": "123abc",
+                    "error": null
+                }
+            }
+        }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let outcome = env
+        .caller
+        .call(env.verifier.id(), "request_email_verification_private")
+        .args_json(json!({
+            "payer_account_id": env.caller.id(),
+            "encrypted_email_blob": {
+                "version": 1,
+                "ephemeral_pub": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+                "nonce": "AAAAAAAAAAAAAAAA",
+                "ciphertext": "AAAAAAAAAAAAAAAAAAAAAA=="
+            },
+            "aead_context": {
+                "account_id": "kerp30.w3a-v1.testnet",
+                "network_id": "testnet",
+                "payer_account_id": env.caller.id(),
+            },
+            "request_id": "123abc",
+        }))
+        .deposit(NearToken::from_yoctonear(MIN_DEPOSIT))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(outcome.is_success(), "{:#?}", outcome.outcomes());
+
+    let vr: VerificationResult = outcome.json()?;
+    assert!(vr.verified);
+    assert_eq!(vr.account_id, "kerp30.w3a-v1.testnet");
+    assert_eq!(
+        vr.new_public_key,
+        "ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm"
+    );
+    assert_eq!(vr.from_address_hash, vec![1, 2, 3]);
+    assert_eq!(vr.email_timestamp_ms, Some(1700000000000));
+    assert_eq!(vr.request_id, "123abc");
+    assert!(vr.error.is_none());
+
+    // The worker input carries the envelope, AEAD context and request id.
+    let input_data: Option<String> = env
+        .worker
+        .view(&env.outlayer_id, "get_last_input_data")
+        .await?
+        .json()?;
+    let input: serde_json::Value = serde_json::from_str(&input_data.expect("input_data recorded"))?;
+    assert_eq!(input["method"], json!("verify-encrypted-email"));
+    assert_eq!(input["args"]["request_id"], json!("123abc"));
+    assert_eq!(
+        input["args"]["context"]["account_id"],
+        json!("kerp30.w3a-v1.testnet")
+    );
+
+    Ok(())
+}
+
+/// Submit a minimal private verification request with `gas` attached.
+async fn request_private_with_gas(
+    env: &Env,
+    gas: Gas,
+) -> anyhow::Result<near_workspaces::result::ExecutionFinalResult> {
+    Ok(env
+        .caller
+        .call(env.verifier.id(), "request_email_verification_private")
+        .args_json(json!({
+            "payer_account_id": env.caller.id(),
+            "encrypted_email_blob": {
+                "version": 1,
+                "ephemeral_pub": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+                "nonce": "AAAAAAAAAAAAAAAA",
+                "ciphertext": "AAAAAAAAAAAAAAAAAAAAAA=="
+            },
+            "aead_context": {
+                "account_id": "kerp30.w3a-v1.testnet",
+                "network_id": "testnet",
+                "payer_account_id": env.caller.id(),
+            },
+            "request_id": "lowgas",
+        }))
+        .deposit(NearToken::from_yoctonear(MIN_DEPOSIT))
+        .gas(gas)
+        .transact()
+        .await?)
+}
+
+#[tokio::test]
+async fn low_gas_private_request_still_runs_its_callback() -> anyhow::Result<()> {
+    let env = setup().await?;
+
+    // Well below `max_gas`; the callback's reserved gas still lets it finish.
+    let outcome = request_private_with_gas(&env, Gas::from_tgas(60)).await?;
+    assert!(outcome.is_success(), "{:#?}", outcome.outcomes());
+
+    // The mock returns `null`; what matters is that the callback produced a result.
+    let vr: VerificationResult = outcome.json()?;
+    assert!(!vr.verified);
+    assert_eq!(vr.request_id, "lowgas");
+    assert_eq!(vr.error.as_deref(), Some("outlayer_execution_failed"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn request_without_room_for_callback_gas_fails_up_front() -> anyhow::Result<()> {
+    let env = setup().await?;
+    env.verifier
+        .call("set_callback_gas_tgas")
+        .args_json(json!({ "tgas": 250 }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let outcome = request_private_with_gas(&env, Gas::from_tgas(100)).await?;
+    assert!(outcome.is_failure());
+
+    // Nothing reached Outlayer, so no deposit was forwarded.
+    let forwarded: NearToken = env
+        .worker
+        .view(&env.outlayer_id, "get_last_deposit")
+        .await?
+        .json()?;
+    assert_eq!(forwarded.as_yoctonear(), 0);
+
+    Ok(())
+}
+
+/// Build the verifier with the `test-hooks` feature; `compile_project` only
+/// builds the default feature set.
+#[cfg(feature = "test-hooks")]
+fn compile_verifier_with_test_hooks() -> anyhow::Result<Vec<u8>> {
+    let out_dir = "target/near/test-hooks";
+    let status = std::process::Command::new("cargo")
+        .args([
+            "near",
+            "build",
+            "non-reproducible-wasm",
+            "--features",
+            "test-hooks",
+            "--no-abi",
+            "--out-dir",
+            out_dir,
+        ])
+        .status()?;
+    anyhow::ensure!(status.success(), "cargo near build --features test-hooks failed");
+    Ok(std::fs::read(format!("{out_dir}/email_dkim_verifier_contract.wasm"))?)
+}
+
+#[cfg(feature = "test-hooks")]
+#[tokio::test]
+async fn private_result_failure_hook_returns_outlayer_execution_failed() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let verifier = worker.dev_deploy(&compile_verifier_with_test_hooks()?).await?;
+    verifier.call("new").transact().await?.into_result()?;
+    let caller = worker.dev_create_account().await?;
+
+    let outcome = caller
+        .call(verifier.id(), "test_on_private_result_failure")
+        .args_json(json!({ "request_id": "RIDFAIL" }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(outcome.is_success(), "{:#?}", outcome.outcomes());
+
+    let vr: VerificationResult = outcome.json()?;
+    assert!(!vr.verified);
+    assert_eq!(vr.request_id, "RIDFAIL");
+    assert_eq!(vr.error.as_deref(), Some("outlayer_execution_failed"));
+    assert!(vr.account_id.is_empty());
+    assert!(vr.from_address_hash.is_empty());
+
+    Ok(())
+}
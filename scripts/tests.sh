@@ -2,4 +2,6 @@
 cd email-dkim-verifier-contract
 cargo near build non-reproducible-wasm
 cargo test --features unit-testing
-cd ../
\ No newline at end of file
+# TEE-only build without the deprecated on-chain DKIM path
+cargo test --no-default-features --features unit-testing
+cd ../target/
@@ -1,11 +1,41 @@
-use crate::crypto::{decrypt_encrypted_email, get_worker_public_key, EncryptedEmailEnvelope};
+use crate::crypto::{
+    aad_bytes, aead_key_commitment, canonical_json, check_client_public_key, context_request_id,
+    decrypt_bytes_with_static_secret, decrypt_encrypted_email_bytes, encrypt_email_to_public_key,
+    get_worker_public_key, get_worker_public_key_bytes, worker_signing_key, EncryptedEmailEnvelope, WorkerKeys,
+    ENVELOPE_CIPHER, ENVELOPE_VERSION_UNSALTED, WORKER_KEY_ALGORITHM,
+};
+#[cfg(any(test, feature = "debug-methods"))]
+use crate::crypto::{decode_hex, hkdf_derive};
+#[cfg(any(test, feature = "debug-methods"))]
+use crate::verify_dkim::verify_rsa_sha256_digest;
 use crate::dns::fetch_txt_records;
+use crate::encoded_word::decode_encoded_words;
+use crate::error_code::VerifyErrorCode;
 use crate::parsers::{
-    extract_dkim_selector_and_domain, extract_header_value, parse_email_timestamp_ms,
-    parse_from_address, parse_recover_instruction, parse_recover_public_key_from_body,
-    parse_recover_request_id, parse_recover_subject,
+    address_in_domain, canonicalize_body_relaxed, canonicalize_body_simple,
+    canonicalize_email_address, extract_dkim_selector_and_domain, extract_google_dkim_signature, extract_header_value,
+    extract_sender_header, has_multiple_from_headers, mailing_list_signals,
+    parse_dkim_canonicalization, parse_dkim_tags, parse_email_timestamp_ms,
+    parse_email_timestamp_ms_with_resent_fallback,
+    parse_headers, parse_recover_request_id, parse_recovery_instruction, parse_to_addresses,
+    split_headers_body,
+    strip_bom,
+    strip_bom_bytes, Canonicalization, SUPPORTED_CANONICALIZATIONS,
+};
+use crate::qp::decode_quoted_printable;
+use crate::verify_dkim::{
+    canonicalized_signed_headers, dkim_header_signing_data, dkim_signed_header_fields,
+    has_dkim_public_key,
+    parse_rsa_public_key,
+    verify_all_dkim_signatures_bytes_with_dot_stuffing,
+    verify_any_dkim_signature_bytes_with_dot_stuffing, verify_dkim_signature,
+    verify_signed_payload, DkimError,
+    DEFAULT_MAX_DKIM_SIGNATURES, SUPPORTED_DKIM_ALGORITHMS,
 };
-use crate::verify_dkim::verify_dkim;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use ed25519_dalek::Signer;
+use rsa::traits::PublicKeyParts;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
@@ -13,7 +43,86 @@ use sha2::{Digest, Sha256};
 // Method names
 const GET_DNS_RECORDS_METHOD: &str = "get-dns-records";
 const VERIFY_ENCRYPTED_EMAIL_METHOD: &str = "verify-encrypted-email";
+const VERIFY_ENCRYPTED_EMAIL_V2_METHOD: &str = "verify-encrypted-email-v2";
 const GET_PUBLIC_KEY_METHOD: &str = "get-public-key";
+const REENCRYPT_EMAIL_METHOD: &str = "reencrypt-email";
+const DECODE_BASE64_DKIM_VALUE_METHOD: &str = "decode-base64-dkim-value";
+const CHECK_PUBLIC_KEY_METHOD: &str = "check-public-key";
+const VERIFY_SIGNED_PAYLOAD_METHOD: &str = "verify-signed-payload";
+const COMPUTE_BODY_HASH_METHOD: &str = "compute-body-hash";
+const CHECK_BODY_HASH_METHOD: &str = "check-body-hash";
+const GET_CANONICALIZED_HEADERS_METHOD: &str = "get-canonicalized-headers";
+const DECODE_QUOTED_PRINTABLE_METHOD: &str = "decode-quoted-printable";
+const LIST_EMAIL_HEADERS_METHOD: &str = "list-email-headers";
+const AEAD_KEYCHECK_METHOD: &str = "aead-keycheck";
+const EXPAND_EMAIL_ADDRESS_METHOD: &str = "expand-email-address";
+const WORKER_SELF_TEST_METHOD: &str = "worker-self-test";
+const INSPECT_ENCRYPTED_ENVELOPE_METHOD: &str = "inspect-encrypted-envelope";
+const PARSE_X_GOOGLE_DKIM_SIGNATURE_METHOD: &str = "parse-x-google-dkim-signature";
+const VERIFY_ENVELOPE_INTEGRITY_METHOD: &str = "verify-envelope-integrity";
+const CHECK_DKIM_DNS_METHOD: &str = "check-dkim-dns";
+const EXTRACT_DKIM_SIGNED_HEADERS_METHOD: &str = "extract-dkim-signed-headers";
+const VALIDATE_AEAD_CONTEXT_METHOD: &str = "validate-aead-context";
+const GET_CAPABILITIES_METHOD: &str = "get-capabilities";
+const PARSE_RECOVERY_INSTRUCTION_METHOD: &str = "parse-recovery-instruction";
+const INSPECT_WORKER_SECRETS_METHOD: &str = "inspect-worker-secrets";
+const GENERATE_REQUEST_ID_METHOD: &str = "generate-request-id";
+const CHECK_RECIPIENT_METHOD: &str = "check-recipient";
+const COMPUTE_HEADER_HASH_METHOD: &str = "compute-header-hash";
+/// `debug-methods` builds only: exposes raw HKDF output for caller-chosen key material.
+#[cfg(any(test, feature = "debug-methods"))]
+const HKDF_DERIVE_METHOD: &str = "hkdf-derive";
+#[cfg(any(test, feature = "debug-methods"))]
+const TEST_RSA_VERIFY_METHOD: &str = "test-rsa-verify";
+
+/// A worker method handler: gets the worker keys (or the error from loading
+/// them) and the request args.
+type MethodHandler = fn(Result<&WorkerKeys, &str>, Value) -> ResponseType;
+
+/// Every method `handle_request_with_keys` dispatches, in the order
+/// `get-capabilities` reports them.
+const WORKER_METHODS: [(&str, MethodHandler); 28] = [
+    (GET_DNS_RECORDS_METHOD, |_, args| handle_dns_lookup(args)),
+    (VERIFY_ENCRYPTED_EMAIL_METHOD, handle_verify_encrypted_dkim),
+    (VERIFY_ENCRYPTED_EMAIL_V2_METHOD, handle_verify_encrypted_dkim_v2),
+    (GET_PUBLIC_KEY_METHOD, |keys, _| handle_get_public_key(keys)),
+    (REENCRYPT_EMAIL_METHOD, handle_reencrypt_email),
+    (DECODE_BASE64_DKIM_VALUE_METHOD, |_, args| handle_decode_base64_dkim_value(args)),
+    (CHECK_PUBLIC_KEY_METHOD, handle_check_public_key),
+    (VERIFY_SIGNED_PAYLOAD_METHOD, |_, args| handle_verify_signed_payload(args)),
+    (COMPUTE_BODY_HASH_METHOD, |_, args| handle_compute_body_hash(args)),
+    (CHECK_BODY_HASH_METHOD, |_, args| handle_check_body_hash(args)),
+    (GET_CANONICALIZED_HEADERS_METHOD, |_, args| handle_get_canonicalized_headers(args)),
+    (DECODE_QUOTED_PRINTABLE_METHOD, |_, args| handle_decode_quoted_printable(args)),
+    (LIST_EMAIL_HEADERS_METHOD, |_, args| handle_list_email_headers(args)),
+    (AEAD_KEYCHECK_METHOD, handle_aead_keycheck),
+    (EXPAND_EMAIL_ADDRESS_METHOD, |_, args| handle_expand_email_address(args)),
+    (WORKER_SELF_TEST_METHOD, |keys, _| handle_worker_self_test(keys)),
+    (INSPECT_ENCRYPTED_ENVELOPE_METHOD, |_, args| handle_inspect_encrypted_envelope(args)),
+    (PARSE_X_GOOGLE_DKIM_SIGNATURE_METHOD, |_, args| handle_parse_x_google_dkim_signature(args)),
+    (VERIFY_ENVELOPE_INTEGRITY_METHOD, |_, args| handle_verify_envelope_integrity(args)),
+    (CHECK_DKIM_DNS_METHOD, |_, args| handle_check_dkim_dns(args)),
+    (EXTRACT_DKIM_SIGNED_HEADERS_METHOD, |_, args| handle_extract_dkim_signed_headers(args)),
+    (VALIDATE_AEAD_CONTEXT_METHOD, |_, args| handle_validate_aead_context(args)),
+    (GET_CAPABILITIES_METHOD, |_, _| handle_get_capabilities()),
+    (PARSE_RECOVERY_INSTRUCTION_METHOD, |_, args| handle_parse_recovery_instruction(args)),
+    (INSPECT_WORKER_SECRETS_METHOD, |_, _| {
+        handle_inspect_worker_secrets(|name| std::env::var(name).ok())
+    }),
+    (GENERATE_REQUEST_ID_METHOD, |_, _| handle_generate_request_id()),
+    (CHECK_RECIPIENT_METHOD, |_, args| handle_check_recipient(args)),
+    (COMPUTE_HEADER_HASH_METHOD, |_, args| handle_compute_header_hash(args)),
+];
+
+/// `hkdf-derive` and `test-rsa-verify`, dispatched and reported only in
+/// `debug-methods` builds.
+#[cfg(any(test, feature = "debug-methods"))]
+const DEBUG_METHODS: [(&str, MethodHandler); 2] = [
+    (HKDF_DERIVE_METHOD, |_, args| handle_hkdf_derive(args)),
+    (TEST_RSA_VERIFY_METHOD, |_, args| handle_test_rsa_verify(args)),
+];
+#[cfg(not(any(test, feature = "debug-methods")))]
+const DEBUG_METHODS: [(&str, MethodHandler); 0] = [];
 
 #[derive(Deserialize)]
 pub struct RequestType {
@@ -48,6 +157,8 @@ impl ResponseType {
                 "from_address_hash": Vec::<u8>::new(),
                 "email_timestamp_ms": Option::<u64>::None,
                 "request_id": request_id,
+                "selector": "",
+                "recovery_expires_at_secs": Option::<u64>::None,
                 "error": error.into(),
                 "context": context.unwrap_or(Value::Null),
             }),
@@ -71,19 +182,52 @@ struct DnsLookupResult {
     #[serde(rename = "type")]
     record_type: String,
     records: Vec<String>,
+    /// `records` tagged with the selector/domain they were fetched for, so the
+    /// contract only checks each DKIM-Signature against its own key records.
+    keys: Vec<DkimKeyRecord>,
     error: Option<String>,
 }
 
+#[derive(Serialize)]
+struct DkimKeyRecord {
+    selector: String,
+    domain: String,
+    record: String,
+}
+
+#[derive(Serialize)]
+struct EmailHeader {
+    name: String,
+    value: String,
+    index: usize,
+}
+
+/// Entry point for `main.rs`: worker keys are loaded from the OutLayer secrets
+/// in the environment.
 pub fn handle_request(request: RequestType) -> ResponseType {
-    match request.method.as_str() {
-        GET_DNS_RECORDS_METHOD => handle_dns_lookup(request.args),
-        VERIFY_ENCRYPTED_EMAIL_METHOD => handle_verify_encrypted_dkim(request.args),
-        GET_PUBLIC_KEY_METHOD => handle_get_public_key(),
-        other => ResponseType {
-            method: other.to_string(),
+    let keys = WorkerKeys::from_env();
+    handle_request_with_keys(request, keys.as_ref().map_err(String::as_str))
+}
+
+/// Dispatch `request` using explicit worker keys. `keys` is the error from
+/// loading them when unavailable; only methods that need the worker secret
+/// report it.
+pub fn handle_request_with_keys(
+    request: RequestType,
+    keys: Result<&WorkerKeys, &str>,
+) -> ResponseType {
+    let handler = WORKER_METHODS
+        .iter()
+        .chain(DEBUG_METHODS.iter())
+        .find(|(name, _)| *name == request.method)
+        .map(|(_, handler)| *handler);
+    match handler {
+        Some(handler) => handler(keys, request.args),
+        None => ResponseType {
             response: serde_json::json!({
-                "error": format!("unknown method: {other}"),
+                "error": format!("unknown method: {}", request.method),
             }),
+            method: request.method,
         },
     }
 }
@@ -118,7 +262,7 @@ fn handle_dns_lookup(args: Value) -> ResponseType {
     let name = if let Some(name) = name {
         name
     } else if let Some(email) = email_blob {
-        match extract_dkim_selector_and_domain(&email) {
+        match extract_dkim_selector_and_domain(strip_bom(&email)) {
             Ok((s, d)) => {
                 selector = Some(s.clone());
                 domain = Some(d.clone());
@@ -138,6 +282,10 @@ fn handle_dns_lookup(args: Value) -> ResponseType {
 
     let records = match record_type.as_str() {
         "TXT" if !name.is_empty() && error.is_none() => match fetch_txt_records(&name) {
+            Ok(records) if records.is_empty() => {
+                error = Some(format!("no TXT records found for {}", name));
+                records
+            }
             Ok(records) => records,
             Err(e) => {
                 error = Some(e);
@@ -154,12 +302,25 @@ fn handle_dns_lookup(args: Value) -> ResponseType {
         }
     };
 
+    let keys = match (&selector, &domain) {
+        (Some(s), Some(d)) => records
+            .iter()
+            .map(|record| DkimKeyRecord {
+                selector: s.clone(),
+                domain: d.clone(),
+                record: record.clone(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
     let result = DnsLookupResult {
         selector,
         domain,
         name,
         record_type,
         records,
+        keys,
         error,
     };
 
@@ -169,7 +330,72 @@ fn handle_dns_lookup(args: Value) -> ResponseType {
     }
 }
 
-fn handle_verify_encrypted_dkim(args: Value) -> ResponseType {
+/// Keys the relayer SDK and the contract put in the AEAD `context`.
+const CONTEXT_KEYS: [&str; 3] = ["account_id", "network_id", "payer_account_id"];
+
+/// Check that `context` is an object with exactly `CONTEXT_KEYS`, plus an
+/// optional string `request_id`. Any other shape yields different AAD bytes
+/// than the client encrypted with, which would otherwise surface as an opaque
+/// "decryption failed".
+fn validate_context(context: &Value) -> Result<(), String> {
+    let invalid = || String::from(VerifyErrorCode::InvalidContextShape);
+    let fields = context.as_object().ok_or_else(invalid)?;
+    let expected = match fields.get("request_id") {
+        None => CONTEXT_KEYS.len(),
+        Some(Value::String(_)) => CONTEXT_KEYS.len() + 1,
+        Some(_) => return Err(invalid()),
+    };
+    if fields.len() != expected || !CONTEXT_KEYS.iter().all(|k| fields.contains_key(*k)) {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+/// Extra fields `verify-encrypted-email-v2` adds to the v1 response, filled
+/// in as far as verification got before succeeding or failing.
+#[derive(Default)]
+struct VerifyDiagnostics {
+    /// `d=` of the signature that verified, else of the first DKIM-Signature.
+    signing_domain: String,
+    message_id: Option<String>,
+    signatures_tried: usize,
+    /// DKIM-Signature headers past `max_signatures`, never looked at.
+    signatures_skipped: usize,
+    /// `mailing_list_signals` of the decrypted email.
+    mailing_list_signals: Vec<&'static str>,
+}
+
+fn handle_verify_encrypted_dkim(keys: Result<&WorkerKeys, &str>, args: Value) -> ResponseType {
+    verify_encrypted_dkim(keys, args, &mut VerifyDiagnostics::default())
+}
+
+/// Same checks and fields as `verify-encrypted-email`, plus `signing_domain`,
+/// `message_id`, `signatures_tried`, `signatures_skipped` and `mailing_list_signals`, for clients that want diagnostics
+/// without a separate debug call. The v1 response shape is left unchanged.
+fn handle_verify_encrypted_dkim_v2(keys: Result<&WorkerKeys, &str>, args: Value) -> ResponseType {
+    let mut diagnostics = VerifyDiagnostics::default();
+    let mut response = verify_encrypted_dkim(keys, args, &mut diagnostics).response;
+    if let Some(fields) = response.as_object_mut() {
+        fields.insert("signing_domain".to_string(), diagnostics.signing_domain.into());
+        fields.insert("message_id".to_string(), diagnostics.message_id.into());
+        fields.insert("signatures_tried".to_string(), diagnostics.signatures_tried.into());
+        fields.insert("signatures_skipped".to_string(), diagnostics.signatures_skipped.into());
+        fields.insert(
+            "mailing_list_signals".to_string(),
+            diagnostics.mailing_list_signals.into(),
+        );
+    }
+    ResponseType {
+        method: VERIFY_ENCRYPTED_EMAIL_V2_METHOD.to_string(),
+        response,
+    }
+}
+
+fn verify_encrypted_dkim(
+    keys: Result<&WorkerKeys, &str>,
+    args: Value,
+    diagnostics: &mut VerifyDiagnostics,
+) -> ResponseType {
     #[derive(Deserialize)]
     struct VerifyArgs {
         encrypted_email_blob: EncryptedEmailEnvelope,
@@ -177,6 +403,43 @@ fn handle_verify_encrypted_dkim(args: Value) -> ResponseType {
         context: Value, // forwarded directly from contract `args.context` as worker `context` (AEAD AAD)
         #[serde(default)]
         request_id: String,
+        #[serde(default)]
+        allow_resent_headers: bool, // contract policy: fall back to Resent-From / Resent-Date
+        #[serde(default)]
+        undo_dot_stuffing: bool, // email was captured from raw SMTP DATA, still dot-stuffed
+        #[serde(default)]
+        expected_from_address_hash: Option<Vec<u8>>, // pre-registered sender (contract `AeadContext`)
+        #[serde(default)]
+        expected_account_id: Option<String>, // account the relayer requested recovery for
+        #[serde(default)]
+        payer_account_id: Option<String>, // account actually paying for this execution
+        #[serde(default)]
+        pinned_keys: Vec<PinnedDkimKey>, // contract-pinned key records, used instead of DNS
+        #[serde(default)]
+        dns_records: Vec<StaticDnsRecord>, // contract-configured records, used instead of DoH
+        #[serde(default)]
+        require_all_signatures: bool, // contract policy: every DKIM-Signature must verify
+        #[serde(default = "default_max_signatures")]
+        max_signatures: usize, // contract policy: DKIM-Signature headers tried at most
+        #[serde(default)]
+        reject_mailing_list: bool, // contract policy: refuse emails relayed by a mailing list
+    }
+
+    fn default_max_signatures() -> usize {
+        DEFAULT_MAX_DKIM_SIGNATURES
+    }
+
+    #[derive(Deserialize)]
+    struct PinnedDkimKey {
+        domain: String,
+        selector: String,
+        records: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct StaticDnsRecord {
+        name: String, // `<selector>._domainkey.<domain>`
+        records: Vec<String>,
     }
 
     let request_id_hint = args
@@ -203,10 +466,52 @@ fn handle_verify_encrypted_dkim(args: Value) -> ResponseType {
         verify_args.request_id.clone()
     };
 
+    if let Err(e) = validate_context(&verify_args.context) {
+        return ResponseType::error(request_id_hint, e, Some(verify_args.context));
+    }
+
+    // A `request_id` inside the context is covered by the AEAD tag, so it wins
+    // over the plain argument; a differing argument is rejected outright.
+    let request_id_hint = match context_request_id(&verify_args.context) {
+        Some(bound) => {
+            let arg = verify_args.request_id.trim();
+            if !arg.is_empty() && arg != bound {
+                return ResponseType::error(
+                    request_id_hint,
+                    VerifyErrorCode::RequestIdMismatch,
+                    Some(verify_args.context),
+                );
+            }
+            bound.to_string()
+        }
+        None => request_id_hint,
+    };
+
+    // The email was encrypted for one payer; refuse to run it on behalf of
+    // another (e.g. a relayer replaying someone else's ciphertext).
+    if let Some(payer) = verify_args.payer_account_id.as_deref() {
+        let bound = verify_args.context.get("payer_account_id").and_then(Value::as_str);
+        if bound != Some(payer) {
+            return ResponseType::error(
+                request_id_hint,
+                VerifyErrorCode::PayerAccountIdContextMismatch,
+                Some(verify_args.context),
+            );
+        }
+    }
+
+    let keys = match keys {
+        Ok(k) => k,
+        Err(e) => {
+            return ResponseType::error(request_id_hint, e.to_string(), Some(verify_args.context));
+        }
+    };
+
     // Pass the JSON `context` object to crypto; it will be serialized with
     // serde_json and used as ChaCha20‑Poly1305 AAD. The SDK constructs this
     // context with keys in alphabetical order to match serde's canonical form.
-    let decrypted_email = match decrypt_encrypted_email(
+    let decrypted_bytes = match decrypt_encrypted_email_bytes(
+        keys,
         &verify_args.encrypted_email_blob,
         &verify_args.context,
     ) {
@@ -220,105 +525,1439 @@ fn handle_verify_encrypted_dkim(args: Value) -> ResponseType {
         }
     };
 
-    let subject = extract_header_value(&decrypted_email, "Subject");
+    // An 8bit body need not be UTF-8 (e.g. Latin-1). DKIM hashes the original
+    // bytes; the lossy text is only used to parse headers and the body key line.
+    let decrypted_bytes = strip_bom_bytes(&decrypted_bytes);
+    let decrypted_text = String::from_utf8_lossy(decrypted_bytes);
+    let decrypted_email: &str = &decrypted_text;
+    diagnostics.message_id = extract_header_value(decrypted_email, "Message-ID");
+    diagnostics.mailing_list_signals = mailing_list_signals(decrypted_email);
+
+    // Legacy clients send the Subject as RFC 2047 encoded-words, possibly in a
+    // non-UTF-8 charset; decode before looking for the recovery instruction.
+    let subject =
+        extract_header_value(decrypted_email, "Subject").map(|s| decode_encoded_words(&s));
     let request_id_from_email = subject
         .as_deref()
         .and_then(parse_recover_request_id)
         .unwrap_or_default();
     let request_id = if request_id_from_email.trim().is_empty() {
         request_id_hint
+    } else if context_request_id(&verify_args.context)
+        .is_some_and(|bound| bound != request_id_from_email)
+    {
+        // The ciphertext is bound to another request; the result must not be
+        // reported (and stored by the contract) under an id it never asked for.
+        return ResponseType::error(request_id_hint, VerifyErrorCode::RequestIdMismatch, None);
     } else {
         request_id_from_email
     };
 
-    let (selector, domain) = match extract_dkim_selector_and_domain(&decrypted_email) {
+    // Checked before DKIM: a second From: can ride along with a valid
+    // signature over the other one.
+    if has_multiple_from_headers(decrypted_email) {
+        return ResponseType::error(request_id, VerifyErrorCode::MultipleFromHeaders, None);
+    }
+
+    let (selector, domain) = match extract_dkim_selector_and_domain(decrypted_email) {
         Ok(v) => v,
         Err(e) => {
             return ResponseType::error(request_id, e, None);
         }
     };
+    diagnostics.signing_domain = domain.clone();
 
-    let name = format!("{}._domainkey.{}", selector, domain);
-    let dns_records = match fetch_txt_records(&name) {
+    // A key pinned in contract state for a signature's selector replaces DNS
+    // for that signature only; otherwise a static record the contract
+    // configured for its name is used in place of the DoH lookup (for
+    // deployments that cannot reach DoH).
+    let key_records = |selector: &str, domain: &str| -> Result<Vec<String>, String> {
+        let pinned = verify_args.pinned_keys.iter().find(|k| {
+            k.domain.eq_ignore_ascii_case(domain) && k.selector.eq_ignore_ascii_case(selector)
+        });
+        if let Some(k) = pinned {
+            return Ok(k.records.clone());
+        }
+        let name = format!("{}._domainkey.{}", selector, domain);
+        match verify_args.dns_records.iter().find(|r| r.name.eq_ignore_ascii_case(&name)) {
+            Some(r) => Ok(r.records.clone()),
+            None => fetch_txt_records(&name),
+        }
+    };
+    let dns_records = match key_records(&selector, &domain) {
         Ok(records) => records,
         Err(e) => {
             return ResponseType::error(request_id, e, None);
         }
     };
 
-    if dns_records.is_empty() {
-        return ResponseType::error(
-            request_id,
-            "no DKIM DNS records found",
-            None,
-        );
+    // No usable key under the signature's selector (e.g. the selector was
+    // rotated away) is reported apart from a key that does not verify.
+    if !has_dkim_public_key(&dns_records) {
+        return ResponseType::error(request_id, VerifyErrorCode::SelectorNotFound, None);
     }
 
-    let verified = verify_dkim(&decrypted_email, &dns_records);
+    // Every signature is checked against its own selector's key, never the
+    // first signature's; a failed lookup for an additional selector leaves
+    // that signature unverified.
+    let records_for = |d: &str, s: &str| {
+        if d.eq_ignore_ascii_case(&domain) && s.eq_ignore_ascii_case(&selector) {
+            dns_records.clone()
+        } else {
+            key_records(s, d).unwrap_or_default()
+        }
+    };
+    let verified_signature = if verify_args.require_all_signatures {
+        verify_all_dkim_signatures_bytes_with_dot_stuffing(
+            decrypted_bytes,
+            verify_args.undo_dot_stuffing,
+            verify_args.max_signatures,
+            records_for,
+        )
+    } else {
+        verify_any_dkim_signature_bytes_with_dot_stuffing(
+            decrypted_bytes,
+            verify_args.undo_dot_stuffing,
+            verify_args.max_signatures,
+            records_for,
+        )
+    };
+    if verified_signature.is_err() {
+        // Every DKIM-Signature up to the cap was checked before giving up.
+        let signature_count = parse_headers(split_headers_body(decrypted_email).0)
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("DKIM-Signature"))
+            .count();
+        diagnostics.signatures_tried = signature_count.min(verify_args.max_signatures);
+        diagnostics.signatures_skipped = signature_count - diagnostics.signatures_tried;
+    }
+    let verified_signature = match verified_signature {
+        Ok(sig) => {
+            // Keys are looked up per signature, so this is the domain whose
+            // published key verified it.
+            diagnostics.signing_domain = sig.domain.clone();
+            diagnostics.signatures_tried = sig.signatures_tried;
+            sig
+        }
+        Err(DkimError::VerificationFailed) => {
+            return ResponseType::error(request_id, VerifyErrorCode::SignatureInvalid, None);
+        }
+        Err(e) => {
+            return ResponseType::error(request_id, e.to_string(), None);
+        }
+    };
 
-    if !verified {
+    // A list that rewrote From: signs as itself, so a verified signature says
+    // nothing about the original author.
+    if verify_args.reject_mailing_list && !diagnostics.mailing_list_signals.is_empty() {
+        let signals = diagnostics.mailing_list_signals.join(", ");
         return ResponseType::error(
             request_id,
-            "DKIM verification failed",
+            VerifyErrorCode::MailingListMessage.with_detail(signals),
             None,
         );
     }
 
-    let (account_id, new_public_key) = if let Some(s) = subject.as_deref() {
-        if let Some((acc, pk)) = parse_recover_instruction(s) {
-            (acc, pk)
-        } else {
-            let acc = parse_recover_subject(s).unwrap_or_default();
-            let pk = parse_recover_public_key_from_body(&decrypted_email).unwrap_or_default();
-            (acc, pk)
-        }
-    } else {
-        let pk = parse_recover_public_key_from_body(&decrypted_email).unwrap_or_default();
-        (String::new(), pk)
+    let (_, body) = split_headers_body(decrypted_email);
+    let instruction = match parse_recovery_instruction(subject.as_deref(), body) {
+        Ok(instruction) => instruction,
+        Err(e) => return ResponseType::error(request_id, e, None),
     };
+    let account_id = instruction.account_id.unwrap_or_default();
+    let new_public_key = instruction.new_public_key.unwrap_or_default();
+    let recovery_expires_at_secs = instruction.expires_at_secs;
+
+    // A relayer could forward a validly signed email for a different account;
+    // the Subject's account must be the one it asked about.
+    if let Some(expected) = verify_args.expected_account_id.as_deref() {
+        if expected != account_id {
+            return ResponseType::error(request_id, VerifyErrorCode::AccountIdMismatch, None);
+        }
+    }
 
-    let email_timestamp_ms = parse_email_timestamp_ms(&decrypted_email);
+    let allow_resent = verify_args.allow_resent_headers;
+    // No upper bound here: the contract checks the timestamp against block time.
+    let email_timestamp_ms =
+        parse_email_timestamp_ms_with_resent_fallback(decrypted_email, allow_resent, None);
 
-    let canonical_from = parse_from_address(&decrypted_email).trim().to_lowercase();
+    let from_header = extract_sender_header(decrypted_email, allow_resent).unwrap_or_default();
     let salt = verify_args
         .context
         .get("account_id")
         .and_then(|v| v.as_str())
-        .unwrap_or(account_id.as_str())
-        .trim()
-        .to_lowercase();
-    let from_address_hash = if canonical_from.is_empty() || salt.is_empty() {
-        Vec::<u8>::new()
-    } else {
-        let input = format!("{canonical_from}|{salt}");
-        let digest = Sha256::digest(input.as_bytes());
-        digest.to_vec()
+        .unwrap_or(account_id.as_str());
+    let from_address_hash = compute_from_address_hash_for_header(&from_header, salt);
+    if let Some(expected) = &verify_args.expected_from_address_hash {
+        if *expected != from_address_hash {
+            return ResponseType::error(request_id, VerifyErrorCode::FromAddressHashMismatch, None);
+        }
+    }
+
+    // Only results computed here are attested: the signature covers the
+    // canonical JSON of every field below, so an off-chain consumer can check
+    // it against `signing_public_key` from `get-public-key`.
+    let signing_key = match worker_signing_key(keys) {
+        Ok(k) => k,
+        Err(e) => return ResponseType::error(request_id, e, None),
     };
+    let mut result = serde_json::json!({
+        "verified": true,
+        "account_id": account_id,
+        "new_public_key": new_public_key,
+        "from_address_hash": from_address_hash,
+        "email_timestamp_ms": email_timestamp_ms,
+        "request_id": request_id,
+        "selector": verified_signature.selector,
+        "signing_domain": verified_signature.domain,
+        // The worker has no trusted clock; the contract enforces this deadline.
+        "recovery_expires_at_secs": recovery_expires_at_secs,
+        "error": serde_json::Value::Null,
+        "context": verify_args.context,
+    });
+
+    let signature = signing_key.sign(canonical_json(&result).as_bytes()).to_bytes();
+    if let Some(fields) = result.as_object_mut() {
+        fields.insert("signature_b64".to_string(), base64::encode(signature).into());
+    }
 
     ResponseType {
         method: VERIFY_ENCRYPTED_EMAIL_METHOD.to_string(),
+        response: result,
+    }
+}
+
+/// `sha256("<canonical_from>|<account_id_lower>")` for a raw `From:` header
+/// value, the same formula as the contract's `compute_from_address_hash_for_header`,
+/// so only the hash (never the address) leaves the TEE. Returns an empty vec
+/// when either the canonical address or the account id is empty.
+pub(crate) fn compute_from_address_hash_for_header(from_header: &str, account_id: &str) -> Vec<u8> {
+    let canonical_from = canonicalize_email_address(from_header);
+    let salt = account_id.trim().to_lowercase();
+    if canonical_from.is_empty() || salt.is_empty() {
+        return Vec::new();
+    }
+    let input = format!("{canonical_from}|{salt}");
+    Sha256::digest(input.as_bytes()).to_vec()
+}
+
+/// The worker's X25519 public key plus metadata for callers that cache it:
+/// the hex SHA-256 of the raw key bytes and the operator-set rotation counter.
+/// Also publishes the Ed25519 key that signs `verify-encrypted-email` results.
+fn handle_get_public_key(keys: Result<&WorkerKeys, &str>) -> ResponseType {
+    let error_response = |error: String| ResponseType {
+        method: GET_PUBLIC_KEY_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let keys = match keys {
+        Ok(keys) => keys,
+        Err(e) => return error_response(e.to_string()),
+    };
+    let signing_key = match worker_signing_key(keys) {
+        Ok(k) => k,
+        Err(e) => return error_response(e),
+    };
+    let pk = get_worker_public_key_bytes(keys);
+    let key_fingerprint_sha256: String =
+        Sha256::digest(pk).iter().map(|b| format!("{b:02x}")).collect();
+    ResponseType {
+        method: GET_PUBLIC_KEY_METHOD.to_string(),
+        response: serde_json::json!({
+            "public_key": base64::encode(pk),
+            "key_algorithm": WORKER_KEY_ALGORITHM,
+            "key_fingerprint_sha256": key_fingerprint_sha256,
+            "key_version": keys.key_version(),
+            "signing_public_key": base64::encode(signing_key.verifying_key().to_bytes()),
+        }),
+    }
+}
+
+/// Debug helper for encryption mismatches: reports whether `client_public_key`
+/// is usable against the worker secret and whether it is the worker's current
+/// public key, plus a non-sensitive fingerprint of that key.
+fn handle_check_public_key(keys: Result<&WorkerKeys, &str>, args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct CheckPublicKeyArgs {
+        client_public_key: String,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: CHECK_PUBLIC_KEY_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: CheckPublicKeyArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => return error_response(format!("invalid {CHECK_PUBLIC_KEY_METHOD} args: {e}")),
+    };
+
+    let keys = match keys {
+        Ok(k) => k,
+        Err(e) => return error_response(e.to_string()),
+    };
+
+    match check_client_public_key(keys, &args.client_public_key) {
+        Ok(check) => ResponseType {
+            method: CHECK_PUBLIC_KEY_METHOD.to_string(),
+            response: serde_json::json!({
+                "ecdh_ok": check.ecdh_ok,
+                "matches_worker_public_key": check.matches_worker_public_key,
+                "worker_public_key_fingerprint": check.worker_public_key_fingerprint,
+            }),
+        },
+        Err(e) => error_response(e),
+    }
+}
+
+/// Deployment check: which worker seed secrets are set, read through `get`
+/// like `WorkerKeys::from_vars`, and whether keys derive from them. Only
+/// presence is reported; the values never leave this function.
+pub(crate) fn handle_inspect_worker_secrets(get: impl Fn(&str) -> Option<String>) -> ResponseType {
+    const PROTECTED_SEED_VAR: &str = "PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32";
+    const FALLBACK_SEED_VAR: &str = "OUTLAYER_WORKER_SK_SEED_HEX32";
+
+    let protected_secret_present = get(PROTECTED_SEED_VAR).is_some();
+    let fallback_secret_present = get(FALLBACK_SEED_VAR).is_some();
+    // Same precedence as `WorkerKeys::from_vars`: a protected seed always wins.
+    let active_secret_source = if protected_secret_present {
+        Some(PROTECTED_SEED_VAR)
+    } else if fallback_secret_present {
+        Some(FALLBACK_SEED_VAR)
+    } else {
+        None
+    };
+
+    ResponseType {
+        method: INSPECT_WORKER_SECRETS_METHOD.to_string(),
+        response: serde_json::json!({
+            "protected_secret_present": protected_secret_present,
+            "fallback_secret_present": fallback_secret_present,
+            "active_secret_source": active_secret_source,
+            "public_key_derivable": WorkerKeys::from_vars(&get).is_ok(),
+        }),
+    }
+}
+
+/// Client discovery: the worker version, its methods and what the DKIM
+/// verification path accepts.
+fn handle_get_capabilities() -> ResponseType {
+    let methods: Vec<&str> = WORKER_METHODS
+        .iter()
+        .chain(DEBUG_METHODS.iter())
+        .map(|(name, _)| *name)
+        .collect();
+
+    ResponseType {
+        method: GET_CAPABILITIES_METHOD.to_string(),
+        response: serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "methods": methods,
+            "supported_algorithms": SUPPORTED_DKIM_ALGORITHMS,
+            "supported_canonicalizations": SUPPORTED_CANONICALIZATIONS,
+            // No separate per-email limit: an email can fill the whole request.
+            "max_email_bytes": crate::MAX_INPUT_BYTES,
+        }),
+    }
+}
+
+/// Relayer pre-check: parse a recovery Subject (and optionally the body) the
+/// way `verify-encrypted-email` does, before paying for a full verification.
+/// `error` is set when verification would not yield an account and key.
+fn handle_parse_recovery_instruction(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct ParseRecoveryInstructionArgs {
+        subject: String,
+        #[serde(default)]
+        body: Option<String>,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: PARSE_RECOVERY_INSTRUCTION_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: ParseRecoveryInstructionArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return error_response(format!("invalid {PARSE_RECOVERY_INSTRUCTION_METHOD} args: {e}"))
+        }
+    };
+    // Same decoding as the verify path, for RFC 2047 encoded-word Subjects.
+    let subject = decode_encoded_words(&args.subject);
+    let instruction =
+        match parse_recovery_instruction(Some(&subject), args.body.as_deref().unwrap_or_default()) {
+            Ok(instruction) => instruction,
+            Err(e) => return error_response(e),
+        };
+
+    let error = if instruction.command.is_empty() {
+        Some("subject is not a recovery instruction")
+    } else if instruction.account_id.is_none() {
+        Some("missing account_id")
+    } else if instruction.new_public_key.is_none() {
+        Some("missing new_public_key")
+    } else {
+        None
+    };
+
+    ResponseType {
+        method: PARSE_RECOVERY_INSTRUCTION_METHOD.to_string(),
+        response: serde_json::json!({
+            "command": instruction.command,
+            "request_id": instruction.request_id,
+            "account_id": instruction.account_id,
+            "new_public_key": instruction.new_public_key,
+            "expires_at_secs": instruction.expires_at_secs,
+            "error": error,
+        }),
+    }
+}
+
+/// A fresh `request_id` for relayers that log it before calling the contract:
+/// 16 lowercase hex characters from the OS RNG, usable as the `<REQUEST_ID>`
+/// in a `recover-<REQUEST_ID> ...` Subject.
+fn handle_generate_request_id() -> ResponseType {
+    let mut bytes = [0u8; 8];
+    let response = match OsRng.try_fill_bytes(&mut bytes) {
+        Ok(()) => serde_json::json!({
+            "request_id": bytes.iter().map(|b| format!("{b:02x}")).collect::<String>(),
+        }),
+        Err(_) => serde_json::json!({ "error": "failed to generate request_id" }),
+    };
+    ResponseType {
+        method: GENERATE_REQUEST_ID_METHOD.to_string(),
+        response,
+    }
+}
+
+/// Interop helper: the exact AAD bytes the worker derives from `context` when
+/// decrypting, so a relayer can compare them with what it encrypted with.
+fn handle_validate_aead_context(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct ValidateAeadContextArgs {
+        context: Value,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: VALIDATE_AEAD_CONTEXT_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: ValidateAeadContextArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => return error_response(format!("invalid {VALIDATE_AEAD_CONTEXT_METHOD} args: {e}")),
+    };
+    let aad = match aad_bytes(&args.context) {
+        Ok(aad) => aad,
+        Err(e) => return error_response(e),
+    };
+    let sha256_hex: String = Sha256::digest(&aad).iter().map(|b| format!("{b:02x}")).collect();
+
+    ResponseType {
+        method: VALIDATE_AEAD_CONTEXT_METHOD.to_string(),
+        response: serde_json::json!({
+            "canonical_json": String::from_utf8_lossy(&aad),
+            "byte_length": aad.len(),
+            "sha256_hex": sha256_hex,
+        }),
+    }
+}
+
+/// Interop helper: derive the envelope AEAD key for a client's `ephemeral_pub`
+/// and return only a SHA-256 commitment to it, so third-party clients can
+/// check their ECDH + HKDF derivation without either side exposing the key.
+/// `version` selects the envelope KDF (v2 salts with `context.network_id`).
+fn handle_aead_keycheck(keys: Result<&WorkerKeys, &str>, args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct AeadKeycheckArgs {
+        ephemeral_pub: String,
+        #[serde(default = "default_keycheck_version")]
+        version: u8,
+        #[serde(default)]
+        context: Value,
+    }
+
+    fn default_keycheck_version() -> u8 {
+        ENVELOPE_VERSION_UNSALTED
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: AEAD_KEYCHECK_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: AeadKeycheckArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => return error_response(format!("invalid {AEAD_KEYCHECK_METHOD} args: {e}")),
+    };
+
+    let keys = match keys {
+        Ok(k) => k,
+        Err(e) => return error_response(e.to_string()),
+    };
+
+    match aead_key_commitment(keys, &args.ephemeral_pub, args.version, &args.context) {
+        Ok(commitment) => ResponseType {
+            method: AEAD_KEYCHECK_METHOD.to_string(),
+            response: serde_json::json!({
+                "version": args.version,
+                "key_commitment": commitment,
+            }),
+        },
+        Err(e) => error_response(e),
+    }
+}
+
+/// Envelope metadata for relayers that hold a stored envelope but not the
+/// worker secret. Only the envelope fields are parsed; nothing is decrypted.
+fn handle_inspect_encrypted_envelope(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct InspectEnvelopeArgs {
+        encrypted_email_blob: EncryptedEmailEnvelope,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: INSPECT_ENCRYPTED_ENVELOPE_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: InspectEnvelopeArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return error_response(format!("invalid {INSPECT_ENCRYPTED_ENVELOPE_METHOD} args: {e}"))
+        }
+    };
+    let envelope = args.encrypted_email_blob;
+
+    let ciphertext_len = match base64::decode(&envelope.ciphertext) {
+        Ok(bytes) => bytes.len(),
+        Err(e) => return error_response(format!("invalid ciphertext base64: {e}")),
+    };
+
+    ResponseType {
+        method: INSPECT_ENCRYPTED_ENVELOPE_METHOD.to_string(),
         response: serde_json::json!({
-            "verified": true,
-            "account_id": account_id,
-            "new_public_key": new_public_key,
-            "from_address_hash": from_address_hash,
-            "email_timestamp_ms": email_timestamp_ms,
-            "request_id": request_id,
-            "error": serde_json::Value::Null,
-            "context": verify_args.context,
+            "version": envelope.version,
+            "ephemeral_pub_b64": envelope.ephemeral_pub,
+            "nonce_b64": envelope.nonce,
+            "ciphertext_len": ciphertext_len,
+            "cipher": ENVELOPE_CIPHER,
         }),
     }
 }
 
-fn handle_get_public_key() -> ResponseType {
-    match get_worker_public_key() {
-        Ok(pk) => ResponseType {
-            method: GET_PUBLIC_KEY_METHOD.to_string(),
-            response: serde_json::json!({ "public_key": pk }),
+/// Debug helper: HKDF-SHA256 (no salt) over caller-supplied key material, to
+/// check that a relayer and the worker derive the same key from a shared secret.
+#[cfg(any(test, feature = "debug-methods"))]
+fn handle_hkdf_derive(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct HkdfDeriveArgs {
+        ikm_hex: String,
+        info: String,
+        output_length: usize,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: HKDF_DERIVE_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: HkdfDeriveArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => return error_response(format!("invalid {HKDF_DERIVE_METHOD} args: {e}")),
+    };
+    let ikm = match decode_hex(&args.ikm_hex) {
+        Ok(bytes) => bytes,
+        Err(e) => return error_response(format!("invalid ikm_hex: {e}")),
+    };
+
+    match hkdf_derive(&ikm, args.info.as_bytes(), args.output_length) {
+        Ok(okm) => ResponseType {
+            method: HKDF_DERIVE_METHOD.to_string(),
+            response: serde_json::json!({
+                "okm_hex": okm.iter().map(|b| format!("{b:02x}")).collect::<String>(),
+            }),
         },
-        Err(e) => ResponseType {
-            method: GET_PUBLIC_KEY_METHOD.to_string(),
-            response: serde_json::json!({ "error": e }),
+        Err(e) => error_response(e),
+    }
+}
+
+/// Debug helper: only the RSA step of DKIM verification, to tell a bad
+/// signature or key apart from a canonicalization or body hash problem.
+/// `data_b64` is the SHA-256 digest of the signed header data.
+#[cfg(any(test, feature = "debug-methods"))]
+fn handle_test_rsa_verify(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct TestRsaVerifyArgs {
+        data_b64: String,
+        signature_b64: String,
+        public_key_b64: String, // DER SubjectPublicKeyInfo, as in a DKIM `p=` tag
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: TEST_RSA_VERIFY_METHOD.to_string(),
+        response: serde_json::json!({ "verified": false, "error": error }),
+    };
+
+    let args: TestRsaVerifyArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => return error_response(format!("invalid {TEST_RSA_VERIFY_METHOD} args: {e}")),
+    };
+    let decode = |field: &str, value: &str| {
+        base64::decode(value.trim()).map_err(|e| format!("invalid {field}: {e}"))
+    };
+    let verified = decode("data_b64", &args.data_b64).and_then(|digest| {
+        let signature = decode("signature_b64", &args.signature_b64)?;
+        let spki = decode("public_key_b64", &args.public_key_b64)?;
+        verify_rsa_sha256_digest(&spki, &digest, &signature)
+    });
+
+    match verified {
+        Ok(verified) => ResponseType {
+            method: TEST_RSA_VERIFY_METHOD.to_string(),
+            response: serde_json::json!({ "verified": verified, "error": Value::Null }),
         },
+        Err(e) => error_response(e),
+    }
+}
+
+/// Re-encrypt an envelope made for the previous worker key (the seed before
+/// the last rotation) to the current one. Both keys derive from the worker's
+/// protected seeds; the caller cannot choose the target key, so the plaintext
+/// never becomes readable outside the worker.
+fn handle_reencrypt_email(keys: Result<&WorkerKeys, &str>, args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct ReencryptArgs {
+        old_envelope: EncryptedEmailEnvelope,
+        #[serde(default)]
+        context: Value,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: REENCRYPT_EMAIL_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: ReencryptArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => return error_response(format!("invalid {REENCRYPT_EMAIL_METHOD} args: {e}")),
+    };
+
+    let keys = match keys {
+        Ok(k) => k,
+        Err(e) => return error_response(e.to_string()),
+    };
+    let previous_secret = match keys.previous_static_secret() {
+        Some(secret) => secret,
+        None => {
+            return error_response(
+                "PROTECTED_OUTLAYER_WORKER_PREVIOUS_SK_SEED_HEX32 is not set".to_string(),
+            )
+        }
+    };
+
+    let plaintext =
+        match decrypt_bytes_with_static_secret(previous_secret, &args.old_envelope, &args.context) {
+            Ok(p) => p,
+            Err(e) => return error_response(e),
+        };
+
+    // Keep the envelope version so clients see the same format back.
+    match encrypt_email_to_public_key(
+        &plaintext,
+        &get_worker_public_key(keys),
+        &args.context,
+        args.old_envelope.version,
+    ) {
+        Ok(envelope) => ResponseType {
+            method: REENCRYPT_EMAIL_METHOD.to_string(),
+            response: serde_json::json!({ "encrypted_email_blob": envelope }),
+        },
+        Err(e) => error_response(e),
+    }
+}
+
+/// RSA keys shorter than this must not be used for DKIM (RFC 8301 §3.2).
+const MIN_DKIM_RSA_KEY_BITS: usize = 1024;
+
+/// Setup check for a new domain: fetch `selector._domainkey.domain` and list
+/// everything wrong with the key record (`v=`, `k=`, `p=`, RSA key parse and
+/// size), so operators can fix DNS before mail depends on it.
+fn handle_check_dkim_dns(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct CheckDkimDnsArgs {
+        selector: String,
+        domain: String,
+    }
+
+    let args: CheckDkimDnsArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return ResponseType {
+                method: CHECK_DKIM_DNS_METHOD.to_string(),
+                response: serde_json::json!({
+                    "error": format!("invalid {CHECK_DKIM_DNS_METHOD} args: {e}"),
+                }),
+            }
+        }
+    };
+
+    let name = format!("{}._domainkey.{}", args.selector.trim(), args.domain.trim());
+    let mut issues = Vec::new();
+    let mut key_bits = None;
+    let mut key_algorithm = None;
+
+    match fetch_txt_records(&name) {
+        Err(e) => issues.push(format!("DNS lookup failed: {e}")),
+        Ok(records) if records.is_empty() => issues.push(format!("no TXT records found for {name}")),
+        Ok(records) => {
+            let key_tags = records
+                .iter()
+                .map(|record| parse_dkim_tags(record))
+                .find(|tags| tags.contains_key("v") || tags.contains_key("p"));
+            match key_tags {
+                None => issues.push(format!(
+                    "no DKIM key record among {} TXT record(s) for {name}",
+                    records.len()
+                )),
+                Some(tags) => {
+                    match tags.get("v").map(String::as_str) {
+                        None => issues.push("missing v= tag".to_string()),
+                        Some("DKIM1") => {}
+                        Some(v) => issues.push(format!("v= must be DKIM1, got {v}")),
+                    }
+
+                    key_algorithm = tags.get("k").map(|k| k.to_ascii_lowercase());
+                    match key_algorithm.as_deref() {
+                        None => issues.push("missing k= tag".to_string()),
+                        Some("rsa") => {}
+                        Some(k) => issues.push(format!("unsupported k= algorithm: {k}")),
+                    }
+
+                    match tags.get("p").map(String::as_str) {
+                        None => issues.push("missing p= tag".to_string()),
+                        Some("") => issues.push("empty p= tag: key is revoked".to_string()),
+                        Some(p) => match base64::decode(p) {
+                            Err(e) => issues.push(format!("p= is not valid base64: {e}")),
+                            Ok(pk_bytes) => match parse_rsa_public_key(&pk_bytes) {
+                                Err(e) => issues.push(format!("p= {e}")),
+                                Ok(public_key) => {
+                                    let bits = public_key.size() * 8;
+                                    if bits < MIN_DKIM_RSA_KEY_BITS {
+                                        issues.push(format!(
+                                            "RSA key is {bits} bits; at least {MIN_DKIM_RSA_KEY_BITS} are required"
+                                        ));
+                                    }
+                                    key_bits = Some(bits);
+                                }
+                            },
+                        },
+                    }
+                }
+            }
+        }
+    }
+
+    ResponseType {
+        method: CHECK_DKIM_DNS_METHOD.to_string(),
+        response: serde_json::json!({
+            "name": name,
+            "valid": issues.is_empty(),
+            "issues": issues,
+            "key_bits": key_bits,
+            "key_algorithm": key_algorithm,
+        }),
+    }
+}
+
+/// Debug utility: strip folding whitespace from a DKIM `b=` / `bh=` value,
+/// base64-decode it and report the lengths plus a short hex preview.
+/// No crypto or DNS I/O is performed.
+fn handle_decode_base64_dkim_value(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct DecodeArgs {
+        value: String,
+        tag: String,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: DECODE_BASE64_DKIM_VALUE_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: DecodeArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return error_response(format!("invalid {DECODE_BASE64_DKIM_VALUE_METHOD} args: {e}"))
+        }
+    };
+
+    let tag = args.tag.trim().to_ascii_lowercase();
+    if tag != "b" && tag != "bh" {
+        return error_response(format!("unsupported DKIM tag: {} (expected b or bh)", args.tag));
+    }
+
+    let cleaned: String = args.value.chars().filter(|c| !c.is_whitespace()).collect();
+    let decoded = match base64::decode(&cleaned) {
+        Ok(bytes) => bytes,
+        Err(e) => return error_response(format!("invalid base64 in {tag}= value: {e}")),
+    };
+
+    let hex_preview: String = decoded.iter().take(16).map(|b| format!("{b:02x}")).collect();
+
+    ResponseType {
+        method: DECODE_BASE64_DKIM_VALUE_METHOD.to_string(),
+        response: serde_json::json!({
+            "tag": tag,
+            "decoded_b64_length": cleaned.len(),
+            "decoded_bytes_length": decoded.len(),
+            "hex_preview": hex_preview,
+        }),
+    }
+}
+
+/// Informational: the parsed tags of Gmail's `X-Google-DKIM-Signature`. That
+/// signature uses a private Google key, so it is never verified.
+fn handle_parse_x_google_dkim_signature(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct ParseGoogleDkimArgs {
+        email_blob: String,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: PARSE_X_GOOGLE_DKIM_SIGNATURE_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: ParseGoogleDkimArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return error_response(format!(
+                "invalid {PARSE_X_GOOGLE_DKIM_SIGNATURE_METHOD} args: {e}"
+            ))
+        }
+    };
+
+    match extract_google_dkim_signature(strip_bom(&args.email_blob)) {
+        Some(tags) => ResponseType {
+            method: PARSE_X_GOOGLE_DKIM_SIGNATURE_METHOD.to_string(),
+            response: serde_json::json!({ "tags": tags }),
+        },
+        None => error_response("no X-Google-DKIM-Signature header".to_string()),
+    }
+}
+
+/// Verify a detached DKIM-style signature over `{headers, body}` that did not
+/// come from an email (see `verify_dkim::verify_signed_payload`). The public key
+/// is passed in directly; no DNS lookup is performed.
+fn handle_verify_signed_payload(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct VerifySignedPayloadArgs {
+        headers: Vec<(String, String)>,
+        #[serde(default)]
+        body: String,
+        signature_b64: String,
+        public_key_der_b64: String,
+        #[serde(default = "default_payload_algorithm")]
+        algorithm: String,
+    }
+
+    fn default_payload_algorithm() -> String {
+        "rsa-sha256".to_string()
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: VERIFY_SIGNED_PAYLOAD_METHOD.to_string(),
+        response: serde_json::json!({ "verified": false, "error": error }),
+    };
+
+    let args: VerifySignedPayloadArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return error_response(format!("invalid {VERIFY_SIGNED_PAYLOAD_METHOD} args: {e}"))
+        }
+    };
+
+    if !args.algorithm.trim().eq_ignore_ascii_case("rsa-sha256") {
+        return error_response(format!(
+            "unsupported algorithm: {} (expected rsa-sha256)",
+            args.algorithm
+        ));
+    }
+
+    let decode = |value: &str, what: &str| {
+        let cleaned: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+        base64::decode(cleaned).map_err(|e| format!("invalid base64 in {what}: {e}"))
+    };
+    let signature = match decode(&args.signature_b64, "signature_b64") {
+        Ok(bytes) => bytes,
+        Err(e) => return error_response(e),
+    };
+    let public_key_der = match decode(&args.public_key_der_b64, "public_key_der_b64") {
+        Ok(bytes) => bytes,
+        Err(e) => return error_response(e),
+    };
+
+    match verify_signed_payload(&args.headers, &args.body, &signature, &public_key_der) {
+        Ok(()) => ResponseType {
+            method: VERIFY_SIGNED_PAYLOAD_METHOD.to_string(),
+            response: serde_json::json!({ "verified": true }),
+        },
+        Err(e) => error_response(e),
+    }
+}
+
+/// Compute the DKIM body hash (`bh=`) of `email_blob` under `relaxed` or
+/// `simple` body canonicalization, so callers can check it independently of
+/// the full signature verification. `l=` truncation is not applied.
+fn handle_compute_body_hash(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct ComputeBodyHashArgs {
+        email_blob: String,
+        canonicalization: String,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: COMPUTE_BODY_HASH_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: ComputeBodyHashArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => return error_response(format!("invalid {COMPUTE_BODY_HASH_METHOD} args: {e}")),
+    };
+
+    let (_, body) = split_headers_body(strip_bom(&args.email_blob));
+    let canon_body = match args.canonicalization.trim().to_ascii_lowercase().as_str() {
+        "relaxed" => canonicalize_body_relaxed(body),
+        "simple" => canonicalize_body_simple(body),
+        _ => {
+            return error_response(format!(
+                "unsupported body canonicalization: {} (expected relaxed or simple)",
+                args.canonicalization
+            ))
+        }
+    };
+
+    ResponseType {
+        method: COMPUTE_BODY_HASH_METHOD.to_string(),
+        response: serde_json::json!({
+            "bh_b64": base64::encode(Sha256::digest(canon_body.as_bytes())),
+            "canon_body_len": canon_body.len(),
+        }),
+    }
+}
+
+/// Compare the first DKIM-Signature's `bh=` with the hash of the body under
+/// that signature's body canonicalization (`c=`) and `l=`. Needs no DNS or
+/// key, so body canonicalization problems show up apart from signature ones.
+fn handle_check_body_hash(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct CheckBodyHashArgs {
+        email_blob: String,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: CHECK_BODY_HASH_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: CheckBodyHashArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => return error_response(format!("invalid {CHECK_BODY_HASH_METHOD} args: {e}")),
+    };
+
+    let check = match check_dkim_body_hash(&args.email_blob) {
+        Ok(c) => c,
+        Err(e) => return error_response(e),
+    };
+
+    ResponseType {
+        method: CHECK_BODY_HASH_METHOD.to_string(),
+        response: serde_json::json!({
+            "matches": check.computed_bh == check.expected_bh,
+            "expected_bh": check.expected_bh,
+            "computed_bh": check.computed_bh,
+        }),
+    }
+}
+
+/// Outcome of comparing a DKIM-Signature's `bh=` with the recomputed hash.
+struct BodyHashCheck {
+    expected_bh: String,
+    computed_bh: String,
+    canonicalization: Canonicalization,
+}
+
+/// Recompute the first DKIM-Signature's body hash under its `c=` body
+/// algorithm and `l=`, alongside the `bh=` it claims.
+fn check_dkim_body_hash(email_blob: &str) -> Result<BodyHashCheck, String> {
+    let (raw_headers, body) = split_headers_body(strip_bom(email_blob));
+    let headers = parse_headers(raw_headers);
+    let tags = match headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("DKIM-Signature"))
+    {
+        Some((_, value)) => parse_dkim_tags(value),
+        None => return Err("no DKIM-Signature header".to_string()),
+    };
+
+    let expected_bh: String = tags
+        .get("bh")
+        .map(String::as_str)
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '+' || *c == '/' || *c == '=')
+        .collect();
+    if expected_bh.is_empty() {
+        return Err("DKIM-Signature has no bh= tag".to_string());
+    }
+
+    let (canonicalization, canon_body) =
+        match parse_dkim_canonicalization(tags.get("c").map(String::as_str)) {
+            Some((_, Canonicalization::Relaxed)) => {
+                (Canonicalization::Relaxed, canonicalize_body_relaxed(body))
+            }
+            Some((_, Canonicalization::Simple)) => {
+                (Canonicalization::Simple, canonicalize_body_simple(body))
+            }
+            None => {
+                return Err(format!(
+                    "unsupported canonicalization: {}",
+                    tags.get("c").map(String::as_str).unwrap_or_default()
+                ))
+            }
+        };
+
+    let hashed = match tags.get("l") {
+        Some(l) => match l.trim().parse::<usize>() {
+            Ok(len) if len <= canon_body.len() => &canon_body.as_bytes()[..len],
+            _ => return Err(format!("invalid l= tag: {l}")),
+        },
+        None => canon_body.as_bytes(),
+    };
+
+    Ok(BodyHashCheck {
+        expected_bh,
+        computed_bh: base64::encode(Sha256::digest(hashed)),
+        canonicalization,
+    })
+}
+
+/// Client-side pre-check before full DKIM verification: whether the first
+/// DKIM-Signature's `bh=` matches the body, without any DNS fetch or RSA work.
+/// A mismatch means the body was altered and DKIM would fail anyway.
+fn handle_verify_envelope_integrity(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct VerifyEnvelopeIntegrityArgs {
+        email_blob: String,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: VERIFY_ENVELOPE_INTEGRITY_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: VerifyEnvelopeIntegrityArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return error_response(format!("invalid {VERIFY_ENVELOPE_INTEGRITY_METHOD} args: {e}"))
+        }
+    };
+
+    let check = match check_dkim_body_hash(&args.email_blob) {
+        Ok(c) => c,
+        Err(e) => return error_response(e),
+    };
+    let canon_algorithm = match check.canonicalization {
+        Canonicalization::Relaxed => "relaxed",
+        Canonicalization::Simple => "simple",
+    };
+
+    ResponseType {
+        method: VERIFY_ENVELOPE_INTEGRITY_METHOD.to_string(),
+        response: serde_json::json!({
+            "body_hash_matches": check.computed_bh == check.expected_bh,
+            "bh_expected_b64": check.expected_bh,
+            "bh_computed_b64": check.computed_bh,
+            "canon_algorithm": canon_algorithm,
+        }),
+    }
+}
+
+/// Return the exact header data a DKIM-Signature's `b=` is checked against
+/// (see `verify_dkim::canonicalized_signed_headers`), with its SHA-256, so
+/// signature failures can be compared with an external signer's input.
+fn handle_get_canonicalized_headers(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct GetCanonicalizedHeadersArgs {
+        email_blob: String,
+        #[serde(default)]
+        dkim_index: usize,
+        canonicalization: String,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: GET_CANONICALIZED_HEADERS_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: GetCanonicalizedHeadersArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return error_response(format!("invalid {GET_CANONICALIZED_HEADERS_METHOD} args: {e}"))
+        }
+    };
+
+    let canon = match args.canonicalization.trim().to_ascii_lowercase().as_str() {
+        "relaxed" => Canonicalization::Relaxed,
+        "simple" => Canonicalization::Simple,
+        _ => {
+            return error_response(format!(
+                "unsupported header canonicalization: {} (expected relaxed or simple)",
+                args.canonicalization
+            ))
+        }
+    };
+
+    let (canonicalized, b_b64_clean) =
+        match canonicalized_signed_headers(&args.email_blob, args.dkim_index, canon) {
+            Ok(v) => v,
+            Err(e) => return error_response(e),
+        };
+    let data_hash_hex: String = Sha256::digest(canonicalized.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    ResponseType {
+        method: GET_CANONICALIZED_HEADERS_METHOD.to_string(),
+        response: serde_json::json!({
+            "canonicalized": canonicalized,
+            "data_hash_hex": data_hash_hex,
+            "b_b64_clean": b_b64_clean,
+        }),
+    }
+}
+
+/// The header data the `b=` signature of one DKIM-Signature is computed over,
+/// under that signature's own `c=` (see `verify_dkim::dkim_header_signing_data`),
+/// with its SHA-256. Unlike `get-canonicalized-headers`, the canonicalization
+/// is not chosen by the caller, so this is exactly the verifier's input.
+fn handle_compute_header_hash(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct ComputeHeaderHashArgs {
+        email_blob: String,
+        #[serde(default)]
+        dkim_index: usize,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: COMPUTE_HEADER_HASH_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: ComputeHeaderHashArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return error_response(format!("invalid {COMPUTE_HEADER_HASH_METHOD} args: {e}"))
+        }
+    };
+
+    let (data, signed_headers) = match dkim_header_signing_data(&args.email_blob, args.dkim_index) {
+        Ok(v) => v,
+        Err(e) => return error_response(e),
+    };
+    let data_hex: String = data.bytes().map(|b| format!("{b:02x}")).collect();
+    let data_sha256_hex: String = Sha256::digest(data.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    ResponseType {
+        method: COMPUTE_HEADER_HASH_METHOD.to_string(),
+        response: serde_json::json!({
+            "data_hex": data_hex,
+            "data_sha256_hex": data_sha256_hex,
+            "signed_headers": signed_headers,
+            "data_length": data.len(),
+        }),
+    }
+}
+
+/// Forensic helper: the header fields one DKIM-Signature covers, each with its
+/// raw and relaxed-canonical value (see `dkim_signed_header_fields`).
+fn handle_extract_dkim_signed_headers(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct ExtractDkimSignedHeadersArgs {
+        email_blob: String,
+        #[serde(default)]
+        dkim_index: usize,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: EXTRACT_DKIM_SIGNED_HEADERS_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: ExtractDkimSignedHeadersArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return error_response(format!(
+                "invalid {EXTRACT_DKIM_SIGNED_HEADERS_METHOD} args: {e}"
+            ))
+        }
+    };
+
+    let fields = match dkim_signed_header_fields(&args.email_blob, args.dkim_index) {
+        Ok(fields) => fields,
+        Err(e) => return error_response(e),
+    };
+    let signed_headers: Vec<Value> = fields
+        .into_iter()
+        .map(|field| {
+            serde_json::json!({
+                "name": field.name,
+                "raw_value": field.raw_value,
+                "canonical_value": field.canonical_value,
+            })
+        })
+        .collect();
+
+    ResponseType {
+        method: EXTRACT_DKIM_SIGNED_HEADERS_METHOD.to_string(),
+        response: serde_json::json!({ "signed_headers": signed_headers }),
+    }
+}
+
+/// Decode a quoted-printable body payload (see `qp::decode_quoted_printable`).
+/// Bytes that are not valid UTF-8 are replaced with U+FFFD and reported in
+/// `error`; `decoded` is still returned.
+fn handle_decode_quoted_printable(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct DecodeQuotedPrintableArgs {
+        text: String,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: DECODE_QUOTED_PRINTABLE_METHOD.to_string(),
+        response: serde_json::json!({ "decoded": "", "error": error }),
+    };
+
+    let args: DecodeQuotedPrintableArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return error_response(format!("invalid {DECODE_QUOTED_PRINTABLE_METHOD} args: {e}"))
+        }
+    };
+
+    let decoded = decode_quoted_printable(&args.text);
+    let (decoded, error) = match String::from_utf8(decoded) {
+        Ok(text) => (text, None),
+        Err(e) => (
+            String::from_utf8_lossy(e.as_bytes()).into_owned(),
+            Some("decoded bytes are not valid UTF-8".to_string()),
+        ),
+    };
+
+    ResponseType {
+        method: DECODE_QUOTED_PRINTABLE_METHOD.to_string(),
+        response: serde_json::json!({ "decoded": decoded, "error": error }),
+    }
+}
+
+/// Debug utility: list every header exactly as `parse_headers` sees it, in
+/// message order, plus the indices of the `DKIM-Signature` headers. Values are
+/// raw (folding CRLFs and leading whitespace kept), as used for `c=simple`.
+fn handle_list_email_headers(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct ListEmailHeadersArgs {
+        email_blob: String,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: LIST_EMAIL_HEADERS_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: ListEmailHeadersArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => return error_response(format!("invalid {LIST_EMAIL_HEADERS_METHOD} args: {e}")),
+    };
+
+    let (raw_headers, _) = split_headers_body(strip_bom(&args.email_blob));
+    let headers: Vec<EmailHeader> = parse_headers(raw_headers)
+        .into_iter()
+        .enumerate()
+        .map(|(index, (name, value))| EmailHeader { name, value, index })
+        .collect();
+    let dkim_signature_indices: Vec<usize> = headers
+        .iter()
+        .filter(|h| h.name.trim_end().eq_ignore_ascii_case("DKIM-Signature"))
+        .map(|h| h.index)
+        .collect();
+
+    ResponseType {
+        method: LIST_EMAIL_HEADERS_METHOD.to_string(),
+        response: serde_json::json!({
+            "headers": headers,
+            "dkim_signature_indices": dkim_signature_indices,
+        }),
+    }
+}
+
+/// Recipient pre-check: whether any `To:` address is in `expected_domain`,
+/// the same test the contract applies when `expected_to_domain` is set.
+fn handle_check_recipient(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct CheckRecipientArgs {
+        email_blob: String,
+        expected_domain: String,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: CHECK_RECIPIENT_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: CheckRecipientArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => return error_response(format!("invalid {CHECK_RECIPIENT_METHOD} args: {e}")),
+    };
+
+    let recipient_addresses = parse_to_addresses(strip_bom(&args.email_blob));
+    let matched_address = recipient_addresses
+        .iter()
+        .find(|address| address_in_domain(address, &args.expected_domain))
+        .cloned();
+
+    ResponseType {
+        method: CHECK_RECIPIENT_METHOD.to_string(),
+        response: serde_json::json!({
+            "matches": matched_address.is_some(),
+            "recipient_addresses": recipient_addresses,
+            "matched_address": matched_address,
+        }),
+    }
+}
+
+/// Canonicalize an address exactly like the contract does before storing or
+/// hashing it, and split the result at the last `@`. An input with no address
+/// in it yields the lowercased input as `local` and an empty `domain`.
+fn handle_expand_email_address(args: Value) -> ResponseType {
+    #[derive(Deserialize)]
+    struct ExpandEmailAddressArgs {
+        address: String,
+    }
+
+    let error_response = |error: String| ResponseType {
+        method: EXPAND_EMAIL_ADDRESS_METHOD.to_string(),
+        response: serde_json::json!({ "error": error }),
+    };
+
+    let args: ExpandEmailAddressArgs = match serde_json::from_value(args) {
+        Ok(a) => a,
+        Err(e) => {
+            return error_response(format!("invalid {EXPAND_EMAIL_ADDRESS_METHOD} args: {e}"))
+        }
+    };
+
+    let canonical = canonicalize_email_address(&args.address);
+    let (local, domain) = canonical.rsplit_once('@').unwrap_or((canonical.as_str(), ""));
+
+    ResponseType {
+        method: EXPAND_EMAIL_ADDRESS_METHOD.to_string(),
+        response: serde_json::json!({
+            "canonical": canonical,
+            "local": local,
+            "domain": domain,
+        }),
+    }
+}
+
+/// DKIM-Signature value used by `worker-self-test`. `bh=` matches
+/// `SELF_TEST_EMAIL`'s body, so verification reaches the RSA check; `b=` is
+/// not a real signature.
+const SELF_TEST_DKIM_SIGNATURE: &str = "v=1; a=rsa-sha256; c=relaxed/relaxed; d=gmail.com; s=20230601; h=from:subject:date; bh=p0RTzJysxJPFK4jt8mURo7nBpcQCajiwzcRhpWk+wX8=; b=AAECAwQFBgcICQoLDA0ODw==";
+
+const SELF_TEST_DATE: &str = "Thu, 1 Jan 2026 00:00:00 +0000";
+const SELF_TEST_DATE_MS: u64 = 1_767_225_600_000;
+
+/// Gmail's `20230601` key record, so the RSA key parsing path runs too.
+const SELF_TEST_DKIM_RECORD: &str = "v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB";
+
+/// Deployment smoke test: runs the key, DKIM tag parsing, DKIM verification
+/// and Date parsing paths on built-in fixtures. Apart from loading the worker
+/// secret, no I/O is performed (in particular no DNS lookup).
+fn handle_worker_self_test(keys: Result<&WorkerKeys, &str>) -> ResponseType {
+    #[derive(Serialize)]
+    struct SelfTestStep {
+        step: &'static str,
+        ok: bool,
+        error: Option<String>,
+    }
+
+    fn run(step: &'static str, outcome: Result<(), String>) -> SelfTestStep {
+        SelfTestStep {
+            step,
+            ok: outcome.is_ok(),
+            error: outcome.err(),
+        }
+    }
+
+    let self_test_email = format!(
+        "DKIM-Signature: {SELF_TEST_DKIM_SIGNATURE}\r\n\
+         From: Self Test <self-test@gmail.com>\r\n\
+         Subject: worker self-test\r\n\
+         Date: {SELF_TEST_DATE}\r\n\
+         \r\n\
+         worker self-test\r\n"
+    );
+
+    let results = vec![
+        run(
+            "get_worker_public_key",
+            keys.map(get_worker_public_key)
+                .map(|_| ())
+                .map_err(str::to_string),
+        ),
+        run("parse_dkim_tags", {
+            let tags = parse_dkim_tags(SELF_TEST_DKIM_SIGNATURE);
+            let expected = [("a", "rsa-sha256"), ("d", "gmail.com"), ("s", "20230601")];
+            match expected
+                .iter()
+                .find(|(tag, value)| tags.get(*tag).map(String::as_str) != Some(*value))
+            {
+                None => Ok(()),
+                Some((tag, value)) => Err(format!("expected {tag}={value}, got {:?}", tags.get(*tag))),
+            }
+        }),
+        run(
+            "verify_dkim",
+            match verify_dkim_signature(&self_test_email, &[SELF_TEST_DKIM_RECORD.to_string()]) {
+                // The fixture's `b=` is not a real signature: rejection is the
+                // expected outcome.
+                Err(DkimError::VerificationFailed) => Ok(()),
+                Err(e) => Err(format!("unexpected DKIM error: {e}")),
+                Ok(_) => Err("synthetic email unexpectedly verified".to_string()),
+            },
+        ),
+        run(
+            "parse_email_timestamp_ms",
+            match parse_email_timestamp_ms(&self_test_email, None) {
+                Some(SELF_TEST_DATE_MS) => Ok(()),
+                other => Err(format!("expected {SELF_TEST_DATE_MS}, got {other:?}")),
+            },
+        ),
+    ];
+
+    let all_passed = results.iter().all(|r| r.ok);
+    ResponseType {
+        method: WORKER_SELF_TEST_METHOD.to_string(),
+        response: serde_json::json!({
+            "all_passed": all_passed,
+            "results": results,
+        }),
     }
 }
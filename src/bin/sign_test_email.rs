@@ -0,0 +1,286 @@
+//! Development tool: DKIM-sign a raw email for use as a test fixture.
+//!
+//! ```text
+//! cargo run --bin sign-test-email -- \
+//!     --private-key-pem key.pem --selector test --domain example.com \
+//!     [--canonicalization relaxed/relaxed] < unsigned.eml > signed.eml
+//! ```
+//!
+//! Reads the unsigned message from stdin and writes it to stdout with a
+//! `DKIM-Signature` header (rsa-sha256) prepended. Canonicalization reuses the
+//! worker's `parsers.rs`, so fixtures match what `verify_dkim.rs` checks.
+
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(dead_code)] // only the canonicalization helpers are used here
+#[path = "../parsers.rs"]
+mod parsers;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+#[allow(dead_code)] // only `verify_dkim_signature` is used, to check signed output
+#[path = "../verify_dkim.rs"]
+mod verify_dkim;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    if let Err(e) = native::run() {
+        eprintln!("sign-test-email: {e}");
+        eprintln!("{}", native::USAGE);
+        std::process::exit(2);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::io::{self, Read, Write};
+
+    use rsa::pkcs1::DecodeRsaPrivateKey;
+    use rsa::pkcs1v15::SigningKey;
+    use rsa::pkcs8::DecodePrivateKey;
+    use rsa::sha2::{Digest, Sha256};
+    use rsa::signature::hazmat::PrehashSigner;
+    use rsa::signature::SignatureEncoding;
+    use rsa::RsaPrivateKey;
+
+    use crate::parsers::{
+        build_canonicalized_dkim_header_relaxed, build_canonicalized_dkim_header_simple,
+        canonicalize_body_relaxed, canonicalize_body_simple, canonicalize_headers_relaxed,
+        canonicalize_headers_simple, parse_headers, split_headers_body, strip_bom,
+    };
+
+    pub const USAGE: &str = "usage: sign-test-email --private-key-pem <path> --selector <s> \
+--domain <d> [--canonicalization <header>/<body>] < email.eml";
+
+    /// Headers covered by `h=` when present in the message, in signing order.
+    const SIGNED_HEADERS: &[&str] = &[
+        "from",
+        "to",
+        "cc",
+        "subject",
+        "date",
+        "message-id",
+        "mime-version",
+        "content-type",
+    ];
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Canon {
+        Simple,
+        Relaxed,
+    }
+
+    impl Canon {
+        fn parse(value: &str) -> Result<Self, String> {
+            match value {
+                "simple" => Ok(Canon::Simple),
+                "relaxed" => Ok(Canon::Relaxed),
+                other => Err(format!("unknown canonicalization: {other}")),
+            }
+        }
+
+        fn as_str(self) -> &'static str {
+            match self {
+                Canon::Simple => "simple",
+                Canon::Relaxed => "relaxed",
+            }
+        }
+    }
+
+    /// Parse a `c=` value. A lone header algorithm implies `simple` for the
+    /// body (RFC 6376 §3.5).
+    pub fn parse_canonicalization(value: &str) -> Result<(Canon, Canon), String> {
+        match value.split_once('/') {
+            Some((header, body)) => Ok((Canon::parse(header)?, Canon::parse(body)?)),
+            None => Ok((Canon::parse(value)?, Canon::Simple)),
+        }
+    }
+
+    pub struct SignOptions<'a> {
+        pub selector: &'a str,
+        pub domain: &'a str,
+        pub header_canon: Canon,
+        pub body_canon: Canon,
+    }
+
+    pub fn load_private_key(pem: &str) -> Result<RsaPrivateKey, String> {
+        RsaPrivateKey::from_pkcs8_pem(pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+            .map_err(|e| format!("invalid RSA private key PEM: {e}"))
+    }
+
+    /// Sign `email` and return it with the `DKIM-Signature` header prepended.
+    /// Bare LF line endings are normalized to CRLF first.
+    pub fn sign_email(
+        email: &str,
+        key: &RsaPrivateKey,
+        opts: &SignOptions,
+    ) -> Result<String, String> {
+        let email = strip_bom(email).replace("\r\n", "\n").replace('\n', "\r\n");
+        let (raw_headers, body) = split_headers_body(&email);
+        let headers = parse_headers(raw_headers);
+
+        let signed_headers: Vec<String> = SIGNED_HEADERS
+            .iter()
+            .filter(|name| {
+                headers
+                    .iter()
+                    .any(|(h, _)| h.trim_end().eq_ignore_ascii_case(name))
+            })
+            .map(|name| name.to_string())
+            .collect();
+        if signed_headers.is_empty() {
+            return Err("email has none of the headers to sign (From, Subject, ...)".to_string());
+        }
+
+        let canon_body = match opts.body_canon {
+            Canon::Simple => canonicalize_body_simple(body),
+            Canon::Relaxed => canonicalize_body_relaxed(body),
+        };
+        let bh = base64::encode(Sha256::digest(canon_body.as_bytes()));
+
+        let unsigned_value = format!(
+            " v=1; a=rsa-sha256; c={}/{}; d={}; s={}; h={}; bh={}; b=",
+            opts.header_canon.as_str(),
+            opts.body_canon.as_str(),
+            opts.domain,
+            opts.selector,
+            signed_headers.join(":"),
+            bh,
+        );
+
+        let mut data = match opts.header_canon {
+            Canon::Simple => canonicalize_headers_simple(&headers, &signed_headers),
+            Canon::Relaxed => canonicalize_headers_relaxed(&headers, &signed_headers),
+        };
+        data.push_str(&match opts.header_canon {
+            Canon::Simple => build_canonicalized_dkim_header_simple("DKIM-Signature", &unsigned_value),
+            Canon::Relaxed => build_canonicalized_dkim_header_relaxed(&unsigned_value),
+        });
+
+        let digest = Sha256::digest(data.as_bytes());
+        let signature = SigningKey::<Sha256>::new(key.clone())
+            .sign_prehash(&digest)
+            .map_err(|e| format!("RSA signing failed: {e}"))?;
+        let b = base64::encode(signature.to_bytes());
+
+        Ok(format!("DKIM-Signature:{unsigned_value}{b}\r\n{email}"))
+    }
+
+    pub fn run() -> Result<(), String> {
+        let mut key_path = None;
+        let mut selector = None;
+        let mut domain = None;
+        let mut canonicalization = "relaxed/relaxed".to_string();
+
+        let mut args = std::env::args().skip(1);
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{flag} requires a value"));
+            match flag.as_str() {
+                "--private-key-pem" => key_path = Some(value()?),
+                "--selector" => selector = Some(value()?),
+                "--domain" => domain = Some(value()?),
+                "--canonicalization" => canonicalization = value()?,
+                other => return Err(format!("unknown argument: {other}")),
+            }
+        }
+
+        let key_path = key_path.ok_or("--private-key-pem is required")?;
+        let selector = selector.ok_or("--selector is required")?;
+        let domain = domain.ok_or("--domain is required")?;
+        let (header_canon, body_canon) = parse_canonicalization(&canonicalization)?;
+
+        let pem = std::fs::read_to_string(&key_path)
+            .map_err(|e| format!("cannot read {key_path}: {e}"))?;
+        let key = load_private_key(&pem)?;
+
+        let mut email = String::new();
+        io::stdin()
+            .read_to_string(&mut email)
+            .map_err(|e| format!("cannot read email from stdin: {e}"))?;
+
+        let signed = sign_email(
+            &email,
+            &key,
+            &SignOptions {
+                selector: &selector,
+                domain: &domain,
+                header_canon,
+                body_canon,
+            },
+        )?;
+
+        let mut stdout = io::stdout();
+        stdout
+            .write_all(signed.as_bytes())
+            .and_then(|_| stdout.flush())
+            .map_err(|e| format!("cannot write signed email: {e}"))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::native::{load_private_key, parse_canonicalization, sign_email, Canon, SignOptions};
+    use super::verify_dkim::verify_dkim_signature;
+    use rsa::pkcs8::EncodePublicKey;
+
+    const TEST_KEY_PEM: &str =
+        include_str!("../../email-dkim-verifier-contract/tests/data/test_dkim_private_key.pem");
+
+    const UNSIGNED_EMAIL: &str = "From: Alice <alice@example.com>\n\
+To: recover@web3authn.org\n\
+Subject: recover-TEST01 alice.testnet ed25519:HPHNMfHwmBJSqcArYZ5ptTZpukvFoMtuU8TcV2T7mEEy\n\
+Date: Thu, 1 Jan 2026 00:00:00 +0000\n\
+\n\
+Hello  world\n\
+\n";
+
+    fn dns_record_for_test_key() -> String {
+        let key = load_private_key(TEST_KEY_PEM).expect("test key");
+        let spki = key
+            .to_public_key()
+            .to_public_key_der()
+            .expect("encode public key");
+        format!("v=DKIM1; k=rsa; p={}", base64::encode(spki.as_bytes()))
+    }
+
+    #[test]
+    fn relaxed_signature_verifies_with_worker_dkim_logic() {
+        let key = load_private_key(TEST_KEY_PEM).expect("test key");
+        let signed = sign_email(
+            UNSIGNED_EMAIL,
+            &key,
+            &SignOptions {
+                selector: "test",
+                domain: "example.com",
+                header_canon: Canon::Relaxed,
+                body_canon: Canon::Relaxed,
+            },
+        )
+        .expect("signs");
+
+        assert!(signed.starts_with("DKIM-Signature: v=1; a=rsa-sha256; c=relaxed/relaxed;"));
+        let sig = verify_dkim_signature(&signed, &[dns_record_for_test_key()])
+            .expect("signature verifies");
+        assert_eq!(sig.selector, "test");
+        assert_eq!(sig.domain, "example.com");
+
+        // Any change to a signed header breaks the signature.
+        let tampered = signed.replace("alice.testnet", "mallory.testnet");
+        assert!(verify_dkim_signature(&tampered, &[dns_record_for_test_key()]).is_err());
+    }
+
+    #[test]
+    fn canonicalization_argument_defaults_body_to_simple() {
+        assert_eq!(
+            parse_canonicalization("relaxed"),
+            Ok((Canon::Relaxed, Canon::Simple))
+        );
+        assert_eq!(
+            parse_canonicalization("simple/relaxed"),
+            Ok((Canon::Simple, Canon::Relaxed))
+        );
+        assert!(parse_canonicalization("nofws").is_err());
+    }
+}
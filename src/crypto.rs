@@ -1,13 +1,27 @@
 use base64;
-use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng, Payload};
 use chacha20poly1305::ChaCha20Poly1305;
+use ed25519_dalek::SigningKey;
 use hkdf::Hkdf;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sha2::Sha256;
+use sha2::{Digest, Sha256};
 use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
 
-#[derive(Deserialize)]
+/// Envelope version whose AEAD key uses HKDF with no salt (original format).
+pub const ENVELOPE_VERSION_UNSALTED: u8 = 1;
+/// Envelope version whose AEAD key uses an HKDF salt derived from
+/// `context.network_id`, so testnet and mainnet never share keys.
+pub const ENVELOPE_VERSION_NETWORK_SALT: u8 = 2;
+/// AEAD cipher used by every envelope version.
+pub const ENVELOPE_CIPHER: &str = "chacha20-poly1305";
+/// Algorithm of the worker's static key pair, as reported by `get-public-key`.
+pub const WORKER_KEY_ALGORITHM: &str = "x25519";
+/// Key version reported when `OUTLAYER_WORKER_KEY_VERSION` is unset.
+pub const INITIAL_WORKER_KEY_VERSION: u32 = 1;
+
+#[derive(Deserialize, Serialize)]
 pub struct EncryptedEmailEnvelope {
     // Versioned envelope so we can evolve the format.
     #[serde(default)]
@@ -23,34 +37,140 @@ pub struct EncryptedEmailEnvelope {
     pub ciphertext: String,
 }
 
-pub fn get_worker_public_key() -> Result<String, String> {
-    let sk = load_worker_static_secret()?;
-    let pk = X25519PublicKey::from(&sk);
-    Ok(base64::encode(pk.as_bytes()))
+/// The worker's X25519 key material, derived from a 32-byte seed.
+///
+/// Built from the OutLayer secrets with `from_env`, or from an explicit seed
+/// with `from_seed` so callers (and tests) need not touch process env.
+pub struct WorkerKeys {
+    static_secret: StaticSecret,
+    key_version: u32,
+    /// Secret from the seed in use before the last rotation, kept only while
+    /// `reencrypt-email` moves old envelopes to the current key.
+    previous_static_secret: Option<StaticSecret>,
 }
 
-pub(crate) fn load_worker_static_secret() -> Result<StaticSecret, String> {
+impl WorkerKeys {
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    pub fn from_seed(seed: [u8; 32]) -> Result<Self, String> {
+        Ok(Self {
+            static_secret: derive_secret_key(seed)?,
+            key_version: INITIAL_WORKER_KEY_VERSION,
+            previous_static_secret: None,
+        })
+    }
+
+    /// Also hold the key derived from the seed before the last rotation.
+    pub fn with_previous_seed(mut self, seed: [u8; 32]) -> Result<Self, String> {
+        self.previous_static_secret = Some(derive_secret_key(seed)?);
+        Ok(self)
+    }
+
+    /// Tag the keys with the rotation counter reported by `get-public-key`.
+    pub fn with_key_version(mut self, key_version: u32) -> Self {
+        self.key_version = key_version;
+        self
+    }
+
+    /// Load the seed through `get`, which maps an env var name to its value.
+    /// The optional `OUTLAYER_WORKER_KEY_VERSION` is bumped by the operator on
+    /// each seed rotation. During a rotation the replaced seed is set as the
+    /// protected `PROTECTED_OUTLAYER_WORKER_PREVIOUS_SK_SEED_HEX32`.
+    pub(crate) fn from_vars(get: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let key_version = match get("OUTLAYER_WORKER_KEY_VERSION") {
+            Some(val) => val
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|v| *v >= 1)
+                .ok_or_else(|| {
+                    "OUTLAYER_WORKER_KEY_VERSION must be a positive integer".to_string()
+                })?,
+            None => INITIAL_WORKER_KEY_VERSION,
+        };
+        let keys = Self::from_seed(Self::seed_from_vars(&get)?)?.with_key_version(key_version);
+        match get("PROTECTED_OUTLAYER_WORKER_PREVIOUS_SK_SEED_HEX32") {
+            Some(val) => keys.with_previous_seed(parse_hex_32(&val).map_err(|_| {
+                "PROTECTED_OUTLAYER_WORKER_PREVIOUS_SK_SEED_HEX32 must be a 64-char hex string (32 bytes)"
+                    .to_string()
+            })?),
+            None => Ok(keys),
+        }
+    }
+
+    fn seed_from_vars(get: impl Fn(&str) -> Option<String>) -> Result<[u8; 32], String> {
+        // Primary source: protected secret, hex-encoded 32-byte seed.
+        if let Some(val) = get("PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32") {
+            return parse_hex_32(&val).map_err(|_| {
+                "PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32 must be a 64-char hex string (32 bytes)"
+                    .to_string()
+            });
+        }
 
-    // Primary source: protected secret, hex-encoded 32-byte seed.
-    if let Ok(val) = std::env::var("PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32") {
-        let seed = parse_hex_32(&val).map_err(|_| {
-            "PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32 must be a 64-char hex string (32 bytes)"
+        // Fallback: unprotected (trusted) hex-encoded 32-byte seed
+        let val = get("OUTLAYER_WORKER_SK_SEED_HEX32").ok_or_else(|| {
+            "Secrets Not Found: PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32 and OUTLAYER_WORKER_SK_SEED_HEX32"
                 .to_string()
         })?;
-        return derive_secret_key(seed);
+        parse_hex_32(&val).map_err(|_| {
+            "OUTLAYER_WORKER_SK_SEED_HEX32 must be a 64-char hex string (32 bytes)"
+                .to_string()
+        })
     }
 
-    // Fallback: unprotected (trusted) hex-encoded 32-byte seed
-    let val = std::env::var("OUTLAYER_WORKER_SK_SEED_HEX32").map_err(|_| {
-        "Secrets Not Found: PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32 and OUTLAYER_WORKER_SK_SEED_HEX32"
-            .to_string()
-    })?;
-    let seed = parse_hex_32(&val).map_err(|_| {
-        "OUTLAYER_WORKER_SK_SEED_HEX32 must be a 64-char hex string (32 bytes)"
-            .to_string()
-    })?;
+    pub(crate) fn static_secret(&self) -> &StaticSecret {
+        &self.static_secret
+    }
+
+    pub(crate) fn previous_static_secret(&self) -> Option<&StaticSecret> {
+        self.previous_static_secret.as_ref()
+    }
+
+    pub fn key_version(&self) -> u32 {
+        self.key_version
+    }
+}
+
+pub fn get_worker_public_key(keys: &WorkerKeys) -> String {
+    base64::encode(get_worker_public_key_bytes(keys))
+}
+
+/// Raw 32-byte X25519 public key of the worker.
+pub fn get_worker_public_key_bytes(keys: &WorkerKeys) -> [u8; 32] {
+    X25519PublicKey::from(keys.static_secret()).to_bytes()
+}
+
+/// Ed25519 key the worker signs its results with, derived from the X25519
+/// static secret so it rotates with the seed.
+pub fn worker_signing_key(keys: &WorkerKeys) -> Result<SigningKey, String> {
+    let hk = Hkdf::<Sha256>::new(None, &keys.static_secret().to_bytes());
+    let mut okm = [0u8; 32];
+    hk.expand(b"signing-key", &mut okm)
+        .map_err(|_| "HKDF expansion failed".to_string())?;
+    Ok(SigningKey::from_bytes(&okm))
+}
 
-    return derive_secret_key(seed);
+/// Compact JSON with object keys sorted at every level, so a signature does
+/// not depend on the key order a client serialized with.
+pub fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(k, _)| *k);
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(k, v)| format!("{}:{}", Value::String(k.clone()), canonical_json(v)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
 }
 
 fn derive_secret_key(seed: [u8; 32]) -> Result<StaticSecret, String> {
@@ -76,12 +196,87 @@ fn parse_hex_32(s: &str) -> Result<[u8; 32], ()> {
     Ok(out)
 }
 
+/// Largest `output_length` accepted by `hkdf_derive`.
+#[cfg(any(test, feature = "debug-methods"))]
+pub const HKDF_DERIVE_MAX_OUTPUT_LEN: usize = 64;
+
+/// Debug helper: HKDF-SHA256 with no salt (the construction behind the
+/// worker key and v1 envelope keys), so a relayer can compare derived keys.
+#[cfg(any(test, feature = "debug-methods"))]
+pub fn hkdf_derive(ikm: &[u8], info: &[u8], output_length: usize) -> Result<Vec<u8>, String> {
+    if output_length == 0 || output_length > HKDF_DERIVE_MAX_OUTPUT_LEN {
+        return Err(format!(
+            "output_length must be between 1 and {HKDF_DERIVE_MAX_OUTPUT_LEN}"
+        ));
+    }
+    let hk = Hkdf::<Sha256>::new(None, ikm);
+    let mut okm = vec![0u8; output_length];
+    hk.expand(info, &mut okm)
+        .map_err(|_| "HKDF expansion failed".to_string())?;
+    Ok(okm)
+}
+
+#[cfg(any(test, feature = "debug-methods"))]
+pub fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
+    let pairs = s.trim().as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err("hex string must have an even length".to_string());
+    }
+    pairs
+        .map(|pair| match (char::from(pair[0]).to_digit(16), char::from(pair[1]).to_digit(16)) {
+            (Some(hi), Some(lo)) => Ok((hi * 16 + lo) as u8),
+            _ => Err("invalid hex digit".to_string()),
+        })
+        .collect()
+}
+
+#[cfg_attr(not(test), allow(dead_code))] // worker methods decrypt to bytes (8bit bodies)
 pub fn decrypt_encrypted_email(
+    keys: &WorkerKeys,
     envelope: &EncryptedEmailEnvelope,
     context: &Value,
 ) -> Result<String, String> {
-    let static_secret = load_worker_static_secret()?;
+    decrypt_with_static_secret(keys.static_secret(), envelope, context)
+}
 
+/// Like `decrypt_encrypted_email`, but returns the raw plaintext without
+/// requiring UTF-8, e.g. for `Content-Transfer-Encoding: 8bit` Latin-1 bodies.
+pub fn decrypt_encrypted_email_bytes(
+    keys: &WorkerKeys,
+    envelope: &EncryptedEmailEnvelope,
+    context: &Value,
+) -> Result<Vec<u8>, String> {
+    decrypt_bytes_with_static_secret(keys.static_secret(), envelope, context)
+}
+
+/// The `request_id` bound into the AEAD context, if any. Once decryption
+/// succeeds it is authenticated, unlike the plain `request_id` argument.
+pub fn context_request_id(context: &Value) -> Option<&str> {
+    context.get("request_id").and_then(Value::as_str)
+}
+
+/// The ChaCha20‑Poly1305 AAD for `context`: its serde_json bytes. The SDK
+/// constructs `context` with keys in alphabetical order (`account_id`,
+/// `network_id`, `payer_account_id`, then `request_id` when set) so that
+/// serde_json produces the same byte sequence on this side.
+pub fn aad_bytes(context: &Value) -> Result<Vec<u8>, String> {
+    serde_json::to_vec(context).map_err(|_| "failed to serialize context for AAD".to_string())
+}
+
+pub(crate) fn decrypt_with_static_secret(
+    static_secret: &StaticSecret,
+    envelope: &EncryptedEmailEnvelope,
+    context: &Value,
+) -> Result<String, String> {
+    let plaintext = decrypt_bytes_with_static_secret(static_secret, envelope, context)?;
+    String::from_utf8(plaintext).map_err(|_| "decrypted email is not valid UTF-8".to_string())
+}
+
+pub(crate) fn decrypt_bytes_with_static_secret(
+    static_secret: &StaticSecret,
+    envelope: &EncryptedEmailEnvelope,
+    context: &Value,
+) -> Result<Vec<u8>, String> {
     let eph_bytes = base64::decode(envelope.ephemeral_pub.trim())
         .map_err(|_| "invalid ephemeral_pub".to_string())?;
 
@@ -93,12 +288,7 @@ pub fn decrypt_encrypted_email(
     let eph_public = X25519PublicKey::from(eph_array);
 
     let shared = static_secret.diffie_hellman(&eph_public);
-    let shared_bytes = shared.as_bytes();
-
-    let hk = Hkdf::<Sha256>::new(None, shared_bytes);
-    let mut key_bytes = [0u8; 32];
-    hk.expand(b"email-dkim-encryption-key", &mut key_bytes)
-        .map_err(|_| "failed to derive AEAD key".to_string())?;
+    let key_bytes = derive_envelope_key(shared.as_bytes(), envelope.version, context)?;
 
     let cipher = ChaCha20Poly1305::new((&key_bytes).into());
 
@@ -112,14 +302,9 @@ pub fn decrypt_encrypted_email(
     let ciphertext =
         base64::decode(envelope.ciphertext.trim()).map_err(|_| "invalid ciphertext".to_string())?;
 
-    // Serialize the logical `context` object as JSON and use the bytes as
-    // ChaCha20‑Poly1305 AAD. The SDK constructs `context` with keys in
-    // alphabetical order (`account_id`, `network_id`, `payer_account_id`)
-    // so that serde_json produces the same byte sequence on this side.
-    let aad = serde_json::to_vec(context)
-        .map_err(|_| "failed to serialize context for AAD".to_string())?;
+    let aad = aad_bytes(context)?;
 
-    let plaintext = cipher
+    cipher
         .decrypt(
             nonce,
             Payload {
@@ -127,7 +312,150 @@ pub fn decrypt_encrypted_email(
                 aad: &aad,
             },
         )
-        .map_err(|_| "decryption failed".to_string())?;
+        .map_err(|_| "decryption failed".to_string())
+}
 
-    String::from_utf8(plaintext).map_err(|_| "decrypted email is not valid UTF-8".to_string())
+/// Encrypt `plaintext` to a recipient X25519 public key (base64) using a fresh
+/// ephemeral keypair and nonce, mirroring the relayer-side envelope format.
+pub fn encrypt_email_to_public_key(
+    plaintext: &[u8],
+    recipient_public_key_b64: &str,
+    context: &Value,
+    version: u8,
+) -> Result<EncryptedEmailEnvelope, String> {
+    let recipient_public = decode_x25519_public_key(recipient_public_key_b64, "recipient public key")?;
+
+    let mut eph_seed = [0u8; 32];
+    OsRng
+        .try_fill_bytes(&mut eph_seed)
+        .map_err(|_| "failed to generate ephemeral key".to_string())?;
+    let eph_secret = StaticSecret::from(eph_seed);
+    let eph_public = X25519PublicKey::from(&eph_secret);
+
+    let mut nonce_bytes = [0u8; 12];
+    OsRng
+        .try_fill_bytes(&mut nonce_bytes)
+        .map_err(|_| "failed to generate nonce".to_string())?;
+    let nonce = chacha20poly1305::Nonce::from_slice(&nonce_bytes);
+
+    let shared = eph_secret.diffie_hellman(&recipient_public);
+    let key_bytes = derive_envelope_key(shared.as_bytes(), version, context)?;
+    let cipher = ChaCha20Poly1305::new((&key_bytes).into());
+
+    let aad = aad_bytes(context)?;
+
+    let ciphertext = cipher
+        .encrypt(
+            nonce,
+            Payload {
+                msg: plaintext,
+                aad: &aad,
+            },
+        )
+        .map_err(|_| "encryption failed".to_string())?;
+
+    Ok(EncryptedEmailEnvelope {
+        version,
+        ephemeral_pub: base64::encode(eph_public.as_bytes()),
+        nonce: base64::encode(nonce_bytes),
+        ciphertext: base64::encode(ciphertext),
+    })
+}
+
+/// Outcome of `check_client_public_key`. Contains no secret material.
+pub struct PublicKeyCheck {
+    /// Whether ECDH with the worker secret yields a usable (non-zero) shared
+    /// secret and the AEAD key derivation succeeds.
+    pub ecdh_ok: bool,
+    /// Whether the client key is the worker's current public key.
+    pub matches_worker_public_key: bool,
+    /// First 4 bytes of SHA-256 over the worker public key, hex-encoded.
+    pub worker_public_key_fingerprint: String,
+}
+
+/// Debug helper: trial ECDH + HKDF of `client_public_key_b64` against the
+/// worker's static secret, plus a fingerprint of the worker public key.
+pub fn check_client_public_key(
+    keys: &WorkerKeys,
+    client_public_key_b64: &str,
+) -> Result<PublicKeyCheck, String> {
+    let static_secret = keys.static_secret();
+    let worker_public = X25519PublicKey::from(static_secret);
+    let client_public = decode_x25519_public_key(client_public_key_b64, "client public key")?;
+
+    let shared = static_secret.diffie_hellman(&client_public);
+    // Low-order points produce an all-zero shared secret.
+    let ecdh_ok = shared.as_bytes().iter().any(|b| *b != 0)
+        && derive_aead_key(shared.as_bytes(), None).is_ok();
+
+    let digest = Sha256::digest(worker_public.as_bytes());
+    let worker_public_key_fingerprint = digest[..4].iter().map(|b| format!("{b:02x}")).collect();
+
+    Ok(PublicKeyCheck {
+        ecdh_ok,
+        matches_worker_public_key: client_public.as_bytes() == worker_public.as_bytes(),
+        worker_public_key_fingerprint,
+    })
+}
+
+/// Interop check: run the worker side of the envelope key agreement for
+/// `ephemeral_pub_b64` and return hex SHA-256 of the derived AEAD key. The
+/// digest is a commitment a client can recompute and compare without either
+/// side revealing the key.
+pub fn aead_key_commitment(
+    keys: &WorkerKeys,
+    ephemeral_pub_b64: &str,
+    version: u8,
+    context: &Value,
+) -> Result<String, String> {
+    let eph_public = decode_x25519_public_key(ephemeral_pub_b64, "ephemeral_pub")?;
+    let shared = keys.static_secret().diffie_hellman(&eph_public);
+    let key_bytes = derive_envelope_key(shared.as_bytes(), version, context)?;
+    Ok(Sha256::digest(key_bytes).iter().map(|b| format!("{b:02x}")).collect())
+}
+
+fn decode_x25519_public_key(public_key_b64: &str, what: &str) -> Result<X25519PublicKey, String> {
+    let bytes = base64::decode(public_key_b64.trim()).map_err(|_| format!("invalid {what}"))?;
+    if bytes.len() != 32 {
+        return Err(format!("{what} must be 32 bytes"));
+    }
+    let mut array = [0u8; 32];
+    array.copy_from_slice(&bytes);
+    Ok(X25519PublicKey::from(array))
+}
+
+/// Derive the ChaCha20-Poly1305 key for an envelope of the given `version`.
+///
+/// - v1 (and unversioned `0`): HKDF salt is `None`.
+/// - v2: HKDF salt is `"email-dkim-encryption-salt:<context.network_id>"`.
+///   Clients must use the same salt when encrypting v2 envelopes.
+pub(crate) fn derive_envelope_key(
+    shared_secret: &[u8],
+    version: u8,
+    context: &Value,
+) -> Result<[u8; 32], String> {
+    match version {
+        0 | ENVELOPE_VERSION_UNSALTED => derive_aead_key(shared_secret, None),
+        ENVELOPE_VERSION_NETWORK_SALT => {
+            let network_id = context
+                .get("network_id")
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| {
+                    "context.network_id is required for envelope version 2".to_string()
+                })?;
+            let salt = format!("email-dkim-encryption-salt:{network_id}");
+            derive_aead_key(shared_secret, Some(salt.as_bytes()))
+        }
+        other => Err(format!("unsupported envelope version: {other}")),
+    }
+}
+
+fn derive_aead_key(shared_secret: &[u8], salt: Option<&[u8]>) -> Result<[u8; 32], String> {
+    let hk = Hkdf::<Sha256>::new(salt, shared_secret);
+    let mut key_bytes = [0u8; 32];
+    hk.expand(b"email-dkim-encryption-key", &mut key_bytes)
+        .map_err(|_| "failed to derive AEAD key".to_string())?;
+    Ok(key_bytes)
 }
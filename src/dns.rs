@@ -1,19 +1,62 @@
+#[cfg(not(test))]
 use serde::Deserialize;
+#[cfg(not(test))]
 use wasi_http_client::Client;
 
+/// CNAME hops `fetch_txt_records` follows before giving up, so a delegation
+/// loop cannot recurse forever.
+pub const MAX_CNAME_HOPS: usize = 4;
+
+#[cfg(not(test))]
+const TXT_TYPE: u16 = 16;
+#[cfg(not(test))]
+const CNAME_TYPE: u16 = 5;
+
+#[cfg(not(test))]
 #[derive(Deserialize)]
 struct DnsAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
     data: String,
 }
 
+#[cfg(not(test))]
 #[derive(Deserialize)]
 struct DnsResponse {
     #[serde(rename = "Answer")]
     answer: Option<Vec<DnsAnswer>>,
 }
 
-#[cfg(not(test))]
+/// One TXT query's answer: the TXT records found, and the CNAME target when
+/// the name is delegated and the resolver did not follow it.
+struct TxtAnswer {
+    records: Vec<String>,
+    cname: Option<String>,
+}
+
+/// TXT records published at `name`. An empty list means the name has no TXT
+/// records; `Err` is reserved for lookup failures.
+///
+/// Providers often delegate `<selector>._domainkey.<domain>` to their own zone
+/// by CNAME. DoH resolvers usually return the target's TXT records directly;
+/// when an answer only carries the CNAME, the target is queried in turn.
 pub fn fetch_txt_records(name: &str) -> Result<Vec<String>, String> {
+    let mut current = name.to_string();
+    for _ in 0..=MAX_CNAME_HOPS {
+        let answer = query_txt(&current)?;
+        if !answer.records.is_empty() {
+            return Ok(answer.records);
+        }
+        match answer.cname {
+            Some(target) => current = target,
+            None => return Ok(Vec::new()),
+        }
+    }
+    Err(format!("CNAME chain for {name} is longer than {MAX_CNAME_HOPS} hops"))
+}
+
+#[cfg(not(test))]
+fn query_txt(name: &str) -> Result<TxtAnswer, String> {
     let url = format!("https://dns.google/resolve?name={name}&type=TXT");
     let client = Client::new();
     let resp = client
@@ -36,32 +79,83 @@ pub fn fetch_txt_records(name: &str) -> Result<Vec<String>, String> {
     let dns: DnsResponse = serde_json::from_slice(&body_bytes)
         .map_err(|e| format!("failed to parse DNS JSON: {e}"))?;
 
-    let mut records = Vec::new();
-    if let Some(answers) = dns.answer {
-        for ans in answers {
-            let mut data = ans.data;
-            // DNS-over-HTTPS TXT answers are often wrapped in quotes.
-            if data.starts_with('\"') && data.ends_with('\"') && data.len() >= 2 {
-                data = data[1..data.len() - 1].to_string();
-            }
-            if !data.is_empty() {
-                records.push(data);
+    let mut answer = TxtAnswer {
+        records: Vec::new(),
+        cname: None,
+    };
+    for ans in dns.answer.unwrap_or_default() {
+        let mut data = ans.data;
+        match ans.record_type {
+            TXT_TYPE => {
+                // DNS-over-HTTPS TXT answers are often wrapped in quotes.
+                if data.starts_with('\"') && data.ends_with('\"') && data.len() >= 2 {
+                    data = data[1..data.len() - 1].to_string();
+                }
+                if !data.is_empty() {
+                    answer.records.push(data);
+                }
             }
+            // A chain of CNAMEs is listed in order; the last one is the target.
+            CNAME_TYPE => answer.cname = Some(data.trim_end_matches('.').to_string()),
+            _ => {}
         }
     }
 
-    if records.is_empty() {
-        Err(format!("no TXT records found for {}", name))
-    } else {
-        Ok(records)
-    }
+    Ok(answer)
+}
+
+#[cfg(test)]
+thread_local! {
+    static DNS_STUBS: std::cell::RefCell<std::collections::HashMap<String, Vec<String>>> =
+        std::cell::RefCell::new(std::collections::HashMap::new());
+    static CNAME_STUBS: std::cell::RefCell<std::collections::HashMap<String, String>> =
+        std::cell::RefCell::new(std::collections::HashMap::new());
 }
 
+/// Register the TXT records returned for `name` by the test stub on this thread.
 #[cfg(test)]
-pub fn fetch_txt_records(_name: &str) -> Result<Vec<String>, String> {
-    // In tests we stub DNS lookups with a fixed, known-good record from a
-    // real Gmail DKIM DNS entry. This avoids network flakiness while still
-    // exercising the full DKIM verification logic.
-    Ok(vec!["v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB".to_string()])
+pub fn set_dns_stub(name: &str, records: Vec<String>) {
+    DNS_STUBS.with(|stubs| {
+        stubs
+            .borrow_mut()
+            .insert(name.to_ascii_lowercase(), records);
+    });
 }
 
+/// Make the test stub answer TXT queries for `name` with only a CNAME to
+/// `target`, like a resolver that does not follow the delegation.
+#[cfg(test)]
+pub fn set_dns_cname_stub(name: &str, target: &str) {
+    CNAME_STUBS.with(|stubs| {
+        stubs
+            .borrow_mut()
+            .insert(name.to_ascii_lowercase(), target.to_string());
+    });
+}
+
+/// Remove all stubbed TXT and CNAME records registered on this thread.
+#[cfg(test)]
+pub fn clear_dns_stubs() {
+    DNS_STUBS.with(|stubs| stubs.borrow_mut().clear());
+    CNAME_STUBS.with(|stubs| stubs.borrow_mut().clear());
+}
+
+#[cfg(test)]
+fn query_txt(name: &str) -> Result<TxtAnswer, String> {
+    // In tests DNS lookups are served from per-thread stubs registered with
+    // `set_dns_stub`, so each test declares exactly which records exist.
+    let name = name.to_ascii_lowercase();
+    if let Some(target) = CNAME_STUBS.with(|stubs| stubs.borrow().get(&name).cloned()) {
+        return Ok(TxtAnswer {
+            records: Vec::new(),
+            cname: Some(target),
+        });
+    }
+    let records = DNS_STUBS
+        .with(|stubs| stubs.borrow().get(&name).cloned())
+        .ok_or_else(|| format!("DnsStubNotFound: no stubbed TXT records for {}", name))?;
+    Ok(TxtAnswer {
+        records,
+        cname: None,
+    })
+}
@@ -0,0 +1,157 @@
+//! RFC 2047 encoded-word decoding for header values (e.g. `Subject`), with
+//! charset conversion for legacy clients.
+
+use crate::qp::hex_value;
+
+/// Decode every `=?charset?B|Q?text?=` encoded-word in `value`.
+///
+/// - `B` is base64 (padding optional); `Q` is quoted-printable with `_` for
+///   space (RFC 2047 §4.2).
+/// - The charset is converted with `encoding_rs` (WHATWG labels such as
+///   `iso-8859-1`, `windows-1252`, `koi8-r`). `utf-7` (RFC 2152) is decoded
+///   here, as `encoding_rs` does not support it. An RFC 2231 language suffix
+///   (`charset*lang`) is ignored.
+/// - Whitespace between two adjacent encoded-words is dropped (§6.2).
+///
+/// A malformed encoded-word, or one in an unknown charset, is kept as-is.
+pub fn decode_encoded_words(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    let mut after_word = false;
+
+    while let Some(start) = rest.find("=?") {
+        let (before, candidate) = rest.split_at(start);
+        match decode_encoded_word(candidate) {
+            Some((decoded, consumed)) => {
+                if !(after_word && before.chars().all(char::is_whitespace)) {
+                    out.push_str(before);
+                }
+                out.push_str(&decoded);
+                rest = &candidate[consumed..];
+                after_word = true;
+            }
+            None => {
+                out.push_str(before);
+                out.push_str("=?");
+                rest = &candidate[2..];
+                after_word = false;
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Decode the encoded-word at the start of `s`, returning the text and the
+/// number of bytes it spans.
+fn decode_encoded_word(s: &str) -> Option<(String, usize)> {
+    let inner = s.strip_prefix("=?")?;
+    let (charset, inner) = inner.split_once('?')?;
+    let (encoding, inner) = inner.split_once('?')?;
+    let text = &inner[..inner.find("?=")?];
+    if charset.is_empty() || charset.contains(char::is_whitespace) || text.contains(char::is_whitespace)
+    {
+        return None;
+    }
+
+    let bytes = match encoding {
+        "B" | "b" => {
+            base64::decode_config(text.trim_end_matches('='), base64::STANDARD_NO_PAD).ok()?
+        }
+        "Q" | "q" => decode_q(text),
+        _ => return None,
+    };
+    let label = charset.split('*').next().unwrap_or(charset);
+    let decoded = decode_charset(label, &bytes)?;
+
+    let consumed = "=?".len() + charset.len() + 1 + encoding.len() + 1 + text.len() + "?=".len();
+    Some((decoded, consumed))
+}
+
+/// RFC 2047 "Q" encoding: `_` is a space and `=XX` a hex byte.
+fn decode_q(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'_' => out.push(b' '),
+            b'=' if i + 2 < bytes.len() => {
+                match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                    (Some(hi), Some(lo)) => {
+                        out.push((hi << 4) | lo);
+                        i += 3;
+                        continue;
+                    }
+                    _ => out.push(b'='),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    out
+}
+
+fn decode_charset(label: &str, bytes: &[u8]) -> Option<String> {
+    if label.eq_ignore_ascii_case("utf-7") || label.eq_ignore_ascii_case("unicode-1-1-utf-7") {
+        return decode_utf7(bytes);
+    }
+    let encoding = encoding_rs::Encoding::for_label(label.trim().as_bytes())?;
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    Some(text.into_owned())
+}
+
+/// UTF-7 (RFC 2152): ASCII, with `+<modified base64 of UTF-16BE>[-]` runs for
+/// other characters and `+-` for a literal `+`.
+fn decode_utf7(bytes: &[u8]) -> Option<String> {
+    let mut out = String::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        i += 1;
+        if b != b'+' {
+            if !b.is_ascii() {
+                return None;
+            }
+            out.push(b as char);
+            continue;
+        }
+        if bytes.get(i) == Some(&b'-') {
+            out.push('+');
+            i += 1;
+            continue;
+        }
+
+        let mut units = Vec::new();
+        let mut acc: u32 = 0;
+        let mut bits = 0;
+        while let Some(v) = bytes.get(i).and_then(|&c| base64_value(c)) {
+            acc = (acc << 6) | u32::from(v);
+            bits += 6;
+            if bits >= 16 {
+                bits -= 16;
+                units.push((acc >> bits) as u16);
+                acc &= (1 << bits) - 1;
+            }
+            i += 1;
+        }
+        if bytes.get(i) == Some(&b'-') {
+            i += 1;
+        }
+        out.push_str(&String::from_utf16(&units).ok()?);
+    }
+    Some(out)
+}
+
+fn base64_value(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Stable codes for the `error` field of a failed verification, shared with the
+/// contract (`email-dkim-verifier-contract/src/error_code.rs` keeps the same
+/// variants and strings; `src/tests/error_code.rs` compares the two). Clients match
+/// on these rather than on messages.
+///
+/// Codes with detail are sent as `"<code>: <detail>"` (see `with_detail`).
+/// Errors from DNS, decryption or parsing helpers without a code here are
+/// passed through as free-form messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(not(test), allow(dead_code))] // contract-only codes are kept for parity
+pub enum VerifyErrorCode {
+    /// AEAD `context` is not exactly `account_id`, `network_id`,
+    /// `payer_account_id` and an optional string `request_id`.
+    InvalidContextShape,
+    /// `request_id` argument differs from the one bound in the AEAD context.
+    RequestIdMismatch,
+    /// The paying account is not the one the envelope was encrypted for.
+    PayerAccountIdContextMismatch,
+    /// No usable DKIM key record for the signature's selector.
+    SelectorNotFound,
+    /// A key record exists but the signature (or body hash) does not verify (worker).
+    SignatureInvalid,
+    /// Sender differs from the pre-registered `expected_from_address_hash`.
+    FromAddressHashMismatch,
+    /// The Subject's account differs from `expected_account_id`.
+    AccountIdMismatch,
+    /// The body names more than one `ed25519:` recovery key.
+    AmbiguousPublicKey,
+    /// The email has more than one `From:` header (RFC 5322 §3.6).
+    MultipleFromHeaders,
+    /// A mailing list relayed the email and list rejection is enabled; detail
+    /// lists the signals found.
+    MailingListMessage,
+    /// The Subject's `exp=` deadline is before block time (contract).
+    RecoveryExpired,
+    /// The Outlayer execution failed or returned nothing (contract).
+    OutlayerExecutionFailed,
+    /// The worker response is not a `{ method, response }` object (contract).
+    InvalidWorkerResponse,
+    /// The `verify-encrypted-email` response did not parse (contract).
+    InvalidVerifyResponse,
+    /// The `get-dns-records` response did not parse (contract).
+    InvalidDnsResponse,
+    /// The worker answered a different method; detail is that method (contract).
+    UnexpectedWorkerMethod,
+    /// The worker's DNS lookup failed; detail is its error (contract).
+    DnsError,
+    /// The DNS lookup returned no key records (contract).
+    DnsRecordsEmpty,
+    /// Only refused algorithms signed the message; detail names them (contract).
+    UnsupportedAlgorithm,
+    /// No DKIM-Signature verified against the fetched key (contract).
+    DkimVerificationFailed,
+    /// The requester or signing domain is over the daily limit (contract).
+    RateLimited,
+}
+
+#[cfg_attr(not(test), allow(dead_code))] // `ALL` and `from_error` are for tests
+impl VerifyErrorCode {
+    pub const ALL: [VerifyErrorCode; 21] = [
+        VerifyErrorCode::InvalidContextShape,
+        VerifyErrorCode::RequestIdMismatch,
+        VerifyErrorCode::PayerAccountIdContextMismatch,
+        VerifyErrorCode::SelectorNotFound,
+        VerifyErrorCode::SignatureInvalid,
+        VerifyErrorCode::FromAddressHashMismatch,
+        VerifyErrorCode::AccountIdMismatch,
+        VerifyErrorCode::AmbiguousPublicKey,
+        VerifyErrorCode::MultipleFromHeaders,
+        VerifyErrorCode::MailingListMessage,
+        VerifyErrorCode::RecoveryExpired,
+        VerifyErrorCode::OutlayerExecutionFailed,
+        VerifyErrorCode::InvalidWorkerResponse,
+        VerifyErrorCode::InvalidVerifyResponse,
+        VerifyErrorCode::InvalidDnsResponse,
+        VerifyErrorCode::UnexpectedWorkerMethod,
+        VerifyErrorCode::DnsError,
+        VerifyErrorCode::DnsRecordsEmpty,
+        VerifyErrorCode::UnsupportedAlgorithm,
+        VerifyErrorCode::DkimVerificationFailed,
+        VerifyErrorCode::RateLimited,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            VerifyErrorCode::InvalidContextShape => "invalid_context_shape",
+            VerifyErrorCode::RequestIdMismatch => "request_id_mismatch",
+            VerifyErrorCode::PayerAccountIdContextMismatch => "payer_account_id_context_mismatch",
+            VerifyErrorCode::SelectorNotFound => "selector_not_found",
+            VerifyErrorCode::SignatureInvalid => "signature_invalid",
+            VerifyErrorCode::FromAddressHashMismatch => "from_address_hash_mismatch",
+            VerifyErrorCode::AccountIdMismatch => "account_id_mismatch",
+            VerifyErrorCode::AmbiguousPublicKey => "ambiguous_public_key",
+            VerifyErrorCode::MultipleFromHeaders => "multiple_from_headers",
+            VerifyErrorCode::MailingListMessage => "mailing_list_message",
+            VerifyErrorCode::RecoveryExpired => "recovery_expired",
+            VerifyErrorCode::OutlayerExecutionFailed => "outlayer_execution_failed",
+            VerifyErrorCode::InvalidWorkerResponse => "invalid_worker_response",
+            VerifyErrorCode::InvalidVerifyResponse => "invalid_verify_response",
+            VerifyErrorCode::InvalidDnsResponse => "invalid_dns_response",
+            VerifyErrorCode::UnexpectedWorkerMethod => "unexpected_worker_method",
+            VerifyErrorCode::DnsError => "dns_error",
+            VerifyErrorCode::DnsRecordsEmpty => "dns_records_empty",
+            VerifyErrorCode::UnsupportedAlgorithm => "unsupported_algorithm",
+            VerifyErrorCode::DkimVerificationFailed => "dkim_verification_failed",
+            VerifyErrorCode::RateLimited => "rate_limited",
+        }
+    }
+
+    /// `"<code>: <detail>"`, the form used for codes that carry detail.
+    pub fn with_detail(self, detail: impl fmt::Display) -> String {
+        format!("{}: {detail}", self.as_str())
+    }
+
+    /// The code an `error` string starts with, with or without detail.
+    pub fn from_error(error: &str) -> Option<Self> {
+        let code = error.split_once(": ").map_or(error, |(code, _)| code);
+        Self::ALL.into_iter().find(|c| c.as_str() == code)
+    }
+}
+
+impl fmt::Display for VerifyErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<VerifyErrorCode> for String {
+    fn from(code: VerifyErrorCode) -> Self {
+        code.as_str().to_string()
+    }
+}
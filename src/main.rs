@@ -3,20 +3,51 @@ mod tests;
 mod api;
 mod crypto;
 mod dns;
+mod encoded_word;
+mod error_code;
 mod parsers;
+mod qp;
 mod verify_dkim;
 
-use crate::api::{handle_request, RequestType};
+use crate::api::{handle_request, RequestType, ResponseType};
 use std::io::{self, Read, Write};
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// Largest request read from stdin. A recovery email is a few KiB; this
+/// leaves room for attachments while keeping a hostile input from exhausting
+/// worker memory.
+const MAX_INPUT_BYTES: usize = 8 * 1024 * 1024;
 
-    let mut input_string = String::new();
-    io::stdin().read_to_string(&mut input_string)?;
+/// Read and parse one JSON request, reading at most `MAX_INPUT_BYTES` (plus
+/// one byte to detect overflow) from `reader`. The JSON is parsed from the raw
+/// bytes, so the input is not also copied into a `String`.
+fn read_request(reader: impl Read) -> Result<RequestType, String> {
+    let mut input = Vec::new();
+    reader
+        .take(MAX_INPUT_BYTES as u64 + 1)
+        .read_to_end(&mut input)
+        .map_err(|e| format!("failed to read request: {e}"))?;
+    if input.len() > MAX_INPUT_BYTES {
+        return Err(format!("request exceeds {MAX_INPUT_BYTES} bytes"));
+    }
+    serde_json::from_slice(&input).map_err(|e| format!("invalid request JSON: {e}"))
+}
 
-    let request: RequestType = serde_json::from_str(&input_string)?;
+/// Handle the request read from `reader`. One that cannot be read or parsed
+/// (e.g. over `MAX_INPUT_BYTES`) still gets a `{ method, response: { error } }`
+/// reply, with an empty `method` since none was read.
+fn handle_input(reader: impl Read) -> ResponseType {
+    match read_request(reader) {
+        Ok(request) => handle_request(request),
+        Err(error) => ResponseType {
+            method: String::new(),
+            response: serde_json::json!({ "error": error }),
+        },
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
 
-    let response = handle_request(request);
+    let response = handle_input(io::stdin().lock());
 
     print!("{}", serde_json::to_string(&response)?);
     io::stdout().flush()?;
@@ -33,7 +33,55 @@ pub fn extract_header_value(email: &str, header_name: &str) -> Option<String> {
     None
 }
 
-pub fn parse_dkim_tags(value: &str) -> HashMap<String, String> {
+/// A DKIM tag value, remembering whether it was written as a quoted string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DkimTagValue {
+    Plain(String),
+    /// Written as `"..."`; holds the value with the quotes stripped and `\"`
+    /// and `\\` unescaped.
+    Quoted(String),
+}
+
+impl DkimTagValue {
+    fn parse(raw: &str) -> Self {
+        match raw.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(inner) => DkimTagValue::Quoted(unescape_quoted_string(inner)),
+            None => DkimTagValue::Plain(raw.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            DkimTagValue::Plain(v) | DkimTagValue::Quoted(v) => v,
+        }
+    }
+}
+
+/// Undo `\"` and `\\` escapes; any other backslash is kept as is.
+fn unescape_quoted_string(inner: &str) -> String {
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(escaped @ ('"' | '\\')) => out.push(escaped),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// DKIM `tag=value` pairs with lowercased tag names. Quoted-string values
+/// (`z=`, some `n=` notes) keep track of their quoting; see `parse_dkim_tags`
+/// for plain strings.
+pub fn parse_dkim_tag_values(value: &str) -> HashMap<String, DkimTagValue> {
     let mut tags = HashMap::new();
     let unfolded = value.replace("\r\n", " ");
     for part in unfolded.split(';') {
@@ -44,24 +92,78 @@ pub fn parse_dkim_tags(value: &str) -> HashMap<String, String> {
         if let Some(pos) = part.find('=') {
             let (k, v) = part.split_at(pos);
             let key = k.trim().to_ascii_lowercase();
-            let val = v[1..].trim().to_string();
-            tags.insert(key, val);
+            tags.insert(key, DkimTagValue::parse(v[1..].trim()));
         }
     }
     tags
 }
 
+/// Like `parse_dkim_tag_values`, with quoted values already unquoted.
+pub fn parse_dkim_tags(value: &str) -> HashMap<String, String> {
+    parse_dkim_tag_values(value)
+        .into_iter()
+        .map(|(key, value)| (key, value.as_str().to_string()))
+        .collect()
+}
+
+/// A DKIM canonicalization algorithm (RFC 6376 §3.4).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Canonicalization {
+    Simple,
+    Relaxed,
+}
+
+/// Canonicalization names `parse_dkim_canonicalization` accepts for either
+/// half of `c=`.
+pub const SUPPORTED_CANONICALIZATIONS: [&str; 2] = ["simple", "relaxed"];
+
+/// Header and body algorithms from a DKIM `c=` tag value (RFC 6376 §3.5).
+/// An absent tag means `simple/simple`, and a lone header algorithm (`relaxed`
+/// or `relaxed/`) implies `simple` for the body. `None` for unknown algorithms.
+pub fn parse_dkim_canonicalization(
+    c: Option<&str>,
+) -> Option<(Canonicalization, Canonicalization)> {
+    let algorithm = |name: &str| match name.trim().to_ascii_lowercase().as_str() {
+        "simple" | "" => Some(Canonicalization::Simple),
+        "relaxed" => Some(Canonicalization::Relaxed),
+        _ => None,
+    };
+    let c = c.unwrap_or("simple/simple");
+    let (header, body) = c.split_once('/').unwrap_or((c, ""));
+    if header.trim().is_empty() {
+        return None;
+    }
+    Some((algorithm(header)?, algorithm(body)?))
+}
+
+/// Remove a leading UTF-8 byte-order mark (`EF BB BF`), which some mail
+/// clients prepend to the raw message and which would otherwise glue itself
+/// onto the first header name.
+pub fn strip_bom(email: &str) -> &str {
+    email.strip_prefix('\u{feff}').unwrap_or(email)
+}
+
+/// Byte counterpart of `strip_bom`, for raw messages that may not be UTF-8.
+pub fn strip_bom_bytes(email: &[u8]) -> &[u8] {
+    email.strip_prefix(b"\xef\xbb\xbf").unwrap_or(email)
+}
+
 pub fn split_headers_body(email: &str) -> (&str, &str) {
-    if let Some(idx) = email.find("\r\n\r\n") {
-        let (h, rest) = email.split_at(idx);
-        let body = &rest[4..];
-        (h, body)
-    } else if let Some(idx) = email.find("\n\n") {
-        let (h, rest) = email.split_at(idx);
-        let body = &rest[2..];
-        (h, body)
+    // The separator is ASCII, so both halves fall on char boundaries.
+    let (headers, body) = split_headers_body_bytes(email.as_bytes());
+    (&email[..headers.len()], &email[email.len() - body.len()..])
+}
+
+/// Byte counterpart of `split_headers_body`: the body of an 8bit message need
+/// not be valid UTF-8.
+pub fn split_headers_body_bytes(email: &[u8]) -> (&[u8], &[u8]) {
+    let find = |needle: &[u8]| email.windows(needle.len()).position(|w| w == needle);
+    if let Some(idx) = find(b"\r\n\r\n") {
+        (&email[..idx], &email[idx + 4..])
+    } else if let Some(idx) = find(b"\n\n") {
+        (&email[..idx], &email[idx + 2..])
     } else {
-        (email, "")
+        (email, &[])
     }
 }
 
@@ -136,6 +238,8 @@ pub fn canonicalize_headers_relaxed(
     let mut result = String::new();
     let mut used = vec![false; headers.len()];
 
+    // RFC 6376 §5.4.2: each listing of a name takes the lowest instance not
+    // yet used, so `h=from:from` signs two distinct From: fields.
     for signed in signed_headers {
         let mut selected: Option<usize> = None;
         for idx in (0..headers.len()).rev() {
@@ -143,14 +247,15 @@ pub fn canonicalize_headers_relaxed(
                 continue;
             }
             let (name, _) = &headers[idx];
-            if name.eq_ignore_ascii_case(signed) {
+            if name.trim_end().eq_ignore_ascii_case(signed.trim()) {
                 selected = Some(idx);
                 break;
             }
         }
         if let Some(idx) = selected {
             let (name, value) = &headers[idx];
-            result.push_str(&name.to_ascii_lowercase());
+            // Relaxed drops whitespace before the colon (RFC 6376 §3.4.2).
+            result.push_str(&name.trim_end().to_ascii_lowercase());
             result.push(':');
             result.push_str(&canonicalize_header_relaxed(value.clone()));
             result.push_str("\r\n");
@@ -161,27 +266,78 @@ pub fn canonicalize_headers_relaxed(
     result
 }
 
+/// Simple header canonicalization (RFC 6376 §3.4.1): each signed field is
+/// emitted exactly as it appears, original name case and whitespace included;
+/// only folding line endings are normalized to CRLF (`parse_headers` already
+/// rejoins continuation lines with `\r\n`).
+pub fn canonicalize_headers_simple(
+    headers: &[(String, String)],
+    signed_headers: &[String],
+) -> String {
+    let mut result = String::new();
+    let mut used = vec![false; headers.len()];
+
+    // Same bottom-up selection as the relaxed variant (RFC 6376 §5.4.2).
+    for signed in signed_headers {
+        let selected = (0..headers.len()).rev().find(|&idx| {
+            !used[idx] && headers[idx].0.trim_end().eq_ignore_ascii_case(signed.trim())
+        });
+        if let Some(idx) = selected {
+            let (name, value) = &headers[idx];
+            result.push_str(name);
+            result.push(':');
+            result.push_str(value);
+            result.push_str("\r\n");
+            used[idx] = true;
+        }
+    }
+
+    result
+}
+
+/// Reverse SMTP dot-stuffing (RFC 5321 §4.5.2): a body line starting with
+/// `..` had its first `.` added in transit and loses it again. Only for
+/// messages captured from raw SMTP DATA; most captured emails are already
+/// un-stuffed, and undoing it twice would change their body hash.
+pub fn undo_dot_stuffing_bytes(body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len());
+    for line in body.split_inclusive(|b| *b == b'\n') {
+        match line.strip_prefix(b".") {
+            Some(rest) if rest.starts_with(b".") => out.extend_from_slice(rest),
+            _ => out.extend_from_slice(line),
+        }
+    }
+    out
+}
+
 pub fn canonicalize_body_relaxed(body: &str) -> String {
-    let mut lines: Vec<String> = Vec::new();
-    for raw_line in body.split('\n') {
-        let mut line = raw_line.trim_end_matches('\r').to_string();
-        line = line.replace('\t', " ");
-        while line.ends_with(' ') {
-            line.pop();
-        }
-        let mut out = String::new();
+    // Only ASCII whitespace and line breaks are rewritten, so valid UTF-8 in
+    // stays valid UTF-8 out and the lossy conversion never replaces anything.
+    String::from_utf8_lossy(&canonicalize_body_relaxed_bytes(body.as_bytes())).into_owned()
+}
+
+/// Relaxed body canonicalization (RFC 6376 §3.4.4) over raw bytes, so 8bit
+/// bodies in a legacy charset hash exactly as sent.
+pub fn canonicalize_body_relaxed_bytes(body: &[u8]) -> Vec<u8> {
+    let mut lines: Vec<Vec<u8>> = Vec::new();
+    for raw_line in body.split(|b| *b == b'\n') {
+        let line = raw_line.strip_suffix(b"\r").unwrap_or(raw_line);
+        let mut out = Vec::with_capacity(line.len());
         let mut prev_space = false;
-        for ch in line.chars() {
-            if ch == ' ' {
+        for &b in line {
+            if b == b' ' || b == b'\t' {
                 if !prev_space {
-                    out.push(' ');
+                    out.push(b' ');
                     prev_space = true;
                 }
             } else {
-                out.push(ch);
+                out.push(b);
                 prev_space = false;
             }
         }
+        if out.last() == Some(&b' ') {
+            out.pop();
+        }
         lines.push(out);
     }
 
@@ -190,15 +346,29 @@ pub fn canonicalize_body_relaxed(body: &str) -> String {
     }
 
     if lines.is_empty() {
-        return "\r\n".to_string();
+        return b"\r\n".to_vec();
     }
 
-    let mut result = lines.join("\r\n");
-    result.push_str("\r\n");
+    let mut result = lines.join(&b"\r\n"[..]);
+    result.extend_from_slice(b"\r\n");
     result
 }
 
 pub fn build_canonicalized_dkim_header_relaxed(value: &str) -> String {
+    let canon_value = canonicalize_header_relaxed(strip_dkim_b_value(value));
+    format!("dkim-signature:{}", canon_value)
+}
+
+/// The DKIM-Signature field with an empty `b=` value under simple header
+/// canonicalization. `name` and `value` are the raw field name and value from
+/// `parse_headers`; simple canonicalization keeps the name exactly as sent.
+pub fn build_canonicalized_dkim_header_simple(name: &str, value: &str) -> String {
+    format!("{name}:{}", strip_dkim_b_value(value))
+}
+
+/// `value` (a raw DKIM-Signature field value) with its `b=` value emptied,
+/// as it is hashed.
+pub fn strip_dkim_b_value(value: &str) -> String {
     let bytes = value.as_bytes();
     let mut b_value_start: Option<usize> = None;
     let mut b_value_end: Option<usize> = None;
@@ -253,17 +423,21 @@ pub fn build_canonicalized_dkim_header_relaxed(value: &str) -> String {
         i += 1;
     }
 
-    let save = if let (Some(start), Some(end)) = (b_value_start, b_value_end) {
+    if let (Some(start), Some(end)) = (b_value_start, b_value_end) {
         let mut tmp = String::new();
         tmp.push_str(&value[..start]);
         tmp.push_str(&value[end..]);
         tmp
     } else {
         value.to_string()
-    };
+    }
+}
 
-    let canon_value = canonicalize_header_relaxed(save);
-    format!("dkim-signature:{}", canon_value)
+/// Tags of Gmail's `X-Google-DKIM-Signature` header, if present. The header is
+/// signed with a Google-internal key and is never used for verification; this
+/// is for diagnostics only.
+pub fn extract_google_dkim_signature(email: &str) -> Option<HashMap<String, String>> {
+    extract_header_value(email, "X-Google-DKIM-Signature").map(|value| parse_dkim_tags(&value))
 }
 
 pub fn extract_dkim_selector_and_domain(email: &str) -> Result<(String, String), String> {
@@ -304,8 +478,105 @@ pub fn extract_dkim_selector_and_domain(email: &str) -> Result<(String, String),
     Ok((selector, domain))
 }
 
-pub fn parse_email_timestamp_ms(email: &str) -> Option<u64> {
-    let date_value = extract_header_value(email, "Date")?;
+/// Simple body canonicalization (RFC 6376 §3.4.3): lines are kept verbatim,
+/// trailing empty lines are removed and the body ends with a single CRLF.
+pub fn canonicalize_body_simple(body: &str) -> String {
+    // Only line breaks are rewritten, so valid UTF-8 stays valid.
+    String::from_utf8_lossy(&canonicalize_body_simple_bytes(body.as_bytes())).into_owned()
+}
+
+/// Byte counterpart of `canonicalize_body_simple`, for 8bit bodies.
+pub fn canonicalize_body_simple_bytes(body: &[u8]) -> Vec<u8> {
+    let mut lines: Vec<&[u8]> = body
+        .split(|b| *b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .collect();
+
+    while matches!(lines.last(), Some(l) if l.is_empty()) {
+        lines.pop();
+    }
+
+    // An empty body canonicalizes to a single CRLF.
+    let mut result = lines.join(&b"\r\n"[..]);
+    result.extend_from_slice(b"\r\n");
+    result
+}
+
+/// `Date:` header as milliseconds since the Unix epoch. `None` when the header
+/// is missing or malformed, or when it lies after `upper_bound_ms` (a sender
+/// cannot date a message into the future to outlive freshness checks).
+pub fn parse_email_timestamp_ms(email: &str, upper_bound_ms: Option<u64>) -> Option<u64> {
+    parse_email_timestamp_ms_with_resent_fallback(email, false, upper_bound_ms)
+}
+
+/// Like `parse_email_timestamp_ms`, but when the `Date:` header is absent and
+/// `allow_resent_fallback` is set, uses `Resent-Date:` (the forward time).
+pub fn parse_email_timestamp_ms_with_resent_fallback(
+    email: &str,
+    allow_resent_fallback: bool,
+    upper_bound_ms: Option<u64>,
+) -> Option<u64> {
+    let ts_ms = match extract_header_value(email, "Date") {
+        Some(date_value) => parse_rfc2822_date_ms(&date_value),
+        None if allow_resent_fallback => {
+            parse_rfc2822_date_ms(&extract_header_value(email, "Resent-Date")?)
+        }
+        None => None,
+    }?;
+    upper_bound_ms.is_none_or(|bound| ts_ms <= bound).then_some(ts_ms)
+}
+
+/// More than one `From:` header, which RFC 5322 §3.6 forbids. DKIM signs the
+/// bottom-most instance while `extract_header_value` and mail clients may read
+/// another, so such messages are rejected rather than interpreted.
+pub fn has_multiple_from_headers(email: &str) -> bool {
+    parse_headers(split_headers_body(email).0)
+        .iter()
+        .filter(|(name, _)| name.trim_end().eq_ignore_ascii_case("From"))
+        .nth(1)
+        .is_some()
+}
+
+/// Signs that a mailing list relayed the message: `"list-id"`,
+/// `"list-unsubscribe"`, and `"sender"` when a `Sender:` address differs from
+/// the `From:` one. Lists often rewrite `From:` (e.g. `Alice via List`), so
+/// the signed sender may be the list rather than the author.
+pub fn mailing_list_signals(email: &str) -> Vec<&'static str> {
+    // `extract_header_value` does not stop at the blank line; a body line must
+    // not count as a header here.
+    let (headers, _) = split_headers_body(email);
+    let mut signals = Vec::new();
+    if extract_header_value(headers, "List-Id").is_some() {
+        signals.push("list-id");
+    }
+    if extract_header_value(headers, "List-Unsubscribe").is_some() {
+        signals.push("list-unsubscribe");
+    }
+    if let Some(sender) = extract_header_value(headers, "Sender") {
+        let from = extract_header_value(headers, "From").unwrap_or_default();
+        if canonicalize_email_address(&sender) != canonicalize_email_address(&from) {
+            signals.push("sender");
+        }
+    }
+    signals
+}
+
+/// `From:` header value, falling back to `Resent-From:` when `From:` is absent
+/// or empty and `allow_resent_fallback` is set. The fallback changes which
+/// identity is authenticated, so it is opt-in.
+pub fn extract_sender_header(email: &str, allow_resent_fallback: bool) -> Option<String> {
+    let from = extract_header_value(email, "From").filter(|v| !v.trim().is_empty());
+    if from.is_some() || !allow_resent_fallback {
+        return from;
+    }
+    extract_header_value(email, "Resent-From").filter(|v| !v.trim().is_empty())
+}
+
+/// Latest `Date:` year accepted. Far-future years are bogus for an email and
+/// would make the year-by-year day count below needlessly expensive.
+const MAX_EMAIL_YEAR: i32 = 9999;
+
+fn parse_rfc2822_date_ms(date_value: &str) -> Option<u64> {
     let date_str = date_value.trim();
 
     let core = match date_str.find(',') {
@@ -323,7 +594,7 @@ pub fn parse_email_timestamp_ms(email: &str) -> Option<u64> {
 
     let day: u32 = day_str.parse().ok()?;
     let year: i32 = year_str.parse().ok()?;
-    if year < 1970 {
+    if !(1970..=MAX_EMAIL_YEAR).contains(&year) {
         return None;
     }
 
@@ -347,18 +618,24 @@ pub fn parse_email_timestamp_ms(email: &str) -> Option<u64> {
     let hour: u32 = time_parts.next()?.parse().ok()?;
     let minute: u32 = time_parts.next()?.parse().ok()?;
     let second: u32 = time_parts.next()?.parse().ok()?;
+    // Allow a leap second (`:60`); anything else out of range is not a real time.
+    if hour >= 24 || minute >= 60 || second > 60 {
+        return None;
+    }
 
     if offset_str.len() < 3 {
         return None;
     }
-    let sign = match &offset_str[0..1] {
+    let sign = match offset_str.get(..1)? {
         "+" => 1i64,
         "-" => -1i64,
         _ => return None,
     };
-    let (off_hour_str, off_min_str) = offset_str[1..].split_at(2);
-    let off_hour: i64 = off_hour_str.parse().ok()?;
-    let off_min: i64 = off_min_str.parse().ok()?;
+    let off_hour: i64 = offset_str.get(1..3)?.parse().ok()?;
+    let off_min: i64 = offset_str.get(3..)?.parse().ok()?;
+    if !(0..24).contains(&off_hour) || !(0..60).contains(&off_min) {
+        return None;
+    }
     let offset_sec = sign
         .checked_mul(off_hour.checked_mul(3600)? + off_min.checked_mul(60)?)?;
 
@@ -450,7 +727,13 @@ pub fn parse_recover_subject(subject: &str) -> Option<String> {
     Some(account_id_str.to_string())
 }
 
-pub fn parse_recover_instruction(subject: &str) -> Option<(String, String)> {
+/// Parse account_id, public key and optional expiry from a recovery Subject header.
+///
+/// Expected format:
+///   "recover-<REQUEST_ID> <account_id> ed25519:<public_key> [exp=<unix_secs>]"
+/// The `exp=` token is optional; when present but not a valid integer the
+/// instruction is rejected.
+pub fn parse_recover_instruction(subject: &str) -> Option<(String, String, Option<u64>)> {
     let subject = subject.trim();
     let mut parts = subject.split_whitespace();
 
@@ -472,15 +755,20 @@ pub fn parse_recover_instruction(subject: &str) -> Option<(String, String)> {
     }
 
     let mut new_public_key: Option<String> = None;
+    let mut expires_at_secs: Option<u64> = None;
     for token in parts {
-        if token.starts_with("ed25519:") && token.len() > "ed25519:".len() {
+        if let Some(exp) = token.strip_prefix("exp=") {
+            expires_at_secs = Some(exp.parse().ok()?);
+        } else if new_public_key.is_none()
+            && token.starts_with("ed25519:")
+            && token.len() > "ed25519:".len()
+        {
             new_public_key = Some(token.to_string());
-            break;
         }
     }
 
     let new_public_key = new_public_key?;
-    Some((account_id_str.to_string(), new_public_key))
+    Some((account_id_str.to_string(), new_public_key, expires_at_secs))
 }
 
 /// Parse the short request_id from a recovery Subject header (worker side).
@@ -502,15 +790,86 @@ pub fn parse_recover_request_id(subject: &str) -> Option<String> {
     None
 }
 
+#[allow(dead_code)] // verification uses the ambiguity-checking variant below
 pub fn parse_recover_public_key_from_body(email: &str) -> Option<String> {
+    parse_all_recover_public_keys_from_body(email).into_iter().next()
+}
+
+/// Every `ed25519:<key>` line in the body, in order (duplicates included).
+pub fn parse_all_recover_public_keys_from_body(email: &str) -> Vec<String> {
     let (_, body) = split_headers_body(email);
-    for line in body.lines() {
-        let trimmed = line.trim();
-        if trimmed.starts_with("ed25519:") && trimmed.len() > "ed25519:".len() {
-            return Some(trimmed.to_string());
+    recover_public_key_lines(body)
+}
+
+fn recover_public_key_lines(body: &str) -> Vec<String> {
+    body.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("ed25519:") && line.len() > "ed25519:".len())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The body's recovery key when every `ed25519:` line carries the same key
+/// (`Ok(None)` when there is none). Quoted replies can put an old key above
+/// the new one, so differing keys are rejected with `"ambiguous_public_key"`.
+#[allow(dead_code)] // verification goes through `parse_recovery_instruction`
+pub fn parse_unambiguous_recover_public_key_from_body(email: &str) -> Result<Option<String>, String> {
+    let (_, body) = split_headers_body(email);
+    unambiguous_recover_public_key(body)
+}
+
+fn unambiguous_recover_public_key(body: &str) -> Result<Option<String>, String> {
+    let mut keys = recover_public_key_lines(body).into_iter();
+    let first = keys.next();
+    if let Some(first) = first.as_deref() {
+        if keys.any(|key| key != first) {
+            return Err("ambiguous_public_key".to_string());
         }
     }
-    None
+    Ok(first)
+}
+
+/// What a recovery email asks for, read from its Subject and body.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecoveryInstruction {
+    /// `"recover"` when the Subject is a recovery command, empty otherwise.
+    pub command: String,
+    pub request_id: Option<String>,
+    pub account_id: Option<String>,
+    pub new_public_key: Option<String>,
+    pub expires_at_secs: Option<u64>,
+}
+
+/// Parse a recovery request from a decoded Subject and the message body.
+///
+/// A complete `recover-<REQUEST_ID> <account_id> ed25519:<key> [exp=<secs>]`
+/// Subject is used as is. Otherwise the account comes from the Subject (if
+/// any) and the key from the body's `ed25519:` lines, which fails with
+/// `"ambiguous_public_key"` when they carry different keys.
+pub fn parse_recovery_instruction(
+    subject: Option<&str>,
+    body: &str,
+) -> Result<RecoveryInstruction, String> {
+    let subject = subject.unwrap_or_default();
+    let is_recover = subject
+        .split_whitespace()
+        .next()
+        .is_some_and(|kind| kind == "recover" || kind.starts_with("recover-"));
+    let mut instruction = RecoveryInstruction {
+        command: if is_recover { "recover".to_string() } else { String::new() },
+        request_id: parse_recover_request_id(subject),
+        ..RecoveryInstruction::default()
+    };
+
+    if let Some((account_id, new_public_key, expires_at_secs)) = parse_recover_instruction(subject) {
+        instruction.account_id = Some(account_id);
+        instruction.new_public_key = Some(new_public_key);
+        instruction.expires_at_secs = expires_at_secs;
+    } else {
+        instruction.account_id = parse_recover_subject(subject);
+        instruction.new_public_key = unambiguous_recover_public_key(body)?;
+    }
+    Ok(instruction)
 }
 
 /// Parse the `From:` header into a bare email address.
@@ -518,12 +877,260 @@ pub fn parse_recover_public_key_from_body(email: &str) -> Option<String> {
 /// This helper normalizes the sender to `user@example.com` (not a display string
 /// like `User <user@example.com>`). Note that the encrypted/private verification
 /// flow intentionally does not surface the sender address in its result payload.
+#[allow(dead_code)] // not used by a worker method; kept for tests and debugging
 pub fn parse_from_address(email: &str) -> String {
-    let value = match extract_header_value(email, "From") {
-        Some(v) => v.trim().to_string(),
-        None => return String::new(),
+    parse_from_address_with_resent_fallback(email, false)
+}
+
+/// Like `parse_from_address`, optionally falling back to `Resent-From:` when
+/// `From:` is absent (see `extract_sender_header`).
+#[allow(dead_code)] // from_address_hash canonicalizes via `canonicalize_email_address`
+pub fn parse_from_address_with_resent_fallback(email: &str, allow_resent_fallback: bool) -> String {
+    match extract_sender_header(email, allow_resent_fallback) {
+        Some(v) => normalize_address(v.trim()),
+        None => String::new(),
+    }
+}
+
+/// Parse the `Reply-To:` header into a bare email address.
+///
+/// Uses the same normalization as `parse_from_address`; returns `None` when the
+/// header is absent or empty.
+#[allow(dead_code)] // not yet wired into a worker method
+pub fn parse_reply_to_address(email: &str) -> Option<String> {
+    let value = extract_header_value(email, "Reply-To")?;
+    let address = normalize_address(value.trim());
+    if address.is_empty() {
+        None
+    } else {
+        Some(address)
+    }
+}
+
+/// Parse the `Cc:` header into a list of bare email addresses.
+///
+/// The header is split on commas (ignoring commas inside quoted display names
+/// or angle brackets) and each entry is normalized like `parse_from_address`.
+#[allow(dead_code)] // not yet wired into a worker method
+pub fn parse_cc_addresses(email: &str) -> Vec<String> {
+    let value = match extract_header_value(email, "Cc") {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+
+    split_address_list(&value)
+        .into_iter()
+        .map(|token| normalize_address(token.trim()))
+        .filter(|address| !address.is_empty())
+        .collect()
+}
+
+/// Parse the `To:` header into a list of bare email addresses, split and
+/// normalized like `parse_cc_addresses`.
+pub fn parse_to_addresses(email: &str) -> Vec<String> {
+    let value = match extract_header_value(email, "To") {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+
+    split_address_list(&value)
+        .into_iter()
+        .map(|token| normalize_address(token.trim()))
+        .filter(|address| !address.is_empty())
+        .collect()
+}
+
+/// Whether `address`'s domain (after the last `@`) is exactly `domain`,
+/// ignoring ASCII case and a leading `@` on `domain`. Subdomains do not match.
+pub fn address_in_domain(address: &str, domain: &str) -> bool {
+    let domain = domain.trim();
+    let domain = domain.strip_prefix('@').unwrap_or(domain);
+    match address.rsplit_once('@') {
+        Some((local, address_domain)) => {
+            !local.is_empty() && !domain.is_empty() && address_domain.eq_ignore_ascii_case(domain)
+        }
+        None => false,
+    }
+}
+
+/// Extract the `boundary` parameter from a `Content-Type: multipart/...` value.
+///
+/// Accepts quoted (`boundary="a b"`) and unquoted (`boundary=ab`) values; the
+/// parameter name is matched case-insensitively and the value is trimmed.
+/// Returns `None` when there is no non-empty `boundary` parameter.
+#[allow(dead_code)] // not yet wired into MIME body extraction
+pub fn parse_mime_boundary(content_type: &str) -> Option<String> {
+    // The first segment is the media type itself.
+    split_mime_params(content_type)
+        .into_iter()
+        .skip(1)
+        .find_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            if !name.trim().eq_ignore_ascii_case("boundary") {
+                return None;
+            }
+            let value = value.trim();
+            let value = match value.strip_prefix('"') {
+                Some(quoted) => quoted.split('"').next().unwrap_or_default(),
+                None => value,
+            };
+            let value = value.trim();
+            if value.is_empty() {
+                None
+            } else {
+                Some(value.to_string())
+            }
+        })
+}
+
+/// Split a header value on `;`, ignoring semicolons inside quoted strings.
+fn split_mime_params(value: &str) -> Vec<&str> {
+    let mut params = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+
+    for (idx, ch) in value.char_indices() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => {
+                params.push(&value[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    params.push(&value[start..]);
+
+    params
+}
+
+/// Canonical form of an email address, exactly as the contract computes it
+/// (`canonicalize_email_address` in `onchain_verify/parsers.rs`): the first
+/// `local@domain` found (preferring `<...>`, after any `Header-Name:` and
+/// `mailto:` prefix), lowercased. Falls back to the lowercased input.
+///
+/// Keep byte-for-byte in sync with the contract; both are checked against
+/// `email-dkim-verifier-contract/tests/data/email_address_vectors.tsv`.
+pub fn canonicalize_email_address(input: &str) -> String {
+    let raw = input.trim();
+    if raw.is_empty() {
+        return String::new();
+    }
+
+    // Strip leading "Header-Name:" when present (e.g. "From: ...").
+    let without_header_name = if let Some(colon_idx) = raw.find(':') {
+        let (prefix, rest) = raw.split_at(colon_idx);
+        let prefix = prefix.trim();
+        if !prefix.is_empty()
+            && prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            rest[1..].trim_start()
+        } else {
+            raw
+        }
+    } else {
+        raw
     };
 
+    // Prefer the common "Name <email@domain>" format.
+    let mut candidates: [&str; 2] = ["", without_header_name];
+    if let Some(start) = without_header_name.find('<') {
+        if let Some(end_rel) = without_header_name[start + 1..].find('>') {
+            let end = start + 1 + end_rel;
+            candidates[0] = &without_header_name[start + 1..end];
+        }
+    }
+
+    for candidate in candidates {
+        let candidate = candidate.trim();
+        if candidate.is_empty() {
+            continue;
+        }
+
+        let candidate = if candidate.len() >= 7 && candidate[..7].eq_ignore_ascii_case("mailto:") {
+            candidate[7..].trim_start()
+        } else {
+            candidate
+        };
+
+        if let Some(found) = extract_email_like(candidate) {
+            return found.to_lowercase();
+        }
+    }
+
+    without_header_name.to_lowercase()
+}
+
+fn extract_email_like(input: &str) -> Option<&str> {
+    let bytes = input.as_bytes();
+    for (idx, b) in bytes.iter().enumerate() {
+        if *b != b'@' {
+            continue;
+        }
+
+        let mut start = idx;
+        while start > 0 && is_email_local_byte(bytes[start - 1]) {
+            start -= 1;
+        }
+
+        let mut end = idx + 1;
+        while end < bytes.len() && (is_email_domain_byte(bytes[end]) || bytes[end] == b'.') {
+            end += 1;
+        }
+
+        if start == idx || end == idx + 1 {
+            continue;
+        }
+
+        // Domain must not end with '.'.
+        if bytes[end - 1] == b'.' {
+            continue;
+        }
+
+        return Some(&input[start..end]);
+    }
+    None
+}
+
+fn is_email_local_byte(b: u8) -> bool {
+    matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9')
+        || matches!(
+            b,
+            b'.' | b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' | b'+' | b'/' | b'=' | b'?' | b'^'
+                | b'_' | b'`' | b'{' | b'|' | b'}' | b'~' | b'-'
+        )
+}
+
+fn is_email_domain_byte(b: u8) -> bool {
+    matches!(b, b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-')
+}
+
+fn split_address_list(value: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut in_quotes = false;
+    let mut in_angle = false;
+    let mut start = 0;
+
+    for (idx, ch) in value.char_indices() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_angle = true,
+            '>' if !in_quotes => in_angle = false,
+            ',' if !in_quotes && !in_angle => {
+                tokens.push(&value[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    tokens.push(&value[start..]);
+
+    tokens
+}
+
+fn normalize_address(value: &str) -> String {
     // Prefer the address inside angle brackets if present.
     if let Some(start) = value.find('<') {
         if let Some(end_rel) = value[start + 1..].find('>') {
@@ -546,5 +1153,5 @@ pub fn parse_from_address(email: &str) -> String {
     }
 
     // As a last resort, return the raw header value.
-    value
+    value.to_string()
 }
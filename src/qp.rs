@@ -0,0 +1,62 @@
+//! Quoted-printable decoding (RFC 2045 §6.7) for email body payloads.
+
+/// Decode quoted-printable `text` into raw bytes.
+///
+/// - `=XX` (hex digits in either case) becomes the byte `0xXX`.
+/// - Soft line breaks (`=\r\n` or `=\n`) are removed.
+/// - Spaces and tabs at the end of an encoded line are dropped (rule 3), as
+///   transports may add them; this also allows whitespace after a soft `=`.
+/// - A `=` not followed by two hex digits is kept as-is.
+///
+/// Hard line breaks keep their original `\r\n` / `\n` form. The charset of the
+/// result is left to the caller.
+pub fn decode_quoted_printable(text: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len());
+
+    for line in text.split_inclusive('\n') {
+        let (content, line_ending) = match line.strip_suffix("\r\n") {
+            Some(c) => (c, "\r\n"),
+            None => match line.strip_suffix('\n') {
+                Some(c) => (c, "\n"),
+                None => (line, ""),
+            },
+        };
+        let content = content.trim_end_matches([' ', '\t']);
+
+        match content.strip_suffix('=') {
+            // Soft line break: join with the next line.
+            Some(soft) => decode_line(soft, &mut out),
+            None => {
+                decode_line(content, &mut out);
+                out.extend_from_slice(line_ending.as_bytes());
+            }
+        }
+    }
+
+    out
+}
+
+fn decode_line(line: &str, out: &mut Vec<u8>) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' && i + 2 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                out.push((hi << 4) | lo);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+}
+
+pub(crate) fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
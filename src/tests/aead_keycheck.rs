@@ -0,0 +1,72 @@
+use crate::api::{handle_request_with_keys, RequestType};
+use crate::crypto::{derive_envelope_key, get_worker_public_key};
+use super::crypto::test_worker_keys;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+fn keycheck(args: Value) -> Value {
+    let response = handle_request_with_keys(
+        RequestType {
+            method: "aead-keycheck".to_string(),
+            args,
+        },
+        Ok(&test_worker_keys()),
+    );
+    assert_eq!(response.method, "aead-keycheck");
+    response.response
+}
+
+/// The client side: ECDH from the ephemeral secret to the worker public key.
+fn client_commitment(eph_secret: &StaticSecret, version: u8, context: &Value) -> String {
+    let worker_pk = base64::decode(get_worker_public_key(&test_worker_keys())).expect("base64");
+    let mut worker_pk_bytes = [0u8; 32];
+    worker_pk_bytes.copy_from_slice(&worker_pk);
+    let shared = eph_secret.diffie_hellman(&X25519PublicKey::from(worker_pk_bytes));
+    let key = derive_envelope_key(shared.as_bytes(), version, context).expect("derive key");
+    Sha256::digest(key).iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn commitment_is_deterministic_and_matches_client_derivation() {
+    let eph_secret = StaticSecret::from([9u8; 32]);
+    let eph_pub = base64::encode(X25519PublicKey::from(&eph_secret).as_bytes());
+
+    let first = keycheck(json!({ "ephemeral_pub": eph_pub }));
+    let second = keycheck(json!({ "ephemeral_pub": eph_pub }));
+
+    assert_eq!(first["version"], 1);
+    assert_eq!(first["key_commitment"], second["key_commitment"]);
+    assert_eq!(first["key_commitment"], client_commitment(&eph_secret, 1, &Value::Null));
+    assert_eq!(first["key_commitment"].as_str().unwrap().len(), 64);
+}
+
+#[test]
+fn v2_commitment_depends_on_network_id() {
+    let eph_secret = StaticSecret::from([9u8; 32]);
+    let eph_pub = base64::encode(X25519PublicKey::from(&eph_secret).as_bytes());
+    let testnet = json!({ "network_id": "testnet" });
+
+    let v1 = keycheck(json!({ "ephemeral_pub": eph_pub }));
+    let v2 = keycheck(json!({ "ephemeral_pub": eph_pub, "version": 2, "context": testnet }));
+    let v2_mainnet = keycheck(json!({
+        "ephemeral_pub": eph_pub,
+        "version": 2,
+        "context": { "network_id": "mainnet" },
+    }));
+
+    assert_eq!(v2["key_commitment"], client_commitment(&eph_secret, 2, &testnet));
+    assert_ne!(v2["key_commitment"], v1["key_commitment"]);
+    assert_ne!(v2["key_commitment"], v2_mainnet["key_commitment"]);
+}
+
+#[test]
+fn invalid_ephemeral_key_or_version_is_reported() {
+    let response = keycheck(json!({ "ephemeral_pub": base64::encode([1u8; 16]) }));
+    assert_eq!(response["error"], "ephemeral_pub must be 32 bytes");
+
+    let eph_pub = base64::encode(X25519PublicKey::from(&StaticSecret::from([9u8; 32])).as_bytes());
+    let response = keycheck(json!({ "ephemeral_pub": eph_pub, "version": 2 }));
+    assert_eq!(response["error"], "context.network_id is required for envelope version 2");
+    assert!(response.get("key_commitment").is_none());
+}
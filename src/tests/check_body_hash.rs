@@ -0,0 +1,45 @@
+use crate::api::{handle_request, RequestType};
+use serde_json::{json, Value};
+
+const GMAIL_EMAIL: &str =
+    include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+fn check_body_hash(email_blob: &str) -> Value {
+    let response = handle_request(RequestType {
+        method: "check-body-hash".to_string(),
+        args: json!({ "email_blob": email_blob }),
+    });
+    assert_eq!(response.method, "check-body-hash");
+    response.response
+}
+
+#[test]
+fn untouched_gmail_body_matches_bh_tag() {
+    let response = check_body_hash(GMAIL_EMAIL);
+    assert_eq!(response["matches"], true);
+    assert_eq!(response["expected_bh"], "DybNgKUUAMbDmXWMvqU5XUFnq2CTnP980Z2v48D+cIc=");
+    assert_eq!(response["computed_bh"], response["expected_bh"]);
+}
+
+#[test]
+fn tampered_body_reports_both_hashes() {
+    let tampered = GMAIL_EMAIL.replacen(
+        "<div dir=\"ltr\"><br></div>",
+        "<div dir=\"ltr\">tampered</div>",
+        1,
+    );
+    let response = check_body_hash(&tampered);
+    assert_eq!(response["matches"], false);
+    assert_eq!(response["expected_bh"], "DybNgKUUAMbDmXWMvqU5XUFnq2CTnP980Z2v48D+cIc=");
+    assert_ne!(response["computed_bh"], response["expected_bh"]);
+
+    // A header-only change leaves the body hash intact.
+    let subject_changed = GMAIL_EMAIL.replacen("Subject: ", "Subject: Re: ", 1);
+    assert_eq!(check_body_hash(&subject_changed)["matches"], true);
+}
+
+#[test]
+fn email_without_dkim_signature_is_an_error() {
+    let response = check_body_hash("From: a@example.com\r\n\r\nHi\r\n");
+    assert_eq!(response["error"], "no DKIM-Signature header");
+}
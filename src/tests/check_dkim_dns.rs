@@ -0,0 +1,133 @@
+use crate::api::{handle_request, RequestType};
+use crate::dns::{clear_dns_stubs, set_dns_stub};
+use super::dns::{stub_gmail_dns, test_key_dkim_record, GMAIL_DKIM_RECORD, TEST_KEY_DKIM_NAME};
+use serde_json::{json, Value};
+
+fn check_dkim_dns(selector: &str, domain: &str) -> Value {
+    let response = handle_request(RequestType {
+        method: "check-dkim-dns".to_string(),
+        args: json!({ "selector": selector, "domain": domain }),
+    });
+    assert_eq!(response.method, "check-dkim-dns");
+    response.response
+}
+
+/// Stub `test._domainkey.example.com` with one record and check it.
+fn check_test_record(record: &str) -> Value {
+    clear_dns_stubs();
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![record.to_string()]);
+    check_dkim_dns("test", "example.com")
+}
+
+fn issues(response: &Value) -> Vec<&str> {
+    response["issues"]
+        .as_array()
+        .expect("issues array")
+        .iter()
+        .map(|i| i.as_str().expect("issue string"))
+        .collect()
+}
+
+#[test]
+fn gmail_key_record_is_valid() {
+    clear_dns_stubs();
+    stub_gmail_dns();
+
+    let response = check_dkim_dns("20230601", "gmail.com");
+    assert_eq!(response["name"], "20230601._domainkey.gmail.com");
+    assert_eq!(response["valid"], true, "{response}");
+    assert!(issues(&response).is_empty());
+    assert_eq!(response["key_bits"], 2048);
+    assert_eq!(response["key_algorithm"], "rsa");
+}
+
+#[test]
+fn key_record_is_picked_out_of_other_txt_records() {
+    clear_dns_stubs();
+    set_dns_stub(
+        TEST_KEY_DKIM_NAME,
+        vec!["google-site-verification=abc".to_string(), test_key_dkim_record()],
+    );
+
+    let response = check_dkim_dns("test", "example.com");
+    assert_eq!(response["valid"], true, "{response}");
+    assert_eq!(response["key_algorithm"], "rsa");
+}
+
+#[test]
+fn revoked_key_with_empty_p_is_invalid() {
+    let response = check_test_record("v=DKIM1; k=rsa; p=");
+    assert_eq!(response["valid"], false);
+    assert_eq!(issues(&response), vec!["empty p= tag: key is revoked"]);
+    assert_eq!(response["key_bits"], Value::Null);
+    assert_eq!(response["key_algorithm"], "rsa");
+}
+
+#[test]
+fn malformed_base64_key_is_invalid() {
+    let response = check_test_record("v=DKIM1; k=rsa; p=MIIB!!not-base64");
+    assert_eq!(response["valid"], false);
+    let issues = issues(&response);
+    assert_eq!(issues.len(), 1);
+    assert!(issues[0].starts_with("p= is not valid base64"), "{issues:?}");
+}
+
+#[test]
+fn base64_that_is_not_an_rsa_key_is_invalid() {
+    let response = check_test_record(&format!("v=DKIM1; k=rsa; p={}", base64::encode([7u8; 64])));
+    assert_eq!(response["valid"], false);
+    let issues = issues(&response);
+    assert_eq!(issues.len(), 1);
+    assert!(issues[0].starts_with("p= invalid RSA public key"), "{issues:?}");
+    assert_eq!(response["key_bits"], Value::Null);
+}
+
+#[test]
+fn missing_v_and_k_tags_are_reported_but_key_still_parses() {
+    let record = test_key_dkim_record().replace("v=DKIM1; k=rsa; ", "");
+    let response = check_test_record(&record);
+    assert_eq!(response["valid"], false);
+    assert_eq!(issues(&response), vec!["missing v= tag", "missing k= tag"]);
+    assert_eq!(response["key_bits"], 2048);
+    assert_eq!(response["key_algorithm"], Value::Null);
+}
+
+#[test]
+fn missing_p_tag_is_reported() {
+    let response = check_test_record("v=DKIM1; k=rsa; t=y");
+    assert_eq!(response["valid"], false);
+    assert_eq!(issues(&response), vec!["missing p= tag"]);
+}
+
+#[test]
+fn wrong_version_and_algorithm_are_reported() {
+    let record = GMAIL_DKIM_RECORD.replace("v=DKIM1; k=rsa", "v=DKIM2; k=ed25519");
+    let response = check_test_record(&record);
+    assert_eq!(response["valid"], false);
+    assert_eq!(
+        issues(&response),
+        vec!["v= must be DKIM1, got DKIM2", "unsupported k= algorithm: ed25519"]
+    );
+    assert_eq!(response["key_algorithm"], "ed25519");
+}
+
+#[test]
+fn failed_dns_lookup_is_an_issue() {
+    clear_dns_stubs();
+    let response = check_dkim_dns("missing", "example.com");
+    assert_eq!(response["valid"], false);
+    let issues = issues(&response);
+    assert!(issues[0].starts_with("DNS lookup failed: DnsStubNotFound"), "{issues:?}");
+}
+
+#[test]
+fn missing_domain_is_an_args_error() {
+    let response = handle_request(RequestType {
+        method: "check-dkim-dns".to_string(),
+        args: json!({ "selector": "test" }),
+    });
+    assert!(response.response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid check-dkim-dns args"));
+}
@@ -0,0 +1,55 @@
+use crate::api::{handle_request_with_keys, RequestType};
+use crate::crypto::get_worker_public_key;
+use super::crypto::test_worker_keys;
+use base64;
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+fn check_public_key(client_public_key: &str) -> serde_json::Value {
+    let request = RequestType {
+        method: "check-public-key".to_string(),
+        args: serde_json::json!({ "client_public_key": client_public_key }),
+    };
+    let response = handle_request_with_keys(request, Ok(&test_worker_keys()));
+    assert_eq!(response.method, "check-public-key");
+    response.response
+}
+
+fn expected_fingerprint(worker_public_key_b64: &str) -> String {
+    let pk = base64::decode(worker_public_key_b64).expect("worker public key is base64");
+    Sha256::digest(&pk)[..4].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn check_public_key_matches_worker_key() {
+    let worker_pk = get_worker_public_key(&test_worker_keys());
+
+    let response = check_public_key(&worker_pk);
+
+    assert_eq!(response["ecdh_ok"], true);
+    assert_eq!(response["matches_worker_public_key"], true);
+    assert_eq!(response["worker_public_key_fingerprint"], expected_fingerprint(&worker_pk));
+    assert!(response.get("secret").is_none());
+}
+
+#[test]
+fn check_public_key_reports_mismatch_for_other_key() {
+    let worker_pk = get_worker_public_key(&test_worker_keys());
+    let other = X25519PublicKey::from(&StaticSecret::from([3u8; 32]));
+
+    let response = check_public_key(&base64::encode(other.as_bytes()));
+
+    assert_eq!(response["ecdh_ok"], true);
+    assert_eq!(response["matches_worker_public_key"], false);
+    assert_eq!(response["worker_public_key_fingerprint"], expected_fingerprint(&worker_pk));
+}
+
+#[test]
+fn check_public_key_rejects_low_order_and_malformed_keys() {
+    let response = check_public_key(&base64::encode([0u8; 32]));
+    assert_eq!(response["ecdh_ok"], false);
+    assert_eq!(response["matches_worker_public_key"], false);
+
+    let response = check_public_key(&base64::encode([1u8; 16]));
+    assert_eq!(response["error"], "client public key must be 32 bytes");
+}
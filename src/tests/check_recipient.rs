@@ -0,0 +1,71 @@
+use crate::api::{handle_request, RequestType};
+use crate::parsers::address_in_domain;
+use serde_json::{json, Value};
+
+const GMAIL_EMAIL: &str =
+    include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+fn check_recipient(email_blob: &str, expected_domain: &str) -> Value {
+    let response = handle_request(RequestType {
+        method: "check-recipient".to_string(),
+        args: json!({ "email_blob": email_blob, "expected_domain": expected_domain }),
+    });
+    assert_eq!(response.method, "check-recipient");
+    response.response
+}
+
+#[test]
+fn gmail_fixture_is_addressed_to_web3authn() {
+    let response = check_recipient(GMAIL_EMAIL, "web3authn.org");
+    assert_eq!(response["matches"], true);
+    assert_eq!(response["recipient_addresses"], json!(["recover@web3authn.org"]));
+    assert_eq!(response["matched_address"], "recover@web3authn.org");
+
+    let response = check_recipient(GMAIL_EMAIL, "example.com");
+    assert_eq!(response["matches"], false);
+    assert_eq!(response["recipient_addresses"], json!(["recover@web3authn.org"]));
+    assert_eq!(response["matched_address"], Value::Null);
+}
+
+#[test]
+fn any_of_several_recipients_can_match() {
+    let email = "From: a@example.com\r\n\
+To: \"Ops, Team\" <ops@example.com>, Recovery <Recover@Web3Authn.org>\r\n\
+\r\n\
+body\r\n";
+    let response = check_recipient(email, "@WEB3AUTHN.ORG");
+    assert_eq!(response["matches"], true);
+    assert_eq!(
+        response["recipient_addresses"],
+        json!(["ops@example.com", "Recover@Web3Authn.org"])
+    );
+    assert_eq!(response["matched_address"], "Recover@Web3Authn.org");
+}
+
+#[test]
+fn missing_to_header_does_not_match() {
+    let response = check_recipient("From: a@example.com\r\n\r\nbody\r\n", "example.com");
+    assert_eq!(response["matches"], false);
+    assert_eq!(response["recipient_addresses"], json!([]));
+}
+
+#[test]
+fn subdomains_and_lookalikes_do_not_match() {
+    assert!(address_in_domain("recover@web3authn.org", "web3authn.org"));
+    assert!(!address_in_domain("recover@mail.web3authn.org", "web3authn.org"));
+    assert!(!address_in_domain("recover@evilweb3authn.org", "web3authn.org"));
+    assert!(!address_in_domain("@web3authn.org", "web3authn.org"));
+    assert!(!address_in_domain("recover@web3authn.org", ""));
+}
+
+#[test]
+fn missing_expected_domain_is_an_error() {
+    let response = handle_request(RequestType {
+        method: "check-recipient".to_string(),
+        args: json!({ "email_blob": GMAIL_EMAIL }),
+    });
+    assert!(response.response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid check-recipient args"));
+}
@@ -0,0 +1,50 @@
+use crate::api::{handle_request, RequestType};
+use crate::parsers::{extract_header_value, parse_dkim_tags};
+use serde_json::{json, Value};
+
+const GMAIL_EMAIL: &str =
+    include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+fn compute_body_hash(email_blob: &str, canonicalization: &str) -> Value {
+    let response = handle_request(RequestType {
+        method: "compute-body-hash".to_string(),
+        args: json!({ "email_blob": email_blob, "canonicalization": canonicalization }),
+    });
+    assert_eq!(response.method, "compute-body-hash");
+    response.response
+}
+
+#[test]
+fn relaxed_body_hash_matches_gmail_bh_tag() {
+    let dkim_value = extract_header_value(GMAIL_EMAIL, "DKIM-Signature").expect("dkim header");
+    let bh = parse_dkim_tags(&dkim_value).get("bh").cloned().expect("bh tag");
+
+    let response = compute_body_hash(GMAIL_EMAIL, "relaxed");
+
+    assert_eq!(response["bh_b64"], bh);
+    assert!(response["canon_body_len"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn simple_body_hash_keeps_whitespace() {
+    let email = "From: a@example.com\r\n\r\nHi.\r\n\r\nWe lost the game. Are you hungry yet?\r\n\r\nJoe.\r\n\r\n";
+
+    let response = compute_body_hash(email, "simple");
+
+    // RFC 6376 Appendix A.2 bh= for this body.
+    assert_eq!(response["bh_b64"], "2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=");
+    assert_eq!(response["canon_body_len"], 54);
+    assert_ne!(
+        compute_body_hash("From: a@example.com\r\n\r\nHi  there\r\n", "simple")["bh_b64"],
+        compute_body_hash("From: a@example.com\r\n\r\nHi there\r\n", "simple")["bh_b64"],
+    );
+}
+
+#[test]
+fn unknown_canonicalization_is_rejected() {
+    let response = compute_body_hash(GMAIL_EMAIL, "nowsp");
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("unsupported body canonicalization: nowsp"));
+}
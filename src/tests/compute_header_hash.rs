@@ -0,0 +1,72 @@
+use crate::api::{handle_request, RequestType};
+use serde_json::{json, Value};
+
+const GMAIL_EMAIL: &str =
+    include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+/// SHA-256 of the Gmail fixture's relaxed header data, the digest its `b=`
+/// signature verifies against (see `get_canonicalized_headers.rs`).
+const GMAIL_HEADER_HASH_HEX: &str =
+    "b630267f901675020b08937bd1a58d85dcc94e02b2da89e1520d36b33f5b9dc6";
+
+fn compute_header_hash(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "compute-header-hash".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "compute-header-hash");
+    response.response
+}
+
+fn unhex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("hex digit"))
+        .collect()
+}
+
+#[test]
+fn gmail_header_hash_matches_known_good_digest() {
+    let response = compute_header_hash(json!({ "email_blob": GMAIL_EMAIL, "dkim_index": 0 }));
+
+    assert_eq!(response["data_sha256_hex"], GMAIL_HEADER_HASH_HEX);
+    assert_eq!(
+        response["signed_headers"],
+        json!([
+            "to", "subject", "message-id", "date", "from", "mime-version", "from", "to", "cc",
+            "subject", "date", "message-id", "reply-to"
+        ])
+    );
+
+    let data = String::from_utf8(unhex(response["data_hex"].as_str().expect("data_hex")))
+        .expect("header data is UTF-8");
+    assert_eq!(response["data_length"], data.len());
+    assert!(data.starts_with("to:recover@web3authn.org\r\n"), "{data}");
+    assert!(data.ends_with("; b="), "{data}");
+}
+
+#[test]
+fn signature_canonicalization_is_used() {
+    // No c= tag: simple header canonicalization, as get-canonicalized-headers
+    // computes when asked for it explicitly.
+    let email = include_str!("../../email-dkim-verifier-contract/tests/data/c_absent.eml");
+    let response = compute_header_hash(json!({ "email_blob": email }));
+    let simple = handle_request(RequestType {
+        method: "get-canonicalized-headers".to_string(),
+        args: json!({ "email_blob": email, "canonicalization": "simple" }),
+    })
+    .response;
+    assert_eq!(response["data_sha256_hex"], simple["data_hash_hex"]);
+}
+
+#[test]
+fn bad_index_and_args_are_errors() {
+    let response = compute_header_hash(json!({ "email_blob": GMAIL_EMAIL, "dkim_index": 1 }));
+    assert_eq!(response["error"], "no DKIM-Signature header at index 1");
+
+    let response = compute_header_hash(json!({ "dkim_index": 0 }));
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid compute-header-hash args:"));
+}
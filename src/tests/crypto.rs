@@ -1,4 +1,7 @@
-use crate::crypto::{decrypt_encrypted_email, EncryptedEmailEnvelope, load_worker_static_secret};
+use crate::crypto::{
+    canonical_json, decrypt_encrypted_email, decrypt_with_static_secret, derive_envelope_key,
+    encrypt_email_to_public_key, get_worker_public_key, EncryptedEmailEnvelope, WorkerKeys,
+};
 use crate::parsers::{extract_header_value, parse_email_timestamp_ms, parse_from_address};
 use base64;
 use chacha20poly1305::aead::{Aead, KeyInit, Payload};
@@ -7,19 +10,16 @@ use hkdf::Hkdf;
 use sha2::Sha256;
 use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
 
-pub(crate) fn setup_worker_static_secret() -> StaticSecret {
-    // Use a fixed hex seed so tests are deterministic.
-    let seed_hex = "07".repeat(32); // 32 bytes of 0x07 as hex
-    std::env::set_var("PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32", seed_hex);
-    std::env::set_var("OUTLAYER_WORKER_SK_SEED_HEX32", "07".repeat(32));
+// Fixed seed so tests are deterministic: 32 bytes of 0x07.
+const TEST_WORKER_SEED: [u8; 32] = [7u8; 32];
 
-    // Derive the same StaticSecret as the worker would.
-    load_worker_static_secret().expect("worker static secret to load from seed")
+pub(crate) fn test_worker_keys() -> WorkerKeys {
+    WorkerKeys::from_seed(TEST_WORKER_SEED).expect("worker keys to derive from seed")
 }
 
 pub(crate) fn encrypt_email(email_blob: &str, context: &serde_json::Value) -> EncryptedEmailEnvelope {
-    let static_secret = setup_worker_static_secret();
-    let static_public = X25519PublicKey::from(&static_secret);
+    let keys = test_worker_keys();
+    let static_public = X25519PublicKey::from(keys.static_secret());
 
     let eph_bytes = [9u8; 32];
     let eph_secret = StaticSecret::from(eph_bytes);
@@ -70,7 +70,7 @@ fn encrypted_email_decrypts_and_parses_fields() {
     let envelope = encrypt_email(email_blob, &context);
 
     let decrypted =
-        decrypt_encrypted_email(&envelope, &context).expect("decrypts email");
+        decrypt_encrypted_email(&test_worker_keys(), &envelope, &context).expect("decrypts email");
 
     assert_eq!(decrypted, email_blob);
 
@@ -88,6 +88,178 @@ fn encrypted_email_decrypts_and_parses_fields() {
     let from_addr = parse_from_address(&decrypted);
     assert_eq!(from_addr, "n6378056@gmail.com");
 
-    let ts = parse_email_timestamp_ms(&decrypted);
+    let ts = parse_email_timestamp_ms(&decrypted, None);
     assert!(ts.is_some(), "expected email timestamp to parse");
 }
+
+#[test]
+fn network_salted_envelope_keys_differ_between_networks() {
+    let shared = [42u8; 32];
+    let testnet = serde_json::json!({ "account_id": "alice.testnet", "network_id": "testnet" });
+    let mainnet = serde_json::json!({ "account_id": "alice.testnet", "network_id": "mainnet" });
+
+    let v2_testnet = derive_envelope_key(&shared, 2, &testnet).expect("v2 testnet key");
+    let v2_mainnet = derive_envelope_key(&shared, 2, &mainnet).expect("v2 mainnet key");
+    assert_ne!(v2_testnet, v2_mainnet);
+
+    // v1 keeps the unsalted derivation, so both networks share a key.
+    let v1_testnet = derive_envelope_key(&shared, 1, &testnet).expect("v1 testnet key");
+    let v1_mainnet = derive_envelope_key(&shared, 1, &mainnet).expect("v1 mainnet key");
+    assert_eq!(v1_testnet, v1_mainnet);
+    assert_ne!(v1_testnet, v2_testnet);
+}
+
+#[test]
+fn network_salted_envelope_requires_network_id() {
+    let err = derive_envelope_key(&[42u8; 32], 2, &serde_json::json!({}))
+        .expect_err("missing network_id");
+    assert_eq!(err, "context.network_id is required for envelope version 2");
+
+    let err = derive_envelope_key(&[42u8; 32], 3, &serde_json::json!({ "network_id": "testnet" }))
+        .expect_err("unknown version");
+    assert_eq!(err, "unsupported envelope version: 3");
+}
+
+#[test]
+fn network_salted_envelope_round_trips() {
+    let keys = test_worker_keys();
+    let worker_secret = keys.static_secret();
+    let worker_public = get_worker_public_key(&keys);
+    let context = serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+    });
+
+    let envelope = encrypt_email_to_public_key(b"hello", &worker_public, &context, 2)
+        .expect("encrypts v2 envelope");
+    assert_eq!(envelope.version, 2);
+
+    let decrypted =
+        decrypt_with_static_secret(worker_secret, &envelope, &context).expect("decrypts");
+    assert_eq!(decrypted, "hello");
+
+    // Decrypting the same ciphertext as v1 uses the unsalted key and fails.
+    let as_v1 = EncryptedEmailEnvelope { version: 1, ..envelope };
+    assert!(decrypt_with_static_secret(worker_secret, &as_v1, &context).is_err());
+}
+
+#[test]
+fn request_id_in_context_binds_ciphertext_to_request() {
+    let keys = test_worker_keys();
+    let context_for = |request_id: Option<&str>| {
+        let mut context = serde_json::json!({
+            "account_id": "kerp30.w3a-v1.testnet",
+            "network_id": "testnet",
+            "payer_account_id": "kerp30.w3a-v1.testnet",
+        });
+        if let Some(id) = request_id {
+            context["request_id"] = serde_json::json!(id);
+        }
+        context
+    };
+
+    let bound = context_for(Some("ABC123"));
+    let envelope = encrypt_email("hello", &bound);
+    assert_eq!(
+        decrypt_encrypted_email(&keys, &envelope, &bound).expect("decrypts"),
+        "hello"
+    );
+
+    // Replaying the envelope under another request_id, or without one, fails.
+    for other in [context_for(Some("XYZ789")), context_for(None)] {
+        assert_eq!(
+            decrypt_encrypted_email(&keys, &envelope, &other),
+            Err("decryption failed".to_string())
+        );
+    }
+}
+
+#[test]
+fn worker_keys_from_vars_prefers_protected_seed() {
+    let seed_hex = "07".repeat(32);
+    let other_hex = "08".repeat(32);
+    let from_seed = get_worker_public_key(&test_worker_keys());
+
+    let keys = WorkerKeys::from_vars(|name| match name {
+        "PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32" => Some(seed_hex.clone()),
+        "OUTLAYER_WORKER_SK_SEED_HEX32" => Some(other_hex.clone()),
+        _ => None,
+    })
+    .expect("keys from protected seed");
+    assert_eq!(get_worker_public_key(&keys), from_seed);
+
+    let keys = WorkerKeys::from_vars(|name| {
+        (name == "OUTLAYER_WORKER_SK_SEED_HEX32").then(|| seed_hex.clone())
+    })
+    .expect("keys from fallback seed");
+    assert_eq!(get_worker_public_key(&keys), from_seed);
+}
+
+#[test]
+fn worker_keys_from_vars_reads_optional_key_version() {
+    let seed_hex = "07".repeat(32);
+    let keys = WorkerKeys::from_vars(|name| {
+        (name == "OUTLAYER_WORKER_SK_SEED_HEX32").then(|| seed_hex.clone())
+    })
+    .expect("keys without version");
+    assert_eq!(keys.key_version(), 1);
+
+    let keys = WorkerKeys::from_vars(|name| match name {
+        "OUTLAYER_WORKER_SK_SEED_HEX32" => Some(seed_hex.clone()),
+        "OUTLAYER_WORKER_KEY_VERSION" => Some(" 3 ".to_string()),
+        _ => None,
+    })
+    .expect("keys with version");
+    assert_eq!(keys.key_version(), 3);
+    assert_eq!(get_worker_public_key(&keys), get_worker_public_key(&test_worker_keys()));
+
+    for bad in ["0", "-1", "two"] {
+        let err = WorkerKeys::from_vars(|name| match name {
+            "OUTLAYER_WORKER_SK_SEED_HEX32" => Some(seed_hex.clone()),
+            "OUTLAYER_WORKER_KEY_VERSION" => Some(bad.to_string()),
+            _ => None,
+        })
+        .err()
+        .expect("invalid version");
+        assert_eq!(err, "OUTLAYER_WORKER_KEY_VERSION must be a positive integer");
+    }
+}
+
+#[test]
+fn worker_keys_from_vars_reports_missing_or_malformed_seed() {
+    let err = WorkerKeys::from_vars(|_| None).err().expect("missing seed");
+    assert!(err.starts_with("Secrets Not Found"), "got: {err}");
+
+    let err = WorkerKeys::from_vars(|name| {
+        (name == "PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32").then(|| "07".to_string())
+    })
+    .err()
+    .expect("short seed");
+    assert_eq!(
+        err,
+        "PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32 must be a 64-char hex string (32 bytes)"
+    );
+}
+
+#[test]
+fn worker_keys_from_different_seeds_decrypt_independently() {
+    let email_blob = "Subject: hi\r\n\r\nbody\r\n";
+    let context = serde_json::json!({ "account_id": "alice.testnet" });
+    let envelope = encrypt_email(email_blob, &context);
+
+    let other = WorkerKeys::from_seed([8u8; 32]).expect("other keys");
+    assert_ne!(get_worker_public_key(&other), get_worker_public_key(&test_worker_keys()));
+    assert!(decrypt_encrypted_email(&other, &envelope, &context).is_err());
+    assert_eq!(
+        decrypt_encrypted_email(&test_worker_keys(), &envelope, &context).as_deref(),
+        Ok(email_blob)
+    );
+}
+
+#[test]
+fn canonical_json_sorts_keys_at_every_level() {
+    let reordered: serde_json::Value =
+        serde_json::from_str(r#"{"b":{"y":2,"x":1},"a":[{"d":4,"c":3}]}"#).unwrap();
+    assert_eq!(canonical_json(&reordered), r#"{"a":[{"c":3,"d":4}],"b":{"x":1,"y":2}}"#);
+}
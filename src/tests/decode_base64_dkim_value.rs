@@ -0,0 +1,61 @@
+use crate::api::{handle_request, RequestType};
+use crate::parsers::{extract_header_value, parse_dkim_tags};
+
+fn decode(value: &str, tag: &str) -> serde_json::Value {
+    let request = RequestType {
+        method: "decode-base64-dkim-value".to_string(),
+        args: serde_json::json!({ "value": value, "tag": tag }),
+    };
+    let response = handle_request(request);
+    assert_eq!(response.method, "decode-base64-dkim-value");
+    response.response
+}
+
+fn gmail_dkim_tag(tag: &str) -> String {
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let dkim_value = extract_header_value(email_blob, "DKIM-Signature").expect("DKIM-Signature header");
+    parse_dkim_tags(&dkim_value)
+        .get(tag)
+        .cloned()
+        .expect("tag present in DKIM-Signature")
+}
+
+#[test]
+fn decodes_gmail_body_hash() {
+    let response = decode(&gmail_dkim_tag("bh"), "bh");
+
+    assert_eq!(response["tag"], "bh");
+    assert_eq!(response["decoded_b64_length"], 44);
+    assert_eq!(response["decoded_bytes_length"], 32);
+    assert_eq!(response["hex_preview"], "0f26cd80a51400c6c399758cbea5395d");
+}
+
+#[test]
+fn decodes_folded_gmail_signature() {
+    let b = gmail_dkim_tag("b");
+    assert!(b.chars().any(char::is_whitespace), "fixture b= value is folded");
+
+    let response = decode(&b, "b");
+
+    assert_eq!(response["tag"], "b");
+    // 2048-bit RSA signature.
+    assert_eq!(response["decoded_bytes_length"], 256);
+    assert_eq!(response["decoded_b64_length"], 344);
+    assert_eq!(response["hex_preview"].as_str().unwrap().len(), 32);
+    assert!(response["hex_preview"].as_str().unwrap().starts_with("736ecb"));
+}
+
+#[test]
+fn rejects_invalid_base64_and_unknown_tags() {
+    let response = decode("not base64!", "bh");
+    assert!(response["error"]
+        .as_str()
+        .unwrap_or_default()
+        .starts_with("invalid base64 in bh= value"));
+
+    let response = decode("AAAA", "h");
+    assert_eq!(
+        response["error"],
+        "unsupported DKIM tag: h (expected b or bh)"
+    );
+}
@@ -0,0 +1,130 @@
+use crate::api::{handle_request, RequestType};
+use crate::dns::{
+    clear_dns_stubs, fetch_txt_records, set_dns_cname_stub, set_dns_stub, MAX_CNAME_HOPS,
+};
+
+pub(crate) const GMAIL_DKIM_NAME: &str = "20230601._domainkey.gmail.com";
+pub(crate) const GMAIL_DKIM_RECORD: &str = "v=DKIM1; k=rsa; p=MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB";
+
+/// Stub the real Gmail DKIM key used to sign the `gmail_reset_full.eml` fixture.
+pub(crate) fn stub_gmail_dns() {
+    set_dns_stub(GMAIL_DKIM_NAME, vec![GMAIL_DKIM_RECORD.to_string()]);
+}
+
+pub(crate) const TEST_KEY_DKIM_NAME: &str = "test._domainkey.example.com";
+
+/// DKIM record for `test_dkim_private_key.pem`, which signs the synthetic
+/// `d=example.com; s=test` fixtures.
+pub(crate) fn test_key_dkim_record() -> String {
+    use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey};
+
+    let pem = include_str!("../../email-dkim-verifier-contract/tests/data/test_dkim_private_key.pem");
+    let spki = rsa::RsaPrivateKey::from_pkcs8_pem(pem)
+        .expect("test key")
+        .to_public_key()
+        .to_public_key_der()
+        .expect("encode public key");
+    format!("v=DKIM1; k=rsa; p={}", base64::encode(spki.as_bytes()))
+}
+
+#[test]
+fn dns_stub_returns_registered_records_only() {
+    clear_dns_stubs();
+    set_dns_stub("s1._domainkey.example.com", vec!["v=DKIM1; p=abc".to_string()]);
+
+    assert_eq!(
+        fetch_txt_records("S1._domainkey.Example.com").expect("stubbed records"),
+        vec!["v=DKIM1; p=abc".to_string()]
+    );
+
+    let err = fetch_txt_records("s2._domainkey.example.com")
+        .expect_err("unregistered name should fail");
+    assert!(err.starts_with("DnsStubNotFound"), "{err}");
+
+    clear_dns_stubs();
+    assert!(fetch_txt_records("s1._domainkey.example.com").is_err());
+}
+
+#[test]
+fn dns_lookup_reports_missing_stub_as_error() {
+    clear_dns_stubs();
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+    let response = handle_request(RequestType {
+        method: "get-dns-records".to_string(),
+        args: serde_json::json!({ "email_blob": email_blob }),
+    });
+
+    assert_eq!(response.response["name"], GMAIL_DKIM_NAME);
+    assert!(response.response["records"].as_array().unwrap().is_empty());
+    let error = response.response["error"].as_str().unwrap_or_default();
+    assert!(error.starts_with("DnsStubNotFound"), "{error}");
+}
+
+#[test]
+fn dns_lookup_returns_stubbed_gmail_record() {
+    stub_gmail_dns();
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+    let response = handle_request(RequestType {
+        method: "get-dns-records".to_string(),
+        args: serde_json::json!({ "email_blob": email_blob }),
+    });
+
+    assert_eq!(response.response["selector"], "20230601");
+    assert_eq!(response.response["domain"], "gmail.com");
+    assert_eq!(response.response["records"][0], GMAIL_DKIM_RECORD);
+    assert_eq!(
+        response.response["keys"],
+        serde_json::json!([{
+            "selector": "20230601",
+            "domain": "gmail.com",
+            "record": GMAIL_DKIM_RECORD,
+        }])
+    );
+    assert!(response.response["error"].is_null());
+}
+
+#[test]
+fn cname_delegation_is_followed_to_the_target_txt_record() {
+    clear_dns_stubs();
+    // gmail.com delegating its selector to a provider zone, one hop away.
+    let target = "20230601.dkim.provider.example";
+    set_dns_cname_stub(GMAIL_DKIM_NAME, target);
+    set_dns_stub(target, vec![GMAIL_DKIM_RECORD.to_string()]);
+
+    assert_eq!(
+        fetch_txt_records(GMAIL_DKIM_NAME).expect("delegated records"),
+        vec![GMAIL_DKIM_RECORD.to_string()]
+    );
+
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let response = handle_request(RequestType {
+        method: "get-dns-records".to_string(),
+        args: serde_json::json!({ "email_blob": email_blob }),
+    });
+    assert_eq!(response.response["name"], GMAIL_DKIM_NAME);
+    assert_eq!(response.response["records"], serde_json::json!([GMAIL_DKIM_RECORD]));
+    assert_eq!(response.response["error"], serde_json::Value::Null);
+}
+
+#[test]
+fn cname_chains_are_capped() {
+    clear_dns_stubs();
+    set_dns_cname_stub("a._domainkey.example.com", "b._domainkey.example.com");
+    set_dns_cname_stub("b._domainkey.example.com", "a._domainkey.example.com");
+
+    let err = fetch_txt_records("a._domainkey.example.com").expect_err("CNAME loop");
+    assert!(err.contains(&format!("longer than {MAX_CNAME_HOPS} hops")), "{err}");
+
+    // A chain exactly MAX_CNAME_HOPS long still resolves.
+    clear_dns_stubs();
+    for hop in 0..MAX_CNAME_HOPS {
+        set_dns_cname_stub(&format!("hop{hop}.example.com"), &format!("hop{}.example.com", hop + 1));
+    }
+    set_dns_stub(&format!("hop{MAX_CNAME_HOPS}.example.com"), vec!["v=DKIM1; p=abc".to_string()]);
+    assert_eq!(
+        fetch_txt_records("hop0.example.com").expect("records at the end of the chain"),
+        vec!["v=DKIM1; p=abc".to_string()]
+    );
+}
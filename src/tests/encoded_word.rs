@@ -0,0 +1,107 @@
+use crate::api::{handle_request_with_keys, RequestType};
+use crate::crypto::{encrypt_email_to_public_key, get_worker_public_key};
+use crate::dns::set_dns_stub;
+use crate::encoded_word::decode_encoded_words;
+use crate::parsers::{parse_recover_instruction, parse_recover_request_id};
+use super::crypto::test_worker_keys;
+use super::dns::{test_key_dkim_record, TEST_KEY_DKIM_NAME};
+
+const PUBLIC_KEY: &str = "ed25519:HPHNMfHwmBJSqcArYZ5ptTZpukvFoMtuU8TcV2T7mEEy";
+
+#[test]
+fn plain_text_passes_through() {
+    assert_eq!(decode_encoded_words("recover-ABC alice.testnet"), "recover-ABC alice.testnet");
+    assert_eq!(decode_encoded_words(""), "");
+}
+
+#[test]
+fn q_and_b_words_decode_in_utf8() {
+    assert_eq!(decode_encoded_words("=?UTF-8?Q?caf=C3=A9_cr=C3=A8me?="), "café crème");
+    assert_eq!(decode_encoded_words("=?utf-8?b?Y2Fmw6k=?="), "café");
+    // Unpadded base64 is accepted too.
+    assert_eq!(decode_encoded_words("=?utf-8?B?Y2Fmw6k?="), "café");
+}
+
+#[test]
+fn iso_8859_1_words_convert_to_unicode() {
+    assert_eq!(decode_encoded_words("=?iso-8859-1?Q?R=E9cup=E9ration?="), "Récupération");
+    assert_eq!(decode_encoded_words("=?ISO-8859-1?B?UuljdXDpcmF0aW9u?="), "Récupération");
+    // RFC 2231 language suffix on the charset.
+    assert_eq!(decode_encoded_words("=?iso-8859-1*fr?Q?r=E9cup?="), "récup");
+}
+
+#[test]
+fn utf7_words_decode() {
+    assert_eq!(decode_encoded_words("=?utf-7?Q?R+AOk-cup+AOk-ration?="), "Récupération");
+    assert_eq!(decode_encoded_words("=?UTF-7?Q?5_+IKw_1+-1?="), "5 € 1+1");
+}
+
+#[test]
+fn whitespace_between_adjacent_words_is_dropped() {
+    assert_eq!(
+        decode_encoded_words("=?utf-8?Q?recover-?= =?utf-8?Q?ABC?= tail"),
+        "recover-ABC tail"
+    );
+    assert_eq!(decode_encoded_words("Re: =?utf-8?Q?caf=C3=A9?= ok"), "Re: café ok");
+}
+
+#[test]
+fn malformed_or_unknown_charset_words_are_kept() {
+    assert_eq!(decode_encoded_words("=?x-unknown?Q?abc?="), "=?x-unknown?Q?abc?=");
+    assert_eq!(decode_encoded_words("=?utf-8?X?abc?="), "=?utf-8?X?abc?=");
+    assert_eq!(decode_encoded_words("=?utf-8?Q?unterminated"), "=?utf-8?Q?unterminated");
+    assert_eq!(decode_encoded_words("a =? b"), "a =? b");
+}
+
+#[test]
+fn q_escape_needs_two_hex_digits() {
+    assert_eq!(decode_encoded_words("=?utf-8?Q?a=+Fb?="), "a=+Fb");
+    assert_eq!(decode_encoded_words("=?utf-8?Q?a=-1b?="), "a=-1b");
+    assert_eq!(decode_encoded_words("=?utf-8?Q?a=4Gb?="), "a=4Gb");
+    assert_eq!(decode_encoded_words("=?utf-8?Q?a=4fb?="), "aOb");
+}
+
+#[test]
+fn encoded_recover_subjects_keep_the_account_id() {
+    let latin1 = format!(
+        "=?ISO-8859-1?Q?recover-LAT1_alice.testnet_{PUBLIC_KEY}?= =?ISO-8859-1?Q?_R=E9cup=E9ration?="
+    );
+    let decoded = decode_encoded_words(&latin1);
+    assert_eq!(decoded, format!("recover-LAT1 alice.testnet {PUBLIC_KEY} Récupération"));
+    let (account_id, public_key, _) = parse_recover_instruction(&decoded).expect("recover subject");
+    assert_eq!(account_id, "alice.testnet");
+    assert_eq!(public_key, PUBLIC_KEY);
+    assert_eq!(parse_recover_request_id(&decoded).as_deref(), Some("LAT1"));
+
+    let utf7 = format!("=?utf-7?Q?recover-UTF7_bob.testnet_{PUBLIC_KEY}_R+AOk-cup+AOk-ration?=");
+    let (account_id, public_key, _) =
+        parse_recover_instruction(&decode_encoded_words(&utf7)).expect("recover subject");
+    assert_eq!(account_id, "bob.testnet");
+    assert_eq!(public_key, PUBLIC_KEY);
+}
+
+#[test]
+fn encrypted_flow_decodes_encoded_word_subject() {
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![test_key_dkim_record()]);
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/encoded_word_subject.eml");
+    let context = serde_json::json!({
+        "account_id": "alice.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "alice.testnet"
+    });
+    let keys = test_worker_keys();
+    let envelope = encrypt_email_to_public_key(email, &get_worker_public_key(&keys), &context, 1)
+        .expect("encrypts email");
+
+    let request = RequestType {
+        method: "verify-encrypted-email".to_string(),
+        args: serde_json::json!({ "encrypted_email_blob": envelope, "context": context }),
+    };
+    let response = handle_request_with_keys(request, Ok(&keys)).response;
+    assert_eq!(response["error"], serde_json::Value::Null);
+    assert_eq!(response["verified"], true);
+    assert_eq!(response["account_id"], "alice.testnet");
+    assert_eq!(response["new_public_key"], PUBLIC_KEY);
+    assert_eq!(response["request_id"], "QLATIN");
+}
@@ -0,0 +1,86 @@
+use crate::error_code::VerifyErrorCode;
+use std::collections::HashMap;
+
+/// The documented string for every code (src/README.md, "Error codes").
+const DOCUMENTED: [(VerifyErrorCode, &str); 21] = [
+    (VerifyErrorCode::InvalidContextShape, "invalid_context_shape"),
+    (VerifyErrorCode::RequestIdMismatch, "request_id_mismatch"),
+    (VerifyErrorCode::PayerAccountIdContextMismatch, "payer_account_id_context_mismatch"),
+    (VerifyErrorCode::SelectorNotFound, "selector_not_found"),
+    (VerifyErrorCode::SignatureInvalid, "signature_invalid"),
+    (VerifyErrorCode::FromAddressHashMismatch, "from_address_hash_mismatch"),
+    (VerifyErrorCode::AccountIdMismatch, "account_id_mismatch"),
+    (VerifyErrorCode::AmbiguousPublicKey, "ambiguous_public_key"),
+    (VerifyErrorCode::MultipleFromHeaders, "multiple_from_headers"),
+    (VerifyErrorCode::MailingListMessage, "mailing_list_message"),
+    (VerifyErrorCode::RecoveryExpired, "recovery_expired"),
+    (VerifyErrorCode::OutlayerExecutionFailed, "outlayer_execution_failed"),
+    (VerifyErrorCode::InvalidWorkerResponse, "invalid_worker_response"),
+    (VerifyErrorCode::InvalidVerifyResponse, "invalid_verify_response"),
+    (VerifyErrorCode::InvalidDnsResponse, "invalid_dns_response"),
+    (VerifyErrorCode::UnexpectedWorkerMethod, "unexpected_worker_method"),
+    (VerifyErrorCode::DnsError, "dns_error"),
+    (VerifyErrorCode::DnsRecordsEmpty, "dns_records_empty"),
+    (VerifyErrorCode::UnsupportedAlgorithm, "unsupported_algorithm"),
+    (VerifyErrorCode::DkimVerificationFailed, "dkim_verification_failed"),
+    (VerifyErrorCode::RateLimited, "rate_limited"),
+];
+
+#[test]
+fn codes_serialize_to_documented_strings() {
+    assert_eq!(DOCUMENTED.len(), VerifyErrorCode::ALL.len());
+    for (code, expected) in DOCUMENTED {
+        assert_eq!(serde_json::to_value(code).unwrap(), expected);
+        assert_eq!(code.as_str(), expected);
+        assert_eq!(code.to_string(), expected);
+        assert_eq!(serde_json::from_value::<VerifyErrorCode>(expected.into()).unwrap(), code);
+    }
+}
+
+#[test]
+fn all_lists_every_code_once() {
+    for (i, code) in VerifyErrorCode::ALL.iter().enumerate() {
+        assert!(!VerifyErrorCode::ALL[..i].contains(code), "{code} listed twice");
+    }
+}
+
+#[test]
+fn from_error_reads_code_with_or_without_detail() {
+    assert_eq!(
+        VerifyErrorCode::from_error("selector_not_found"),
+        Some(VerifyErrorCode::SelectorNotFound)
+    );
+    let detailed = VerifyErrorCode::DnsError.with_detail("timeout");
+    assert_eq!(detailed, "dns_error: timeout");
+    assert_eq!(VerifyErrorCode::from_error(&detailed), Some(VerifyErrorCode::DnsError));
+    assert_eq!(VerifyErrorCode::from_error("decryption failed"), None);
+}
+
+/// The contract keeps its own copy of the enum (it derives through
+/// `near_sdk::serde` and `schemars`). Read its `ALL` list and `as_str` arms
+/// from source and check they serialize to the same strings, in order.
+#[test]
+fn contract_codes_match_worker_codes() {
+    let source = include_str!("../../email-dkim-verifier-contract/src/error_code.rs");
+    let strings: HashMap<&str, &str> = source
+        .lines()
+        .filter_map(|line| {
+            let (variant, string) =
+                line.trim().strip_prefix("VerifyErrorCode::")?.split_once(" => ")?;
+            Some((variant, string.trim_end_matches(',').trim_matches('"')))
+        })
+        .collect();
+    let all_start = source.find("pub const ALL").expect("contract `ALL` list");
+    let all_end = all_start + source[all_start..].find("];").expect("end of `ALL`");
+    let contract_all: Vec<&str> = source[all_start..all_end]
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("VerifyErrorCode::")?.strip_suffix(','))
+        .map(|variant| strings[variant])
+        .collect();
+
+    let worker_all: Vec<String> = VerifyErrorCode::ALL
+        .iter()
+        .map(|code| serde_json::to_value(code).unwrap().as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(contract_all, worker_all);
+}
@@ -0,0 +1,60 @@
+use crate::api::{handle_request, RequestType};
+use crate::parsers::canonicalize_email_address;
+use serde_json::{json, Value};
+
+/// `input<TAB>canonical` pairs the contract's `canonicalize_email_address` is
+/// tested against too, so both copies must agree byte for byte.
+const VECTORS: &str =
+    include_str!("../../email-dkim-verifier-contract/tests/data/email_address_vectors.tsv");
+
+fn vectors() -> impl Iterator<Item = (&'static str, &'static str)> {
+    VECTORS
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| line.split_once('\t').expect("input<TAB>canonical"))
+}
+
+fn expand(address: &str) -> Value {
+    let response = handle_request(RequestType {
+        method: "expand-email-address".to_string(),
+        args: json!({ "address": address }),
+    });
+    assert_eq!(response.method, "expand-email-address");
+    response.response
+}
+
+#[test]
+fn canonicalization_matches_shared_contract_vectors() {
+    let mut count = 0;
+    for (input, canonical) in vectors() {
+        assert_eq!(canonicalize_email_address(input), canonical, "input: {input:?}");
+        assert_eq!(expand(input)["canonical"], canonical, "input: {input:?}");
+        count += 1;
+    }
+    assert!(count >= 10, "expected the shared vector file to be loaded");
+}
+
+#[test]
+fn expanded_address_splits_local_and_domain() {
+    let response = expand("\"Doe, Jane\" <Jane.Doe+tag@Sub.Example.co.uk>");
+    assert_eq!(response["canonical"], "jane.doe+tag@sub.example.co.uk");
+    assert_eq!(response["local"], "jane.doe+tag");
+    assert_eq!(response["domain"], "sub.example.co.uk");
+}
+
+#[test]
+fn address_without_at_sign_has_empty_domain() {
+    let response = expand("Grace");
+    assert_eq!(response["canonical"], "grace");
+    assert_eq!(response["local"], "grace");
+    assert_eq!(response["domain"], "");
+
+    let response = handle_request(RequestType {
+        method: "expand-email-address".to_string(),
+        args: json!({}),
+    });
+    assert!(response.response["error"]
+        .as_str()
+        .unwrap_or_default()
+        .starts_with("invalid expand-email-address args"));
+}
@@ -0,0 +1,90 @@
+use crate::api::{handle_request, RequestType};
+use crate::parsers::Canonicalization;
+use crate::verify_dkim::canonicalized_signed_headers;
+use serde_json::{json, Value};
+
+const GMAIL_EMAIL: &str =
+    include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+fn extract_dkim_signed_headers(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "extract-dkim-signed-headers".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "extract-dkim-signed-headers");
+    response.response
+}
+
+fn field<'a>(fields: &'a [Value], name: &str) -> &'a Value {
+    fields
+        .iter()
+        .find(|f| f["name"].as_str().unwrap().eq_ignore_ascii_case(name))
+        .unwrap_or_else(|| panic!("no {name} entry"))
+}
+
+#[test]
+fn gmail_fields_rebuild_the_hashed_header_data() {
+    let response = extract_dkim_signed_headers(json!({ "email_blob": GMAIL_EMAIL }));
+    let fields = response["signed_headers"].as_array().expect("signed_headers");
+
+    // Every `h=` name present in the message, in `h=` order, then the signature.
+    let names: Vec<String> = fields
+        .iter()
+        .map(|f| f["name"].as_str().unwrap().to_ascii_lowercase())
+        .collect();
+    assert_eq!(
+        names,
+        ["to", "subject", "message-id", "date", "from", "mime-version", "dkim-signature"]
+    );
+
+    let mut rebuilt = String::new();
+    for (i, f) in fields.iter().enumerate() {
+        rebuilt.push_str(&names[i]);
+        rebuilt.push(':');
+        rebuilt.push_str(f["canonical_value"].as_str().unwrap());
+        if i + 1 < fields.len() {
+            rebuilt.push_str("\r\n");
+        }
+    }
+    let (expected, _) = canonicalized_signed_headers(GMAIL_EMAIL, 0, Canonicalization::Relaxed)
+        .expect("Gmail fixture has a DKIM-Signature");
+    assert_eq!(rebuilt, expected);
+}
+
+#[test]
+fn raw_and_canonical_values_differ_only_by_relaxed_rules() {
+    let response = extract_dkim_signed_headers(json!({ "email_blob": GMAIL_EMAIL }));
+    let fields = response["signed_headers"].as_array().unwrap();
+
+    let from = field(fields, "from");
+    assert_eq!(from["raw_value"], " Pta <n6378056@gmail.com>");
+    assert_eq!(from["canonical_value"], "Pta <n6378056@gmail.com>");
+}
+
+#[test]
+fn dkim_signature_entry_has_cleared_b_value() {
+    let response = extract_dkim_signed_headers(json!({ "email_blob": GMAIL_EMAIL }));
+    let fields = response["signed_headers"].as_array().unwrap();
+    let dkim = fields.last().unwrap();
+    assert_eq!(dkim["name"], "DKIM-Signature");
+    for key in ["raw_value", "canonical_value"] {
+        let value = dkim[key].as_str().unwrap();
+        assert!(value.trim_end().ends_with("b="), "{key}: {value}");
+        assert!(value.contains("bh=DybNgKUUAMbDmXWMvqU5XUFnq2CTnP980Z2v48D+cIc="));
+    }
+}
+
+#[test]
+fn missing_signature_or_args_is_an_error() {
+    let response = extract_dkim_signed_headers(json!({
+        "email_blob": GMAIL_EMAIL,
+        "dkim_index": 1,
+    }));
+    assert_eq!(response["error"], "no DKIM-Signature header at index 1");
+
+    let response = extract_dkim_signed_headers(json!({}));
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid extract-dkim-signed-headers args"));
+}
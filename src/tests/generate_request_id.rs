@@ -0,0 +1,40 @@
+use crate::api::{handle_request, RequestType};
+use crate::parsers::parse_recover_request_id;
+use std::collections::HashSet;
+
+fn generate_request_id() -> String {
+    let response = handle_request(RequestType {
+        method: "generate-request-id".to_string(),
+        args: serde_json::json!({}),
+    });
+    assert_eq!(response.method, "generate-request-id");
+    response.response["request_id"]
+        .as_str()
+        .expect("request_id string")
+        .to_string()
+}
+
+#[test]
+fn request_id_is_16_lowercase_hex_chars() {
+    let request_id = generate_request_id();
+    assert_eq!(request_id.len(), 16, "{request_id}");
+    assert!(
+        request_id
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)),
+        "{request_id}"
+    );
+}
+
+#[test]
+fn request_id_round_trips_through_recovery_subject() {
+    let request_id = generate_request_id();
+    let subject = format!("recover-{request_id} alice.testnet ed25519:NEWKEY");
+    assert_eq!(parse_recover_request_id(&subject), Some(request_id));
+}
+
+#[test]
+fn consecutive_request_ids_are_unique() {
+    let ids: HashSet<String> = (0..100).map(|_| generate_request_id()).collect();
+    assert_eq!(ids.len(), 100);
+}
@@ -0,0 +1,79 @@
+use crate::api::{handle_request, RequestType};
+use serde_json::{json, Value};
+
+const GMAIL_EMAIL: &str =
+    include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+/// Relaxed header data for the Gmail fixture's signature, worked out by hand
+/// from its `h=` list (bottom-up selection, absent headers skipped).
+const GMAIL_CANONICALIZED_HEADERS: &str = "to:recover@web3authn.org\r\n\
+subject:recover-123abc kerp30.w3a-v1.testnet ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm\r\n\
+message-id:<CAHUSCDST_Dd-+71+suCndVo0kLepS4yW7Na4fE7dj3QoDU67BA@mail.gmail.com>\r\n\
+date:Tue, 9 Dec 2025 17:13:23 +0900\r\n\
+from:Pta <n6378056@gmail.com>\r\n\
+mime-version:1.0\r\n\
+dkim-signature:v=1; a=rsa-sha256; c=relaxed/relaxed; d=gmail.com; s=20230601; t=1765268018; \
+x=1765872818; darn=web3authn.org; h=to:subject:message-id:date:from:mime-version:from:to:cc:subject \
+:date:message-id:reply-to; bh=DybNgKUUAMbDmXWMvqU5XUFnq2CTnP980Z2v48D+cIc=; b=";
+
+fn get_canonicalized_headers(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "get-canonicalized-headers".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "get-canonicalized-headers");
+    response.response
+}
+
+#[test]
+fn relaxed_headers_match_gmail_fixture_byte_for_byte() {
+    let response = get_canonicalized_headers(json!({
+        "email_blob": GMAIL_EMAIL,
+        "dkim_index": 0,
+        "canonicalization": "relaxed",
+    }));
+
+    assert_eq!(response["canonicalized"], GMAIL_CANONICALIZED_HEADERS);
+    assert_eq!(
+        response["data_hash_hex"],
+        "b630267f901675020b08937bd1a58d85dcc94e02b2da89e1520d36b33f5b9dc6"
+    );
+    let b = response["b_b64_clean"].as_str().expect("b= value");
+    assert!(b.starts_with("c27LHVyH7CNaLPKYoX0puatQi+DxPwr31JpP0So9Z0D0"), "{b}");
+    assert!(b.ends_with("GgGSL2oyI9aIuzw=="), "{b}");
+    assert!(!b.contains(char::is_whitespace));
+}
+
+#[test]
+fn simple_headers_keep_original_names_and_spacing() {
+    let response = get_canonicalized_headers(json!({
+        "email_blob": GMAIL_EMAIL,
+        "dkim_index": 0,
+        "canonicalization": "simple",
+    }));
+
+    let canonicalized = response["canonicalized"].as_str().expect("canonicalized");
+    assert!(canonicalized.starts_with("To: recover@web3authn.org\r\n"));
+    assert!(canonicalized.contains("\r\nFrom: Pta <n6378056@gmail.com>\r\n"));
+    assert!(canonicalized.contains("\r\nDKIM-Signature: v=1; a=rsa-sha256;"));
+    assert!(canonicalized.ends_with("b="));
+}
+
+#[test]
+fn bad_index_and_canonicalization_are_errors() {
+    let response = get_canonicalized_headers(json!({
+        "email_blob": GMAIL_EMAIL,
+        "dkim_index": 1,
+        "canonicalization": "relaxed",
+    }));
+    assert_eq!(response["error"], "no DKIM-Signature header at index 1");
+
+    let response = get_canonicalized_headers(json!({
+        "email_blob": GMAIL_EMAIL,
+        "canonicalization": "nowsp",
+    }));
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("unsupported header canonicalization: nowsp"));
+}
@@ -0,0 +1,76 @@
+use crate::api::{handle_request, RequestType};
+use crate::parsers::parse_dkim_canonicalization;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+fn get_capabilities() -> Value {
+    let response = handle_request(RequestType {
+        method: "get-capabilities".to_string(),
+        args: json!({}),
+    });
+    assert_eq!(response.method, "get-capabilities");
+    response.response
+}
+
+fn methods(capabilities: &Value) -> Vec<&str> {
+    capabilities["methods"]
+        .as_array()
+        .expect("methods array")
+        .iter()
+        .map(|m| m.as_str().expect("method name"))
+        .collect()
+}
+
+#[test]
+fn lists_core_methods() {
+    let capabilities = get_capabilities();
+    let methods = methods(&capabilities);
+    for method in ["get-dns-records", "verify-encrypted-email", "get-public-key"] {
+        assert!(methods.contains(&method), "{method} missing from {methods:?}");
+    }
+    assert!(methods.contains(&"get-capabilities"));
+    assert_eq!(capabilities["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(capabilities["max_email_bytes"], crate::MAX_INPUT_BYTES);
+}
+
+#[test]
+fn every_listed_method_is_dispatched() {
+    let capabilities = get_capabilities();
+    let methods = methods(&capabilities);
+    assert_eq!(methods.iter().collect::<HashSet<_>>().len(), methods.len());
+
+    for method in methods {
+        // Null args stop methods that take arguments before any DNS lookup.
+        let response = handle_request(RequestType {
+            method: method.to_string(),
+            args: Value::Null,
+        });
+        let error = response.response["error"].as_str().unwrap_or_default();
+        assert!(!error.starts_with("unknown method"), "{method}: {error}");
+    }
+
+    let response = handle_request(RequestType {
+        method: "no-such-method".to_string(),
+        args: Value::Null,
+    });
+    assert!(response.response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("unknown method"));
+}
+
+#[test]
+fn reports_verify_path_algorithms_and_canonicalizations() {
+    let capabilities = get_capabilities();
+    assert_eq!(capabilities["supported_algorithms"], json!(["rsa-sha256"]));
+
+    let canonicalizations = capabilities["supported_canonicalizations"]
+        .as_array()
+        .expect("canonicalizations array");
+    assert_eq!(canonicalizations.len(), 2);
+    for name in canonicalizations {
+        let name = name.as_str().unwrap();
+        let c = format!("{name}/{name}");
+        assert!(parse_dkim_canonicalization(Some(&c)).is_some(), "{c}");
+    }
+}
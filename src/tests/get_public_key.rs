@@ -0,0 +1,55 @@
+use crate::api::{handle_request_with_keys, RequestType};
+use crate::crypto::{get_worker_public_key, worker_signing_key, WorkerKeys};
+use super::crypto::test_worker_keys;
+use base64;
+use sha2::{Digest, Sha256};
+
+fn get_public_key(keys: Result<&WorkerKeys, &str>) -> serde_json::Value {
+    let request = RequestType {
+        method: "get-public-key".to_string(),
+        args: serde_json::json!({}),
+    };
+    let response = handle_request_with_keys(request, keys);
+    assert_eq!(response.method, "get-public-key");
+    response.response
+}
+
+#[test]
+fn get_public_key_reports_fingerprint_and_metadata() {
+    let keys = test_worker_keys();
+    let response = get_public_key(Ok(&keys));
+
+    let worker_pk = get_worker_public_key(&keys);
+    assert_eq!(response["public_key"], worker_pk);
+    assert_eq!(response["key_algorithm"], "x25519");
+    assert_eq!(response["key_version"], 1);
+
+    let pk_bytes = base64::decode(&worker_pk).expect("worker public key is base64");
+    let expected: String = Sha256::digest(&pk_bytes).iter().map(|b| format!("{b:02x}")).collect();
+    assert_eq!(response["key_fingerprint_sha256"], expected);
+    assert_eq!(expected.len(), 64);
+
+    let signing_pk = worker_signing_key(&keys).unwrap().verifying_key().to_bytes();
+    assert_eq!(response["signing_public_key"], base64::encode(signing_pk));
+}
+
+#[test]
+fn signing_public_key_follows_worker_seed() {
+    let response = get_public_key(Ok(&test_worker_keys()));
+    let other = get_public_key(Ok(&WorkerKeys::from_seed([8u8; 32]).unwrap()));
+    assert_ne!(response["signing_public_key"], other["signing_public_key"]);
+}
+
+#[test]
+fn get_public_key_reports_rotated_key_version() {
+    let keys = test_worker_keys().with_key_version(2);
+    let response = get_public_key(Ok(&keys));
+    assert_eq!(response["key_version"], 2);
+}
+
+#[test]
+fn get_public_key_without_keys_is_an_error() {
+    let response = get_public_key(Err("Secrets Not Found"));
+    assert_eq!(response["error"], "Secrets Not Found");
+    assert!(response.get("public_key").is_none());
+}
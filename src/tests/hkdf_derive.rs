@@ -0,0 +1,71 @@
+use crate::api::{handle_request, RequestType};
+use crate::crypto::derive_envelope_key;
+use serde_json::{json, Value};
+
+fn hkdf_derive(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "hkdf-derive".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "hkdf-derive");
+    response.response
+}
+
+#[test]
+fn hkdf_derive_matches_rfc5869_vector_without_salt() {
+    // RFC 5869 test case 3: no salt, empty info.
+    let response = hkdf_derive(json!({
+        "ikm_hex": "0b".repeat(22),
+        "info": "",
+        "output_length": 42,
+    }));
+    assert_eq!(
+        response["okm_hex"],
+        "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"
+    );
+}
+
+#[test]
+fn hkdf_derive_matches_v1_envelope_key() {
+    let shared_secret = [7u8; 32];
+    let expected = derive_envelope_key(&shared_secret, 1, &json!({})).unwrap();
+
+    let response = hkdf_derive(json!({
+        "ikm_hex": "07".repeat(32),
+        "info": "email-dkim-encryption-key",
+        "output_length": 32,
+    }));
+    let expected_hex: String = expected.iter().map(|b| format!("{b:02x}")).collect();
+    assert_eq!(response["okm_hex"], expected_hex);
+}
+
+#[test]
+fn hkdf_derive_caps_output_length() {
+    let args = |output_length: usize| {
+        json!({ "ikm_hex": "00", "info": "x", "output_length": output_length })
+    };
+
+    let response = hkdf_derive(args(64));
+    assert_eq!(response["okm_hex"].as_str().unwrap().len(), 128);
+
+    for output_length in [0, 65] {
+        let response = hkdf_derive(args(output_length));
+        assert_eq!(response["okm_hex"], Value::Null);
+        assert_eq!(response["error"], "output_length must be between 1 and 64");
+    }
+}
+
+#[test]
+fn hkdf_derive_rejects_invalid_args() {
+    let response = hkdf_derive(json!({ "ikm_hex": "zz", "info": "", "output_length": 32 }));
+    assert_eq!(response["error"], "invalid ikm_hex: invalid hex digit");
+
+    let response = hkdf_derive(json!({ "ikm_hex": "abc", "info": "", "output_length": 32 }));
+    assert_eq!(response["error"], "invalid ikm_hex: hex string must have an even length");
+
+    let response = hkdf_derive(json!({ "ikm_hex": "00" }));
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid hkdf-derive args:"));
+}
@@ -0,0 +1,49 @@
+use crate::api::{handle_request, RequestType};
+use super::crypto::encrypt_email;
+use serde_json::{json, Value};
+
+fn inspect(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "inspect-encrypted-envelope".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "inspect-encrypted-envelope");
+    response.response
+}
+
+#[test]
+fn inspect_reports_envelope_metadata_without_decrypting() {
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let context = json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet"
+    });
+    let envelope = encrypt_email(email_blob, &context);
+
+    let response = inspect(json!({ "encrypted_email_blob": envelope }));
+    assert_eq!(response["error"], Value::Null);
+    assert_eq!(response["version"], envelope.version);
+    assert_eq!(response["ephemeral_pub_b64"], envelope.ephemeral_pub);
+    assert_eq!(response["nonce_b64"], envelope.nonce);
+    assert_eq!(response["cipher"], "chacha20-poly1305");
+
+    let ciphertext_len = base64::decode(&envelope.ciphertext).unwrap().len();
+    assert_eq!(response["ciphertext_len"], ciphertext_len);
+    // ChaCha20-Poly1305 appends a 16-byte tag to the plaintext.
+    assert_eq!(ciphertext_len, email_blob.len() + 16);
+}
+
+#[test]
+fn inspect_rejects_invalid_ciphertext_base64() {
+    let response = inspect(json!({
+        "encrypted_email_blob": {
+            "version": 1,
+            "ephemeral_pub": "",
+            "nonce": "",
+            "ciphertext": "not base64!",
+        }
+    }));
+    let error = response["error"].as_str().unwrap_or_default();
+    assert!(error.starts_with("invalid ciphertext base64"), "{error}");
+}
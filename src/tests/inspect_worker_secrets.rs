@@ -0,0 +1,80 @@
+use crate::api::{handle_inspect_worker_secrets, handle_request, RequestType};
+use serde_json::{json, Value};
+
+fn inspect_worker_secrets(vars: &[(&str, &str)]) -> Value {
+    let response = handle_inspect_worker_secrets(|name| {
+        vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string())
+    });
+    assert_eq!(response.method, "inspect-worker-secrets");
+    response.response
+}
+
+#[test]
+fn no_secrets_configured() {
+    assert_eq!(
+        inspect_worker_secrets(&[]),
+        json!({
+            "protected_secret_present": false,
+            "fallback_secret_present": false,
+            "active_secret_source": null,
+            "public_key_derivable": false,
+        })
+    );
+}
+
+#[test]
+fn protected_secret_wins_over_fallback() {
+    let seed = "07".repeat(32);
+    let response = inspect_worker_secrets(&[
+        ("PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32", &seed),
+        ("OUTLAYER_WORKER_SK_SEED_HEX32", &seed),
+    ]);
+    assert_eq!(response["protected_secret_present"], true);
+    assert_eq!(response["fallback_secret_present"], true);
+    assert_eq!(response["active_secret_source"], "PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32");
+    assert_eq!(response["public_key_derivable"], true);
+    assert!(!response.to_string().contains(&seed));
+}
+
+#[test]
+fn fallback_secret_alone_is_active() {
+    let response = inspect_worker_secrets(&[("OUTLAYER_WORKER_SK_SEED_HEX32", &"08".repeat(32))]);
+    assert_eq!(response["protected_secret_present"], false);
+    assert_eq!(response["fallback_secret_present"], true);
+    assert_eq!(response["active_secret_source"], "OUTLAYER_WORKER_SK_SEED_HEX32");
+    assert_eq!(response["public_key_derivable"], true);
+}
+
+#[test]
+fn malformed_active_secret_is_present_but_not_derivable() {
+    // The protected seed is used even when malformed, so the valid fallback
+    // does not help.
+    let response = inspect_worker_secrets(&[
+        ("PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32", "07"),
+        ("OUTLAYER_WORKER_SK_SEED_HEX32", &"08".repeat(32)),
+    ]);
+    assert_eq!(response["protected_secret_present"], true);
+    assert_eq!(response["active_secret_source"], "PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32");
+    assert_eq!(response["public_key_derivable"], false);
+    assert!(!response.to_string().contains(&"08".repeat(32)));
+}
+
+#[test]
+fn dispatched_method_reports_presence_flags_only() {
+    let response = handle_request(RequestType {
+        method: "inspect-worker-secrets".to_string(),
+        args: json!({}),
+    });
+    let fields = response.response.as_object().expect("response object");
+    let mut keys: Vec<&str> = fields.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    assert_eq!(
+        keys,
+        [
+            "active_secret_source",
+            "fallback_secret_present",
+            "protected_secret_present",
+            "public_key_derivable"
+        ]
+    );
+}
@@ -0,0 +1,72 @@
+use crate::api::{handle_request, RequestType};
+use serde_json::{json, Value};
+
+const MULTI_SIGNATURE_EMAIL: &str = "DKIM-Signature: v=1; a=rsa-sha256; d=example.com; s=s2;\r\n\
+\th=from:subject; bh=abc=; b=def=\r\n\
+DKIM-Signature: v=1; a=rsa-sha256; d=mail.example.net; s=s1; h=from; bh=ghi=; b=jkl=\r\n\
+From: Alice <alice@example.com>\r\n\
+Subject: hello\r\n\
+dkim-signature: v=1; a=rsa-sha1; d=legacy.example; s=old; h=from; bh=mno=; b=pqr=\r\n\
+\r\n\
+body\r\n";
+
+fn list_headers(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "list-email-headers".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "list-email-headers");
+    response.response
+}
+
+#[test]
+fn lists_headers_in_order_with_indices() {
+    let response = list_headers(json!({ "email_blob": MULTI_SIGNATURE_EMAIL }));
+
+    let headers = response["headers"].as_array().expect("headers array");
+    let names: Vec<&str> = headers.iter().map(|h| h["name"].as_str().unwrap()).collect();
+    assert_eq!(
+        names,
+        ["DKIM-Signature", "DKIM-Signature", "From", "Subject", "dkim-signature"]
+    );
+    for (i, header) in headers.iter().enumerate() {
+        assert_eq!(header["index"], i);
+    }
+    assert_eq!(headers[3]["value"], " hello");
+}
+
+#[test]
+fn folded_values_are_returned_raw() {
+    let response = list_headers(json!({ "email_blob": MULTI_SIGNATURE_EMAIL }));
+
+    assert_eq!(
+        response["headers"][0]["value"],
+        " v=1; a=rsa-sha256; d=example.com; s=s2;\r\n\th=from:subject; bh=abc=; b=def="
+    );
+}
+
+#[test]
+fn reports_every_dkim_signature_index_case_insensitively() {
+    let response = list_headers(json!({ "email_blob": MULTI_SIGNATURE_EMAIL }));
+    assert_eq!(response["dkim_signature_indices"], json!([0, 1, 4]));
+
+    let gmail = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let response = list_headers(json!({ "email_blob": gmail }));
+    let indices = response["dkim_signature_indices"].as_array().expect("indices");
+    assert_eq!(indices.len(), 1);
+    let idx = indices[0].as_u64().unwrap() as usize;
+    assert_eq!(response["headers"][idx]["name"], "DKIM-Signature");
+}
+
+#[test]
+fn email_without_headers_or_bad_args() {
+    let response = list_headers(json!({ "email_blob": "" }));
+    assert_eq!(response["headers"], json!([]));
+    assert_eq!(response["dkim_signature_indices"], json!([]));
+
+    let response = list_headers(json!({}));
+    assert!(response["error"]
+        .as_str()
+        .unwrap_or_default()
+        .starts_with("invalid list-email-headers args"));
+}
@@ -1,2 +1,35 @@
+pub mod aead_keycheck;
+pub mod check_body_hash;
+pub mod check_dkim_dns;
+pub mod check_public_key;
+pub mod check_recipient;
+pub mod compute_body_hash;
+pub mod compute_header_hash;
 pub mod crypto;
+pub mod decode_base64_dkim_value;
+pub mod dns;
+pub mod encoded_word;
+pub mod error_code;
+pub mod expand_email_address;
+pub mod extract_dkim_signed_headers;
+pub mod generate_request_id;
+pub mod get_canonicalized_headers;
+pub mod get_capabilities;
+pub mod get_public_key;
+pub mod hkdf_derive;
+pub mod inspect_encrypted_envelope;
+pub mod inspect_worker_secrets;
+pub mod list_email_headers;
+pub mod parse_recovery_instruction;
+pub mod parse_x_google_dkim_signature;
+pub mod parsers;
+pub mod qp;
+pub mod read_request;
+pub mod reencrypt_email;
+pub mod test_rsa_verify;
+pub mod validate_aead_context;
+pub mod verify_dkim;
 pub mod verify_encrypted_dkim;
+pub mod verify_envelope_integrity;
+pub mod verify_signed_payload;
+pub mod worker_self_test;
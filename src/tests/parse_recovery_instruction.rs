@@ -0,0 +1,117 @@
+use crate::api::{handle_request, RequestType};
+use serde_json::{json, Value};
+
+fn parse_recovery_instruction(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "parse-recovery-instruction".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "parse-recovery-instruction");
+    response.response
+}
+
+#[test]
+fn parses_subject_with_request_id_and_expiry() {
+    let response = parse_recovery_instruction(json!({
+        "subject": "recover-ABC123 alice.testnet ed25519:NEW_KEY exp=1767225600",
+    }));
+    assert_eq!(
+        response,
+        json!({
+            "command": "recover",
+            "request_id": "ABC123",
+            "account_id": "alice.testnet",
+            "new_public_key": "ed25519:NEW_KEY",
+            "expires_at_secs": 1_767_225_600u64,
+            "error": null,
+        })
+    );
+}
+
+#[test]
+fn parses_legacy_subject_without_request_id() {
+    let response =
+        parse_recovery_instruction(json!({ "subject": "recover alice.testnet ed25519:NEW_KEY" }));
+    assert_eq!(response["command"], "recover");
+    assert_eq!(response["request_id"], Value::Null);
+    assert_eq!(response["account_id"], "alice.testnet");
+    assert_eq!(response["new_public_key"], "ed25519:NEW_KEY");
+    assert_eq!(response["expires_at_secs"], Value::Null);
+    assert_eq!(response["error"], Value::Null);
+}
+
+#[test]
+fn decodes_encoded_word_subject() {
+    let response = parse_recovery_instruction(json!({
+        "subject": "=?ISO-8859-1?Q?recover-LAT1_alice.testnet_ed25519:NEWKEY?=",
+    }));
+    assert_eq!(response["request_id"], "LAT1");
+    assert_eq!(response["account_id"], "alice.testnet");
+    assert_eq!(response["new_public_key"], "ed25519:NEWKEY");
+    assert_eq!(response["error"], Value::Null);
+}
+
+#[test]
+fn falls_back_to_body_key() {
+    let response = parse_recovery_instruction(json!({
+        "subject": "recover-ABC123 alice.testnet",
+        "body": "Please recover my account.\r\ned25519:BODY_KEY\r\n",
+    }));
+    assert_eq!(response["request_id"], "ABC123");
+    assert_eq!(response["account_id"], "alice.testnet");
+    assert_eq!(response["new_public_key"], "ed25519:BODY_KEY");
+    assert_eq!(response["error"], Value::Null);
+
+    // An unparseable `exp=` rejects the Subject key, as in verification.
+    let response = parse_recovery_instruction(json!({
+        "subject": "recover-ABC123 alice.testnet ed25519:SUBJECT_KEY exp=soon",
+        "body": "ed25519:BODY_KEY\r\n",
+    }));
+    assert_eq!(response["new_public_key"], "ed25519:BODY_KEY");
+    assert_eq!(response["expires_at_secs"], Value::Null);
+}
+
+#[test]
+fn differing_body_keys_are_ambiguous() {
+    let response = parse_recovery_instruction(json!({
+        "subject": "recover-ABC123 alice.testnet",
+        "body": "ed25519:NEW_KEY\r\n\r\nOn Mon, Bob wrote:\r\n> ed25519:OLD_KEY\r\n",
+    }));
+    // Quoted lines are trimmed of whitespace but not of `>`, so only NEW_KEY counts.
+    assert_eq!(response["new_public_key"], "ed25519:NEW_KEY");
+
+    let response = parse_recovery_instruction(json!({
+        "subject": "recover-ABC123 alice.testnet",
+        "body": "ed25519:NEW_KEY\r\n\r\nOn Mon, Bob wrote:\r\ned25519:OLD_KEY\r\n",
+    }));
+    assert_eq!(response, json!({ "error": "ambiguous_public_key" }));
+}
+
+#[test]
+fn incomplete_instructions_report_an_error() {
+    let response = parse_recovery_instruction(json!({ "subject": "recover-ABC123 alice.testnet" }));
+    assert_eq!(response["account_id"], "alice.testnet");
+    assert_eq!(response["new_public_key"], Value::Null);
+    assert_eq!(response["error"], "missing new_public_key");
+
+    let response = parse_recovery_instruction(json!({ "subject": "recover-ABC123" }));
+    assert_eq!(response["command"], "recover");
+    assert_eq!(response["error"], "missing account_id");
+
+    let response = parse_recovery_instruction(json!({
+        "subject": "Re: hello",
+        "body": "ed25519:NEW_KEY\r\n",
+    }));
+    assert_eq!(response["command"], "");
+    assert_eq!(response["new_public_key"], "ed25519:NEW_KEY");
+    assert_eq!(response["error"], "subject is not a recovery instruction");
+}
+
+#[test]
+fn missing_subject_is_an_error() {
+    let response = parse_recovery_instruction(json!({ "body": "ed25519:NEW_KEY" }));
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid parse-recovery-instruction args"));
+}
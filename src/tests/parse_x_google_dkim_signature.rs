@@ -0,0 +1,58 @@
+use crate::api::{handle_request, RequestType};
+use crate::parsers::{extract_google_dkim_signature, extract_header_value, parse_dkim_tags};
+use serde_json::{json, Value};
+
+const GMAIL_EMAIL: &str =
+    include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+fn parse_x_google_dkim_signature(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "parse-x-google-dkim-signature".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "parse-x-google-dkim-signature");
+    response.response
+}
+
+#[test]
+fn extracts_google_signature_tags_from_gmail_fixture() {
+    let tags = extract_google_dkim_signature(GMAIL_EMAIL).expect("X-Google-DKIM-Signature");
+    assert_eq!(tags["d"], "1e100.net");
+    assert_eq!(tags["s"], "20230601");
+    assert_eq!(tags["a"], "rsa-sha256");
+    assert_eq!(tags["t"], "1765268018");
+    assert_eq!(tags["bh"], "DybNgKUUAMbDmXWMvqU5XUFnq2CTnP980Z2v48D+cIc=");
+
+    // The standard DKIM-Signature is a separate header (d=gmail.com).
+    let dkim = parse_dkim_tags(&extract_header_value(GMAIL_EMAIL, "DKIM-Signature").unwrap());
+    assert_eq!(dkim["d"], "gmail.com");
+}
+
+#[test]
+fn missing_google_signature_is_none() {
+    let email = include_str!("../../email-dkim-verifier-contract/tests/data/empty_body.eml");
+    assert!(extract_google_dkim_signature(email).is_none());
+}
+
+#[test]
+fn method_returns_parsed_tags() {
+    let response = parse_x_google_dkim_signature(json!({ "email_blob": GMAIL_EMAIL }));
+    assert_eq!(response["error"], Value::Null);
+    assert_eq!(response["tags"]["d"], "1e100.net");
+    assert_eq!(response["tags"]["s"], "20230601");
+    // Informational only: nothing is verified.
+    assert_eq!(response["verified"], Value::Null);
+}
+
+#[test]
+fn method_reports_missing_header_and_bad_args() {
+    let email = include_str!("../../email-dkim-verifier-contract/tests/data/empty_body.eml");
+    let response = parse_x_google_dkim_signature(json!({ "email_blob": email }));
+    assert_eq!(response["error"], "no X-Google-DKIM-Signature header");
+
+    let response = parse_x_google_dkim_signature(json!({}));
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid parse-x-google-dkim-signature args:"));
+}
@@ -0,0 +1,390 @@
+use crate::parsers::{
+    canonicalize_body_relaxed, canonicalize_body_simple, canonicalize_headers_relaxed,
+    has_multiple_from_headers, mailing_list_signals, parse_cc_addresses, parse_dkim_canonicalization,
+    parse_dkim_tag_values, parse_dkim_tags, parse_email_timestamp_ms,
+    parse_email_timestamp_ms_with_resent_fallback, parse_from_address,
+    parse_from_address_with_resent_fallback, parse_headers, parse_mime_boundary,
+    parse_recover_instruction, parse_reply_to_address,
+    parse_unambiguous_recover_public_key_from_body, split_headers_body, undo_dot_stuffing_bytes,
+    Canonicalization, DkimTagValue,
+};
+
+#[test]
+fn reply_to_with_display_name_parses_bare_address() {
+    let email = "From: Alice <alice@example.com>\r\n\
+Reply-To: \"Alice (Support)\" <support@example.com>\r\n\
+Subject: hello\r\n\
+\r\n\
+body\r\n";
+
+    assert_eq!(parse_from_address(email), "alice@example.com");
+    assert_eq!(
+        parse_reply_to_address(email).as_deref(),
+        Some("support@example.com")
+    );
+}
+
+#[test]
+fn reply_to_missing_returns_none() {
+    let email = "From: alice@example.com\r\nSubject: hello\r\n\r\nbody\r\n";
+    assert!(parse_reply_to_address(email).is_none());
+}
+
+#[test]
+fn cc_with_multiple_recipients_parses_all_addresses() {
+    let email = concat!(
+        "From: alice@example.com\r\n",
+        "Cc: Bob <bob@example.com>, \"Doe, Carol\" <carol@example.org>,\r\n",
+        " dave@example.net\r\n",
+        "Subject: hello\r\n",
+        "\r\n",
+        "body\r\n",
+    );
+
+    assert_eq!(
+        parse_cc_addresses(email),
+        vec![
+            "bob@example.com".to_string(),
+            "carol@example.org".to_string(),
+            "dave@example.net".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn cc_missing_returns_empty() {
+    let email = "From: alice@example.com\r\nSubject: hello\r\n\r\nbody\r\n";
+    assert!(parse_cc_addresses(email).is_empty());
+}
+
+#[test]
+fn recover_instruction_parses_optional_expiry() {
+    let (account_id, public_key, expires_at_secs) =
+        parse_recover_instruction("recover-ABC alice.testnet ed25519:KEY exp=1767225600")
+            .expect("instruction");
+    assert_eq!(account_id, "alice.testnet");
+    assert_eq!(public_key, "ed25519:KEY");
+    assert_eq!(expires_at_secs, Some(1_767_225_600));
+
+    let (_, _, expires_at_secs) =
+        parse_recover_instruction("recover-ABC alice.testnet ed25519:KEY").expect("instruction");
+    assert!(expires_at_secs.is_none());
+
+    assert!(parse_recover_instruction("recover-ABC alice.testnet ed25519:KEY exp=soon").is_none());
+}
+
+#[test]
+fn resent_from_is_ignored_unless_allowed() {
+    let email = include_str!("../../email-dkim-verifier-contract/tests/data/resent_headers.eml");
+
+    assert_eq!(parse_from_address(email), "");
+    assert_eq!(parse_from_address_with_resent_fallback(email, false), "");
+    assert_eq!(
+        parse_from_address_with_resent_fallback(email, true),
+        "n6378056@gmail.com"
+    );
+    assert_eq!(parse_email_timestamp_ms_with_resent_fallback(email, false, None), None);
+    assert_eq!(
+        parse_email_timestamp_ms_with_resent_fallback(email, true, None),
+        Some(1_763_460_900_000)
+    );
+}
+
+#[test]
+fn empty_from_falls_back_to_resent_from_when_allowed() {
+    let email = "From: \r\nResent-From: Bob <bob@example.com>\r\n\r\nbody\r\n";
+    assert_eq!(parse_from_address_with_resent_fallback(email, true), "bob@example.com");
+}
+
+#[test]
+fn simple_body_canonicalization_matches_rfc6376_appendix_a() {
+    use sha2::{Digest, Sha256};
+
+    let body = "Hi.\r\n\r\nWe lost the game. Are you hungry yet?\r\n\r\nJoe.\r\n\r\n";
+    let canon = canonicalize_body_simple(body);
+    let bh = base64::encode(Sha256::digest(canon.as_bytes()));
+    assert_eq!(bh, "2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=");
+}
+
+fn email_with_date(date: &str) -> String {
+    format!("From: alice@example.com\r\nDate: {date}\r\n\r\nbody\r\n")
+}
+
+#[test]
+fn email_timestamp_rejects_out_of_range_time_fields() {
+    assert_eq!(
+        parse_email_timestamp_ms(&email_with_date("Thu, 1 Jan 2026 23:59:60 +0000"), None),
+        Some(1_767_312_000_000)
+    );
+    assert!(parse_email_timestamp_ms(&email_with_date("Thu, 1 Jan 2026 25:00:00 +0000"), None).is_none());
+    assert!(parse_email_timestamp_ms(&email_with_date("Thu, 1 Jan 2026 12:70:00 +0000"), None).is_none());
+    assert!(parse_email_timestamp_ms(&email_with_date("Thu, 1 Jan 2026 12:00:61 +0000"), None).is_none());
+    assert!(parse_email_timestamp_ms(&email_with_date("Thu, 1 Jan 2026 12:00:00 +2500"), None).is_none());
+    assert!(parse_email_timestamp_ms(&email_with_date("Thu, 1 Jan 2026 12:00:00 +0075"), None).is_none());
+}
+
+#[test]
+fn email_timestamp_rejects_huge_year_and_malformed_offset() {
+    assert!(parse_email_timestamp_ms(&email_with_date("1 Jan 2147483647 00:00:00 +0000"), None).is_none());
+    assert!(parse_email_timestamp_ms(&email_with_date("1 Jan 10000 00:00:00 +0000"), None).is_none());
+    // Non-ASCII offset must not panic on a char boundary.
+    assert!(parse_email_timestamp_ms(&email_with_date("1 Jan 2026 00:00:00 é000"), None).is_none());
+    assert!(parse_email_timestamp_ms(&email_with_date("1 Jan 2026 00:00:00 +0é0"), None).is_none());
+}
+
+#[test]
+fn email_timestamp_rejects_dates_past_upper_bound() {
+    // Now: 2026-01-01T00:00:00Z, with five minutes of tolerance.
+    let bound = Some(1_767_225_600_000 + 5 * 60 * 1000);
+
+    let one_year_ahead = email_with_date("Fri, 1 Jan 2027 00:00:00 +0000");
+    assert!(parse_email_timestamp_ms(&one_year_ahead, bound).is_none());
+    assert_eq!(parse_email_timestamp_ms(&one_year_ahead, None), Some(1_798_761_600_000));
+
+    let six_minutes_ahead = email_with_date("Thu, 1 Jan 2026 00:06:00 +0000");
+    assert!(parse_email_timestamp_ms(&six_minutes_ahead, bound).is_none());
+
+    let four_minutes_ahead = email_with_date("Thu, 1 Jan 2026 00:04:00 +0000");
+    assert_eq!(parse_email_timestamp_ms(&four_minutes_ahead, bound), Some(1_767_225_840_000));
+
+    // The bound applies to the Resent-Date fallback too.
+    let resent = "Resent-Date: Thu, 1 Jan 2026 00:06:00 +0000\r\n\r\nbody\r\n";
+    assert!(parse_email_timestamp_ms_with_resent_fallback(resent, true, bound).is_none());
+}
+
+#[test]
+fn body_with_single_or_repeated_key_is_unambiguous() {
+    let single = "Subject: recover-1 alice.testnet\r\n\r\ned25519:NEW_KEY\r\n";
+    assert_eq!(
+        parse_unambiguous_recover_public_key_from_body(single),
+        Ok(Some("ed25519:NEW_KEY".to_string()))
+    );
+
+    let repeated = "Subject: recover-1 alice.testnet\r\n\r\ned25519:NEW_KEY\r\n\r\ned25519:NEW_KEY\r\n";
+    assert_eq!(
+        parse_unambiguous_recover_public_key_from_body(repeated),
+        Ok(Some("ed25519:NEW_KEY".to_string()))
+    );
+}
+
+#[test]
+fn body_with_differing_keys_is_ambiguous() {
+    // A quoted reply carries the previous key below the new one.
+    let email = "Subject: recover-1 alice.testnet\r\n\r\ned25519:NEW_KEY\r\n\r\nOn Mon, Bob wrote:\r\ned25519:OLD_KEY\r\n";
+    assert_eq!(
+        parse_unambiguous_recover_public_key_from_body(email),
+        Err("ambiguous_public_key".to_string())
+    );
+}
+
+#[test]
+fn mime_boundary_quoted() {
+    assert_eq!(
+        parse_mime_boundary("multipart/alternative; boundary=\"000000000000a1b2c3d4e5f6\"")
+            .as_deref(),
+        Some("000000000000a1b2c3d4e5f6")
+    );
+}
+
+#[test]
+fn mime_boundary_unquoted() {
+    assert_eq!(
+        parse_mime_boundary("multipart/mixed; boundary=simple-boundary").as_deref(),
+        Some("simple-boundary")
+    );
+}
+
+#[test]
+fn mime_boundary_with_special_chars() {
+    assert_eq!(
+        parse_mime_boundary("multipart/mixed; boundary=\"----=_Part_0_1234.5678(a+b),/:?\"")
+            .as_deref(),
+        Some("----=_Part_0_1234.5678(a+b),/:?")
+    );
+    assert_eq!(
+        parse_mime_boundary("multipart/alternative; boundary=----=_NextPart_000_0001").as_deref(),
+        Some("----=_NextPart_000_0001")
+    );
+}
+
+#[test]
+fn mime_boundary_quoted_value_may_contain_spaces_and_semicolons() {
+    assert_eq!(
+        parse_mime_boundary("multipart/mixed; boundary=\"a b; c\"; charset=utf-8").as_deref(),
+        Some("a b; c")
+    );
+}
+
+#[test]
+fn mime_boundary_is_trimmed() {
+    assert_eq!(
+        parse_mime_boundary("multipart/mixed; boundary=  spaced  ").as_deref(),
+        Some("spaced")
+    );
+    assert_eq!(
+        parse_mime_boundary("multipart/mixed; boundary = \"  padded \"").as_deref(),
+        Some("padded")
+    );
+}
+
+#[test]
+fn mime_boundary_missing_returns_none() {
+    assert_eq!(parse_mime_boundary("multipart/mixed; charset=utf-8"), None);
+    assert_eq!(parse_mime_boundary("text/plain"), None);
+    assert_eq!(parse_mime_boundary(""), None);
+}
+
+#[test]
+fn mime_boundary_empty_returns_none() {
+    assert_eq!(parse_mime_boundary("multipart/mixed; boundary=\"\""), None);
+    assert_eq!(parse_mime_boundary("multipart/mixed; boundary="), None);
+}
+
+#[test]
+fn mime_boundary_among_multiple_parameters() {
+    assert_eq!(
+        parse_mime_boundary(
+            "multipart/related; type=\"text/html\"; boundary=\"rel-42\"; start=\"<root@x>\""
+        )
+        .as_deref(),
+        Some("rel-42")
+    );
+}
+
+#[test]
+fn mime_boundary_parameter_name_is_case_insensitive() {
+    assert_eq!(
+        parse_mime_boundary("Multipart/Mixed; BOUNDARY=\"Upper\"").as_deref(),
+        Some("Upper")
+    );
+}
+
+#[test]
+fn mime_boundary_ignores_parameters_that_only_end_in_boundary() {
+    assert_eq!(parse_mime_boundary("multipart/mixed; xboundary=nope"), None);
+    assert_eq!(
+        parse_mime_boundary("multipart/mixed; xboundary=nope; boundary=yes").as_deref(),
+        Some("yes")
+    );
+}
+
+#[test]
+fn undo_dot_stuffing_strips_one_leading_dot_per_stuffed_line() {
+    let body = b"..signature\r\n...two dots\r\n.single\r\nmid..dle\r\n..";
+    assert_eq!(
+        undo_dot_stuffing_bytes(body),
+        b".signature\r\n..two dots\r\n.single\r\nmid..dle\r\n.".to_vec()
+    );
+}
+
+#[test]
+fn header_separator_at_end_of_message_yields_empty_body() {
+    let (headers, body) = split_headers_body("From: a@example.com\r\nSubject: hi\r\n\r\n");
+    assert_eq!(headers, "From: a@example.com\r\nSubject: hi");
+    assert_eq!(body, "");
+    assert_eq!(canonicalize_body_relaxed(""), "\r\n");
+    assert_eq!(canonicalize_body_simple(""), "\r\n");
+}
+
+#[test]
+fn dkim_canonicalization_defaults_follow_rfc6376() {
+    use Canonicalization::{Relaxed, Simple};
+
+    assert_eq!(parse_dkim_canonicalization(None), Some((Simple, Simple)));
+    assert_eq!(parse_dkim_canonicalization(Some("relaxed")), Some((Relaxed, Simple)));
+    assert_eq!(parse_dkim_canonicalization(Some("relaxed/")), Some((Relaxed, Simple)));
+    assert_eq!(parse_dkim_canonicalization(Some("Relaxed/Relaxed")), Some((Relaxed, Relaxed)));
+    assert_eq!(parse_dkim_canonicalization(Some("simple/relaxed")), Some((Simple, Relaxed)));
+    assert_eq!(parse_dkim_canonicalization(Some("nofws")), None);
+    assert_eq!(parse_dkim_canonicalization(Some("/relaxed")), None);
+}
+
+fn signed(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+#[test]
+fn repeated_signed_header_is_selected_bottom_up_when_interleaved() {
+    let headers = parse_headers(
+        "From: first@example.com\r\nTo: bob@example.com\r\nSubject: hi\r\nFrom: second@example.com",
+    );
+
+    // h=from:to:from takes the lowest From first, then the one above it.
+    assert_eq!(
+        canonicalize_headers_relaxed(&headers, &signed(&["from", "to", "from"])),
+        "from:second@example.com\r\nto:bob@example.com\r\nfrom:first@example.com\r\n"
+    );
+    // Listing From fewer times than it appears signs only the bottom instance.
+    assert_eq!(
+        canonicalize_headers_relaxed(&headers, &signed(&["to", "from", "subject"])),
+        "to:bob@example.com\r\nfrom:second@example.com\r\nsubject:hi\r\n"
+    );
+    // Extra listings of an exhausted name contribute nothing (RFC 6376 §5.4).
+    assert_eq!(
+        canonicalize_headers_relaxed(&headers, &signed(&["from", "from", "from"])),
+        "from:second@example.com\r\nfrom:first@example.com\r\n"
+    );
+}
+
+#[test]
+fn relaxed_header_name_drops_whitespace_before_colon() {
+    let headers = parse_headers("From : alice@example.com\r\nSubject:hi");
+    assert_eq!(
+        canonicalize_headers_relaxed(&headers, &signed(&["from", "subject"])),
+        "from:alice@example.com\r\nsubject:hi\r\n"
+    );
+}
+
+#[test]
+fn quoted_dkim_tag_values_are_unquoted_and_unescaped() {
+    let sig = "v=1; a=rsa-sha256; d=example.com; s=sel;\r\n\
+\tn=\"relayed by \\\"mx\\\" at c:\\\\spool\"; bh=abc=; b=def";
+
+    let values = parse_dkim_tag_values(sig);
+    assert_eq!(
+        values["n"],
+        DkimTagValue::Quoted(r#"relayed by "mx" at c:\spool"#.to_string())
+    );
+    assert_eq!(values["d"], DkimTagValue::Plain("example.com".to_string()));
+    assert_eq!(values["bh"].as_str(), "abc=");
+
+    let tags = parse_dkim_tags(sig);
+    assert_eq!(tags["n"], r#"relayed by "mx" at c:\spool"#);
+    assert_eq!(tags["s"], "sel");
+}
+
+#[test]
+fn dkim_tag_values_with_unbalanced_quotes_stay_plain() {
+    let values = parse_dkim_tag_values("n=\"open; z=close\"; q=\"");
+    assert_eq!(values["n"], DkimTagValue::Plain("\"open".to_string()));
+    assert_eq!(values["z"], DkimTagValue::Plain("close\"".to_string()));
+    assert_eq!(values["q"], DkimTagValue::Plain("\"".to_string()));
+}
+
+#[test]
+fn multiple_from_headers_are_detected() {
+    assert!(!has_multiple_from_headers(
+        "From: a@example.com\r\nResent-From: b@example.com\r\n\r\nFrom: quoted in body\r\n"
+    ));
+    assert!(has_multiple_from_headers(
+        "From: a@example.com\r\nTo: c@example.com\r\nfrom : b@example.com\r\n\r\nbody\r\n"
+    ));
+}
+
+#[test]
+fn mailing_list_signals_are_detected() {
+    assert!(mailing_list_signals(
+        "From: Alice <alice@example.com>\r\nSender: ALICE@example.com\r\n\r\nList-Id: in body\r\n"
+    )
+    .is_empty());
+    assert_eq!(
+        mailing_list_signals(
+            "From: Alice via List <list@lists.example.org>\r\nSender: list-bounces@lists.example.org\r\nList-Id: <list.lists.example.org>\r\n\r\nbody\r\n"
+        ),
+        vec!["list-id", "sender"]
+    );
+    assert_eq!(
+        mailing_list_signals("List-Unsubscribe: <mailto:u@example.org>\r\n\r\nbody\r\n"),
+        vec!["list-unsubscribe"]
+    );
+}
@@ -0,0 +1,115 @@
+use crate::api::{handle_request, RequestType};
+use crate::qp::decode_quoted_printable;
+use serde_json::{json, Value};
+
+fn decode(text: &str) -> String {
+    String::from_utf8(decode_quoted_printable(text)).expect("utf-8")
+}
+
+fn decode_method(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "decode-quoted-printable".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "decode-quoted-printable");
+    response.response
+}
+
+#[test]
+fn plain_text_passes_through() {
+    assert_eq!(decode("Hello, world!\r\n"), "Hello, world!\r\n");
+    assert_eq!(decode(""), "");
+}
+
+#[test]
+fn hex_sequences_decode_in_either_case() {
+    assert_eq!(decode("a=3Db"), "a=b");
+    assert_eq!(decode("caf=C3=A9"), "café");
+    assert_eq!(decode("caf=c3=a9"), "café");
+}
+
+#[test]
+fn every_hex_digit_decodes() {
+    assert_eq!(
+        decode_quoted_printable("=00=11=22=33=44=55=66=77=88=99=AA=BB=CC=DD=EE=FF=aa=ff"),
+        vec![
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff, 0xaa, 0xff,
+        ]
+    );
+}
+
+#[test]
+fn soft_line_breaks_join_lines() {
+    assert_eq!(decode("long =\r\nline\r\n"), "long line\r\n");
+    assert_eq!(decode("long =\nline\n"), "long line\n");
+}
+
+#[test]
+fn multi_line_body_keeps_hard_line_breaks() {
+    let encoded = "Line one with =C3=A9\r\n\
+Line two is very long and wraps =\r\n\
+onto the next line\r\n\
+\r\n\
+ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm\r\n";
+    assert_eq!(
+        decode(encoded),
+        "Line one with é\r\n\
+Line two is very long and wraps onto the next line\r\n\
+\r\n\
+ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm\r\n"
+    );
+}
+
+#[test]
+fn trailing_whitespace_on_encoded_lines_is_dropped() {
+    assert_eq!(decode("padded \t \r\nnext\r\n"), "padded\r\nnext\r\n");
+    // Encoded whitespace survives.
+    assert_eq!(decode("kept=20\r\n"), "kept \r\n");
+    // Whitespace after a soft break `=` is also transport padding.
+    assert_eq!(decode("soft= \t\r\nbreak"), "softbreak");
+}
+
+#[test]
+fn invalid_hex_sequences_are_kept_as_is() {
+    assert_eq!(decode("=ZZ"), "=ZZ");
+    assert_eq!(decode("a=4g"), "a=4g");
+    assert_eq!(decode("100 =G0"), "100 =G0");
+}
+
+#[test]
+fn truncated_escape_at_end_of_line_is_kept() {
+    assert_eq!(decode("x=4\r\ny"), "x=4\r\ny");
+    assert_eq!(decode("x=4"), "x=4");
+}
+
+#[test]
+fn gmail_body_ending_with_soft_break_drops_it() {
+    let encoded = "<div dir=3D\"ltr\">ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm</div>=\r\n";
+    assert_eq!(
+        decode(encoded),
+        "<div dir=\"ltr\">ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm</div>"
+    );
+}
+
+#[test]
+fn decode_method_returns_decoded_text() {
+    let response = decode_method(json!({ "text": "caf=C3=A9 =\r\nau lait" }));
+    assert_eq!(response["decoded"], "café au lait");
+    assert_eq!(response["error"], Value::Null);
+}
+
+#[test]
+fn decode_method_reports_non_utf8_output() {
+    // Latin-1 é is not valid UTF-8 on its own.
+    let response = decode_method(json!({ "text": "caf=E9" }));
+    assert_eq!(response["decoded"], "caf\u{fffd}");
+    assert_eq!(response["error"], "decoded bytes are not valid UTF-8");
+
+    let response = decode_method(json!({}));
+    assert_eq!(response["decoded"], "");
+    assert!(response["error"]
+        .as_str()
+        .unwrap_or_default()
+        .starts_with("invalid decode-quoted-printable args"));
+}
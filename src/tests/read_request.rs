@@ -0,0 +1,48 @@
+use crate::{handle_input, read_request, MAX_INPUT_BYTES};
+use std::io::{self, Read};
+
+#[test]
+fn request_is_parsed_from_reader() {
+    let request = read_request(&br#"{"method": "get-capabilities", "args": {}}"#[..])
+        .expect("parses request");
+    assert_eq!(request.method, "get-capabilities");
+}
+
+#[test]
+fn input_at_the_limit_is_read() {
+    // Trailing whitespace is valid JSON, so only the size is being tested.
+    let request = br#"{"method": "get-capabilities", "args": {}}"#;
+    let padding = io::repeat(b' ').take((MAX_INPUT_BYTES - request.len()) as u64);
+    let request = read_request((&request[..]).chain(padding)).expect("parses request");
+    assert_eq!(request.method, "get-capabilities");
+}
+
+#[test]
+fn oversized_input_is_rejected() {
+    // An endless reader: the read must stop at the limit rather than buffer it all.
+    let error = read_request(io::repeat(b' ')).err().expect("oversized input fails");
+    assert_eq!(error, format!("request exceeds {MAX_INPUT_BYTES} bytes"));
+}
+
+#[test]
+fn malformed_json_is_an_error() {
+    let error = read_request(&b"{\"method\":"[..]).err().expect("malformed input fails");
+    assert!(error.starts_with("invalid request JSON:"), "{error}");
+}
+
+#[test]
+fn oversized_input_gets_an_error_response() {
+    let response = handle_input(io::repeat(b' '));
+    assert_eq!(response.method, "");
+    assert_eq!(
+        response.response,
+        serde_json::json!({ "error": format!("request exceeds {MAX_INPUT_BYTES} bytes") })
+    );
+}
+
+#[test]
+fn readable_input_is_dispatched() {
+    let response = handle_input(&br#"{"method": "get-capabilities", "args": {}}"#[..]);
+    assert_eq!(response.method, "get-capabilities");
+    assert!(response.response.get("error").is_none());
+}
@@ -0,0 +1,164 @@
+use crate::api::{handle_request_with_keys, RequestType};
+use crate::crypto::{
+    decrypt_encrypted_email, decrypt_with_static_secret, encrypt_email_to_public_key,
+    get_worker_public_key, EncryptedEmailEnvelope, WorkerKeys,
+};
+use super::crypto::{encrypt_email, test_worker_keys};
+use base64;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+fn test_context() -> serde_json::Value {
+    serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+    })
+}
+
+fn new_worker_keypair() -> (StaticSecret, String) {
+    let secret = StaticSecret::from([5u8; 32]);
+    let public = X25519PublicKey::from(&secret);
+    (secret, base64::encode(public.as_bytes()))
+}
+
+#[test]
+fn encrypt_to_public_key_round_trips() {
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let context = test_context();
+    let (new_secret, new_public_b64) = new_worker_keypair();
+
+    let envelope = encrypt_email_to_public_key(email_blob.as_bytes(), &new_public_b64, &context, 1)
+        .expect("encrypts email");
+    assert_eq!(envelope.version, 1);
+
+    let decrypted =
+        decrypt_with_static_secret(&new_secret, &envelope, &context).expect("decrypts email");
+    assert_eq!(decrypted, email_blob);
+
+    let wrong_context = serde_json::json!({ "account_id": "other.testnet" });
+    assert!(decrypt_with_static_secret(&new_secret, &envelope, &wrong_context).is_err());
+}
+
+#[test]
+fn encrypt_to_public_key_uses_fresh_ephemeral_key_and_nonce() {
+    let context = test_context();
+    let (_, new_public_b64) = new_worker_keypair();
+
+    let a = encrypt_email_to_public_key(b"hello", &new_public_b64, &context, 1).expect("encrypts");
+    let b = encrypt_email_to_public_key(b"hello", &new_public_b64, &context, 1).expect("encrypts");
+    assert_ne!(a.ephemeral_pub, b.ephemeral_pub);
+    assert_ne!(a.nonce, b.nonce);
+}
+
+#[test]
+fn encrypt_to_public_key_rejects_invalid_key() {
+    let context = test_context();
+    let result = encrypt_email_to_public_key(b"hello", &base64::encode([1u8; 16]), &context, 1);
+    assert_eq!(
+        result.err().as_deref(),
+        Some("recipient public key must be 32 bytes")
+    );
+}
+
+/// Seed the worker used before the last rotation.
+const PREVIOUS_SEED: [u8; 32] = [5u8; 32];
+
+fn reencrypt(keys: &WorkerKeys, args: serde_json::Value) -> serde_json::Value {
+    let request = RequestType {
+        method: "reencrypt-email".to_string(),
+        args,
+    };
+    let response = handle_request_with_keys(request, Ok(keys));
+    assert_eq!(response.method, "reencrypt-email");
+    response.response
+}
+
+#[test]
+fn reencrypt_email_moves_envelope_from_previous_to_current_key() {
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let context = test_context();
+    let previous_keys = WorkerKeys::from_seed(PREVIOUS_SEED).expect("previous keys");
+    let old_envelope = encrypt_email_to_public_key(
+        email_blob.as_bytes(),
+        &get_worker_public_key(&previous_keys),
+        &context,
+        1,
+    )
+    .expect("encrypts email");
+
+    let keys = test_worker_keys()
+        .with_previous_seed(PREVIOUS_SEED)
+        .expect("previous seed derives");
+    let response = reencrypt(
+        &keys,
+        serde_json::json!({ "old_envelope": old_envelope, "context": context }),
+    );
+    let new_envelope: EncryptedEmailEnvelope = serde_json::from_value(
+        response
+            .get("encrypted_email_blob")
+            .cloned()
+            .expect("encrypted_email_blob in response"),
+    )
+    .expect("envelope deserializes");
+
+    let decrypted = decrypt_encrypted_email(&keys, &new_envelope, &context).expect("decrypts email");
+    assert_eq!(decrypted, email_blob);
+}
+
+#[test]
+fn reencrypt_email_rejects_caller_chosen_key() {
+    let context = test_context();
+    let (_, attacker_public_b64) = new_worker_keypair();
+    let keys = test_worker_keys()
+        .with_previous_seed(PREVIOUS_SEED)
+        .expect("previous seed derives");
+    let response = reencrypt(
+        &keys,
+        serde_json::json!({
+            "old_envelope": encrypt_email("hello", &context),
+            "context": context,
+            "new_public_key_b64": attacker_public_b64,
+        }),
+    );
+    let error = response["error"].as_str().expect("error");
+    assert!(
+        error.starts_with("invalid reencrypt-email args: unknown field `new_public_key_b64`"),
+        "{error}"
+    );
+}
+
+#[test]
+fn reencrypt_email_needs_previous_seed() {
+    let context = test_context();
+    let response = reencrypt(
+        &test_worker_keys(),
+        serde_json::json!({ "old_envelope": encrypt_email("hello", &context), "context": context }),
+    );
+    assert_eq!(
+        response["error"],
+        "PROTECTED_OUTLAYER_WORKER_PREVIOUS_SK_SEED_HEX32 is not set"
+    );
+}
+
+#[test]
+fn previous_seed_is_read_from_protected_secret() {
+    let seed_hex = |seed: [u8; 32]| -> String { seed.iter().map(|b| format!("{b:02x}")).collect() };
+    let keys = WorkerKeys::from_vars(|name| match name {
+        "PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32" => Some(seed_hex([7u8; 32])),
+        "PROTECTED_OUTLAYER_WORKER_PREVIOUS_SK_SEED_HEX32" => Some(seed_hex(PREVIOUS_SEED)),
+        _ => None,
+    })
+    .expect("keys derive");
+    assert!(keys.previous_static_secret().is_some());
+    assert_eq!(get_worker_public_key(&keys), get_worker_public_key(&test_worker_keys()));
+
+    let result = WorkerKeys::from_vars(|name| match name {
+        "PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32" => Some(seed_hex([7u8; 32])),
+        "PROTECTED_OUTLAYER_WORKER_PREVIOUS_SK_SEED_HEX32" => Some("zz".to_string()),
+        _ => None,
+    });
+    assert_eq!(
+        result.err().as_deref(),
+        Some("PROTECTED_OUTLAYER_WORKER_PREVIOUS_SK_SEED_HEX32 must be a 64-char hex string (32 bytes)")
+    );
+}
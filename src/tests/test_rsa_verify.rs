@@ -0,0 +1,85 @@
+use super::dns::GMAIL_DKIM_RECORD;
+use crate::api::{handle_request, RequestType};
+use crate::parsers::Canonicalization;
+use crate::verify_dkim::canonicalized_signed_headers;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+const GMAIL_EMAIL: &str =
+    include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+fn test_rsa_verify(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "test-rsa-verify".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "test-rsa-verify");
+    response.response
+}
+
+/// The Gmail fixture's `p=` key (SPKI) and its signature's header digest
+/// and `b=` value, all base64.
+fn gmail_rsa_inputs() -> (String, String, String) {
+    let public_key_b64 = GMAIL_DKIM_RECORD.split_once("p=").unwrap().1.to_string();
+    let (data, b_b64) =
+        canonicalized_signed_headers(GMAIL_EMAIL, 0, Canonicalization::Relaxed).unwrap();
+    let data_b64 = base64::encode(Sha256::digest(data.as_bytes()));
+    (data_b64, b_b64, public_key_b64)
+}
+
+#[test]
+fn gmail_fixture_signature_verifies() {
+    let (data_b64, signature_b64, public_key_b64) = gmail_rsa_inputs();
+    let response = test_rsa_verify(json!({
+        "data_b64": data_b64,
+        "signature_b64": signature_b64,
+        "public_key_b64": public_key_b64,
+    }));
+    assert_eq!(response, json!({ "verified": true, "error": null }));
+}
+
+#[test]
+fn other_digest_does_not_verify() {
+    let (_, signature_b64, public_key_b64) = gmail_rsa_inputs();
+    // Simple canonicalization hashes different header data than c= says.
+    let (data, _) = canonicalized_signed_headers(GMAIL_EMAIL, 0, Canonicalization::Simple).unwrap();
+    let response = test_rsa_verify(json!({
+        "data_b64": base64::encode(Sha256::digest(data.as_bytes())),
+        "signature_b64": signature_b64,
+        "public_key_b64": public_key_b64,
+    }));
+    assert_eq!(response, json!({ "verified": false, "error": null }));
+}
+
+#[test]
+fn malformed_inputs_are_errors() {
+    let (data_b64, signature_b64, public_key_b64) = gmail_rsa_inputs();
+
+    let response = test_rsa_verify(json!({
+        "data_b64": base64::encode(b"not a digest"),
+        "signature_b64": signature_b64,
+        "public_key_b64": public_key_b64,
+    }));
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "data must be a 32-byte SHA-256 digest, got 12 bytes");
+
+    let response = test_rsa_verify(json!({
+        "data_b64": data_b64,
+        "signature_b64": signature_b64,
+        "public_key_b64": base64::encode(b"not a key"),
+    }));
+    assert!(response["error"].as_str().unwrap().starts_with("invalid SPKI public key"));
+
+    let response = test_rsa_verify(json!({
+        "data_b64": data_b64,
+        "signature_b64": "!!!",
+        "public_key_b64": public_key_b64,
+    }));
+    assert!(response["error"].as_str().unwrap().starts_with("invalid signature_b64"));
+
+    let response = test_rsa_verify(json!({ "data_b64": data_b64 }));
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid test-rsa-verify args"));
+}
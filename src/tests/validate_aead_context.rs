@@ -0,0 +1,77 @@
+use crate::api::{handle_request, RequestType};
+use crate::crypto::{
+    aad_bytes, decrypt_encrypted_email, encrypt_email_to_public_key, get_worker_public_key,
+};
+use super::crypto::test_worker_keys;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+fn validate_aead_context(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "validate-aead-context".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "validate-aead-context");
+    response.response
+}
+
+/// Context as the contract builds it (alphabetical keys, `request_id` last).
+fn contract_context() -> Value {
+    json!({
+        "account_id": "alice.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "relayer.testnet",
+        "request_id": "123ABC",
+    })
+}
+
+#[test]
+fn returns_the_aad_bytes_used_for_decryption() {
+    let context = contract_context();
+    let response = validate_aead_context(json!({ "context": context }));
+
+    let aad = aad_bytes(&context).unwrap();
+    let canonical = response["canonical_json"].as_str().unwrap();
+    assert_eq!(canonical.as_bytes(), aad.as_slice());
+    assert_eq!(
+        canonical,
+        r#"{"account_id":"alice.testnet","network_id":"testnet","payer_account_id":"relayer.testnet","request_id":"123ABC"}"#
+    );
+    assert_eq!(response["byte_length"], aad.len());
+    let expected: String = Sha256::digest(&aad).iter().map(|b| format!("{b:02x}")).collect();
+    assert_eq!(response["sha256_hex"], expected);
+}
+
+#[test]
+fn envelope_decrypts_under_the_reported_context() {
+    let keys = test_worker_keys();
+    let context = contract_context();
+    let worker_pk = get_worker_public_key(&keys);
+    let envelope =
+        encrypt_email_to_public_key(b"Subject: hi\r\n\r\n", &worker_pk, &context, 1).unwrap();
+
+    // Re-parsing the reported bytes yields a context that decrypts the envelope.
+    let response = validate_aead_context(json!({ "context": context }));
+    let reported: Value =
+        serde_json::from_str(response["canonical_json"].as_str().unwrap()).unwrap();
+    assert!(decrypt_encrypted_email(&keys, &envelope, &reported).is_ok());
+
+    // The worker parses `context` into a sorted map, so the key order a
+    // relayer sends does not change the bytes; it must encrypt with this order.
+    let other = validate_aead_context(json!({ "context": {
+        "network_id": "testnet",
+        "account_id": "alice.testnet",
+        "payer_account_id": "relayer.testnet",
+        "request_id": "123ABC",
+    }}));
+    assert_eq!(other["sha256_hex"], response["sha256_hex"]);
+}
+
+#[test]
+fn missing_context_is_an_error() {
+    let response = validate_aead_context(json!({}));
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid validate-aead-context args"));
+}
@@ -0,0 +1,292 @@
+use crate::verify_dkim::{
+    build_canonicalized_email_string, parse_rsa_public_key,
+    verify_all_dkim_signatures_bytes_with_dot_stuffing, verify_dkim_signature,
+    verify_dkim_signature_bytes, verify_dkim_signature_bytes_with_dot_stuffing,
+    verify_dkim_with_explicit_key, DkimError, DEFAULT_MAX_DKIM_SIGNATURES,
+};
+use std::cell::Cell;
+use crate::parsers::{
+    build_canonicalized_dkim_header_relaxed, extract_dkim_selector_and_domain, parse_headers,
+    split_headers_body, strip_bom,
+};
+use super::dns::{test_key_dkim_record, GMAIL_DKIM_RECORD};
+
+#[test]
+fn canonicalized_email_string_for_gmail_fixture() {
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+    let canonical = build_canonicalized_email_string(email_blob, 0).expect("canonicalizes");
+
+    // Body CRLFs are shown literally.
+    assert!(canonical.contains("\\r\\n\n"));
+    // Signed headers use relaxed canonicalization (lowercase name, no space after ':').
+    assert!(canonical.contains(
+        "subject:recover-123abc kerp30.w3a-v1.testnet ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm\r\n"
+    ));
+    assert!(canonical.contains("from:Pta <n6378056@gmail.com>\r\n"));
+    // The DKIM-Signature line comes last, with an empty b= and no trailing CRLF.
+    let dkim_line = canonical
+        .rsplit("\r\n")
+        .next()
+        .expect("final dkim-signature line");
+    assert!(dkim_line.starts_with("dkim-signature:v=1; a=rsa-sha256;"));
+    assert!(dkim_line.ends_with("b="));
+}
+
+#[test]
+fn canonicalized_email_string_rejects_missing_signature_index() {
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+    let err = build_canonicalized_email_string(email_blob, 1).expect_err("only one signature");
+    assert_eq!(err, "no DKIM-Signature header at index 1");
+}
+
+#[test]
+fn pkcs1_encoded_dkim_key_verifies_gmail_fixture() {
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    // Same Gmail key as GMAIL_DKIM_RECORD, with the SPKI wrapper stripped.
+    let pkcs1_p = "MIIBCgKCAQEAntvSKT1hkqhKe0xcaZ0x+QbouDsJuBfby/S82jxsoC/SodmfmVs2D1KAH3mi1AqdMdU12h2VfETeOJkgGYq5ljd996AJ7ud2SyOLQmlhaNHH7Lx+Mdab8/zDN1SdxPARDgcM7AsRECHwQ15R20FaKUABGu4NTbR2fDKnYwiq5jQyBkLWP+LgGOgfUF4T4HZb2PY2bQtEP6QeqOtcW4rrsH24L7XhD+HSZb1hsitrE0VPbhJzxDwI4JF815XMnSVjZgYUXP8CxI1Y0FONlqtQYgsorZ9apoW1KPQe8brSSlRsi9sXB/tu56LmG7tEDNmrZ5XUwQYUUADBOu7t1niwXwIDAQAB";
+    let pk_bytes = base64::decode(pkcs1_p).expect("base64");
+    assert!(parse_rsa_public_key(&pk_bytes).is_ok());
+
+    let records = vec![format!("v=DKIM1; k=rsa; p={pkcs1_p}")];
+    assert!(verify_dkim_signature(email_blob, &records).is_ok());
+    assert!(verify_dkim_signature(email_blob, &[GMAIL_DKIM_RECORD.to_string()]).is_ok());
+}
+
+#[test]
+fn relaxed_dkim_header_drops_crlf_folded_b_value() {
+    // On the wire Gmail folds `b=` over several CRLF + WSP continuation lines.
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml")
+        .replace('\n', "\r\n");
+    let (raw_headers, _) = split_headers_body(&email_blob);
+    let headers = parse_headers(raw_headers);
+    let (_, dkim_value) = headers
+        .iter()
+        .find(|(name, _)| name == "DKIM-Signature")
+        .expect("DKIM-Signature header");
+    let b_value = dkim_value.split("b=").last().expect("b= value");
+    assert!(b_value.matches("\r\n ").count() > 1, "b= is not folded: {b_value:?}");
+
+    assert_eq!(
+        build_canonicalized_dkim_header_relaxed(dkim_value),
+        "dkim-signature:v=1; a=rsa-sha256; c=relaxed/relaxed; d=gmail.com; s=20230601; \
+         t=1765268018; x=1765872818; darn=web3authn.org; \
+         h=to:subject:message-id:date:from:mime-version:from:to:cc:subject :date:message-id:reply-to; \
+         bh=DybNgKUUAMbDmXWMvqU5XUFnq2CTnP980Z2v48D+cIc=; b="
+    );
+    assert!(verify_dkim_signature(&email_blob, &[GMAIL_DKIM_RECORD.to_string()]).is_ok());
+}
+
+#[test]
+fn utf8_bom_prefixed_gmail_fixture_verifies() {
+    let email_blob = format!(
+        "\u{feff}{}",
+        include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml")
+    );
+    assert!(verify_dkim_signature(&email_blob, &[GMAIL_DKIM_RECORD.to_string()]).is_ok());
+    assert_eq!(
+        extract_dkim_selector_and_domain(strip_bom(&email_blob)),
+        Ok(("20230601".to_string(), "gmail.com".to_string()))
+    );
+}
+
+#[test]
+fn explicit_key_verifies_gmail_fixture_and_rejects_tampering() {
+    use crate::parsers::parse_dkim_tags;
+    use rsa::pkcs8::DecodePublicKey;
+    use rsa::RsaPublicKey;
+
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let p = parse_dkim_tags(GMAIL_DKIM_RECORD).get("p").cloned().expect("p tag");
+    let public_key = RsaPublicKey::from_public_key_der(&base64::decode(p).expect("p base64"))
+        .expect("SPKI key");
+
+    assert!(verify_dkim_with_explicit_key(email_blob, &public_key));
+
+    let tampered = email_blob.replace("kerp30.w3a-v1.testnet", "mallory.testnet");
+    assert!(!verify_dkim_with_explicit_key(&tampered, &public_key));
+}
+
+#[test]
+fn rsa_sha1_signature_is_rejected_with_specific_error() {
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml")
+        .replace("a=rsa-sha256", "a=rsa-sha1");
+
+    let err = verify_dkim_signature(&email_blob, &[GMAIL_DKIM_RECORD.to_string()])
+        .expect_err("sha1 never verifies");
+    assert_eq!(
+        err,
+        DkimError::UnsupportedAlgorithm("rsa-sha1: sha1 disallowed".to_string())
+    );
+    assert_eq!(err.to_string(), "unsupported DKIM algorithm rsa-sha1: sha1 disallowed");
+
+    // Other unknown algorithms are still skipped with the generic failure.
+    let email_blob = email_blob.replace("a=rsa-sha1", "a=ed25519-sha256");
+    assert_eq!(
+        verify_dkim_signature(&email_blob, &[GMAIL_DKIM_RECORD.to_string()]),
+        Err(DkimError::VerificationFailed)
+    );
+}
+
+#[test]
+fn latin1_8bit_body_verifies_over_original_bytes() {
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/latin1_8bit_body.eml");
+    let sig = verify_dkim_signature_bytes(email, &[test_key_dkim_record()])
+        .expect("8bit body verifies");
+    assert_eq!(sig.domain, "example.com");
+    assert_eq!(sig.selector, "test");
+
+    // The body is Latin-1, so hashing the UTF-8-lossy text instead of the sent
+    // bytes breaks bh=.
+    let lossy = String::from_utf8_lossy(email);
+    assert_eq!(
+        verify_dkim_signature(&lossy, &[test_key_dkim_record()]),
+        Err(DkimError::VerificationFailed)
+    );
+}
+
+#[test]
+fn dot_stuffed_body_verifies_only_when_unstuffed() {
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/dot_stuffed_body.eml");
+    let records = [test_key_dkim_record()];
+
+    // Captured straight from SMTP DATA: the extra leading dots break bh=.
+    assert_eq!(
+        verify_dkim_signature_bytes(email, &records),
+        Err(DkimError::VerificationFailed)
+    );
+
+    let sig = verify_dkim_signature_bytes_with_dot_stuffing(email, &records, true, DEFAULT_MAX_DKIM_SIGNATURES)
+        .expect("un-stuffed body verifies");
+    assert_eq!(sig.domain, "example.com");
+    assert_eq!(sig.selector, "test");
+}
+
+#[test]
+fn empty_body_signature_verifies() {
+    let email =
+        include_str!("../../email-dkim-verifier-contract/tests/data/empty_body.eml");
+    assert!(email.ends_with("\r\n\r\n"), "fixture has no body bytes");
+    // bh= of the RFC 6376 §3.4.4 empty body, i.e. sha256("\r\n").
+    assert!(email.contains("bh=frcCV1k9oG9oKj3dpUqdJg1PxRT2RSN/XKdLCPjaYaY="));
+
+    let sig = verify_dkim_signature(email, &[test_key_dkim_record()])
+        .expect("empty-body signature verifies");
+    assert_eq!(sig.domain, "example.com");
+}
+
+#[test]
+fn c_tag_with_header_algorithm_only_defaults_body_to_simple() {
+    for (fixture, c_tag) in [
+        (
+            include_str!("../../email-dkim-verifier-contract/tests/data/c_relaxed_header_only.eml"),
+            " c=relaxed;",
+        ),
+        (
+            include_str!("../../email-dkim-verifier-contract/tests/data/c_relaxed_trailing_slash.eml"),
+            " c=relaxed/;",
+        ),
+    ] {
+        assert!(fixture.contains(c_tag));
+        let sig = verify_dkim_signature(fixture, &[test_key_dkim_record()])
+            .unwrap_or_else(|e| panic!("{c_tag} verifies: {e}"));
+        assert_eq!(sig.selector, "test");
+
+        // Simple body canonicalization keeps whitespace, so it now matters.
+        let respaced = fixture.replacen("Hello  world", "Hello world", 1);
+        assert!(verify_dkim_signature(&respaced, &[test_key_dkim_record()]).is_err());
+    }
+}
+
+#[test]
+fn absent_c_tag_means_simple_simple() {
+    let email = include_str!("../../email-dkim-verifier-contract/tests/data/c_absent.eml");
+    assert!(!email.contains(" c="));
+    verify_dkim_signature(email, &[test_key_dkim_record()]).expect("simple/simple verifies");
+
+    // Simple header canonicalization keeps header whitespace as signed.
+    let respaced = email.replacen("Subject: recover", "Subject:  recover", 1);
+    assert!(verify_dkim_signature(&respaced, &[test_key_dkim_record()]).is_err());
+}
+
+#[test]
+fn simple_header_canonicalization_keeps_dkim_signature_name_casing() {
+    // Signed as `dkim-signature:`; simple canonicalization hashes the name as sent.
+    let email =
+        include_str!("../../email-dkim-verifier-contract/tests/data/c_absent_lowercase_name.eml");
+    assert!(email.starts_with("dkim-signature:"));
+    verify_dkim_signature(email, &[test_key_dkim_record()]).expect("lowercase name verifies");
+
+    let recased = email.replacen("dkim-signature:", "DKIM-Signature:", 1);
+    assert!(verify_dkim_signature(&recased, &[test_key_dkim_record()]).is_err());
+}
+
+/// The Gmail fixture with `forged` DKIM-Signatures above the real one. Each
+/// forged signature has the right body hash, so it costs a key lookup and an
+/// RSA verification before failing.
+fn gmail_email_with_forged_signatures(forged: usize) -> String {
+    let email = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let forged_header = "DKIM-Signature: v=1; a=rsa-sha256; c=relaxed/relaxed; d=gmail.com;\r\n \
+         s=20230601; h=from; bh=DybNgKUUAMbDmXWMvqU5XUFnq2CTnP980Z2v48D+cIc=; b=AAAA\r\n";
+    format!("{}{email}", forged_header.repeat(forged))
+}
+
+#[test]
+fn signatures_past_the_cap_are_not_tried() {
+    let email = gmail_email_with_forged_signatures(49);
+    let records = [GMAIL_DKIM_RECORD.to_string()];
+
+    // The real signature is the 50th, past the default cap.
+    assert_eq!(
+        verify_dkim_signature_bytes(email.as_bytes(), &records),
+        Err(DkimError::VerificationFailed)
+    );
+    let sig = verify_dkim_signature_bytes_with_dot_stuffing(email.as_bytes(), &records, false, 50)
+        .expect("real signature within a cap of 50");
+    assert_eq!(sig.signatures_tried, 50);
+
+    let lookups = Cell::new(0);
+    let result = verify_all_dkim_signatures_bytes_with_dot_stuffing(
+        email.as_bytes(),
+        false,
+        DEFAULT_MAX_DKIM_SIGNATURES,
+        |_, _| {
+            lookups.set(lookups.get() + 1);
+            records.to_vec()
+        },
+    );
+    assert_eq!(result, Err(DkimError::VerificationFailed));
+    assert_eq!(lookups.get(), DEFAULT_MAX_DKIM_SIGNATURES);
+}
+
+#[test]
+fn repeated_from_in_h_tag_signs_both_from_fields() {
+    // Both signatures list `h=from:from:to`, covering the bottom From: and
+    // then the one above it.
+    let email =
+        include_str!("../../email-dkim-verifier-contract/tests/data/two_signatures_repeated_from.eml");
+    let records = [test_key_dkim_record()];
+    let result = verify_all_dkim_signatures_bytes_with_dot_stuffing(
+        email.as_bytes(),
+        false,
+        DEFAULT_MAX_DKIM_SIGNATURES,
+        |_, _| records.to_vec(),
+    );
+    assert_eq!(result.expect("all signatures verify").signatures_tried, 2);
+
+    // Swapping the two From: fields changes the signed order.
+    let swapped = email
+        .replacen("From: Alice <alice@example.com>\r\n", "", 1)
+        .replacen(
+            "From: Bob <bob@example.com>\r\n",
+            "From: Bob <bob@example.com>\r\nFrom: Alice <alice@example.com>\r\n",
+            1,
+        );
+    assert_eq!(
+        verify_dkim_signature(&swapped, &records),
+        Err(DkimError::VerificationFailed)
+    );
+}
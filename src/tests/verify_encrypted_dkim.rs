@@ -1,13 +1,36 @@
-use crate::api::{handle_request, RequestType};
-use super::crypto::encrypt_email;
+use crate::api::{compute_from_address_hash_for_header, handle_request_with_keys, RequestType};
+use crate::crypto::{canonical_json, encrypt_email_to_public_key, get_worker_public_key, WorkerKeys};
+use crate::dns::{clear_dns_stubs, set_dns_stub};
+use crate::verify_dkim::verify_dkim_signature;
+use super::crypto::{encrypt_email, test_worker_keys};
+use super::dns::{stub_gmail_dns, test_key_dkim_record, GMAIL_DKIM_RECORD, TEST_KEY_DKIM_NAME};
 use base64;
-use sha2::{Digest, Sha256};
-use crate::parsers::parse_from_address;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+/// `sha256("n6378056@gmail.com|kerp30.w3a-v1.testnet")`, as the contract's
+/// `compute_from_address_hash_for_header` computes it for the Gmail fixture.
+const GMAIL_FROM_ADDRESS_HASH_HEX: &str =
+    "112cbffde7d9251124755c45cdf15add98657a634117250b85a3bac362a6ba89";
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn from_address_hash_matches_contract_reference_value() {
+    let hash = compute_from_address_hash_for_header(
+        "Pta <N6378056@Gmail.com>",
+        " Kerp30.w3a-v1.testnet ",
+    );
+    assert_eq!(hex(&hash), GMAIL_FROM_ADDRESS_HASH_HEX);
+
+    assert!(compute_from_address_hash_for_header("", "kerp30.w3a-v1.testnet").is_empty());
+    assert!(compute_from_address_hash_for_header("Pta <n6378056@gmail.com>", " ").is_empty());
+}
 
 #[test]
 fn verify_encrypted_dkim_flow_fails_without_secret() {
-    std::env::remove_var("PROTECTED_OUTLAYER_WORKER_SK_SEED_HEX32");
-    std::env::remove_var("OUTLAYER_WORKER_SK_SEED_HEX32");
+    let keys = WorkerKeys::from_vars(|_| None);
     let params = serde_json::json!({
         "encrypted_email_blob": {
             "version": 1,
@@ -15,7 +38,11 @@ fn verify_encrypted_dkim_flow_fails_without_secret() {
             "nonce": base64::encode(&[0u8; 12]),
             "ciphertext": base64::encode(&[0u8; 16]),
         },
-        "context": {},
+        "context": {
+            "account_id": "kerp30.w3a-v1.testnet",
+            "network_id": "testnet",
+            "payer_account_id": "kerp30.w3a-v1.testnet",
+        },
     });
 
     let request = RequestType {
@@ -23,18 +50,19 @@ fn verify_encrypted_dkim_flow_fails_without_secret() {
         args: params,
     };
 
-    let response = handle_request(request);
+    let response = handle_request_with_keys(request, keys.as_ref().map_err(String::as_str));
     assert_eq!(response.method, "verify-encrypted-email");
     let err = response
         .response
         .get("error")
         .and_then(|v| v.as_str())
         .unwrap_or_default();
-    assert!(!err.is_empty());
+    assert!(err.starts_with("Secrets Not Found"), "got: {err}");
 }
 
 #[test]
 fn encrypted_flow_runs_dkim_verification_in_worker() {
+    stub_gmail_dns();
     let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
     let context = serde_json::json!({
         "account_id": "kerp30.w3a-v1.testnet",
@@ -59,7 +87,7 @@ fn encrypted_flow_runs_dkim_verification_in_worker() {
         args: args,
     };
 
-    let response = handle_request(request);
+    let response = handle_request_with_keys(request, Ok(&test_worker_keys()));
     assert_eq!(response.method, "verify-encrypted-email");
 
     let verified = response
@@ -86,6 +114,13 @@ fn encrypted_flow_runs_dkim_verification_in_worker() {
         "ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm"
     );
 
+    let selector = response
+        .response
+        .get("selector")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    assert_eq!(selector, "20230601");
+
     assert!(
         response.response.get("from_address").is_none(),
         "worker response must not leak sender email address"
@@ -101,10 +136,7 @@ fn encrypted_flow_runs_dkim_verification_in_worker() {
         .iter()
         .map(|v| v.as_u64().expect("hash byte") as u8)
         .collect();
-    let canonical_from = parse_from_address(email_blob).trim().to_lowercase();
-    let salt = "kerp30.w3a-v1.testnet";
-    let expected = Sha256::digest(format!("{canonical_from}|{salt}").as_bytes()).to_vec();
-    assert_eq!(from_address_hash_bytes, expected);
+    assert_eq!(hex(&from_address_hash_bytes), GMAIL_FROM_ADDRESS_HASH_HEX);
 
     let email_timestamp_ms = response
         .response
@@ -121,6 +153,7 @@ fn encrypted_flow_runs_dkim_verification_in_worker() {
 
 #[test]
 fn encrypted_flow_fails_for_tampered_public_key() {
+    stub_gmail_dns();
     let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
     let tampered = email_blob.replacen(
         "ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm",
@@ -129,7 +162,8 @@ fn encrypted_flow_fails_for_tampered_public_key() {
     );
     let context = serde_json::json!({
         "account_id": "kerp30.w3a-v1.testnet",
-        "network_id": "testnet"
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet"
     });
 
     let envelope = encrypt_email(&tampered, &context);
@@ -149,7 +183,7 @@ fn encrypted_flow_fails_for_tampered_public_key() {
         args: params,
     };
 
-    let response = handle_request(request);
+    let response = handle_request_with_keys(request, Ok(&test_worker_keys()));
     assert_eq!(response.method, "verify-encrypted-email");
 
     let verified = response
@@ -193,8 +227,825 @@ fn encrypted_flow_fails_for_tampered_public_key() {
         .get("error")
         .and_then(|v| v.as_str())
         .unwrap_or_default();
-    assert!(
-        error.contains("DKIM verification failed"),
-        "expected DKIM failure error, got: {error}"
+    assert_eq!(error, "signature_invalid");
+}
+
+fn verify_test_key_email(email: &[u8]) -> serde_json::Value {
+    verify_test_key_email_with_pinned_keys(email, serde_json::json!([]))
+}
+
+fn verify_test_key_email_with_pinned_keys(
+    email: &[u8],
+    pinned_keys: serde_json::Value,
+) -> serde_json::Value {
+    verify_test_key_email_with_args(email, serde_json::json!({ "pinned_keys": pinned_keys }))
+}
+
+/// Verify `email` with `extra_args` (contract policy fields) merged into the args.
+fn verify_test_key_email_with_args(email: &[u8], extra_args: serde_json::Value) -> serde_json::Value {
+    verify_test_key_email_with_method("verify-encrypted-email", email, extra_args)
+}
+
+fn verify_test_key_email_with_method(
+    method: &str,
+    email: &[u8],
+    extra_args: serde_json::Value,
+) -> serde_json::Value {
+    let context = serde_json::json!({
+        "account_id": "alice.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "alice.testnet"
+    });
+    let keys = test_worker_keys();
+    let envelope = encrypt_email_to_public_key(email, &get_worker_public_key(&keys), &context, 1)
+        .expect("encrypts email");
+    let mut args = serde_json::json!({
+        "encrypted_email_blob": envelope,
+        "context": context,
+    });
+    if let (Some(args), Some(extra)) = (args.as_object_mut(), extra_args.as_object()) {
+        args.extend(extra.clone());
+    }
+    let request = RequestType {
+        method: method.to_string(),
+        args,
+    };
+    let response = handle_request_with_keys(request, Ok(&keys));
+    assert_eq!(response.method, method);
+    response.response
+}
+
+#[test]
+fn encrypted_flow_reports_selector_without_dns_key() {
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/empty_body.eml");
+
+    // No TXT records at all for the selector.
+    set_dns_stub(TEST_KEY_DKIM_NAME, Vec::new());
+    let response = verify_test_key_email(email);
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "selector_not_found");
+
+    // Only a revoked key (empty `p=`) is published for the selector.
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec!["v=DKIM1; k=rsa; p=".to_string()]);
+    let response = verify_test_key_email(email);
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "selector_not_found");
+}
+
+#[test]
+fn encrypted_flow_reports_signature_invalid_for_other_key() {
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/empty_body.eml");
+
+    // A valid key is published under the selector, but it did not sign the email.
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![super::dns::GMAIL_DKIM_RECORD.to_string()]);
+    let response = verify_test_key_email(email);
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "signature_invalid");
+
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![test_key_dkim_record()]);
+    let response = verify_test_key_email(email);
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+}
+
+#[test]
+fn encrypted_flow_rejects_second_from_header_despite_valid_signature() {
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![test_key_dkim_record()]);
+    let email = include_str!("../../email-dkim-verifier-contract/tests/data/empty_body.eml")
+        .replacen("From: Alice", "From: Mallory <mallory@example.net>\r\nFrom: Alice", 1);
+
+    // `h=from` signs only the bottom-most From:, so the signature still verifies.
+    verify_dkim_signature(&email, &[test_key_dkim_record()]).expect("DKIM verifies");
+
+    let response = verify_test_key_email(email.as_bytes());
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "multiple_from_headers");
+    assert_eq!(response["request_id"], "EMPTY1");
+}
+
+#[test]
+fn encrypted_flow_flags_and_optionally_rejects_mailing_list_email() {
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/mailing_list_rewritten.eml");
+    let args = |reject_mailing_list: bool| {
+        serde_json::json!({
+            "reject_mailing_list": reject_mailing_list,
+            "pinned_keys": [
+                { "domain": "lists.example.org", "selector": "test", "records": [test_key_dkim_record()] },
+            ],
+        })
+    };
+
+    // Annotated by default: the list's own signature verifies.
+    let response = verify_test_key_email_with_method("verify-encrypted-email-v2", email, args(false));
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    assert_eq!(response["signing_domain"], "lists.example.org");
+    assert_eq!(
+        response["mailing_list_signals"],
+        serde_json::json!(["list-id", "list-unsubscribe", "sender"])
     );
+
+    let response = verify_test_key_email_with_method("verify-encrypted-email-v2", email, args(true));
+    assert_eq!(response["verified"], false);
+    assert_eq!(
+        response["error"],
+        "mailing_list_message: list-id, list-unsubscribe, sender"
+    );
+    assert_eq!(response["request_id"], "LIST01");
+}
+
+#[test]
+fn encrypted_flow_verifies_8bit_latin1_body() {
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![test_key_dkim_record()]);
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/latin1_8bit_body.eml");
+    let context = serde_json::json!({
+        "account_id": "alice.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "alice.testnet"
+    });
+
+    let keys = test_worker_keys();
+    let envelope = encrypt_email_to_public_key(email, &get_worker_public_key(&keys), &context, 1)
+        .expect("encrypts email");
+
+    let request = RequestType {
+        method: "verify-encrypted-email".to_string(),
+        args: serde_json::json!({ "encrypted_email_blob": envelope, "context": context }),
+    };
+
+    let response = handle_request_with_keys(request, Ok(&keys));
+    assert_eq!(response.response["error"], serde_json::Value::Null);
+    assert_eq!(response.response["verified"], true);
+    assert_eq!(response.response["account_id"], "alice.testnet");
+    assert_eq!(
+        response.response["new_public_key"],
+        "ed25519:HPHNMfHwmBJSqcArYZ5ptTZpukvFoMtuU8TcV2T7mEEy"
+    );
+    assert_eq!(response.response["selector"], "test");
+}
+
+fn verify_with_context(context: serde_json::Value) -> serde_json::Value {
+    stub_gmail_dns();
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let envelope = encrypt_email(email_blob, &context);
+    let request = RequestType {
+        method: "verify-encrypted-email".to_string(),
+        args: serde_json::json!({ "encrypted_email_blob": envelope, "context": context }),
+    };
+    handle_request_with_keys(request, Ok(&test_worker_keys())).response
+}
+
+#[test]
+fn well_formed_context_passes_validation() {
+    let response = verify_with_context(serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+    }));
+    assert_eq!(response["error"], serde_json::Value::Null);
+    assert_eq!(response["verified"], true);
+}
+
+#[test]
+fn context_missing_key_is_rejected_before_decryption() {
+    let context = serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+    });
+    let response = verify_with_context(context.clone());
+    assert_eq!(response["error"], "invalid_context_shape");
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["context"], context);
+}
+
+#[test]
+fn context_with_extra_key_is_rejected_before_decryption() {
+    let response = verify_with_context(serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+        "nonce": "1",
+    }));
+    assert_eq!(response["error"], "invalid_context_shape");
+
+    let response = verify_with_context(serde_json::json!("kerp30.w3a-v1.testnet"));
+    assert_eq!(response["error"], "invalid_context_shape");
+}
+
+#[test]
+fn context_request_id_is_accepted_and_echoed() {
+    // Matches the fixture's `Subject: recover-123abc ...`.
+    let response = verify_with_context(serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+        "request_id": "123abc",
+    }));
+    assert_eq!(response["error"], serde_json::Value::Null);
+    assert_eq!(response["verified"], true);
+    assert_eq!(response["request_id"], "123abc");
+
+    let response = verify_with_context(serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+        "request_id": 7,
+    }));
+    assert_eq!(response["error"], "invalid_context_shape");
+}
+
+#[test]
+fn request_id_argument_must_match_context_request_id() {
+    stub_gmail_dns();
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let context = serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+        "request_id": "BOUND1",
+    });
+    let request = RequestType {
+        method: "verify-encrypted-email".to_string(),
+        args: serde_json::json!({
+            "encrypted_email_blob": encrypt_email(email_blob, &context),
+            "context": context,
+            "request_id": "OTHER2",
+        }),
+    };
+
+    let response = handle_request_with_keys(request, Ok(&test_worker_keys())).response;
+    assert_eq!(response["error"], "request_id_mismatch");
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["request_id"], "OTHER2");
+}
+
+#[test]
+fn subject_request_id_must_match_context_request_id() {
+    let response = verify_with_context(serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+        "request_id": "BOUND1",
+    }));
+    assert_eq!(response["error"], "request_id_mismatch");
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["request_id"], "BOUND1");
+}
+
+fn verify_gmail_with_expected_hash(expected_from_address_hash: Vec<u8>) -> serde_json::Value {
+    stub_gmail_dns();
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let context = serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+    });
+    let request = RequestType {
+        method: "verify-encrypted-email".to_string(),
+        args: serde_json::json!({
+            "encrypted_email_blob": encrypt_email(email_blob, &context),
+            "context": context,
+            "expected_from_address_hash": expected_from_address_hash,
+        }),
+    };
+    handle_request_with_keys(request, Ok(&test_worker_keys())).response
+}
+
+#[test]
+fn expected_from_address_hash_accepts_registered_sender() {
+    let registered =
+        compute_from_address_hash_for_header("n6378056@gmail.com", "kerp30.w3a-v1.testnet");
+    let response = verify_gmail_with_expected_hash(registered.clone());
+    assert_eq!(response["error"], serde_json::Value::Null);
+    assert_eq!(response["verified"], true);
+    assert_eq!(response["from_address_hash"], serde_json::json!(registered));
+}
+
+#[test]
+fn expected_from_address_hash_rejects_other_sender() {
+    let other =
+        compute_from_address_hash_for_header("mallory@example.com", "kerp30.w3a-v1.testnet");
+    let response = verify_gmail_with_expected_hash(other);
+    assert_eq!(response["error"], "from_address_hash_mismatch");
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["request_id"], "123abc");
+}
+
+fn verify_gmail_with_expected_account(expected_account_id: Option<&str>) -> serde_json::Value {
+    stub_gmail_dns();
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let context = serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+    });
+    let request = RequestType {
+        method: "verify-encrypted-email".to_string(),
+        args: serde_json::json!({
+            "encrypted_email_blob": encrypt_email(email_blob, &context),
+            "context": context,
+            "expected_account_id": expected_account_id,
+        }),
+    };
+    handle_request_with_keys(request, Ok(&test_worker_keys())).response
+}
+
+#[test]
+fn expected_account_id_accepts_subject_account() {
+    let response = verify_gmail_with_expected_account(Some("kerp30.w3a-v1.testnet"));
+    assert_eq!(response["error"], serde_json::Value::Null);
+    assert_eq!(response["verified"], true);
+    assert_eq!(response["account_id"], "kerp30.w3a-v1.testnet");
+}
+
+#[test]
+fn expected_account_id_rejects_other_account() {
+    let response = verify_gmail_with_expected_account(Some("mallory.testnet"));
+    assert_eq!(response["error"], "account_id_mismatch");
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["account_id"], "");
+    assert_eq!(response["request_id"], "123abc");
+}
+
+#[test]
+fn absent_expected_account_id_accepts_any_account() {
+    let response = verify_gmail_with_expected_account(None);
+    assert_eq!(response["error"], serde_json::Value::Null);
+    assert_eq!(response["verified"], true);
+    assert_eq!(response["account_id"], "kerp30.w3a-v1.testnet");
+}
+
+fn verify_gmail_with_payer(payer_account_id: &str) -> serde_json::Value {
+    stub_gmail_dns();
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let context = serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+    });
+    let request = RequestType {
+        method: "verify-encrypted-email".to_string(),
+        args: serde_json::json!({
+            "encrypted_email_blob": encrypt_email(email_blob, &context),
+            "context": context,
+            "payer_account_id": payer_account_id,
+        }),
+    };
+    handle_request_with_keys(request, Ok(&test_worker_keys())).response
+}
+
+#[test]
+fn payer_account_id_matching_context_verifies() {
+    let response = verify_gmail_with_payer("kerp30.w3a-v1.testnet");
+    assert_eq!(response["error"], serde_json::Value::Null);
+    assert_eq!(response["verified"], true);
+}
+
+#[test]
+fn payer_account_id_differing_from_context_is_rejected() {
+    let response = verify_gmail_with_payer("relayer.testnet");
+    assert_eq!(response["error"], "payer_account_id_context_mismatch");
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["account_id"], "");
+}
+
+#[test]
+fn pinned_key_is_used_instead_of_dns() {
+    clear_dns_stubs();
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/empty_body.eml");
+    let response = verify_test_key_email_with_pinned_keys(
+        email,
+        serde_json::json!([{
+            "domain": "Example.com",
+            "selector": "test",
+            "records": [test_key_dkim_record()],
+        }]),
+    );
+    assert_eq!(response["error"], serde_json::Value::Null);
+    assert_eq!(response["verified"], true);
+    assert_eq!(response["selector"], "test");
+}
+
+#[test]
+fn static_dns_record_is_used_instead_of_fetch() {
+    clear_dns_stubs();
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let context = serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+    });
+    let request = RequestType {
+        method: "verify-encrypted-email".to_string(),
+        args: serde_json::json!({
+            "encrypted_email_blob": encrypt_email(email_blob, &context),
+            "context": context,
+            "dns_records": [{
+                "name": "20230601._domainkey.Gmail.com",
+                "records": [GMAIL_DKIM_RECORD],
+            }],
+        }),
+    };
+    let response = handle_request_with_keys(request, Ok(&test_worker_keys())).response;
+    assert_eq!(response["error"], serde_json::Value::Null);
+    assert_eq!(response["verified"], true);
+    assert_eq!(response["selector"], "20230601");
+}
+
+#[test]
+fn static_dns_record_verifies_only_its_own_name() {
+    clear_dns_stubs();
+    // Broken forwarder signature first; the `d=example.com` one was made with
+    // the key configured for the forwarder's name.
+    let email = include_str!("../../email-dkim-verifier-contract/tests/data/two_signatures.eml")
+        .replacen("b=WWs6S/14", "b=AAAAS/14", 1);
+    let forwarder_record = serde_json::json!({
+        "name": "fwd._domainkey.forwarder.example",
+        "records": [test_key_dkim_record()],
+    });
+    let response = verify_test_key_email_with_args(
+        email.as_bytes(),
+        serde_json::json!({ "dns_records": [forwarder_record] }),
+    );
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "signature_invalid");
+
+    let example_record =
+        serde_json::json!({ "name": TEST_KEY_DKIM_NAME, "records": [test_key_dkim_record()] });
+    let response = verify_test_key_email_with_args(
+        email.as_bytes(),
+        serde_json::json!({ "dns_records": [forwarder_record, example_record] }),
+    );
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    assert_eq!(response["selector"], "test");
+}
+
+#[test]
+fn pinned_key_takes_precedence_over_static_dns_record() {
+    clear_dns_stubs();
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/empty_body.eml");
+    let response = verify_test_key_email_with_args(
+        email,
+        serde_json::json!({
+            "pinned_keys": [{
+                "domain": "example.com",
+                "selector": "test",
+                "records": ["v=DKIM1; k=rsa; p="],
+            }],
+            "dns_records": [{ "name": TEST_KEY_DKIM_NAME, "records": [test_key_dkim_record()] }],
+        }),
+    );
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "selector_not_found");
+}
+
+#[test]
+fn pinned_revoked_key_overrides_valid_dns_record() {
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![test_key_dkim_record()]);
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/empty_body.eml");
+    let response = verify_test_key_email_with_pinned_keys(
+        email,
+        serde_json::json!([{
+            "domain": "example.com",
+            "selector": "test",
+            "records": ["v=DKIM1; k=rsa; p="],
+        }]),
+    );
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "selector_not_found");
+
+    // A pin for another selector leaves the DNS lookup in place.
+    let response = verify_test_key_email_with_pinned_keys(
+        email,
+        serde_json::json!([{
+            "domain": "example.com",
+            "selector": "other",
+            "records": ["v=DKIM1; k=rsa; p="],
+        }]),
+    );
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+}
+
+#[test]
+fn pinned_key_verifies_only_its_own_selector() {
+    clear_dns_stubs();
+    // Broken forwarder signature first; the `d=example.com` one was made with
+    // the key pinned for the forwarder.
+    let email = include_str!("../../email-dkim-verifier-contract/tests/data/two_signatures.eml")
+        .replacen("b=WWs6S/14", "b=AAAAS/14", 1);
+    let forwarder_pin = serde_json::json!({
+        "domain": "forwarder.example",
+        "selector": "fwd",
+        "records": [test_key_dkim_record()],
+    });
+    let response =
+        verify_test_key_email_with_pinned_keys(email.as_bytes(), serde_json::json!([forwarder_pin]));
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "signature_invalid");
+
+    let example_pin = serde_json::json!({
+        "domain": "example.com",
+        "selector": "test",
+        "records": [test_key_dkim_record()],
+    });
+    let response = verify_test_key_email_with_pinned_keys(
+        email.as_bytes(),
+        serde_json::json!([forwarder_pin, example_pin]),
+    );
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    assert_eq!(response["selector"], "test");
+}
+
+#[test]
+fn require_all_signatures_policy_rejects_forwarded_email() {
+    clear_dns_stubs();
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/two_signatures.eml");
+    // The forwarder's signature (first) verifies; the original signer's key
+    // no longer matches its signature.
+    let pinned_keys = |original_record: String| {
+        serde_json::json!([
+            { "domain": "forwarder.example", "selector": "fwd", "records": [test_key_dkim_record()] },
+            { "domain": "example.com", "selector": "test", "records": [original_record] },
+        ])
+    };
+
+    let response = verify_test_key_email_with_args(
+        email,
+        serde_json::json!({ "pinned_keys": pinned_keys(GMAIL_DKIM_RECORD.to_string()) }),
+    );
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+
+    let response = verify_test_key_email_with_args(
+        email,
+        serde_json::json!({
+            "pinned_keys": pinned_keys(GMAIL_DKIM_RECORD.to_string()),
+            "require_all_signatures": true,
+        }),
+    );
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "signature_invalid");
+
+    let response = verify_test_key_email_with_args(
+        email,
+        serde_json::json!({
+            "pinned_keys": pinned_keys(test_key_dkim_record()),
+            "require_all_signatures": true,
+        }),
+    );
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    assert_eq!(response["selector"], "fwd");
+}
+
+#[test]
+fn require_all_signatures_looks_up_each_selector_in_dns() {
+    clear_dns_stubs();
+    set_dns_stub("fwd._domainkey.forwarder.example", vec![test_key_dkim_record()]);
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/two_signatures.eml");
+    let require_all = serde_json::json!({ "require_all_signatures": true });
+
+    // No record for the original signer's selector.
+    let response = verify_test_key_email_with_args(email, require_all.clone());
+    assert_eq!(response["error"], "signature_invalid");
+
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![test_key_dkim_record()]);
+    let response = verify_test_key_email_with_args(email, require_all);
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+}
+
+#[test]
+fn first_signature_key_does_not_verify_another_domain() {
+    clear_dns_stubs();
+    // The first signature (`d=forwarder.example`) is broken; the second claims
+    // `d=example.com` but was made with the forwarder's key.
+    let email = include_str!("../../email-dkim-verifier-contract/tests/data/two_signatures.eml")
+        .replacen("b=WWs6S/14", "b=AAAAS/14", 1);
+    set_dns_stub("fwd._domainkey.forwarder.example", vec![test_key_dkim_record()]);
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![GMAIL_DKIM_RECORD.to_string()]);
+    let response = verify_test_key_email(email.as_bytes());
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "signature_invalid");
+
+    // Once example.com publishes that key, the second signature verifies.
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![test_key_dkim_record()]);
+    let response = verify_test_key_email(email.as_bytes());
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    assert_eq!(response["selector"], "test");
+}
+
+#[test]
+fn v2_response_adds_diagnostics_for_gmail_fixture() {
+    stub_gmail_dns();
+    let email_blob = include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+    let context = serde_json::json!({
+        "account_id": "kerp30.w3a-v1.testnet",
+        "network_id": "testnet",
+        "payer_account_id": "kerp30.w3a-v1.testnet",
+    });
+    let request = RequestType {
+        method: "verify-encrypted-email-v2".to_string(),
+        args: serde_json::json!({
+            "encrypted_email_blob": encrypt_email(email_blob, &context),
+            "context": context,
+        }),
+    };
+    let response = handle_request_with_keys(request, Ok(&test_worker_keys()));
+    assert_eq!(response.method, "verify-encrypted-email-v2");
+    let response = response.response;
+
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    assert_eq!(response["error"], serde_json::Value::Null);
+    assert_eq!(response["account_id"], "kerp30.w3a-v1.testnet");
+    assert_eq!(response["new_public_key"], "ed25519:86mqiBdv45gM4c5uLmvT3TU4g7DAg6KLpuabBSFweigm");
+    let from_address_hash: Vec<u8> = serde_json::from_value(response["from_address_hash"].clone())
+        .expect("from_address_hash bytes");
+    assert_eq!(hex(&from_address_hash), GMAIL_FROM_ADDRESS_HASH_HEX);
+    assert!(response["email_timestamp_ms"].is_u64());
+    assert_eq!(response["request_id"], "123abc");
+
+    assert_eq!(response["signing_domain"], "gmail.com");
+    assert_eq!(response["selector"], "20230601");
+    assert_eq!(
+        response["message_id"],
+        "<CAHUSCDST_Dd-+71+suCndVo0kLepS4yW7Na4fE7dj3QoDU67BA@mail.gmail.com>"
+    );
+    assert_eq!(response["signatures_tried"], 1);
+    assert_eq!(response["signatures_skipped"], 0);
+    assert_eq!(response["mailing_list_signals"], serde_json::json!([]));
+}
+
+#[test]
+fn v2_counts_every_signature_checked() {
+    clear_dns_stubs();
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/two_signatures.eml");
+    let args = |original_record: String| {
+        serde_json::json!({
+            "require_all_signatures": true,
+            "pinned_keys": [
+                { "domain": "forwarder.example", "selector": "fwd", "records": [test_key_dkim_record()] },
+                { "domain": "example.com", "selector": "test", "records": [original_record] },
+            ],
+        })
+    };
+
+    let response =
+        verify_test_key_email_with_method("verify-encrypted-email-v2", email, args(test_key_dkim_record()));
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    assert_eq!(response["signing_domain"], "forwarder.example");
+    assert_eq!(response["selector"], "fwd");
+    assert_eq!(response["signatures_tried"], 2);
+    assert_eq!(response["message_id"], serde_json::Value::Null);
+
+    let response = verify_test_key_email_with_method(
+        "verify-encrypted-email-v2",
+        email,
+        args(GMAIL_DKIM_RECORD.to_string()),
+    );
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "signature_invalid");
+    assert_eq!(response["signing_domain"], "forwarder.example");
+    assert_eq!(response["signatures_tried"], 2);
+}
+
+#[test]
+fn v2_signing_domain_is_the_domain_whose_key_verified() {
+    clear_dns_stubs();
+    // Broken forwarder signature first; the `d=example.com` one was made with
+    // the forwarder's key, which example.com does not publish.
+    let email = include_str!("../../email-dkim-verifier-contract/tests/data/two_signatures.eml")
+        .replacen("b=WWs6S/14", "b=AAAAS/14", 1);
+    set_dns_stub("fwd._domainkey.forwarder.example", vec![test_key_dkim_record()]);
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![GMAIL_DKIM_RECORD.to_string()]);
+    let response =
+        verify_test_key_email_with_method("verify-encrypted-email-v2", email.as_bytes(), serde_json::json!({}));
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["signing_domain"], "forwarder.example");
+    assert_eq!(response["signatures_tried"], 2);
+
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![test_key_dkim_record()]);
+    let response =
+        verify_test_key_email_with_method("verify-encrypted-email-v2", email.as_bytes(), serde_json::json!({}));
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    assert_eq!(response["signing_domain"], "example.com");
+    assert_eq!(response["selector"], "test");
+    assert_eq!(response["signatures_tried"], 2);
+}
+
+#[test]
+fn max_signatures_caps_the_signatures_checked() {
+    clear_dns_stubs();
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/two_signatures.eml");
+    let args = |max_signatures: usize| {
+        serde_json::json!({
+            "require_all_signatures": true,
+            "max_signatures": max_signatures,
+            "pinned_keys": [
+                { "domain": "forwarder.example", "selector": "fwd", "records": [test_key_dkim_record()] },
+                { "domain": "example.com", "selector": "test", "records": [test_key_dkim_record()] },
+            ],
+        })
+    };
+
+    let response = verify_test_key_email_with_method("verify-encrypted-email-v2", email, args(2));
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    assert_eq!(response["signatures_skipped"], 0);
+
+    // The second signature is never checked, so not every signature verified.
+    let response = verify_test_key_email_with_method("verify-encrypted-email-v2", email, args(1));
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "signature_invalid");
+    assert_eq!(response["signatures_tried"], 1);
+    assert_eq!(response["signatures_skipped"], 1);
+}
+
+#[test]
+fn v1_response_omits_v2_diagnostics() {
+    clear_dns_stubs();
+    set_dns_stub("fwd._domainkey.forwarder.example", vec![test_key_dkim_record()]);
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/two_signatures.eml");
+    let response = verify_test_key_email_with_args(email, serde_json::json!({}));
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    // The contract rate limits by the verified domain, so v1 reports it too.
+    assert_eq!(response["signing_domain"], "forwarder.example");
+    for field in ["message_id", "signatures_tried", "signatures_skipped"] {
+        assert!(response.get(field).is_none(), "v1 response has {field}");
+    }
+}
+
+/// Check `signature_b64` over the canonical JSON of the other response fields
+/// against the `signing_public_key` that `get-public-key` publishes.
+fn worker_signature_verifies(response: &serde_json::Value) -> bool {
+    let request = RequestType {
+        method: "get-public-key".to_string(),
+        args: serde_json::json!({}),
+    };
+    let published = handle_request_with_keys(request, Ok(&test_worker_keys())).response;
+    let pk = base64::decode(published["signing_public_key"].as_str().unwrap()).unwrap();
+    let verifying_key = VerifyingKey::from_bytes(&pk.try_into().unwrap()).unwrap();
+
+    let mut signed = response.clone();
+    let Some(sig) = signed.as_object_mut().and_then(|f| f.remove("signature_b64")) else {
+        return false;
+    };
+    let sig: [u8; 64] = base64::decode(sig.as_str().unwrap()).unwrap().try_into().unwrap();
+    verifying_key
+        .verify(canonical_json(&signed).as_bytes(), &Signature::from_bytes(&sig))
+        .is_ok()
+}
+
+#[test]
+fn verified_result_is_signed_by_the_worker() {
+    clear_dns_stubs();
+    set_dns_stub("fwd._domainkey.forwarder.example", vec![test_key_dkim_record()]);
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/two_signatures.eml");
+    let response = verify_test_key_email_with_args(email, serde_json::json!({}));
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    assert!(worker_signature_verifies(&response));
+
+    let mut tampered = response.clone();
+    tampered["account_id"] = serde_json::json!("mallory.testnet");
+    assert!(!worker_signature_verifies(&tampered));
+}
+
+#[test]
+fn failed_verification_is_not_signed() {
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/empty_body.eml");
+    set_dns_stub(TEST_KEY_DKIM_NAME, vec![GMAIL_DKIM_RECORD.to_string()]);
+    let response = verify_test_key_email(email);
+    assert_eq!(response["verified"], false);
+    assert!(response.get("signature_b64").is_none());
+}
+
+#[test]
+fn v2_signature_covers_only_v1_fields() {
+    clear_dns_stubs();
+    set_dns_stub("fwd._domainkey.forwarder.example", vec![test_key_dkim_record()]);
+    let email: &[u8] =
+        include_bytes!("../../email-dkim-verifier-contract/tests/data/two_signatures.eml");
+    let mut response =
+        verify_test_key_email_with_method("verify-encrypted-email-v2", email, serde_json::json!({}));
+    assert_eq!(response["verified"], true, "{}", response["error"]);
+    assert!(!worker_signature_verifies(&response));
+
+    let fields = response.as_object_mut().unwrap();
+    for diagnostic in [
+        "message_id",
+        "signatures_tried",
+        "signatures_skipped",
+        "mailing_list_signals",
+    ] {
+        fields.remove(diagnostic);
+    }
+    assert!(worker_signature_verifies(&response));
 }
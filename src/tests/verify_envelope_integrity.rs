@@ -0,0 +1,84 @@
+use crate::api::{handle_request, RequestType};
+use serde_json::{json, Value};
+
+const GMAIL_EMAIL: &str =
+    include_str!("../../email-dkim-verifier-contract/tests/data/gmail_reset_full.eml");
+
+const GMAIL_BH: &str = "DybNgKUUAMbDmXWMvqU5XUFnq2CTnP980Z2v48D+cIc=";
+
+/// SHA-256 of `Hello world\r\n`, the simple-canonicalized body below.
+const HELLO_WORLD_BH: &str = "yGIXoM91E1DiKjvCBcC8NlWyw54TdfMQ08sdtwtOO4I=";
+
+fn verify_envelope_integrity(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "verify-envelope-integrity".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "verify-envelope-integrity");
+    response.response
+}
+
+fn simple_body_email(c_tag: &str) -> String {
+    format!(
+        "DKIM-Signature: v=1; a=rsa-sha256; {c_tag}d=example.com; s=test;\r\n\
+         \th=from; bh={HELLO_WORLD_BH}; b=AAAA\r\n\
+         From: a@example.com\r\n\
+         \r\n\
+         Hello world\r\n\
+         \r\n\
+         \r\n"
+    )
+}
+
+#[test]
+fn untouched_gmail_body_matches() {
+    let response = verify_envelope_integrity(json!({ "email_blob": GMAIL_EMAIL }));
+    assert_eq!(response["body_hash_matches"], true);
+    assert_eq!(response["bh_expected_b64"], GMAIL_BH);
+    assert_eq!(response["bh_computed_b64"], GMAIL_BH);
+    assert_eq!(response["canon_algorithm"], "relaxed");
+}
+
+#[test]
+fn tampered_gmail_body_does_not_match() {
+    let tampered = GMAIL_EMAIL.replacen(
+        "<div dir=\"ltr\"><br></div>",
+        "<div dir=\"ltr\">tampered</div>",
+        1,
+    );
+    let response = verify_envelope_integrity(json!({ "email_blob": tampered }));
+    assert_eq!(response["body_hash_matches"], false);
+    assert_eq!(response["bh_expected_b64"], GMAIL_BH);
+    assert_ne!(response["bh_computed_b64"], GMAIL_BH);
+}
+
+#[test]
+fn uses_simple_body_canonicalization_from_c_tag() {
+    for c_tag in ["c=relaxed/simple; ", ""] {
+        let response = verify_envelope_integrity(json!({ "email_blob": simple_body_email(c_tag) }));
+        assert_eq!(response["body_hash_matches"], true, "{c_tag:?}: {response}");
+        assert_eq!(response["canon_algorithm"], "simple");
+        assert_eq!(response["bh_computed_b64"], HELLO_WORLD_BH);
+    }
+
+    // Simple canonicalization keeps trailing whitespace, so it breaks the hash.
+    let email = simple_body_email("c=relaxed/simple; ").replace("Hello world", "Hello world ");
+    let response = verify_envelope_integrity(json!({ "email_blob": email }));
+    assert_eq!(response["body_hash_matches"], false);
+}
+
+#[test]
+fn email_without_dkim_signature_is_an_error() {
+    let response =
+        verify_envelope_integrity(json!({ "email_blob": "From: a@example.com\r\n\r\nHi\r\n" }));
+    assert_eq!(response["error"], "no DKIM-Signature header");
+}
+
+#[test]
+fn missing_email_blob_is_an_error() {
+    let response = verify_envelope_integrity(json!({}));
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("invalid verify-envelope-integrity args"));
+}
@@ -0,0 +1,106 @@
+use crate::api::{handle_request, RequestType};
+use crate::parsers::{canonicalize_body_relaxed, canonicalize_headers_relaxed};
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey};
+use rsa::sha2::{Digest, Sha256};
+use rsa::signature::hazmat::PrehashSigner;
+use rsa::signature::SignatureEncoding;
+use rsa::RsaPrivateKey;
+use serde_json::{json, Value};
+
+const TEST_KEY_PEM: &str =
+    include_str!("../../email-dkim-verifier-contract/tests/data/test_dkim_private_key.pem");
+
+fn headers() -> Vec<(String, String)> {
+    vec![
+        ("X-Payload-Id".to_string(), " order-42".to_string()),
+        ("X-Issuer".to_string(), "  shop.example   ".to_string()),
+    ]
+}
+
+const BODY: &str = "amount: 1000\r\ncurrency:  USD \r\n";
+
+/// Signs the payload the same way an integrator would: relaxed headers, then
+/// relaxed body, SHA-256, RSASSA-PKCS1-v1_5.
+fn sign_payload(headers: &[(String, String)], body: &str) -> (String, String) {
+    let key = RsaPrivateKey::from_pkcs8_pem(TEST_KEY_PEM).expect("test key");
+    let names: Vec<String> = headers.iter().map(|(n, _)| n.to_ascii_lowercase()).collect();
+    let mut data = canonicalize_headers_relaxed(headers, &names);
+    data.push_str(&canonicalize_body_relaxed(body));
+
+    let public_key_der = key.to_public_key().to_public_key_der().expect("spki");
+    let signature = SigningKey::<Sha256>::new(key)
+        .sign_prehash(&Sha256::digest(data.as_bytes()))
+        .expect("sign");
+    (
+        base64::encode(signature.to_bytes()),
+        base64::encode(public_key_der.as_bytes()),
+    )
+}
+
+fn verify(args: Value) -> Value {
+    let response = handle_request(RequestType {
+        method: "verify-signed-payload".to_string(),
+        args,
+    });
+    assert_eq!(response.method, "verify-signed-payload");
+    response.response
+}
+
+#[test]
+fn signed_payload_verifies() {
+    let (signature_b64, public_key_der_b64) = sign_payload(&headers(), BODY);
+
+    let response = verify(json!({
+        "headers": headers(),
+        "body": BODY,
+        "signature_b64": signature_b64,
+        "public_key_der_b64": public_key_der_b64,
+        "algorithm": "rsa-sha256",
+    }));
+
+    assert_eq!(response, json!({ "verified": true }));
+}
+
+#[test]
+fn tampered_payload_fails() {
+    let (signature_b64, public_key_der_b64) = sign_payload(&headers(), BODY);
+
+    let response = verify(json!({
+        "headers": headers(),
+        "body": BODY.replace("1000", "9000"),
+        "signature_b64": signature_b64,
+        "public_key_der_b64": public_key_der_b64,
+    }));
+    assert_eq!(response["verified"], false);
+    assert_eq!(response["error"], "signature verification failed");
+
+    let mut tampered_headers = headers();
+    tampered_headers[1].1 = "evil.example".to_string();
+    let response = verify(json!({
+        "headers": tampered_headers,
+        "body": BODY,
+        "signature_b64": signature_b64,
+        "public_key_der_b64": public_key_der_b64,
+    }));
+    assert_eq!(response["verified"], false);
+}
+
+#[test]
+fn unsupported_algorithm_is_rejected() {
+    let (signature_b64, public_key_der_b64) = sign_payload(&headers(), BODY);
+
+    let response = verify(json!({
+        "headers": headers(),
+        "body": BODY,
+        "signature_b64": signature_b64,
+        "public_key_der_b64": public_key_der_b64,
+        "algorithm": "ed25519-sha256",
+    }));
+
+    assert_eq!(response["verified"], false);
+    assert!(response["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("unsupported algorithm: ed25519-sha256"));
+}
@@ -0,0 +1,54 @@
+use crate::api::{handle_request_with_keys, RequestType};
+use crate::crypto::WorkerKeys;
+use super::crypto::test_worker_keys;
+use serde_json::Value;
+
+fn self_test(keys: Result<&WorkerKeys, &str>) -> Value {
+    let response = handle_request_with_keys(
+        RequestType {
+            method: "worker-self-test".to_string(),
+            args: Value::Null,
+        },
+        keys,
+    );
+    assert_eq!(response.method, "worker-self-test");
+    response.response
+}
+
+fn step_names(response: &Value) -> Vec<&str> {
+    response["results"]
+        .as_array()
+        .expect("results array")
+        .iter()
+        .map(|r| r["step"].as_str().expect("step name"))
+        .collect()
+}
+
+#[test]
+fn self_test_passes_with_worker_secret() {
+    let response = self_test(Ok(&test_worker_keys()));
+    assert_eq!(response["all_passed"], true, "{response}");
+    assert_eq!(
+        step_names(&response),
+        ["get_worker_public_key", "parse_dkim_tags", "verify_dkim", "parse_email_timestamp_ms"]
+    );
+    for result in response["results"].as_array().unwrap() {
+        assert_eq!(result["ok"], true);
+        assert_eq!(result["error"], Value::Null);
+    }
+}
+
+#[test]
+fn self_test_reports_missing_secret_and_runs_remaining_steps() {
+    let keys = WorkerKeys::from_vars(|_| None);
+    let response = self_test(keys.as_ref().map_err(String::as_str));
+    assert_eq!(response["all_passed"], false);
+
+    let results = response["results"].as_array().expect("results array");
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0]["step"], "get_worker_public_key");
+    assert_eq!(results[0]["ok"], false);
+    let error = results[0]["error"].as_str().unwrap_or_default();
+    assert!(error.starts_with("Secrets Not Found"), "{error}");
+    assert!(results[1..].iter().all(|r| r["ok"] == true), "{response}");
+}
@@ -1,30 +1,186 @@
 use base64;
 use rsa::pkcs1v15::{Signature as RsaSignature, VerifyingKey};
+use rsa::pkcs1::DecodeRsaPublicKey;
 use rsa::pkcs8::DecodePublicKey;
 use rsa::sha2::{Digest, Sha256};
 use rsa::signature::hazmat::PrehashVerifier;
 use rsa::RsaPublicKey;
+use std::collections::HashMap;
+use std::fmt;
 
 use crate::parsers::{
-    build_canonicalized_dkim_header_relaxed, canonicalize_body_relaxed,
-    canonicalize_headers_relaxed, parse_dkim_tags, parse_headers, split_headers_body,
+    build_canonicalized_dkim_header_relaxed, build_canonicalized_dkim_header_simple,
+    canonicalize_body_relaxed, canonicalize_body_relaxed_bytes, canonicalize_body_simple_bytes,
+    canonicalize_header_relaxed, canonicalize_headers_relaxed, canonicalize_headers_simple,
+    parse_dkim_canonicalization, parse_dkim_tags, parse_headers, split_headers_body,
+    split_headers_body_bytes, strip_bom, strip_bom_bytes, strip_dkim_b_value,
+    undo_dot_stuffing_bytes, Canonicalization,
 };
+#[cfg(any(test, feature = "debug-dkim"))]
+use crate::parsers::canonicalize_body_simple;
 
-pub fn verify_dkim(email_blob: &str, dns_records: &[String]) -> bool {
-    let (raw_headers, body) = split_headers_body(email_blob);
+/// DKIM `a=` algorithms a signature can verify with.
+pub const SUPPORTED_DKIM_ALGORITHMS: [&str; 1] = ["rsa-sha256"];
+
+/// DKIM-Signature headers tried per message unless the contract sets its own
+/// cap. Each one can cost an RSA verification, so a message carrying hundreds
+/// must not make the worker try them all.
+pub const DEFAULT_MAX_DKIM_SIGNATURES: usize = 5;
+
+/// Why no DKIM-Signature on a message verified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DkimError {
+    /// Nothing verified and at least one signature used an algorithm that is
+    /// refused outright (`rsa-sha1`), rather than merely not implemented.
+    UnsupportedAlgorithm(String),
+    /// No DKIM-Signature verified.
+    VerificationFailed,
+}
+
+impl fmt::Display for DkimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DkimError::UnsupportedAlgorithm(detail) => {
+                write!(f, "unsupported DKIM algorithm {detail}")
+            }
+            DkimError::VerificationFailed => f.write_str("DKIM verification failed"),
+        }
+    }
+}
+
+/// The DKIM-Signature that verified: its `d=` domain and `s=` selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedDkimSignature {
+    pub domain: String,
+    pub selector: String,
+    /// DKIM-Signature headers checked, up to and including this one (all of
+    /// them when every signature had to verify).
+    pub signatures_tried: usize,
+}
+
+pub fn verify_dkim_signature(
+    email_blob: &str,
+    dns_records: &[String],
+) -> Result<VerifiedDkimSignature, DkimError> {
+    verify_dkim_signature_bytes(email_blob.as_bytes(), dns_records)
+}
+
+/// Like `verify_dkim_signature`, for a raw message whose body need not be UTF-8
+/// (e.g. `Content-Transfer-Encoding: 8bit` in Latin-1). Headers must be UTF-8;
+/// the body hash is computed over the original bytes.
+#[cfg_attr(not(test), allow(dead_code))] // the worker always passes its dot-stuffing option
+pub fn verify_dkim_signature_bytes(
+    email: &[u8],
+    dns_records: &[String],
+) -> Result<VerifiedDkimSignature, DkimError> {
+    verify_dkim_signature_bytes_with_dot_stuffing(
+        email,
+        dns_records,
+        false,
+        DEFAULT_MAX_DKIM_SIGNATURES,
+    )
+}
+
+/// Like `verify_dkim_signature_bytes`; with `undo_dot_stuffing` the body is
+/// un-stuffed (`undo_dot_stuffing_bytes`) before canonicalization, for emails
+/// captured from raw SMTP DATA. Only the first `max_signatures`
+/// DKIM-Signature headers are tried.
+///
+/// Every signature is checked against `dns_records`, whatever its `d=`/`s=`;
+/// callers verifying a message from several signers use
+/// `verify_any_dkim_signature_bytes_with_dot_stuffing` instead.
+#[cfg_attr(not(test), allow(dead_code))] // the worker looks keys up per signature
+pub fn verify_dkim_signature_bytes_with_dot_stuffing(
+    email: &[u8],
+    dns_records: &[String],
+    undo_dot_stuffing: bool,
+    max_signatures: usize,
+) -> Result<VerifiedDkimSignature, DkimError> {
+    verify_signatures(email, undo_dot_stuffing, false, max_signatures, |_, _| {
+        public_key_from_records(dns_records.iter().map(String::as_str))
+    })
+}
+
+/// Like `verify_dkim_signature_bytes_with_dot_stuffing`, but each
+/// DKIM-Signature is checked against `records_for(domain, selector)`, so the
+/// `d=` reported for the one that verified is the domain whose key verified it.
+pub fn verify_any_dkim_signature_bytes_with_dot_stuffing(
+    email: &[u8],
+    undo_dot_stuffing: bool,
+    max_signatures: usize,
+    records_for: impl Fn(&str, &str) -> Vec<String>,
+) -> Result<VerifiedDkimSignature, DkimError> {
+    verify_signatures(email, undo_dot_stuffing, false, max_signatures, |domain, selector| {
+        public_key_from_records(records_for(domain, selector).iter().map(String::as_str))
+    })
+}
+
+/// Like `verify_dkim_signature_bytes_with_dot_stuffing`, but fails unless
+/// every DKIM-Signature verifies, each against `records_for(domain, selector)`;
+/// returns the first one. A message with more than `max_signatures`
+/// signatures therefore never verifies.
+pub fn verify_all_dkim_signatures_bytes_with_dot_stuffing(
+    email: &[u8],
+    undo_dot_stuffing: bool,
+    max_signatures: usize,
+    records_for: impl Fn(&str, &str) -> Vec<String>,
+) -> Result<VerifiedDkimSignature, DkimError> {
+    verify_signatures(email, undo_dot_stuffing, true, max_signatures, |domain, selector| {
+        public_key_from_records(records_for(domain, selector).iter().map(String::as_str))
+    })
+}
+
+/// Verify against `public_key` directly, skipping DNS. Native test helper only;
+/// never compiled into the wasm32 worker.
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn verify_dkim_with_explicit_key(email_blob: &str, public_key: &RsaPublicKey) -> bool {
+    verify_signatures(email_blob.as_bytes(), false, false, DEFAULT_MAX_DKIM_SIGNATURES, |_, _| {
+        Some(public_key.clone())
+    })
+    .is_ok()
+}
+
+/// Shared verification loop; `key_for(domain, selector)` returns the public key
+/// a signature is checked against. With `require_all`, every signature must
+/// verify rather than any one. Signatures past the first `max_signatures`
+/// are skipped without being looked at.
+fn verify_signatures(
+    email: &[u8],
+    undo_dot_stuffing: bool,
+    require_all: bool,
+    max_signatures: usize,
+    key_for: impl Fn(&str, &str) -> Option<RsaPublicKey>,
+) -> Result<VerifiedDkimSignature, DkimError> {
+    let (raw_headers, body) = split_headers_body_bytes(strip_bom_bytes(email));
+    let unstuffed;
+    let body = if undo_dot_stuffing {
+        unstuffed = undo_dot_stuffing_bytes(body);
+        unstuffed.as_slice()
+    } else {
+        body
+    };
+    let raw_headers =
+        std::str::from_utf8(raw_headers).map_err(|_| DkimError::VerificationFailed)?;
     let headers = parse_headers(raw_headers);
 
-    let dkim_values: Vec<String> = headers
+    let dkim_values: Vec<(String, String)> = headers
         .iter()
         .filter(|(name, _)| name.eq_ignore_ascii_case("DKIM-Signature"))
-        .map(|(_, v)| v.clone())
+        .cloned()
         .collect();
 
     if dkim_values.is_empty() {
-        return false;
+        return Err(DkimError::VerificationFailed);
     }
 
-    'signatures: for dkim_value in dkim_values {
+    let signature_count = dkim_values.len();
+    let mut verified = Vec::new();
+    let mut rejected = None;
+
+    'signatures: for (index, (dkim_name, dkim_value)) in
+        dkim_values.into_iter().take(max_signatures).enumerate()
+    {
         let tags = parse_dkim_tags(&dkim_value);
 
         if let Some(v) = tags.get("v") {
@@ -42,27 +198,26 @@ pub fn verify_dkim(email_blob: &str, dns_records: &[String]) -> bool {
             _ => continue 'signatures,
         };
 
-        let _ = (d, s);
-
         match tags.get("a").map(String::as_str) {
-            Some("rsa-sha256") => {}
+            Some(a) if SUPPORTED_DKIM_ALGORITHMS.contains(&a) => {}
+            Some(a) if a.eq_ignore_ascii_case("rsa-sha1") => {
+                rejected = Some(DkimError::UnsupportedAlgorithm(format!("{a}: sha1 disallowed")));
+                continue 'signatures;
+            }
             _ => continue 'signatures,
         }
 
-        let canon = tags.get("c").map(String::as_str).unwrap_or("simple/simple");
-        if canon != "relaxed/relaxed" {
-            continue 'signatures;
-        }
+        let (header_canon, body_canon) =
+            match parse_dkim_canonicalization(tags.get("c").map(String::as_str)) {
+                Some(c) => c,
+                None => continue 'signatures,
+            };
 
         let bh_b64 = match tags.get("bh") {
             Some(v) if !v.is_empty() => v,
             _ => continue 'signatures,
         };
-        let bh_clean: String = bh_b64
-            .chars()
-            .filter(|c| c.is_ascii_alphanumeric() || *c == '+' || *c == '/' || *c == '=')
-            .collect();
-        let bh = match base64::decode(&bh_clean) {
+        let bh = match base64::decode(clean_base64(bh_b64)) {
             Ok(v) => v,
             Err(_) => continue 'signatures,
         };
@@ -71,24 +226,21 @@ pub fn verify_dkim(email_blob: &str, dns_records: &[String]) -> bool {
             Some(v) if !v.is_empty() => v,
             _ => continue 'signatures,
         };
-        let b_clean: String = b_b64
-            .chars()
-            .filter(|c| c.is_ascii_alphanumeric() || *c == '+' || *c == '/' || *c == '=')
-            .collect();
-        let signature = match base64::decode(&b_clean) {
+        let signature = match base64::decode(clean_base64(b_b64)) {
             Ok(v) => v,
             Err(_) => continue 'signatures,
         };
 
-        let h_list = match tags.get("h") {
-            Some(v) if !v.is_empty() => v,
-            _ => continue 'signatures,
+        let signed_headers = match signed_header_names(&tags) {
+            Ok(h) => h,
+            Err(_) => continue 'signatures,
         };
-        let signed_headers: Vec<String> =
-            h_list.split(':').map(|s| s.trim().to_ascii_lowercase()).collect();
 
-        let canon_body = canonicalize_body_relaxed(body);
-        let body_bytes = canon_body.as_bytes();
+        let canon_body = match body_canon {
+            Canonicalization::Simple => canonicalize_body_simple_bytes(body),
+            Canonicalization::Relaxed => canonicalize_body_relaxed_bytes(body),
+        };
+        let body_bytes = canon_body.as_slice();
         let body_to_hash: &[u8] = if let Some(l_str) = tags.get("l") {
             let l_val = match l_str.parse::<u128>() {
                 Ok(v) => v,
@@ -109,61 +261,338 @@ pub fn verify_dkim(email_blob: &str, dns_records: &[String]) -> bool {
             continue 'signatures;
         }
 
-        let canon_headers = canonicalize_headers_relaxed(&headers, &signed_headers);
-        let canon_dkim_header = build_canonicalized_dkim_header_relaxed(&dkim_value);
-        let mut data = canon_headers;
-        data.push_str(&canon_dkim_header);
+        let data =
+            signed_header_data(&headers, &signed_headers, &dkim_name, &dkim_value, header_canon);
 
         let mut hasher = Sha256::new();
         hasher.update(data.as_bytes());
         let data_hash = hasher.finalize().to_vec();
 
-        let mut pk_bytes_opt = None;
-        for rec in dns_records {
-            let key_tags = parse_dkim_tags(rec);
+        let public_key = match key_for(d, s) {
+            Some(k) => k,
+            None => continue 'signatures,
+        };
+
+        if verify_rsa_sha256_prehash(public_key, &data_hash, &signature) {
+            let signature = VerifiedDkimSignature {
+                domain: d.clone(),
+                selector: s.clone(),
+                signatures_tried: index + 1,
+            };
+            if !require_all {
+                return Ok(signature);
+            }
+            verified.push(signature);
+        }
+    }
+
+    if require_all && verified.len() == signature_count {
+        let mut signature = verified.swap_remove(0);
+        signature.signatures_tried = signature_count;
+        return Ok(signature);
+    }
+    Err(rejected.unwrap_or(DkimError::VerificationFailed))
+}
+
+/// Verify a detached rsa-sha256 signature over a DKIM-style payload that is
+/// not an email.
+///
+/// The signed data is the relaxed-canonicalized `headers` (all of them, in the
+/// given order; repeated names are matched bottom-up as with DKIM `h=`)
+/// followed by the relaxed-canonicalized `body`. `public_key_der` is SPKI or
+/// bare PKCS#1 DER, as accepted by `parse_rsa_public_key`.
+pub fn verify_signed_payload(
+    headers: &[(String, String)],
+    body: &str,
+    signature: &[u8],
+    public_key_der: &[u8],
+) -> Result<(), String> {
+    let names: Vec<String> = headers
+        .iter()
+        .map(|(name, _)| name.trim().to_ascii_lowercase())
+        .collect();
+    let mut data = canonicalize_headers_relaxed(headers, &names);
+    data.push_str(&canonicalize_body_relaxed(body));
+
+    let public_key = parse_rsa_public_key(public_key_der)?;
+    let data_hash = Sha256::digest(data.as_bytes());
+    if verify_rsa_sha256_prehash(public_key, &data_hash, signature) {
+        Ok(())
+    } else {
+        Err("signature verification failed".to_string())
+    }
+}
+
+/// Whether any of `dns_records` is a usable DKIM RSA key record, i.e. whether a
+/// signature under this selector could be checked at all.
+pub fn has_dkim_public_key(dns_records: &[String]) -> bool {
+    public_key_from_records(dns_records.iter().map(String::as_str)).is_some()
+}
+
+/// The RSA public key from the first usable DKIM key record (`p=` tag).
+fn public_key_from_records<'a>(records: impl IntoIterator<Item = &'a str>) -> Option<RsaPublicKey> {
+    let mut pk_bytes_opt = None;
+    for rec in records {
+        let key_tags = parse_dkim_tags(rec);
 
-            if let Some(v) = key_tags.get("v") {
-                if v != "DKIM1" {
-                    continue;
-                }
+        if let Some(v) = key_tags.get("v") {
+            if v != "DKIM1" {
+                continue;
             }
-            if let Some(k) = key_tags.get("k") {
-                if k.to_ascii_lowercase() != "rsa" {
-                    continue;
-                }
+        }
+        if let Some(k) = key_tags.get("k") {
+            if k.to_ascii_lowercase() != "rsa" {
+                continue;
             }
+        }
 
-            if let Some(p) = key_tags.get("p") {
-                if p.is_empty() {
-                    continue;
-                }
-                if let Ok(bytes) = base64::decode(p) {
-                    pk_bytes_opt = Some(bytes);
-                    break;
-                }
+        if let Some(p) = key_tags.get("p") {
+            if p.is_empty() {
+                continue;
+            }
+            if let Ok(bytes) = base64::decode(p) {
+                pk_bytes_opt = Some(bytes);
+                break;
             }
         }
-        let pk_bytes = match pk_bytes_opt {
-            Some(v) => v,
-            None => continue 'signatures,
-        };
+    }
 
-        let public_key = match RsaPublicKey::from_public_key_der(&pk_bytes) {
-            Ok(k) => k,
-            Err(_) => continue 'signatures,
-        };
+    parse_rsa_public_key(&pk_bytes_opt?).ok()
+}
 
-        let verifying_key = VerifyingKey::<Sha256>::new(public_key);
-        let sig = match RsaSignature::try_from(signature.as_slice()) {
-            Ok(s) => s,
-            Err(_) => continue 'signatures,
-        };
+/// The header data hashed for a `b=` signature: the `signed_headers` fields
+/// under `canon`, then the DKIM-Signature field itself (`dkim_name` as it
+/// appears in the message) with an empty `b=` and no trailing CRLF
+/// (RFC 6376 §3.7).
+fn signed_header_data(
+    headers: &[(String, String)],
+    signed_headers: &[String],
+    dkim_name: &str,
+    dkim_value: &str,
+    canon: Canonicalization,
+) -> String {
+    match canon {
+        Canonicalization::Simple => {
+            let mut data = canonicalize_headers_simple(headers, signed_headers);
+            data.push_str(&build_canonicalized_dkim_header_simple(dkim_name, dkim_value));
+            data
+        }
+        Canonicalization::Relaxed => {
+            let mut data = canonicalize_headers_relaxed(headers, signed_headers);
+            data.push_str(&build_canonicalized_dkim_header_relaxed(dkim_value));
+            data
+        }
+    }
+}
+
+/// Drop folding whitespace (and anything else outside the base64 alphabet)
+/// that some signers leave inside `b=` / `bh=` values.
+fn clean_base64(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '+' || *c == '/' || *c == '=')
+        .collect()
+}
+
+/// The `dkim_header_index`-th (0-based) DKIM-Signature field of `headers`,
+/// as its name and value.
+fn nth_dkim_signature(
+    headers: &[(String, String)],
+    dkim_header_index: usize,
+) -> Result<(String, String), String> {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("DKIM-Signature"))
+        .nth(dkim_header_index)
+        .cloned()
+        .ok_or_else(|| format!("no DKIM-Signature header at index {dkim_header_index}"))
+}
 
-        if verifying_key.verify_prehash(&data_hash, &sig).is_ok() {
-            return true;
+/// The signed `h=` header names of a DKIM-Signature, lowercased.
+fn signed_header_names(tags: &HashMap<String, String>) -> Result<Vec<String>, String> {
+    match tags.get("h") {
+        Some(v) if !v.is_empty() => {
+            Ok(v.split(':').map(|s| s.trim().to_ascii_lowercase()).collect())
         }
+        _ => Err("missing h= tag in DKIM-Signature".to_string()),
     }
+}
+
+/// Debug helper: the exact header data hashed before checking the
+/// `dkim_header_index`-th (0-based) DKIM-Signature, using header
+/// canonicalization `canon` (whatever its `c=` says), and that signature's
+/// `b=` value with folding whitespace removed.
+pub fn canonicalized_signed_headers(
+    email_blob: &str,
+    dkim_header_index: usize,
+    canon: Canonicalization,
+) -> Result<(String, String), String> {
+    let (raw_headers, _) = split_headers_body(strip_bom(email_blob));
+    let headers = parse_headers(raw_headers);
+    let (dkim_name, dkim_value) = nth_dkim_signature(&headers, dkim_header_index)?;
+    let tags = parse_dkim_tags(&dkim_value);
+    let signed_headers = signed_header_names(&tags)?;
 
-    false
+    let data = signed_header_data(&headers, &signed_headers, &dkim_name, &dkim_value, canon);
+    let b_clean = clean_base64(tags.get("b").map(String::as_str).unwrap_or_default());
+    Ok((data, b_clean))
 }
 
+/// The header data hashed for the `dkim_header_index`-th (0-based)
+/// DKIM-Signature under its own `c=` header canonicalization, with the `h=`
+/// names it lists (lowercased, repeats kept).
+pub fn dkim_header_signing_data(
+    email_blob: &str,
+    dkim_header_index: usize,
+) -> Result<(String, Vec<String>), String> {
+    let (raw_headers, _) = split_headers_body(strip_bom(email_blob));
+    let headers = parse_headers(raw_headers);
+    let (dkim_name, dkim_value) = nth_dkim_signature(&headers, dkim_header_index)?;
+    let tags = parse_dkim_tags(&dkim_value);
+
+    let c_tag = tags.get("c").map(String::as_str);
+    let (header_canon, _) = parse_dkim_canonicalization(c_tag)
+        .ok_or_else(|| format!("unsupported canonicalization: {}", c_tag.unwrap_or_default()))?;
+    let signed_headers = signed_header_names(&tags)?;
+
+    let data =
+        signed_header_data(&headers, &signed_headers, &dkim_name, &dkim_value, header_canon);
+    Ok((data, signed_headers))
+}
+
+/// One header field covered by a DKIM-Signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedHeaderField {
+    pub name: String,
+    /// Field value as parsed (folding kept as `\r\n`).
+    pub raw_value: String,
+    /// Field value under relaxed header canonicalization.
+    pub canonical_value: String,
+}
+
+/// The header fields the `dkim_header_index`-th (0-based) DKIM-Signature
+/// covers, in `h=` order, each picked bottom-up as for hashing; `h=` names
+/// with no (remaining) field are skipped, since they hash as nothing. Ends
+/// with the DKIM-Signature field itself with its `b=` value emptied.
+pub fn dkim_signed_header_fields(
+    email_blob: &str,
+    dkim_header_index: usize,
+) -> Result<Vec<SignedHeaderField>, String> {
+    let (raw_headers, _) = split_headers_body(strip_bom(email_blob));
+    let headers = parse_headers(raw_headers);
+    let (dkim_name, dkim_value) = nth_dkim_signature(&headers, dkim_header_index)?;
+    let signed_headers = signed_header_names(&parse_dkim_tags(&dkim_value))?;
+
+    let mut fields = Vec::new();
+    let mut used = vec![false; headers.len()];
+    for signed in &signed_headers {
+        let selected = (0..headers.len()).rev().find(|&idx| {
+            !used[idx] && headers[idx].0.trim_end().eq_ignore_ascii_case(signed)
+        });
+        if let Some(idx) = selected {
+            let (name, value) = &headers[idx];
+            fields.push(SignedHeaderField {
+                name: name.trim_end().to_string(),
+                raw_value: value.clone(),
+                canonical_value: canonicalize_header_relaxed(value.clone()),
+            });
+            used[idx] = true;
+        }
+    }
+
+    let cleared = strip_dkim_b_value(&dkim_value);
+    fields.push(SignedHeaderField {
+        name: dkim_name.trim_end().to_string(),
+        canonical_value: canonicalize_header_relaxed(cleared.clone()),
+        raw_value: cleared,
+    });
+    Ok(fields)
+}
+
+/// RSASSA-PKCS1-v1_5 check of `signature` against a SHA-256 `data_hash`.
+fn verify_rsa_sha256_prehash(public_key: RsaPublicKey, data_hash: &[u8], signature: &[u8]) -> bool {
+    let verifying_key = VerifyingKey::<Sha256>::new(public_key);
+    match RsaSignature::try_from(signature) {
+        Ok(sig) => verifying_key.verify_prehash(data_hash, &sig).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Debug helper: the rsa-sha256 check alone, over the SHA-256 `digest` of the
+/// signed data, with a DER SubjectPublicKeyInfo key. `Err` is for malformed
+/// input; `Ok(false)` means the signature does not match.
+#[cfg(any(test, feature = "debug-methods"))]
+pub fn verify_rsa_sha256_digest(
+    spki_der: &[u8],
+    digest: &[u8],
+    signature: &[u8],
+) -> Result<bool, String> {
+    let public_key = RsaPublicKey::from_public_key_der(spki_der)
+        .map_err(|e| format!("invalid SPKI public key: {e}"))?;
+    if digest.len() != 32 {
+        return Err(format!("data must be a 32-byte SHA-256 digest, got {} bytes", digest.len()));
+    }
+    let signature =
+        RsaSignature::try_from(signature).map_err(|e| format!("invalid signature: {e}"))?;
+    Ok(VerifyingKey::<Sha256>::new(public_key).verify_prehash(digest, &signature).is_ok())
+}
+
+/// Parse a DKIM `p=` key: DER-encoded SubjectPublicKeyInfo (RFC 6376), falling
+/// back to a bare PKCS#1 `RSAPublicKey`, which some DNS records publish instead.
+pub fn parse_rsa_public_key(pk_bytes: &[u8]) -> Result<RsaPublicKey, String> {
+    RsaPublicKey::from_public_key_der(pk_bytes).or_else(|spki_err| {
+        RsaPublicKey::from_pkcs1_der(pk_bytes).map_err(|pkcs1_err| {
+            format!("invalid RSA public key (SPKI: {spki_err}; PKCS#1: {pkcs1_err})")
+        })
+    })
+}
+
+/// Debug helper: the canonicalized input for the `dkim_header_index`-th
+/// DKIM-Signature header (0-based).
+///
+/// Returns the canonicalized body (truncated to `l=` when present, with each
+/// `\r\n` rendered literally and followed by a newline), then the canonicalized
+/// signed headers and the final `dkim-signature:` line with an empty `b=`,
+/// exactly as hashed for the `b=` signature.
+#[cfg(any(test, feature = "debug-dkim"))]
+#[cfg_attr(not(test), allow(dead_code))] // debugging aid; no worker method calls it
+pub fn build_canonicalized_email_string(
+    email_blob: &str,
+    dkim_header_index: usize,
+) -> Result<String, String> {
+    let (raw_headers, body) = split_headers_body(strip_bom(email_blob));
+    let headers = parse_headers(raw_headers);
+    let (dkim_name, dkim_value) = nth_dkim_signature(&headers, dkim_header_index)?;
+    let tags = parse_dkim_tags(&dkim_value);
+
+    let c_tag = tags.get("c").map(String::as_str);
+    let (header_canon, body_canon) = parse_dkim_canonicalization(c_tag)
+        .ok_or_else(|| format!("unsupported canonicalization: {}", c_tag.unwrap_or_default()))?;
+    let signed_headers = signed_header_names(&tags)?;
+
+    let canon_body = match body_canon {
+        Canonicalization::Simple => canonicalize_body_simple(body),
+        Canonicalization::Relaxed => canonicalize_body_relaxed(body),
+    };
+    let body_bytes = canon_body.as_bytes();
+    let body_to_hash: &[u8] = if let Some(l_str) = tags.get("l") {
+        let l_val = l_str
+            .parse::<usize>()
+            .map_err(|_| format!("invalid l= tag: {l_str}"))?;
+        if l_val > body_bytes.len() {
+            return Err(format!(
+                "l= tag ({l_val}) exceeds canonicalized body length ({})",
+                body_bytes.len()
+            ));
+        }
+        &body_bytes[..l_val]
+    } else {
+        body_bytes
+    };
+    let body_shown = String::from_utf8_lossy(body_to_hash).replace("\r\n", "\\r\\n\n");
+
+    let data =
+        signed_header_data(&headers, &signed_headers, &dkim_name, &dkim_value, header_canon);
+
+    Ok(format!("{body_shown}{data}"))
+}